@@ -0,0 +1,342 @@
+use crate::csv;
+use crate::errors::CsvlensResult;
+use crate::normalize::parse_number;
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::{self};
+
+/// Summary of the values in a column: the sum if every non-empty value is numeric, otherwise the
+/// number of non-empty values
+#[derive(Clone, Debug, PartialEq)]
+pub enum Aggregate {
+    Sum(f64),
+    Count(usize),
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregate::Sum(sum) => write!(f, "sum: {}", format_number(*sum)),
+            Aggregate::Count(count) => write!(f, "count: {count}"),
+        }
+    }
+}
+
+/// Sum and average of a column over a range of rows, if every non-empty value in it is numeric
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeSummary {
+    pub sum: f64,
+    pub count: usize,
+}
+
+impl RangeSummary {
+    fn new(accumulator: &ColumnAccumulator) -> Option<Self> {
+        match accumulator.finish() {
+            Aggregate::Sum(sum) => Some(RangeSummary {
+                sum,
+                count: accumulator.count,
+            }),
+            Aggregate::Count(_) => None,
+        }
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+impl fmt::Display for RangeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sum: {}, avg: {}",
+            format_number(self.sum),
+            format_number(self.mean())
+        )
+    }
+}
+
+fn format_number(x: f64) -> String {
+    // Avoid showing floating point noise such as 0.30000000000000004
+    let s = format!("{x:.6}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[derive(Debug)]
+pub struct Aggregator {
+    internal: Arc<Mutex<AggregatorInternalState>>,
+}
+
+impl Aggregator {
+    /// Aggregate the given origin columns over all rows, or only over the given record indices
+    pub fn new(
+        config: Arc<csv::CsvConfig>,
+        row_indices: Option<Vec<u64>>,
+        column_indices: Vec<usize>,
+    ) -> Self {
+        let internal = AggregatorInternalState::init(config, row_indices, column_indices);
+        Aggregator { internal }
+    }
+
+    pub fn results(&self) -> Option<Vec<Aggregate>> {
+        let m = self.internal.lock().unwrap();
+        let accumulators = m.accumulators.as_ref()?;
+        Some(accumulators.iter().map(|a| a.finish()).collect())
+    }
+
+    /// Sum and average of each column, for the columns that are numeric
+    pub fn range_summaries(&self) -> Option<Vec<Option<RangeSummary>>> {
+        let m = self.internal.lock().unwrap();
+        let accumulators = m.accumulators.as_ref()?;
+        Some(accumulators.iter().map(RangeSummary::new).collect())
+    }
+
+    pub fn terminate(&self) {
+        let mut m = self.internal.lock().unwrap();
+        m.should_terminate = true;
+    }
+
+    /// Block until the background thread is done
+    pub fn wait_internal(&self) {
+        loop {
+            if self.internal.lock().unwrap().done {
+                break;
+            }
+            thread::sleep(core::time::Duration::from_millis(100));
+        }
+    }
+}
+
+impl Drop for Aggregator {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct ColumnAccumulator {
+    sum: f64,
+    count: usize,
+    is_numeric: bool,
+    thousands_separator: Option<char>,
+}
+
+impl ColumnAccumulator {
+    fn new(thousands_separator: Option<char>) -> Self {
+        ColumnAccumulator {
+            is_numeric: true,
+            thousands_separator,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        self.count += 1;
+        if self.is_numeric {
+            match parse_number(value, self.thousands_separator) {
+                Some(x) => self.sum += x,
+                None => self.is_numeric = false,
+            }
+        }
+    }
+
+    fn finish(&self) -> Aggregate {
+        if self.is_numeric && self.count > 0 {
+            Aggregate::Sum(self.sum)
+        } else {
+            Aggregate::Count(self.count)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AggregatorInternalState {
+    accumulators: Option<Vec<ColumnAccumulator>>,
+    should_terminate: bool,
+    done: bool,
+}
+
+impl AggregatorInternalState {
+    fn init(
+        config: Arc<csv::CsvConfig>,
+        row_indices: Option<Vec<u64>>,
+        column_indices: Vec<usize>,
+    ) -> Arc<Mutex<AggregatorInternalState>> {
+        let internal = AggregatorInternalState {
+            accumulators: None,
+            should_terminate: false,
+            done: false,
+        };
+
+        let m_state = Arc::new(Mutex::new(internal));
+
+        let _m = m_state.clone();
+
+        let _handle = thread::spawn(move || {
+            fn run(
+                m: &Arc<Mutex<AggregatorInternalState>>,
+                config: Arc<csv::CsvConfig>,
+                row_indices: Option<Vec<u64>>,
+                column_indices: Vec<usize>,
+            ) -> CsvlensResult<Option<Vec<ColumnAccumulator>>> {
+                let mut accumulators = vec![
+                    ColumnAccumulator::new(config.thousands_separator());
+                    column_indices.len()
+                ];
+
+                // Found rows are not necessarily in file order, e.g. when sorted
+                let mut wanted = row_indices.map(|mut indices| {
+                    indices.sort_unstable();
+                    indices.into_iter().peekable()
+                });
+
+                let mut reader = config.new_reader()?;
+                for (row_index, record) in reader.records().enumerate() {
+                    if row_index % 1000 == 0 && m.lock().unwrap().should_terminate {
+                        return Ok(None);
+                    }
+                    if let Some(wanted) = wanted.as_mut() {
+                        match wanted.peek() {
+                            Some(next) if *next == row_index as u64 => {
+                                wanted.next();
+                            }
+                            Some(_) => continue,
+                            None => break,
+                        }
+                    }
+                    // Leave out records that cannot be read, e.g. with invalid UTF-8, rather than
+                    // giving up on the whole column
+                    let record = match record {
+                        Ok(record) => record,
+                        Err(e) if e.is_io_error() => return Err(e.into()),
+                        Err(_) => continue,
+                    };
+                    for (accumulator, column_index) in
+                        accumulators.iter_mut().zip(column_indices.iter())
+                    {
+                        accumulator.add(record.get(*column_index).unwrap_or_default());
+                    }
+                }
+
+                Ok(Some(accumulators))
+            }
+
+            // Leave the footer empty on errors, the table itself will surface problems with the
+            // file anyway
+            let accumulators = run(&_m, config, row_indices, column_indices).ok().flatten();
+
+            let mut m = _m.lock().unwrap();
+            m.accumulators = accumulators;
+            m.done = true;
+        });
+
+        m_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_skip_invalid_records() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"a\n1\n\xff\n2\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let aggregator = Aggregator::new(config, None, vec![0]);
+        aggregator.wait_internal();
+        assert_eq!(aggregator.results(), Some(vec![Aggregate::Sum(3.0)]));
+    }
+
+    #[test]
+    fn test_simple() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"a,b,c\n1,x,\n2.5,y,\n,z,\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let aggregator = Aggregator::new(config, None, vec![0, 1, 2]);
+        aggregator.wait_internal();
+        assert_eq!(
+            aggregator.results(),
+            Some(vec![
+                Aggregate::Sum(3.5),
+                Aggregate::Count(3),
+                Aggregate::Count(0)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_row_indices() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"n,s\n1,a\n2,b\n3,c\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let aggregator = Aggregator::new(config, Some(vec![2, 0]), vec![0, 1]);
+        aggregator.wait_internal();
+        let results = aggregator
+            .results()
+            .unwrap()
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(results, vec!["sum: 4", "count: 2"]);
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let config = Arc::new(
+            csv::CsvConfig::from_source(
+                csv::CsvSource::Bytes(b"a\n\"1,000\"\n9\n\"2,500.5\"\n".to_vec().into()),
+                b',',
+                false,
+            )
+            .with_thousands_separator(Some(',')),
+        );
+        let aggregator = Aggregator::new(config, None, vec![0]);
+        aggregator.wait_internal();
+        assert_eq!(aggregator.results(), Some(vec![Aggregate::Sum(3509.5)]));
+    }
+
+    #[test]
+    fn test_range_summaries() {
+        let config = Arc::new(
+            csv::CsvConfig::from_source(
+                csv::CsvSource::Bytes(b"a;b\n1.000;x\n9;y\n2,5;z\n".to_vec().into()),
+                b';',
+                false,
+            )
+            .with_thousands_separator(Some('.')),
+        );
+        let aggregator = Aggregator::new(config, Some(vec![2, 0]), vec![0, 1]);
+        aggregator.wait_internal();
+        let summaries = aggregator
+            .range_summaries()
+            .unwrap()
+            .iter()
+            .map(|s| s.as_ref().map(|s| s.to_string()))
+            .collect::<Vec<Option<String>>>();
+        assert_eq!(
+            summaries,
+            vec![Some("sum: 1002.5, avg: 501.25".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(6.0), "6");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(-1.25), "-1.25");
+    }
+}
@@ -1,27 +1,57 @@
 extern crate csv_sniffer;
 
+use crate::aggregate::Aggregator;
+use crate::border::BorderStyle;
+use crate::clipboard::{Clipboard, ClipboardBackend};
+use crate::column_letters::parse_column_letters;
 use crate::columns_filter::ColumnsFilter;
+use crate::common::InputMode;
 use crate::csv;
-use crate::delimiter::{sniff_delimiter, Delimiter};
+use crate::delimiter::Delimiter;
+use crate::diff::{self, DiffRow};
+use crate::download;
+use crate::duplicates::{DuplicateCounter, DuplicateCounterStatus};
 use crate::errors::{CsvlensError, CsvlensResult};
+use crate::export;
 use crate::find;
 use crate::help;
-use crate::input::{Control, InputHandler};
+use crate::input::{Control, InputHandler, KeyMap};
+use crate::messages::{MessageLog, MessageLogPage, MessageLogPageState};
+use crate::normalize;
+use crate::output::{CopyMode, OutputGranularity, OutputMode, RowOutput, SelectionOutput};
+use crate::palette::{self, CommandPalette, CommandPaletteState};
+use crate::schema;
 use crate::sort::{self, SortOrder, SorterStatus};
-use crate::ui::{CsvTable, CsvTableState, FilterColumnsState, FinderState};
+use crate::theme::Theme;
+use crate::truncate::Truncation;
+use crate::ui::{
+    ColumnWidthOverrides, CsvTable, CsvTableState, FilterColumnsState, FinderState, TablePosition,
+    DEFAULT_SPACES_AFTER_LINE_NUMBER, DEFAULT_SPACES_BETWEEN_COLUMNS, STATUS_HEIGHT,
+};
+use crate::undo::UndoStack;
+use crate::validate::{ValidationPage, ValidationPageState, Validator, ValidatorStatus};
 use crate::view;
+use crate::whitespace::WhitespaceSymbols;
 
-#[cfg(feature = "clipboard")]
-use arboard::Clipboard;
-use ratatui::backend::Backend;
+use ratatui::backend::{Backend, TestBackend};
+use ratatui::buffer::Buffer;
 use ratatui::prelude::Position;
+use ratatui::style::Color;
+use ratatui::text::Span;
 use ratatui::{Frame, Terminal};
 
-use anyhow::Result;
 use regex::Regex;
-use std::cmp::min;
+use std::borrow::Cow;
+use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+
+/// How long typing has to pause before matches of a find being typed are highlighted
+const FIND_PREVIEW_DELAY: Duration = Duration::from_millis(150);
 
 fn get_offsets_to_make_visible(
     found_record: &find::FoundEntry,
@@ -105,6 +135,62 @@ fn get_cols_offset_to_fill_frame_width(
     }
 }
 
+/// Split a comma separated list of columns to echo, checking that each of them exists. A name
+/// that matches a header as a whole is taken as is, even if it contains commas.
+fn parse_echo_columns(columns: &str, headers: &[view::Header]) -> CsvlensResult<Vec<String>> {
+    let has_column = |name: &str| headers.iter().any(|h| h.name == name);
+    if has_column(columns) {
+        return Ok(vec![columns.to_string()]);
+    }
+    columns
+        .split(',')
+        .map(|name| {
+            if has_column(name) {
+                Ok(name.to_string())
+            } else {
+                Err(CsvlensError::ColumnNameNotFound(name.to_string()))
+            }
+        })
+        .collect()
+}
+
+/// Resolve the columns to color by name to their origin indices, checking that the colors are
+/// valid
+fn parse_column_colors(
+    column_colors: &HashMap<String, String>,
+    headers: &[view::Header],
+) -> CsvlensResult<HashMap<usize, Color>> {
+    column_colors
+        .iter()
+        .map(|(name, color)| {
+            let header = headers
+                .iter()
+                .find(|h| &h.name == name)
+                .ok_or_else(|| CsvlensError::ColumnNameNotFound(name.to_string()))?;
+            let color = Color::from_str(color)
+                .map_err(|_| CsvlensError::InvalidColor(name.to_string(), color.to_string()))?;
+            Ok((header.origin_index, color))
+        })
+        .collect()
+}
+
+/// Convert the content of a rendered buffer to lines of plain text
+fn to_lines(buf: &Buffer) -> Vec<String> {
+    let mut symbols: String = "".to_owned();
+    let area = buf.area();
+    for y in 0..area.bottom() {
+        for x in 0..area.right() {
+            let symbol = buf[Position::new(x, y)].symbol();
+            symbols.push_str(symbol);
+        }
+        if y != area.bottom() - 1 {
+            symbols.push('\n');
+        }
+    }
+
+    symbols.split('\n').map(|s| s.to_string()).collect()
+}
+
 #[derive(Default)]
 pub struct LineWrapState {
     pub enable_line_wrap: bool,
@@ -137,43 +223,335 @@ impl LineWrapState {
     }
 }
 
+/// A find, filter or sort as it was run, to run again with `Control::RepeatLastCommand` on the
+/// same column and with the same options whatever is selected by then
+#[derive(Clone)]
+enum RepeatableCommand {
+    FindOrFilter(FinderSnapshot),
+    /// Sort by the column with this origin index, or toggle its sort direction
+    ToggleSort(usize),
+}
+
+/// Maximum number of changes that can be undone
+const UNDO_CAPACITY: usize = 100;
+
+/// What is found or filtered, to create a new finder from
+#[derive(Clone)]
+struct FinderSnapshot {
+    target: Regex,
+    duplicates: bool,
+    is_filter: bool,
+    column_index: Option<usize>,
+    filter_stack: Vec<find::FilterCondition>,
+}
+
+impl PartialEq for FinderSnapshot {
+    fn eq(&self, other: &Self) -> bool {
+        let targets = |stack: &[find::FilterCondition]| -> Vec<(String, bool)> {
+            stack
+                .iter()
+                .map(|c| (c.target().to_string(), c.duplicates()))
+                .collect()
+        };
+        self.target.as_str() == other.target.as_str()
+            && self.duplicates == other.duplicates
+            && self.is_filter == other.is_filter
+            && self.column_index == other.column_index
+            && targets(&self.filter_stack) == targets(&other.filter_stack)
+    }
+}
+
+/// Filters, sort and column options that can be undone. Only what is needed to rebuild finders
+/// and sorters is kept, so that their background threads stop once they are replaced.
+#[derive(Clone)]
+struct ViewSnapshot {
+    finder: Option<FinderSnapshot>,
+    sort: Option<(Option<usize>, SortOrder)>,
+    columns_filter: Option<Arc<ColumnsFilter>>,
+    column_width_overrides: ColumnWidthOverrides,
+    wrapped_columns: HashSet<usize>,
+    collapsed_column: Option<usize>,
+}
+
+impl PartialEq for ViewSnapshot {
+    fn eq(&self, other: &Self) -> bool {
+        let same_columns_filter = match (&self.columns_filter, &other.columns_filter) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        self.finder == other.finder
+            && self.sort == other.sort
+            && same_columns_filter
+            && self.column_width_overrides == other.column_width_overrides
+            && self.wrapped_columns == other.wrapped_columns
+            && self.collapsed_column == other.collapsed_column
+    }
+}
+
+/// What decides which rows are shown and in which order, to tell when the rows are sorted or
+/// filtered differently
+#[derive(PartialEq)]
+struct RowOrder {
+    sort: Option<(Option<usize>, SortOrder)>,
+    filter: Option<String>,
+}
+
+/// What the range summary is computed over
+#[derive(PartialEq)]
+struct RangeKey {
+    range: (u64, u64),
+    column_index: usize,
+    row_order: RowOrder,
+}
+
 pub struct App {
     input_handler: InputHandler,
-    num_rows_not_visible: u16,
     shared_config: Arc<csv::CsvConfig>,
     rows_view: view::RowsView,
     columns_filter: Option<Arc<ColumnsFilter>>,
     csv_table_state: CsvTableState,
     finder: Option<find::Finder>,
+    /// Filters stacked below the current one, which found rows also have to match
+    filter_stack: Vec<find::FilterCondition>,
     first_found_scrolled: bool,
+    find_preview: Option<(String, Instant)>,
+    /// Finds the pattern being typed in the find prompt among the rows shown, only to highlight it
+    preview_finder: Option<find::Finder>,
+    /// How long typing has to pause before the pattern is previewed
+    find_preview_delay: Duration,
     frame_width: Option<u16>,
     transient_message: Option<String>,
+    message_log: MessageLog,
+    message_log_page_state: MessageLogPageState,
+    /// Whether the source stopping short while written in the background was shown already
+    source_error_shown: bool,
     show_stats: bool,
-    echo_column: Option<String>,
+    echo_columns: Vec<String>,
     ignore_case: bool,
+    ignore_accents: bool,
+    search_visible_columns: bool,
+    wrap_search: bool,
     help_page_state: help::HelpPageState,
+    command_palette_state: CommandPaletteState,
     sorter: Option<Arc<sort::Sorter>>,
     sort_order: SortOrder,
+    sort_nulls_last: bool,
     line_wrap_state: LineWrapState,
-    #[cfg(feature = "clipboard")]
-    clipboard: Result<Clipboard>,
+    marks: HashMap<char, u64>,
+    /// Position in the view of the row a range selection started from
+    range_anchor: Option<u64>,
+    /// Controls recorded as macros, by the letter naming them
+    macros: HashMap<char, Vec<Control>>,
+    /// Macro being recorded and the controls recorded so far
+    macro_recording: Option<(char, Vec<Control>)>,
+    /// Controls of the macros being played, fed one by one once the previous one is done
+    macro_queue: VecDeque<Control>,
+    /// Set once the app should exit, with the selection to print if any
+    exit: Option<Option<String>>,
+    /// Column whose value identifies a row, by its index among all columns
+    key_column: Option<usize>,
+    /// Record number of the row selected before the rows were last sorted or filtered, to select
+    /// again once the new order is complete
+    pending_record: Option<u64>,
+    /// Record selected, or at the top if none, before filtering. Scrolled back to once the filter
+    /// is cleared.
+    filter_origin: Option<usize>,
+    clipboard: Clipboard,
+    on_selection_change: Option<view::SelectionCallback>,
+    last_selection_event: Option<view::SelectionEvent>,
+    pending_initial_row: Option<u64>,
+    /// Whether to check for columns too wide for the terminal once the first rows are shown
+    pending_auto_wrap: bool,
+    find_threads: usize,
+    output_mode: OutputMode,
+    output_granularity: OutputGranularity,
+    copy_mode: CopyMode,
+    aggregator: Option<Aggregator>,
+    /// Sums the selected column over the range selection in the background
+    range_aggregator: Option<(RangeKey, Aggregator)>,
+    validator: Option<Validator>,
+    validation_page_state: ValidationPageState,
+    duplicate_counter: Option<DuplicateCounter>,
+    /// Whether the transient message follows the progress of the distinct row count
+    show_distinct_count: bool,
+    undo_stack: UndoStack<ViewSnapshot>,
+    /// Most recent find, filter or sort, whichever came last
+    last_command: Option<RepeatableCommand>,
+}
+
+/// Settings of an [`App`], usually built from the [`crate::CsvlensOptions`] it is run with
+pub struct AppOptions {
+    pub delimiter: Delimiter,
+    /// Name of the file shown in the status line, if different from the source
+    pub original_filename: Option<String>,
+    pub show_stats: bool,
+    /// Column or comma separated columns to print for the selected row on Enter
+    pub echo_column: Option<String>,
+    pub ignore_case: bool,
+    pub no_headers: bool,
+    /// Only show the columns whose names match this regex
+    pub columns_regex: Option<String>,
+    /// Filter the rows by this regex at startup
+    pub filter_regex: Option<String>,
+    /// Find this regex at startup, unless `filter_regex` is given
+    pub find_regex: Option<String>,
+    pub scrolloff: u64,
+    pub clipboard_backend: ClipboardBackend,
+    pub copy_to_primary: bool,
+    pub on_selection_change: Option<view::SelectionCallback>,
+    pub initial_row: Option<u64>,
+    pub initial_column: Option<String>,
+    pub keymap: KeyMap,
+    pub show_scrollbars: bool,
+    /// Threads to find with, as many as there are cores if not given
+    pub find_threads: Option<usize>,
+    pub pos_table_config: csv::PosTableConfig,
+    pub output_mode: OutputMode,
+    pub whitespace_symbols: WhitespaceSymbols,
+    pub status_format: Option<String>,
+    pub compact: bool,
+    pub ignore_accents: bool,
+    pub wrap_search: bool,
+    pub filter_context: u64,
+    /// Changes to show when viewing the diff of two files
+    pub diff_rows: Option<Vec<DiffRow>>,
+    pub empty_placeholder: Option<String>,
+    pub terminator: Option<u8>,
+    pub max_rows: Option<u64>,
+    pub sort_nulls_last: bool,
+    pub column_spacing: u16,
+    pub line_number_spacing: u16,
+    pub border_style: BorderStyle,
+    pub output_granularity: OutputGranularity,
+    pub column_colors: HashMap<String, String>,
+    pub units_row: bool,
+    pub copy_mode: CopyMode,
+    pub hide_row_numbers: bool,
+    pub zero_based_row_numbers: bool,
+    pub monochrome: bool,
+    pub auto_wrap: bool,
+    /// Only show these columns, in this order. Takes precedence over `columns_regex`.
+    pub column_names: Option<Vec<String>>,
+    pub truncation: Truncation,
+    pub key_column: Option<String>,
+    pub max_row_height: Option<u16>,
+    pub column_letters: bool,
+    pub thousands_separator: Option<char>,
+    /// Shown in the status line at startup and kept in the message log
+    pub startup_message: Option<String>,
+}
+
+impl Default for AppOptions {
+    fn default() -> Self {
+        AppOptions {
+            delimiter: Delimiter::Default,
+            original_filename: None,
+            show_stats: false,
+            echo_column: None,
+            ignore_case: false,
+            no_headers: false,
+            columns_regex: None,
+            filter_regex: None,
+            find_regex: None,
+            scrolloff: 0,
+            clipboard_backend: ClipboardBackend::default(),
+            copy_to_primary: false,
+            on_selection_change: None,
+            initial_row: None,
+            initial_column: None,
+            keymap: KeyMap::default(),
+            show_scrollbars: false,
+            find_threads: None,
+            pos_table_config: csv::PosTableConfig::default(),
+            output_mode: OutputMode::default(),
+            whitespace_symbols: WhitespaceSymbols::default(),
+            status_format: None,
+            compact: false,
+            ignore_accents: false,
+            wrap_search: false,
+            filter_context: 0,
+            diff_rows: None,
+            empty_placeholder: None,
+            terminator: None,
+            max_rows: None,
+            sort_nulls_last: false,
+            column_spacing: DEFAULT_SPACES_BETWEEN_COLUMNS,
+            line_number_spacing: DEFAULT_SPACES_AFTER_LINE_NUMBER,
+            border_style: BorderStyle::default(),
+            output_granularity: OutputGranularity::default(),
+            column_colors: HashMap::new(),
+            units_row: false,
+            copy_mode: CopyMode::default(),
+            hide_row_numbers: false,
+            zero_based_row_numbers: false,
+            monochrome: false,
+            auto_wrap: false,
+            column_names: None,
+            truncation: Truncation::default(),
+            key_column: None,
+            max_row_height: None,
+            column_letters: false,
+            thousands_separator: None,
+            startup_message: None,
+        }
+    }
 }
 
 impl App {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        filename: &str,
-        delimiter: Delimiter,
-        original_filename: Option<String>,
-        show_stats: bool,
-        echo_column: Option<String>,
-        ignore_case: bool,
-        no_headers: bool,
-        columns_regex: Option<String>,
-        filter_regex: Option<String>,
-        find_regex: Option<String>,
-    ) -> CsvlensResult<Self> {
-        let input_handler = InputHandler::new();
+    pub fn new(source: csv::CsvSource, options: AppOptions) -> CsvlensResult<Self> {
+        let AppOptions {
+            delimiter,
+            original_filename,
+            show_stats,
+            echo_column,
+            ignore_case,
+            no_headers,
+            columns_regex,
+            filter_regex,
+            find_regex,
+            scrolloff,
+            clipboard_backend,
+            copy_to_primary,
+            on_selection_change,
+            initial_row,
+            initial_column,
+            keymap,
+            show_scrollbars,
+            find_threads,
+            pos_table_config,
+            output_mode,
+            whitespace_symbols,
+            status_format,
+            compact,
+            ignore_accents,
+            wrap_search,
+            filter_context,
+            diff_rows,
+            empty_placeholder,
+            terminator,
+            max_rows,
+            sort_nulls_last,
+            column_spacing,
+            line_number_spacing,
+            border_style,
+            output_granularity,
+            column_colors,
+            units_row,
+            copy_mode,
+            hide_row_numbers,
+            zero_based_row_numbers,
+            monochrome,
+            auto_wrap,
+            column_names,
+            truncation,
+            key_column,
+            max_row_height,
+            column_letters,
+            thousands_separator,
+            startup_message,
+        } = options;
+        let command_palette_state = CommandPaletteState::new(palette::commands(&keymap));
+        let input_handler = InputHandler::new(keymap);
 
         // Some lines are reserved for plotting headers (3 lines for headers + 2 lines for status bar)
         let num_rows_not_visible: u16 = 5;
@@ -181,67 +559,150 @@ impl App {
         // Number of rows that are visible in the current frame
         let num_rows = 50 - num_rows_not_visible;
 
-        let delimiter = match delimiter {
-            Delimiter::Default => b',',
-            Delimiter::Tab => b'\t',
-            Delimiter::Character(d) => d,
-            Delimiter::Auto => sniff_delimiter(filename).unwrap_or(b','),
-        };
-        let config = csv::CsvConfig::new(filename, delimiter, no_headers);
+        let delimiter = delimiter.resolve(&source, original_filename.as_deref());
+        let config = csv::CsvConfig::from_source(source, delimiter, no_headers)
+            .with_terminator(terminator)
+            .with_max_rows(max_rows)
+            .with_units_row(units_row)
+            .with_thousands_separator(thousands_separator)
+            .with_pos_table_config(pos_table_config);
         let shared_config = Arc::new(config);
 
         let csvlens_reader = csv::CsvLensReader::new(shared_config.clone())?;
-        let rows_view = view::RowsView::new(csvlens_reader, num_rows as u64)?;
-
-        if let Some(column_name) = &echo_column {
-            if !rows_view.headers().iter().any(|h| h.name == *column_name) {
-                return Err(CsvlensError::ColumnNameNotFound(column_name.clone()));
-            }
-        }
+        let units = csvlens_reader.units.clone();
+        let mut rows_view = view::RowsView::new(csvlens_reader, num_rows as u64)?;
+        rows_view.set_scrolloff(scrolloff);
+        rows_view.set_filter_context(filter_context);
+
+        let echo_columns = match &echo_column {
+            Some(columns) => parse_echo_columns(columns, rows_view.headers())?,
+            None => vec![],
+        };
+        let column_colors = parse_column_colors(&column_colors, rows_view.headers())?;
 
-        let csv_table_state = CsvTableState::new(
+        let mut csv_table_state = CsvTableState::new(
             original_filename,
             rows_view.headers().len(),
             &echo_column,
             ignore_case,
         );
+        csv_table_state.show_scrollbars = show_scrollbars;
+        csv_table_state.show_row_numbers = !hide_row_numbers;
+        csv_table_state.zero_based_row_numbers = zero_based_row_numbers;
+        csv_table_state.thousands_separator = thousands_separator;
+        csv_table_state.theme = Theme::new(monochrome);
+        csv_table_state.truncation = truncation;
+        csv_table_state.max_row_height = max_row_height;
+        csv_table_state.show_column_letters = column_letters;
+        csv_table_state.compact = compact;
+        csv_table_state.column_spacing = column_spacing;
+        csv_table_state.line_number_spacing = line_number_spacing;
+        csv_table_state.border_style = border_style;
+        csv_table_state.column_colors = column_colors;
+        csv_table_state.units = units;
+        csv_table_state.ignore_accents = ignore_accents;
+        csv_table_state.whitespace_symbols = whitespace_symbols;
+        if let Some(empty_placeholder) = empty_placeholder {
+            csv_table_state.show_empty_placeholder = true;
+            csv_table_state.empty_placeholder = empty_placeholder;
+        }
+        if let Some(status_format) = status_format {
+            csv_table_state.status_format = status_format;
+        }
 
         let finder: Option<find::Finder> = None;
         let first_found_scrolled = false;
         let frame_width = None;
 
-        let transient_message: Option<String> = None;
         let help_page_state = help::HelpPageState::new();
 
-        #[cfg(feature = "clipboard")]
-        let clipboard = match Clipboard::new() {
-            Ok(clipboard) => Ok(clipboard),
-            Err(e) => Err(anyhow::anyhow!(e)),
-        };
+        let clipboard = Clipboard::new(clipboard_backend, copy_to_primary);
+        // Surface a broken clipboard once at startup rather than only when copying. A warning
+        // about the file itself is shown ahead of it, and all of them are in the message log.
+        let startup_messages: Vec<String> = [
+            startup_message,
+            clipboard
+                .unavailable_reason()
+                .map(|reason| format!("Clipboard unavailable: {reason}")),
+            diff_rows.as_deref().map(diff::summary),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let mut message_log = MessageLog::new();
+        for message in startup_messages.iter().rev() {
+            message_log.push(message);
+        }
+        let transient_message = startup_messages.into_iter().next();
+        if let Some(diff_rows) = diff_rows {
+            csv_table_state.diff_rows = diff_rows;
+        }
 
         let mut app = App {
             input_handler,
-            num_rows_not_visible,
             shared_config,
             rows_view,
             columns_filter: None,
             csv_table_state,
             finder,
+            filter_stack: vec![],
             first_found_scrolled,
+            find_preview: None,
+            preview_finder: None,
+            find_preview_delay: FIND_PREVIEW_DELAY,
             frame_width,
             transient_message,
+            message_log,
+            message_log_page_state: MessageLogPageState::new(),
+            source_error_shown: false,
             show_stats,
-            echo_column,
+            echo_columns,
             ignore_case,
+            ignore_accents,
+            search_visible_columns: false,
+            wrap_search,
             help_page_state,
+            command_palette_state,
             sorter: None,
             sort_order: SortOrder::Ascending,
+            sort_nulls_last,
             line_wrap_state: LineWrapState::default(),
-            #[cfg(feature = "clipboard")]
+            marks: HashMap::new(),
+            range_anchor: None,
+            macros: HashMap::new(),
+            macro_recording: None,
+            macro_queue: VecDeque::new(),
+            exit: None,
+            key_column: None,
+            pending_record: None,
+            filter_origin: None,
             clipboard,
+            on_selection_change,
+            last_selection_event: None,
+            // Applied in step() once enough rows are indexed to tell where the row is
+            pending_initial_row: initial_row,
+            pending_auto_wrap: auto_wrap,
+            find_threads: find_threads.unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+            }),
+            output_mode,
+            output_granularity,
+            copy_mode,
+            aggregator: None,
+            range_aggregator: None,
+            validator: None,
+            validation_page_state: ValidationPageState::new(),
+            duplicate_counter: None,
+            show_distinct_count: false,
+            undo_stack: UndoStack::new(UNDO_CAPACITY),
+            last_command: None,
         };
 
-        if let Some(pat) = &columns_regex {
+        if let Some(names) = &column_names {
+            app.set_columns_filter_by_names(names);
+        } else if let Some(pat) = &columns_regex {
             app.set_columns_filter(pat);
         }
 
@@ -251,6 +712,14 @@ impl App {
             app.handle_find_or_filter(pat, false, false);
         }
 
+        if let Some(column_name) = &initial_column {
+            app.goto_column(column_name);
+        }
+
+        if let Some(column_name) = &key_column {
+            app.set_key_column(column_name);
+        }
+
         app.rows_view.set_sort_order(app.sort_order)?;
         app.csv_table_state.debug_stats.show_stats(app.show_stats);
 
@@ -263,28 +732,56 @@ impl App {
     ) -> CsvlensResult<Option<String>> {
         loop {
             let control = self.input_handler.next();
-            if matches!(control, Control::Quit) {
-                if self.help_page_state.is_active() {
-                    self.help_page_state.deactivate();
-                    self.input_handler.exit_help_mode();
-                } else {
-                    return Ok(None);
-                }
-            }
-            if matches!(control, Control::Select) {
-                if let Some(result) = self.get_selection() {
-                    return Ok(Some(result));
-                }
-            }
-            if matches!(control, Control::Help) {
-                self.help_page_state.activate();
-                self.input_handler.enter_help_mode();
-            }
             self.step(&control)?;
+            if let Some(result) = self.exit.take() {
+                return Ok(result);
+            }
+            self.play_queued_macro()?;
             self.draw(terminal)?;
         }
     }
 
+    pub fn headers(&self) -> &[view::Header] {
+        self.rows_view.headers()
+    }
+
+    pub fn visible_rows(&self) -> &[csv::Row] {
+        self.rows_view.visible_rows()
+    }
+
+    /// Block until background work such as indexing, finding and sorting is done
+    pub fn wait_until_ready(&self) {
+        self.rows_view.wait_internal();
+        if let Some(sorter) = &self.sorter {
+            sorter.wait_internal();
+        }
+        if let Some(finder) = &self.finder {
+            finder.wait_internal();
+        }
+        if let Some(finder) = &self.preview_finder {
+            finder.wait_internal();
+        }
+        if let Some(aggregator) = &self.aggregator {
+            aggregator.wait_internal();
+        }
+        if let Some((_, aggregator)) = &self.range_aggregator {
+            aggregator.wait_internal();
+        }
+    }
+
+    /// Render the current view to lines of plain text without a real terminal
+    pub fn render_to_lines(&mut self, width: u16, height: u16) -> CsvlensResult<Vec<String>> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+        // Some states are only updated after drawing (e.g. number of rows rendered), so step and
+        // draw a few times for them to settle
+        for _ in 0..3 {
+            self.wait_until_ready();
+            self.step(&Control::Nothing)?;
+            self.draw(&mut terminal)?;
+        }
+        Ok(to_lines(terminal.backend().buffer()))
+    }
+
     fn step_help(&mut self, control: &Control) -> CsvlensResult<()> {
         match &control {
             Control::ScrollDown => {
@@ -293,31 +790,221 @@ impl App {
             Control::ScrollUp => {
                 self.help_page_state.scroll_up();
             }
+            Control::BufferContent(input) => {
+                self.help_page_state
+                    .set_prompt(Some(input.value().to_string()));
+            }
+            Control::BufferReset => {
+                self.help_page_state.set_prompt(None);
+            }
+            Control::Find(pat) => match self.create_regex(pat, false) {
+                Ok(regex) => {
+                    self.help_page_state.find(pat, regex);
+                }
+                Err(_) => {
+                    self.help_page_state
+                        .set_prompt(Some(format!("{pat} (invalid regex)")));
+                }
+            },
+            Control::ScrollToNextFound => {
+                self.help_page_state.next_match();
+            }
+            Control::ScrollToPrevFound => {
+                self.help_page_state.prev_match();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn step_validation(&mut self, control: &Control) -> CsvlensResult<()> {
+        let Some(validator) = &self.validator else {
+            return Ok(());
+        };
+        match &control {
+            Control::ScrollDown => {
+                self.validation_page_state
+                    .select_next(validator.num_issues_kept());
+            }
+            Control::ScrollUp => {
+                self.validation_page_state.select_previous();
+            }
+            Control::Select => {
+                if let Some(issue) = validator.get_issue(self.validation_page_state.selected()) {
+                    self.close_validation_page();
+                    let record_num = issue.record_num as u64;
+                    if !self.scroll_to_record(record_num)? {
+                        let row_number = self.csv_table_state.row_number(issue.record_num);
+                        self.set_message(format!("Row {row_number} is filtered out"));
+                    }
+                }
+            }
             _ => {}
         }
         Ok(())
     }
 
+    fn step_command_palette(&mut self, control: &Control) {
+        match &control {
+            Control::ScrollDown => {
+                self.command_palette_state.select_next();
+            }
+            Control::ScrollUp => {
+                self.command_palette_state.select_previous();
+            }
+            Control::BufferContent(input) => {
+                self.command_palette_state.set_query(input.value());
+            }
+            _ => {}
+        }
+    }
+
+    /// Close the page shown over the table if any, returning whether one was closed
+    fn close_page(&mut self) -> bool {
+        if self.command_palette_state.is_active() {
+            self.close_command_palette();
+        } else if self.message_log_page_state.is_active() {
+            self.message_log_page_state.deactivate();
+            self.input_handler.exit_message_log_mode();
+        } else if self.help_page_state.is_active() {
+            self.help_page_state.deactivate();
+            self.input_handler.exit_help_mode();
+        } else if self.validation_page_state.is_active() {
+            self.close_validation_page();
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Close the command palette and return the control of the highlighted command
+    fn run_selected_command(&mut self) -> Control {
+        let action = self.command_palette_state.selected_action();
+        self.close_command_palette();
+        match action {
+            Some(action) => self.input_handler.run_action(action),
+            None => Control::Nothing,
+        }
+    }
+
     fn step(&mut self, control: &Control) -> CsvlensResult<()> {
+        if matches!(control, Control::Quit) && !self.close_page() {
+            self.exit = Some(None);
+            return Ok(());
+        }
+        // Enter jumps to the selected row in the validation page and runs the highlighted command
+        // in the command palette instead
+        if matches!(control, Control::Select)
+            && !self.validation_page_state.is_active()
+            && !self.command_palette_state.is_active()
+        {
+            if let Some(result) = self.get_selection() {
+                self.exit = Some(Some(result));
+                return Ok(());
+            }
+        }
+        if matches!(control, Control::Help) {
+            self.help_page_state.activate();
+            self.input_handler.enter_help_mode();
+        }
+
         if self.help_page_state.is_active() {
             return self.step_help(control);
         }
 
+        if self.command_palette_state.is_active() {
+            if matches!(control, Control::Select) {
+                // Run the highlighted command as if its key was pressed
+                let control = self.run_selected_command();
+                return self.step(&control);
+            }
+            self.step_command_palette(control);
+            return Ok(());
+        }
+
+        if self.message_log_page_state.is_active() {
+            match control {
+                Control::ScrollDown => {
+                    self.message_log_page_state
+                        .scroll_down(self.message_log.num_messages());
+                }
+                Control::ScrollUp => {
+                    self.message_log_page_state.scroll_up();
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        if self.validation_page_state.is_active() {
+            self.step_validation(control)?;
+            if self.validation_page_state.is_active() {
+                return Ok(());
+            }
+            // Closed after jumping to a row, update the view accordingly
+            return self.step(&Control::Nothing);
+        }
+
         // clear message without changing other states on any action
         if !matches!(control, Control::Nothing) {
             self.transient_message = None;
+            self.message_log.hide();
+            self.show_distinct_count = false;
+        }
+
+        if let Some((_, controls)) = &mut self.macro_recording {
+            if control.is_recorded_in_macro() {
+                controls.push(control.clone());
+            }
         }
 
-        self.rows_view.handle_control(control)?;
+        match control {
+            // Rows are gone to by the numbers shown
+            Control::ScrollTo(n) if self.csv_table_state.zero_based_row_numbers => {
+                self.rows_view.handle_control(&Control::ScrollTo(n + 1))?;
+            }
+            _ => self.rows_view.handle_control(control)?,
+        }
+        self.apply_initial_row()?;
+        self.apply_auto_wrap();
         self.rows_view
             .selection
             .column
             .set_bound(self.csv_table_state.num_cols_rendered);
 
+        // Row selected in the current order, kept if the order changes below
+        let row_order_before = self.row_order();
+        let record_before = self
+            .key_column
+            .and(self.rows_view.selected_record_num())
+            .map(|record_num| record_num as u64);
+
+        // State to go back to on undo, recorded below if the control changes it
+        let view_before = if control.is_undoable() {
+            Some(self.view_snapshot())
+        } else {
+            None
+        };
+
         match &control {
             Control::ScrollTo(_) => {
                 self.csv_table_state.reset_buffer();
             }
+            Control::ScrollToFromEnd(n) => {
+                self.csv_table_state.reset_buffer();
+                let still_counting = self.rows_view.get_total_line_numbers().is_none()
+                    || (self.rows_view.is_filter()
+                        && self.finder.as_ref().is_some_and(|f| !f.done()));
+                if still_counting {
+                    let message = if self.rows_view.get_total_line_numbers_indexed().is_some() {
+                        format!("Total not known yet, row {n} from the end is approximate")
+                    } else {
+                        // Nothing to count from yet, so the view stays where it is
+                        format!("Total not known yet, cannot go to row {n} from the end")
+                    };
+                    self.set_message(message);
+                }
+            }
             Control::ScrollLeft => {
                 if let Some(i) = self.rows_view.selection.column.index() {
                     if i == 0 {
@@ -353,38 +1040,73 @@ impl App {
                     self.rows_view.set_cols_offset(new_cols_offset);
                 }
             }
-            Control::ScrollPageRight => {
-                if self.csv_table_state.has_more_cols_to_show() {
-                    // num_cols_rendered includes the last truncated column
-                    let mut new_cols_offset = self
-                        .csv_table_state
-                        .cols_offset
-                        .saturating_add(self.csv_table_state.num_cols_rendered.saturating_sub(1));
-                    new_cols_offset = min(
-                        new_cols_offset,
-                        self.rows_view.headers().len().saturating_sub(1) as u64,
-                    );
-                    if new_cols_offset != self.csv_table_state.cols_offset {
-                        self.rows_view.set_cols_offset(new_cols_offset);
-                    }
+            Control::ScrollPageRight if self.csv_table_state.has_more_cols_to_show() => {
+                // num_cols_rendered includes the last truncated column
+                let mut new_cols_offset = self
+                    .csv_table_state
+                    .cols_offset
+                    .saturating_add(self.csv_table_state.num_cols_rendered.saturating_sub(1));
+                new_cols_offset = min(
+                    new_cols_offset,
+                    self.rows_view.headers().len().saturating_sub(1) as u64,
+                );
+                if new_cols_offset != self.csv_table_state.cols_offset {
+                    self.rows_view.set_cols_offset(new_cols_offset);
+                }
+            }
+            Control::ScrollHalfPageLeft => {
+                let page_left_cols_offset = match self.frame_width {
+                    Some(frame_width) => get_cols_offset_to_fill_frame_width(
+                        frame_width,
+                        self.csv_table_state.cols_offset.saturating_sub(1),
+                        &self.csv_table_state,
+                    ),
+                    _ => Some(0),
+                };
+                if let Some(page_left_cols_offset) = page_left_cols_offset {
+                    // Half of the columns a page would scroll by, rounded up
+                    let cols_offset = self.csv_table_state.cols_offset;
+                    let num_cols = cols_offset.saturating_sub(page_left_cols_offset);
+                    self.rows_view
+                        .set_cols_offset(cols_offset.saturating_sub(num_cols.div_ceil(2)));
+                }
+            }
+            Control::ScrollHalfPageRight if self.csv_table_state.has_more_cols_to_show() => {
+                // num_cols_rendered includes the last truncated column
+                let num_cols = self.csv_table_state.num_cols_rendered.saturating_sub(1);
+                let mut new_cols_offset = self
+                    .csv_table_state
+                    .cols_offset
+                    .saturating_add(max(num_cols / 2, 1));
+                // No further than where the last column comes into view
+                let last_col = self.rows_view.headers().len().saturating_sub(1) as u64;
+                let max_cols_offset = match self.frame_width {
+                    Some(frame_width) => get_cols_offset_to_fill_frame_width(
+                        frame_width,
+                        last_col,
+                        &self.csv_table_state,
+                    ),
+                    _ => None,
+                };
+                new_cols_offset = min(new_cols_offset, max_cols_offset.unwrap_or(last_col));
+                if new_cols_offset > self.csv_table_state.cols_offset {
+                    self.rows_view.set_cols_offset(new_cols_offset);
                 }
             }
             Control::ScrollLeftMost => {
                 self.rows_view.set_cols_offset(0);
             }
-            Control::ScrollRightMost => {
-                if self.csv_table_state.has_more_cols_to_show() {
-                    let new_cols_offset = match self.frame_width {
-                        Some(frame_width) => get_cols_offset_to_fill_frame_width(
-                            frame_width,
-                            self.rows_view.headers().len().saturating_sub(1) as u64,
-                            &self.csv_table_state,
-                        ),
-                        _ => Some(0),
-                    };
-                    if let Some(new_cols_offset) = new_cols_offset {
-                        self.rows_view.set_cols_offset(new_cols_offset);
-                    }
+            Control::ScrollRightMost if self.csv_table_state.has_more_cols_to_show() => {
+                let new_cols_offset = match self.frame_width {
+                    Some(frame_width) => get_cols_offset_to_fill_frame_width(
+                        frame_width,
+                        self.rows_view.headers().len().saturating_sub(1) as u64,
+                        &self.csv_table_state,
+                    ),
+                    _ => Some(0),
+                };
+                if let Some(new_cols_offset) = new_cols_offset {
+                    self.rows_view.set_cols_offset(new_cols_offset);
                 }
             }
             Control::ScrollToNextFound if !self.rows_view.is_filter() => {
@@ -396,6 +1118,9 @@ impl App {
                             &mut self.csv_table_state,
                         );
                     }
+                    if fdr.wrapped() {
+                        self.set_message("Search wrapped to the top".to_string());
+                    }
                 }
             }
             Control::ScrollToPrevFound if !self.rows_view.is_filter() => {
@@ -407,37 +1132,69 @@ impl App {
                             &mut self.csv_table_state,
                         );
                     }
+                    if fdr.wrapped() {
+                        self.set_message("Search wrapped to the bottom".to_string());
+                    }
                 }
             }
             Control::Find(s) | Control::Filter(s) => {
+                self.filter_stack.clear();
                 self.handle_find_or_filter(s, matches!(control, Control::Filter(_)), false);
+                self.remember_find_or_filter();
             }
             Control::FindLikeCell | Control::FilterLikeCell => {
                 if let Some(value) = self.rows_view.get_cell_value_from_selection() {
+                    self.filter_stack.clear();
                     self.handle_find_or_filter(
-                        value.as_str(),
+                        &value,
                         matches!(control, Control::FilterLikeCell),
                         true,
                     );
+                    self.remember_find_or_filter();
                 } else {
-                    self.transient_message.replace(
+                    self.set_message(
                         "Select a cell first before finding (#) or filtering (@) rows like it"
                             .to_string(),
                     );
                 }
             }
+            Control::FilterWithin(s) => {
+                self.filter_within(s);
+                self.remember_find_or_filter();
+            }
+            Control::FilterDuplicates => {
+                self.filter_duplicates();
+            }
             Control::FilterColumns(pat) => {
                 self.set_columns_filter(pat);
             }
+            Control::ExportMatches(path) => {
+                self.csv_table_state.reset_buffer();
+                self.export_matches(path);
+            }
             Control::BufferContent(input) => {
-                self.csv_table_state
-                    .set_buffer(self.input_handler.mode(), input.clone());
+                let mode = self.input_handler.mode();
+                if mode == InputMode::Find {
+                    self.find_preview = Some((input.value().to_string(), Instant::now()));
+                }
+                self.csv_table_state.set_buffer(mode, input.clone());
+                if mode == InputMode::FilterColumns && !input.value().is_empty() {
+                    self.csv_table_state.buffer_hint =
+                        Some(self.columns_filter_preview(input.value()));
+                }
             }
             Control::BufferReset => {
                 self.csv_table_state.reset_buffer();
-                self.reset_filter();
+                if self.filter_stack.is_empty() {
+                    self.reset_filter();
+                } else {
+                    self.pop_filter();
+                }
                 self.reset_columns_filter();
             }
+            Control::BufferCancel => {
+                self.csv_table_state.reset_buffer();
+            }
             Control::ToggleSelectionType => {
                 self.rows_view.selection.toggle_selection_type();
             }
@@ -446,98 +1203,475 @@ impl App {
                 self.line_wrap_state.toggle(*word_wrap);
                 self.csv_table_state.enable_line_wrap = self.line_wrap_state.enable_line_wrap;
                 self.csv_table_state.is_word_wrap = self.line_wrap_state.is_word_wrap;
-                self.transient_message
-                    .replace(self.line_wrap_state.transient_message());
+                self.set_message(self.line_wrap_state.transient_message());
             }
-            Control::ToggleSort => {
-                if let Some(selected_column_index) = self.get_global_selected_column_index() {
-                    let mut should_create_new_sorter = false;
-                    if let Some(column_index) = self.sorter.as_ref().map(|s| s.column_index) {
-                        if selected_column_index as usize != column_index {
-                            should_create_new_sorter = true;
-                        } else {
-                            match self.sort_order {
-                                SortOrder::Ascending => {
-                                    self.sort_order = SortOrder::Descending;
-                                }
-                                SortOrder::Descending => {
-                                    self.sort_order = SortOrder::Ascending;
-                                }
-                            }
-                            self.rows_view.set_sort_order(self.sort_order)?;
-                        }
-                    } else {
-                        should_create_new_sorter = true;
-                    }
-                    if should_create_new_sorter {
-                        let column_name = self
-                            .rows_view
-                            .get_column_name_from_global_index(selected_column_index as usize);
-                        let _sorter = sort::Sorter::new(
-                            self.shared_config.clone(),
-                            selected_column_index as usize,
-                            column_name,
-                        );
-                        self.sorter = Some(Arc::new(_sorter));
-                    }
-                } else {
-                    self.transient_message
-                        .replace("Press TAB and select a column before sorting".to_string());
-                }
+            Control::ToggleColumnWrap => {
+                self.csv_table_state.reset_buffer();
+                self.toggle_column_wrap();
             }
-            Control::IncreaseWidth => {
-                self.adjust_column_width(4);
+            Control::ToggleCollapseColumn => {
+                self.csv_table_state.reset_buffer();
+                self.toggle_collapse_column();
             }
-            Control::DecreaseWidth => {
-                self.adjust_column_width(-4);
+            Control::ToggleShowWhitespace => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_whitespace = !self.csv_table_state.show_whitespace;
+                let message = if self.csv_table_state.show_whitespace {
+                    "Showing whitespace and control characters"
+                } else {
+                    "Hiding whitespace and control characters"
+                };
+                self.set_message(message.to_string());
             }
-            #[cfg(feature = "clipboard")]
-            Control::CopySelection => {
-                if let Some(selected) = self.rows_view.get_cell_value_from_selection() {
-                    match self.clipboard.as_mut().map(|c| c.set_text(&selected)) {
-                        Ok(_) => self
-                            .transient_message
-                            .replace(format!("Copied {} to clipboard", selected.as_str())),
-                        Err(e) => self
-                            .transient_message
-                            .replace(format!("Failed to copy to clipboard: {e}")),
-                    };
-                } else if let Some((index, row)) = self.rows_view.get_row_value() {
-                    match self.clipboard.as_mut().map(|c| c.set_text(&row)) {
-                        Ok(_) => self
-                            .transient_message
-                            .replace(format!("Copied row {} to clipboard", index)),
-                        Err(e) => self
-                            .transient_message
-                            .replace(format!("Failed to copy to clipboard: {e}")),
-                    };
-                }
+            Control::ToggleEmptyPlaceholder => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_empty_placeholder =
+                    !self.csv_table_state.show_empty_placeholder;
+                let message = if self.csv_table_state.show_empty_placeholder {
+                    format!(
+                        "Showing empty cells as {}",
+                        self.csv_table_state.empty_placeholder
+                    )
+                } else {
+                    "Showing empty cells as blank".to_string()
+                };
+                self.set_message(message);
             }
-            Control::Reset => {
-                self.csv_table_state.column_width_overrides.reset();
-                self.reset_filter();
-                self.reset_columns_filter();
-                self.reset_sorter();
+            Control::ToggleCompact => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.compact = !self.csv_table_state.compact;
+                let message = if self.csv_table_state.compact {
+                    "Compact mode enabled"
+                } else {
+                    "Compact mode disabled"
+                };
+                self.set_message(message.to_string());
             }
-            Control::UnknownOption(s) => {
+            Control::ToggleIgnoreCase => {
                 self.csv_table_state.reset_buffer();
-                self.transient_message
-                    .replace(format!("Unknown option: {s}"));
+                self.ignore_case = !self.ignore_case;
+                self.csv_table_state.ignore_case = self.ignore_case;
+                // Sorted again so that the order follows the new case sensitivity
+                if let Some(column_index) = self.sorter.as_ref().and_then(|s| s.column_index) {
+                    self.sorter = Some(self.new_sorter(Some(column_index)));
+                }
+                let message = if self.ignore_case {
+                    "Ignoring case in sort, find and filter"
+                } else {
+                    "Matching case in sort, find and filter"
+                };
+                self.set_message(message.to_string());
             }
-            _ => {}
-        }
-
-        if let Some(sorter) = &self.sorter {
-            // Update rows_view sorter if outdated
-            let mut should_set_rows_view_sorter = false;
-            if sorter.status() == SorterStatus::Finished {
-                if let Some(rows_view_sorter) = self.rows_view.sorter() {
-                    // Sorter can be reused by rows view even if sort order is different.
-                    if rows_view_sorter.column_index != sorter.column_index {
-                        should_set_rows_view_sorter = true;
-                    }
+            Control::ToggleIgnoreAccents => {
+                self.csv_table_state.reset_buffer();
+                self.ignore_accents = !self.ignore_accents;
+                self.csv_table_state.ignore_accents = self.ignore_accents;
+                let message = if self.ignore_accents {
+                    "Ignoring accents in the next find or filter"
                 } else {
-                    should_set_rows_view_sorter = true;
+                    "Matching accents in the next find or filter"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleWrapSearch => {
+                self.wrap_search = !self.wrap_search;
+                if let Some(fdr) = self.finder.as_mut() {
+                    fdr.set_wrap_around(self.wrap_search);
+                }
+                let message = if self.wrap_search {
+                    "Search wraps around at the first and last match"
+                } else {
+                    "Search stops at the first and last match"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ReverseOrder => {
+                self.reverse_order()?;
+            }
+            Control::ToggleSortNullsLast => {
+                self.sort_nulls_last = !self.sort_nulls_last;
+                if let Some(column_index) = self.sorter.as_ref().and_then(|s| s.column_index) {
+                    self.sorter = Some(self.new_sorter(Some(column_index)));
+                }
+                let message = if self.sort_nulls_last {
+                    "Sorting empty values last"
+                } else {
+                    "Sorting empty values first when ascending"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::IncreaseFilterContext | Control::DecreaseFilterContext => {
+                self.csv_table_state.reset_buffer();
+                let filter_context = if matches!(control, Control::IncreaseFilterContext) {
+                    self.rows_view.filter_context().saturating_add(1)
+                } else {
+                    self.rows_view.filter_context().saturating_sub(1)
+                };
+                self.set_filter_context(filter_context)?;
+                self.set_message(format!(
+                    "Showing {filter_context} rows of context around filtered matches"
+                ));
+            }
+            Control::IncreaseMaxRowHeight | Control::DecreaseMaxRowHeight => {
+                self.csv_table_state.reset_buffer();
+                let tallest = self.csv_table_state.tallest_row_height;
+                let max_row_height = match (control, self.csv_table_state.max_row_height) {
+                    (Control::IncreaseMaxRowHeight, Some(n)) if n.saturating_add(1) < tallest => {
+                        Some(n + 1)
+                    }
+                    (Control::IncreaseMaxRowHeight, _) => None,
+                    // Starting from the tallest row shown if there is no limit yet
+                    (_, n) => Some(n.unwrap_or(tallest).saturating_sub(1).max(1)),
+                };
+                self.csv_table_state.max_row_height = max_row_height;
+                let message = match max_row_height {
+                    Some(n) => format!("Showing at most {n} lines of each wrapped row"),
+                    None => "Showing wrapped rows in full".to_string(),
+                };
+                self.set_message(message);
+            }
+            Control::ToggleSearchVisibleColumns => {
+                self.search_visible_columns = !self.search_visible_columns;
+                let message = if self.search_visible_columns {
+                    "Searching only visible columns in the next find or filter"
+                } else {
+                    "Searching all columns in the next find or filter"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleScrollbars => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_scrollbars = !self.csv_table_state.show_scrollbars;
+                let message = if self.csv_table_state.show_scrollbars {
+                    "Scrollbars enabled"
+                } else {
+                    "Scrollbars disabled"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleRowNumbers => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_row_numbers = !self.csv_table_state.show_row_numbers;
+                let message = if self.csv_table_state.show_row_numbers {
+                    "Row numbers shown"
+                } else {
+                    "Row numbers hidden"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleZeroBasedRowNumbers => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.zero_based_row_numbers =
+                    !self.csv_table_state.zero_based_row_numbers;
+                let message = if self.csv_table_state.zero_based_row_numbers {
+                    "Rows numbered from 0"
+                } else {
+                    "Rows numbered from 1"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleCrosshair => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_crosshair = !self.csv_table_state.show_crosshair;
+                let message = if self.csv_table_state.show_crosshair {
+                    "Crosshair enabled"
+                } else {
+                    "Crosshair disabled"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleColumnFocus => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.focus_column = !self.csv_table_state.focus_column;
+                let message = if !self.csv_table_state.focus_column {
+                    "Column focus disabled"
+                } else if self.rows_view.selection.column.index().is_some() {
+                    "Dimming columns other than the selected one"
+                } else {
+                    "Select a column first to focus on it"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleHighlightAllColumns => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.highlight_all_columns =
+                    !self.csv_table_state.highlight_all_columns;
+                let message = if self.csv_table_state.highlight_all_columns {
+                    "Highlighting matches in all columns"
+                } else {
+                    "Highlighting matches only in the searched column"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleTextStats => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_text_stats = !self.csv_table_state.show_text_stats;
+                let message = if self.csv_table_state.show_text_stats {
+                    "Showing word and character counts"
+                } else {
+                    "Hiding word and character counts"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleByteOffset => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_byte_offset = !self.csv_table_state.show_byte_offset;
+                let message = if self.csv_table_state.show_byte_offset {
+                    "Showing byte offset of the top row"
+                } else {
+                    "Hiding byte offset"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::GotoByte(_) => {
+                self.csv_table_state.reset_buffer();
+                if self.rows_view.is_filter() || self.sorter.is_some() {
+                    self.set_message(
+                        "Going to a byte offset needs the rows unfiltered and unsorted".to_string(),
+                    );
+                }
+            }
+            Control::ToggleFooter => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_footer = !self.csv_table_state.show_footer;
+                let message = if self.csv_table_state.show_footer {
+                    "Footer enabled"
+                } else {
+                    // Stop aggregating in the background when no longer shown
+                    self.aggregator = None;
+                    "Footer disabled"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::ToggleColumnTypes => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_column_types = !self.csv_table_state.show_column_types;
+                let message = if !self.csv_table_state.show_column_types {
+                    "Column types hidden".to_string()
+                } else if self.csv_table_state.column_types.is_some() {
+                    "Showing column types".to_string()
+                } else {
+                    match schema::column_types(&self.shared_config) {
+                        Ok(column_types) => {
+                            self.csv_table_state.column_types = Some(column_types);
+                            "Showing column types inferred from the first rows".to_string()
+                        }
+                        Err(e) => {
+                            self.csv_table_state.show_column_types = false;
+                            format!("Could not infer column types: {e}")
+                        }
+                    }
+                };
+                self.set_message(message);
+            }
+            Control::ToggleColumnLetters => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.show_column_letters =
+                    !self.csv_table_state.show_column_letters;
+                let message = if self.csv_table_state.show_column_letters {
+                    "Showing column letters"
+                } else {
+                    "Column letters hidden"
+                };
+                self.set_message(message.to_string());
+            }
+            Control::GotoColumn(column) => {
+                self.csv_table_state.reset_buffer();
+                self.goto_column(column);
+            }
+            Control::ToggleMatchMode => {
+                self.csv_table_state.reset_buffer();
+                self.csv_table_state.match_mode = self.csv_table_state.match_mode.next();
+                self.set_message(format!(
+                    "Find and filter match mode: {}",
+                    self.csv_table_state.match_mode.description()
+                ));
+            }
+            Control::ToggleSort => {
+                self.toggle_sort()?;
+            }
+            Control::NextRaggedRow => {
+                self.jump_to_ragged_row(true)?;
+            }
+            Control::PrevRaggedRow => {
+                self.jump_to_ragged_row(false)?;
+            }
+            Control::NextEmptyInColumn => {
+                self.jump_to_empty_cell(true)?;
+            }
+            Control::PrevEmptyInColumn => {
+                self.jump_to_empty_cell(false)?;
+            }
+            Control::NextGroup => {
+                self.jump_to_group(true)?;
+            }
+            Control::PrevGroup => {
+                self.jump_to_group(false)?;
+            }
+            Control::Validate => {
+                // Reuse the previous scan unless it failed, the file does not change underneath
+                if !matches!(
+                    self.validator.as_ref().map(|v| v.status()),
+                    Some(ValidatorStatus::Running | ValidatorStatus::Finished)
+                ) {
+                    self.validator = Some(Validator::new(self.shared_config.clone()));
+                }
+                self.validation_page_state.activate();
+                self.input_handler.enter_validation_mode();
+            }
+            Control::ShowMessages => {
+                self.message_log_page_state.activate();
+                self.input_handler.enter_message_log_mode();
+            }
+            Control::CommandPalette => {
+                self.command_palette_state.activate();
+                self.input_handler.enter_command_palette_mode();
+            }
+            Control::CountDistinct => {
+                // Reuse the previous count unless it failed or was cancelled
+                if !matches!(
+                    self.duplicate_counter.as_ref().map(|c| c.status()),
+                    Some(DuplicateCounterStatus::Running | DuplicateCounterStatus::Finished)
+                ) {
+                    self.duplicate_counter =
+                        Some(DuplicateCounter::new(self.shared_config.clone()));
+                }
+                self.show_distinct_count = true;
+            }
+            Control::Cancel => {
+                self.cancel_background_tasks();
+            }
+            Control::MouseClick(x, y) => match self.csv_table_state.table_position_at(*x, *y) {
+                Some(TablePosition::Cell(row_index, column_index)) => {
+                    self.rows_view.selection.row.set_index(row_index);
+                    self.rows_view.selection.column.set_index(column_index);
+                }
+                Some(TablePosition::Header(column_index)) => {
+                    self.rows_view.selection.column.set_index(column_index);
+                    self.toggle_sort()?;
+                }
+                None => {}
+            },
+            Control::SetMark(c) => {
+                self.csv_table_state.reset_buffer();
+                if let Some(record_num) = self.rows_view.selected_record_num() {
+                    self.marks.insert(*c, record_num as u64);
+                    let row_number = self.csv_table_state.row_number(record_num);
+                    self.set_message(format!("Marked row {row_number} as '{c}'"));
+                } else {
+                    self.set_message("Select a row first before marking it".to_string());
+                }
+            }
+            Control::JumpToMark(c) => {
+                self.csv_table_state.reset_buffer();
+                self.jump_to_mark(*c)?;
+            }
+            Control::SelectRange => {
+                if self.range_anchor.take().is_some() {
+                    self.set_message("Range selection cleared".to_string());
+                } else if let Some(selected) = self.rows_view.selection.row.index() {
+                    self.range_anchor = Some(self.rows_view.rows_from().saturating_add(selected));
+                    self.set_message("Range selection started, move to extend it".to_string());
+                } else {
+                    self.set_message("Select a row first to start a range from it".to_string());
+                }
+            }
+            Control::RecordMacro(c) => {
+                self.csv_table_state.reset_buffer();
+                self.macro_recording = Some((*c, vec![]));
+                self.csv_table_state.recording_macro = Some(*c);
+            }
+            Control::StopRecordingMacro => {
+                if let Some((c, controls)) = self.macro_recording.take() {
+                    if self.plays_macro(&controls, c, &mut HashSet::new()) {
+                        self.set_message(format!("Macro '{c}' not recorded, it would play itself"));
+                    } else {
+                        self.set_message(format!(
+                            "Recorded macro '{c}' of {} commands",
+                            controls.len()
+                        ));
+                        self.macros.insert(c, controls);
+                    }
+                }
+                self.csv_table_state.recording_macro = None;
+            }
+            Control::PlayMacro(c) => {
+                self.csv_table_state.reset_buffer();
+                self.play_macro(*c);
+            }
+            Control::IncreaseWidth => {
+                self.adjust_column_width(4);
+            }
+            Control::DecreaseWidth => {
+                self.adjust_column_width(-4);
+            }
+            Control::CopySelection => {
+                if let Some((text, message)) = self.selection_to_copy() {
+                    self.copy_to_clipboard(&text, message);
+                }
+            }
+            Control::CopyColumnName => {
+                if let Some(column_name) = self.rows_view.get_column_name_from_selection() {
+                    let message = format!("Copied column name {column_name} to clipboard");
+                    self.copy_to_clipboard(&column_name, message);
+                } else {
+                    self.set_message("Select a column first to copy its name".to_string());
+                }
+            }
+            Control::CopyFilePath => {
+                self.copy_file_path();
+            }
+            Control::Reset => {
+                self.range_anchor = None;
+                self.csv_table_state.column_width_overrides.reset();
+                self.csv_table_state.wrapped_columns.clear();
+                self.csv_table_state.collapsed_column = None;
+                self.reset_filter();
+                self.reset_columns_filter();
+                self.reset_sorter();
+            }
+            Control::Undo => {
+                self.undo()?;
+            }
+            Control::Redo => {
+                self.redo()?;
+            }
+            Control::RepeatLastCommand => match self.last_command.clone() {
+                Some(RepeatableCommand::FindOrFilter(finder)) => self.restore_finder(finder),
+                Some(RepeatableCommand::ToggleSort(column_index)) => {
+                    self.toggle_sort_by(column_index)?;
+                }
+                None => {
+                    self.set_message("No find, filter or sort to repeat yet".to_string());
+                }
+            },
+            Control::UnknownOption(s) => {
+                self.csv_table_state.reset_buffer();
+                self.set_message(format!("Unknown option: {s}"));
+            }
+            _ => {}
+        }
+
+        if let Some(view_before) = view_before {
+            if view_before != self.view_snapshot() {
+                self.undo_stack.push(view_before);
+            }
+        }
+
+        if let Some(sorter) = &self.sorter {
+            // Update rows_view sorter if outdated
+            let mut should_set_rows_view_sorter = false;
+            if sorter.status() == SorterStatus::Finished {
+                if let Some(rows_view_sorter) = self.rows_view.sorter() {
+                    // Sorter can be reused by rows view even if sort order is different.
+                    if rows_view_sorter.column_index != sorter.column_index
+                        || rows_view_sorter.nulls_last() != sorter.nulls_last()
+                        || rows_view_sorter.ignore_case() != sorter.ignore_case()
+                    {
+                        should_set_rows_view_sorter = true;
+                    }
+                } else {
+                    should_set_rows_view_sorter = true;
                 }
             }
             if should_set_rows_view_sorter {
@@ -552,6 +1686,8 @@ impl App {
                         // Internal state of finder needs to be rebuilt if sorter is different,
                         // including sort order.
                         if finder_sorter.column_index != sorter.column_index
+                            || finder_sorter.nulls_last() != sorter.nulls_last()
+                            || finder_sorter.ignore_case() != sorter.ignore_case()
                             || finder.sort_order != self.sort_order
                         {
                             should_create_new_finder = true;
@@ -569,6 +1705,7 @@ impl App {
                     // selected column intended for sorter
                     self.create_finder_with_column_index(
                         target,
+                        finder.duplicates(),
                         self.rows_view.is_filter(),
                         finder.column_index(),
                         sorter,
@@ -579,6 +1716,18 @@ impl App {
             }
         }
 
+        if self.row_order() != row_order_before {
+            // The range is made of positions in the view, which now hold other rows
+            self.range_anchor = None;
+            if self.pending_record.is_none() {
+                self.pending_record = record_before;
+            }
+        }
+        // Before the filter is set below, so that the rows shown are the ones scrolled to
+        self.apply_pending_record()?;
+
+        self.update_find_preview();
+
         if let Some(fdr) = self.finder.as_mut() {
             if !self.rows_view.is_filter() {
                 // scroll to first result once ready
@@ -627,7 +1776,15 @@ impl App {
         self.csv_table_state
             .set_cols_offset(self.rows_view.cols_offset());
         self.csv_table_state.selection = Some(self.rows_view.selection.clone());
+        self.notify_selection_change();
 
+        self.csv_table_state.indexing_progress = self.rows_view.get_indexing_progress();
+        if !self.source_error_shown {
+            if let Some(error) = self.shared_config.source().error() {
+                self.source_error_shown = true;
+                self.set_message(error);
+            }
+        }
         if let Some(n) = self.rows_view.get_total_line_numbers() {
             self.csv_table_state.set_total_line_number(n, false);
         } else if let Some(n) = self.rows_view.get_total_line_numbers_approx() {
@@ -635,16 +1792,43 @@ impl App {
         }
         self.csv_table_state
             .set_total_cols(self.rows_view.headers().len());
+        self.csv_table_state.top_byte_offset = if self.csv_table_state.show_byte_offset {
+            self.rows_view.top_byte_offset()?
+        } else {
+            None
+        };
+        self.csv_table_state.selected_range = self.selected_range();
+        self.csv_table_state.range_summary = self.range_summary();
 
-        if let Some(f) = &self.finder {
+        if let Some(f) = &self.preview_finder {
+            self.csv_table_state.finder_state = FinderState::from_preview(f, &self.rows_view);
+        } else if let Some(f) = &self.finder {
             // TODO: need to create a new finder every time?
             self.csv_table_state.finder_state = FinderState::from_finder(f, &self.rows_view);
+        } else {
+            self.csv_table_state.finder_state = FinderState::FinderInactive;
         }
         self.csv_table_state.filter_columns_state =
             FilterColumnsState::from_rows_view(&self.rows_view);
+        self.csv_table_state.context_rows = self.rows_view.context_rows();
 
         self.csv_table_state
             .update_sorter(&self.sorter, self.sort_order);
+        self.csv_table_state.validation_status = self.validator.as_ref().map(|v| v.status_line());
+        if let (true, Some(counter)) = (self.show_distinct_count, &self.duplicate_counter) {
+            let message = counter.message();
+            self.message_log.update(&message);
+            self.transient_message = Some(message);
+        }
+
+        if self.csv_table_state.show_footer && self.aggregator.is_none() {
+            self.create_aggregator();
+        }
+        self.csv_table_state.footer = self
+            .aggregator
+            .as_ref()
+            .and_then(|aggregator| aggregator.results())
+            .map(|results| results.iter().map(|r| r.to_string()).collect());
 
         self.csv_table_state
             .transient_message
@@ -657,20 +1841,59 @@ impl App {
         Ok(())
     }
 
-    fn get_selection(&self) -> Option<String> {
-        if let Some(result) = self.rows_view.get_cell_value_from_selection() {
-            return Some(result);
-        } else if let Some(column_name) = &self.echo_column {
-            if let Some(result) = self.rows_view.get_cell_value(column_name) {
-                return Some(result);
+    fn notify_selection_change(&mut self) {
+        if let Some(callback) = self.on_selection_change.as_mut() {
+            let event = self.rows_view.selection_event();
+            if self.last_selection_event.as_ref() != Some(&event) {
+                self.last_selection_event = Some(event.clone());
+                (callback.0)(event);
             }
+        }
+    }
+
+    fn get_selection(&self) -> Option<String> {
+        if self.output_granularity == OutputGranularity::Row
+            && matches!(
+                self.rows_view.selection.selection_type(),
+                view::SelectionType::Row
+            )
+        {
+            let row = self.rows_view.selected_row()?;
+            let output = RowOutput {
+                record_num: row.record_num,
+                headers: self.rows_view.headers(),
+                fields: &row.fields,
+            };
+            return Some(output.format(self.output_mode, self.shared_config.delimiter()));
+        }
+        let (value, column_name) =
+            if let Some(value) = self.rows_view.get_cell_value_from_selection() {
+                (value, self.rows_view.get_column_name_from_selection())
+            } else if !self.echo_columns.is_empty() {
+                let values = self
+                    .echo_columns
+                    .iter()
+                    .map(|column_name| self.rows_view.get_cell_value(column_name))
+                    .collect::<Option<Vec<String>>>()?;
+                (values.join("\t"), Some(self.echo_columns.join(",")))
+            } else {
+                return None;
+            };
+        let selected_row = self.rows_view.selected_row();
+        let output = SelectionOutput {
+            value,
+            record_num: selected_row.map(|row| row.record_num),
+            column_name,
+            row: selected_row
+                .map(|row| (self.rows_view.headers().as_slice(), row.fields.as_slice())),
         };
-        None
+        Some(output.format(self.output_mode))
     }
 
     fn create_finder(&mut self, target: Regex, is_filter: bool, sorter: Option<Arc<sort::Sorter>>) {
         self.create_finder_with_column_index(
             target,
+            false,
             is_filter,
             self.get_selected_column_index().map(|x| x as usize),
             sorter,
@@ -680,21 +1903,28 @@ impl App {
     fn create_finder_with_column_index(
         &mut self,
         target: Regex,
+        duplicates: bool,
         is_filter: bool,
         column_index: Option<usize>,
         sorter: Option<Arc<sort::Sorter>>,
     ) {
-        let _finder = find::Finder::new(
-            self.shared_config.clone(),
-            target,
-            column_index,
-            sorter,
-            self.sort_order,
-            self.columns_filter.clone(),
-        )
-        .unwrap();
+        let conditions = if is_filter {
+            self.filter_stack.clone()
+        } else {
+            vec![]
+        };
+        let mut _finder = self.new_finder(target, duplicates, column_index, conditions, sorter);
+        _finder.set_wrap_around(self.wrap_search);
         self.finder = Some(_finder);
+        self.aggregator = None;
         if is_filter {
+            // Filtering within a filter keeps where the view was before the first one
+            if !self.rows_view.is_filter() {
+                self.filter_origin = self
+                    .rows_view
+                    .selected_record_num()
+                    .or_else(|| self.rows_view.rows().first().map(|row| row.record_num));
+            }
             self.rows_view.set_rows_from(0).unwrap();
             self.rows_view
                 .set_filter(self.finder.as_ref().unwrap())
@@ -702,6 +1932,7 @@ impl App {
         } else {
             // will scroll to first result below once ready
             self.first_found_scrolled = false;
+            self.filter_origin = None;
             self.rows_view.reset_filter().unwrap();
         }
     }
@@ -726,331 +1957,4720 @@ impl App {
             let columns_filter = Arc::new(ColumnsFilter::new(target, self.rows_view.raw_headers()));
             self.columns_filter = Some(columns_filter.clone());
             self.rows_view.set_columns_filter(&columns_filter).unwrap();
+            self.aggregator = None;
         } else {
             self.reset_columns_filter();
-            self.transient_message = Some(format!("Invalid regex: {pat}"));
+            self.set_message(format!("Invalid regex: {pat}"));
         }
         self.csv_table_state.reset_buffer();
         self.csv_table_state.set_cols_offset(0);
     }
 
+    /// Show only the columns with the given names, in that order
+    fn set_columns_filter_by_names(&mut self, names: &[String]) {
+        let columns_filter = Arc::new(ColumnsFilter::from_names(
+            names,
+            self.rows_view.raw_headers(),
+        ));
+        if !columns_filter.missing_names().is_empty() {
+            self.set_message(format!(
+                "Column names not found: {}",
+                columns_filter.missing_names().join(", ")
+            ));
+        }
+        self.columns_filter = Some(columns_filter.clone());
+        self.rows_view.set_columns_filter(&columns_filter).unwrap();
+        self.aggregator = None;
+        self.csv_table_state.set_cols_offset(0);
+    }
+
+    /// How many columns the columns regex being typed would keep, without applying it
+    fn columns_filter_preview(&mut self, pat: &str) -> String {
+        match self.create_regex(pat, false) {
+            Ok(target) => {
+                let columns_filter = ColumnsFilter::new(target, self.rows_view.raw_headers());
+                format!(
+                    "{}/{} cols",
+                    columns_filter.num_matched(),
+                    columns_filter.num_original()
+                )
+            }
+            Err(_) => "invalid".to_string(),
+        }
+    }
+
     fn reset_columns_filter(&mut self) {
         self.columns_filter = None;
         self.rows_view.reset_columns_filter().unwrap();
+        self.aggregator = None;
     }
 
-    fn handle_find_or_filter(&mut self, pat: &str, is_filter: bool, escape: bool) {
-        let re = self.create_regex(pat, escape);
-        if let Ok(target) = re {
-            let _sorter = if let Some(s) = &self.sorter {
-                if s.status() == SorterStatus::Finished {
-                    Some(s.clone())
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
-            self.create_finder(target, is_filter, _sorter);
+    /// Regex to find or filter with, taking the accent and match mode options into account
+    fn create_find_regex(
+        &mut self,
+        pat: &str,
+        escape: bool,
+    ) -> std::result::Result<Regex, regex::Error> {
+        // Fields are stripped of diacritics too when matching
+        let pat = if self.ignore_accents {
+            normalize::strip_diacritics(pat)
         } else {
-            self.finder = None;
-            // TODO: how to show multi-line error
-            self.transient_message = Some(format!("Invalid regex: {pat}"));
+            Cow::Borrowed(pat)
+        };
+        let pat = pat.as_ref();
+        // Finding like a cell already matches the whole cell exactly
+        if escape {
+            self.create_regex(pat, escape)
+        } else {
+            let wrapped = self.csv_table_state.match_mode.wrap_pattern(pat);
+            self.create_regex(&wrapped, escape)
         }
-        self.csv_table_state.reset_buffer();
     }
 
-    fn increase_cols_offset(&mut self) {
-        if self.csv_table_state.has_more_cols_to_show() {
-            let new_cols_offset = self.rows_view.cols_offset().saturating_add(1);
-            self.rows_view.set_cols_offset(new_cols_offset);
-        }
+    fn finished_sorter(&self) -> Option<Arc<sort::Sorter>> {
+        self.sorter
+            .as_ref()
+            .filter(|s| s.status() == SorterStatus::Finished)
+            .cloned()
     }
 
-    fn decrease_cols_offset(&mut self) {
-        let new_cols_offset = self.rows_view.cols_offset().saturating_sub(1);
-        self.rows_view.set_cols_offset(new_cols_offset);
+    /// Remember the find or filter just run, unless it failed, to be repeated later
+    fn remember_find_or_filter(&mut self) {
+        if let Some(finder) = self.finder_snapshot() {
+            self.last_command = Some(RepeatableCommand::FindOrFilter(finder));
+        }
     }
 
-    fn adjust_column_width(&mut self, delta: i16) {
-        if let Some(column_index) = self.get_selected_column_index() {
-            if let Some(view_layout) = &mut self.csv_table_state.view_layout {
-                let current_width = view_layout.column_widths[column_index as usize];
-                let new_width = (current_width as i16).saturating_add(delta);
-
-                if new_width > 0 {
-                    let origin_index = self
-                        .rows_view
-                        .get_column_origin_index(column_index as usize);
-                    self.csv_table_state
-                        .column_width_overrides
-                        .set(origin_index, new_width as u16);
-                }
-            }
+    /// Filter the rows shown by the current filter further, keeping the current one as a condition
+    fn filter_within(&mut self, pat: &str) {
+        if let (true, Some(finder)) = (self.rows_view.is_filter(), &self.finder) {
+            self.filter_stack.push(finder.as_condition());
         }
+        self.handle_find_or_filter(pat, true, false);
     }
 
-    fn get_selected_column_index(&self) -> Option<u64> {
-        // local index as in local to the view port
-        if let Some(local_column_index) = self.rows_view.selection.column.index() {
-            return Some(local_column_index.saturating_add(self.csv_table_state.cols_offset));
-        }
-        None
+    /// Drop the current filter, going back to the one it was stacked on
+    fn pop_filter(&mut self) {
+        let Some(condition) = self.filter_stack.pop() else {
+            return;
+        };
+        let sorter = self.finished_sorter();
+        self.create_finder_with_column_index(
+            condition.target().clone(),
+            condition.duplicates(),
+            true,
+            condition.column_index(),
+            sorter,
+        );
     }
 
-    fn get_global_selected_column_index(&self) -> Option<u64> {
-        // TODO: maybe this and above should be methods provided by RowsView directly?
-        self.get_selected_column_index()
-            .map(|local_index| self.rows_view.get_column_origin_index(local_index as usize) as u64)
+    /// Filter to the rows with the same value in the selected column as another row, or with the
+    /// same values in all columns if no column is selected
+    fn filter_duplicates(&mut self) {
+        self.filter_stack.clear();
+        let sorter = self.finished_sorter();
+        // The target is not used when finding duplicates
+        self.create_finder_with_column_index(
+            Regex::new("").unwrap(),
+            true,
+            true,
+            self.get_selected_column_index().map(|x| x as usize),
+            sorter,
+        );
     }
 
-    fn reset_filter(&mut self) {
-        if self.finder.is_some() {
+    fn handle_find_or_filter(&mut self, pat: &str, is_filter: bool, escape: bool) {
+        if let Ok(target) = self.create_find_regex(pat, escape) {
+            let _sorter = self.finished_sorter();
+            self.create_finder(target, is_filter, _sorter);
+        } else {
             self.finder = None;
-            self.csv_table_state.finder_state = FinderState::FinderInactive;
-            self.rows_view.reset_filter().unwrap();
+            self.filter_stack.clear();
+            // TODO: how to show multi-line error
+            self.set_message(format!("Invalid regex: {pat}"));
         }
+        self.csv_table_state.reset_buffer();
     }
 
-    fn reset_sorter(&mut self) {
-        // TODO: consolidate rows_view reset
-        self.sorter = None;
-        self.rows_view.reset_sorter().unwrap();
+    /// Change the number of context rows, keeping the selected record in view if it is still shown
+    fn set_filter_context(&mut self, filter_context: u64) -> CsvlensResult<()> {
+        let selected_record_num = self.rows_view.selected_record_num();
+        self.rows_view.set_filter_context(filter_context);
+        if let (true, Some(fdr)) = (self.rows_view.is_filter(), &self.finder) {
+            self.rows_view.set_filter(fdr)?;
+            if let Some(record_num) = selected_record_num {
+                if !self.scroll_to_record(record_num as u64)? {
+                    self.rows_view.set_rows_from(0)?;
+                }
+            }
+        }
+        Ok(())
     }
 
-    fn render_frame(&mut self, f: &mut Frame) {
-        let size = f.area();
+    fn new_finder(
+        &self,
+        target: Regex,
+        duplicates: bool,
+        column_index: Option<usize>,
+        conditions: Vec<find::FilterCondition>,
+        sorter: Option<Arc<sort::Sorter>>,
+    ) -> find::Finder {
+        find::Finder::new(
+            self.shared_config.clone(),
+            target,
+            duplicates,
+            column_index,
+            self.get_visible_columns(),
+            self.ignore_accents,
+            conditions,
+            sorter,
+            self.sort_order,
+            self.columns_filter.clone(),
+            self.rows_view
+                .get_complete_pos_table()
+                .map(|pos_table| find::ParallelScan {
+                    pos_table,
+                    num_threads: self.find_threads,
+                }),
+        )
+        .unwrap()
+    }
 
-        // Render help; if so exit early.
-        if self.help_page_state.is_active() {
-            f.render_stateful_widget(help::HelpPage::new(), size, &mut self.help_page_state);
+    /// Highlight the matches of the pattern being typed in the find prompt, once typing has paused.
+    /// The preview has its own finder, so the current find or filter is left as it is.
+    fn update_find_preview(&mut self) {
+        if self.csv_table_state.buffer_mode() != Some(InputMode::Find) {
+            // Dropping the preview finder stops its search
+            self.find_preview = None;
+            self.preview_finder = None;
             return;
         }
-
-        // Render table
-        // TODO: check type of num_rows too big?
-        let num_rows_adjusted = size.height.saturating_sub(self.num_rows_not_visible) as u64;
-        if let Some(view_layout) = &self.csv_table_state.view_layout {
-            self.rows_view.set_num_rows_rendered(
-                view_layout.num_rows_renderable(num_rows_adjusted as u16) as u64,
-            );
+        let Some((pat, typed_at)) = &self.find_preview else {
+            return;
+        };
+        if typed_at.elapsed() < self.find_preview_delay {
+            return;
         }
-        self.rows_view.set_num_rows(num_rows_adjusted).unwrap();
-        self.frame_width = Some(size.width);
+        let pat = pat.clone();
+        self.find_preview = None;
+        self.preview_finder = None;
+        if pat.is_empty() {
+            return;
+        }
+        // Typing an incomplete regex is expected, so invalid ones are not reported here
+        if let Ok(target) = self.create_find_regex(&pat, false) {
+            // Only among the rows the current filter shows
+            let mut conditions = vec![];
+            if let (true, Some(finder)) = (self.rows_view.is_filter(), &self.finder) {
+                conditions = self.filter_stack.clone();
+                conditions.push(finder.as_condition());
+            }
+            let column_index = self.get_selected_column_index().map(|x| x as usize);
+            let sorter = self.finished_sorter();
+            self.preview_finder =
+                Some(self.new_finder(target, false, column_index, conditions, sorter));
+        }
+    }
 
-        let rows = self.rows_view.rows();
-        let csv_table = CsvTable::new(self.rows_view.headers(), rows);
-        f.render_stateful_widget(csv_table, size, &mut self.csv_table_state);
-        if let Some((x, y)) = self.csv_table_state.cursor_xy {
-            f.set_cursor_position(Position::new(x, y));
+    /// Queue the controls recorded as the macro to run one after another, as if their keys were
+    /// pressed. They go ahead of what is left of a macro playing it.
+    fn play_macro(&mut self, c: char) {
+        let Some(controls) = self.macros.get(&c) else {
+            self.set_message(format!("Macro '{c}' is not recorded"));
+            return;
+        };
+        for control in controls.iter().rev() {
+            self.macro_queue.push_front(control.clone());
         }
     }
 
-    fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> CsvlensResult<()> {
-        let start = Instant::now();
-        terminal.draw(|f| {
-            self.render_frame(f);
-        })?;
-        self.csv_table_state
-            .debug_stats
-            .render_elapsed(Some(start.elapsed()));
-        Ok(())
+    /// Run the queued controls of the macros being played for as long as finding and sorting are
+    /// done, so that each control sees the same view as when recorded. The rest is left for later
+    /// steps, leaving keys such as Esc to cancel it handled in the meantime.
+    fn play_queued_macro(&mut self) -> CsvlensResult<()> {
+        loop {
+            let sorting = self
+                .sorter
+                .as_ref()
+                .is_some_and(|sorter| sorter.status() == SorterStatus::Running);
+            let finding = self.finder.as_ref().is_some_and(|f| !f.done());
+            if sorting || finding {
+                return Ok(());
+            }
+            let Some(control) = self.macro_queue.pop_front() else {
+                return Ok(());
+            };
+            // Played controls are not recorded again, only the key playing them is
+            let recording = self.macro_recording.take();
+            let result = self
+                .step(&control)
+                .and_then(|_| self.step(&Control::Nothing));
+            self.macro_recording = recording;
+            result?;
+        }
     }
-}
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use ratatui::backend::TestBackend;
-    use ratatui::buffer::Buffer;
 
-    struct AppBuilder {
-        filename: String,
-        delimiter: Delimiter,
-        original_filename: Option<String>,
-        show_stats: bool,
-        echo_column: Option<String>,
-        ignore_case: bool,
-        no_headers: bool,
-        columns_regex: Option<String>,
-        filter_regex: Option<String>,
-        find_regex: Option<String>,
+    /// Whether the controls play the given macro, directly or through other macros
+    fn plays_macro(&self, controls: &[Control], c: char, seen: &mut HashSet<char>) -> bool {
+        controls.iter().any(|control| match control {
+            Control::PlayMacro(played) if *played == c => true,
+            Control::PlayMacro(played) if seen.insert(*played) => self
+                .macros
+                .get(played)
+                .is_some_and(|controls| self.plays_macro(controls, c, seen)),
+            _ => false,
+        })
     }
 
-    impl AppBuilder {
-        fn new(filename: &str) -> Self {
-            AppBuilder {
-                filename: filename.to_owned(),
-                delimiter: Delimiter::Default,
-                original_filename: None,
-                show_stats: false,
-                echo_column: None,
-                ignore_case: false,
-                no_headers: false,
-                columns_regex: None,
-                filter_regex: None,
-                find_regex: None,
+    fn jump_to_mark(&mut self, c: char) -> CsvlensResult<()> {
+        let record_num = match self.marks.get(&c) {
+            Some(record_num) => *record_num,
+            None => {
+                self.set_message(format!("Mark '{c}' is not set"));
+                return Ok(());
             }
-        }
+        };
 
-        fn build(self) -> CsvlensResult<App> {
-            App::new(
-                self.filename.as_str(),
-                self.delimiter,
-                self.original_filename,
-                self.show_stats,
-                self.echo_column,
-                self.ignore_case,
-                self.no_headers,
-                self.columns_regex,
-                self.filter_regex,
-                self.find_regex,
-            )
+        if !self.scroll_to_record(record_num)? {
+            let row_number = self.csv_table_state.row_number(record_num as usize);
+            self.set_message(format!("Row {row_number} marked as '{c}' is filtered out"));
         }
+        Ok(())
+    }
 
-        fn delimiter(mut self, delimiter: Delimiter) -> Self {
-            self.delimiter = delimiter;
-            self
-        }
+    /// Scroll to the given record number, taking any active filter or sorter into account.
+    /// Returns false if the record is not in the current view, e.g. filtered out.
+    fn scroll_to_record(&mut self, record_num: u64) -> CsvlensResult<bool> {
+        let record_index = record_num.saturating_sub(1);
+        let row_order = if self.rows_view.is_filter() {
+            self.rows_view
+                .get_position_with_context(record_index)
+                .or_else(|| {
+                    self.finder
+                        .as_ref()
+                        .and_then(|f| f.get_found_position(record_index as usize))
+                        .map(|x| x as u64)
+                })
+        } else if let Some(sorter) = self.rows_view.sorter() {
+            sorter.get_record_order(record_index, self.sort_order)
+        } else {
+            Some(record_index)
+        };
 
-        fn ignore_case(mut self, ignore_case: bool) -> Self {
-            self.ignore_case = ignore_case;
-            self
+        if let Some(row_order) = row_order {
+            self.rows_view.scroll_to_row_order(row_order)?;
+            Ok(true)
+        } else {
+            Ok(false)
         }
+    }
 
-        fn no_headers(mut self, no_headers: bool) -> Self {
-            self.no_headers = no_headers;
-            self
+    /// Jump to the next or previous row (in file order) with a different number of fields than the
+    /// header
+    fn jump_to_ragged_row(&mut self, forward: bool) -> CsvlensResult<()> {
+        let Some(current_record_num) = self
+            .rows_view
+            .selected_record_num()
+            .or_else(|| self.rows_view.rows().first().map(|row| row.record_num))
+        else {
+            return Ok(());
+        };
+        let current_record_index = current_record_num.saturating_sub(1) as u64;
+        let record_index = if forward {
+            self.rows_view.get_next_ragged_record(current_record_index)
+        } else {
+            self.rows_view.get_prev_ragged_record(current_record_index)
+        };
+        match record_index {
+            Some(record_index) => {
+                let record_num = record_index + 1;
+                if !self.scroll_to_record(record_num)? {
+                    let row_number = self.csv_table_state.row_number(record_num as usize);
+                    self.set_message(format!("Row {row_number} is filtered out"));
+                }
+            }
+            None => {
+                let direction = if forward { "below" } else { "above" };
+                let mut message = format!(
+                    "No rows {direction} with a different number of fields than the header"
+                );
+                if self.rows_view.get_total_line_numbers().is_none() {
+                    message += " (still indexing)";
+                }
+                self.set_message(message);
+            }
         }
+        Ok(())
+    }
 
-        fn columns_regex(mut self, columns: Option<String>) -> Self {
-            self.columns_regex = columns;
-            self
+    /// Jump to the next or previous row (in file order) with an empty value in the selected
+    /// column, wrapping around if find does
+    fn jump_to_empty_cell(&mut self, forward: bool) -> CsvlensResult<()> {
+        let (Some(origin_index), Some(column_name)) = (
+            self.get_global_selected_column_index(),
+            self.rows_view.get_column_name_from_selection(),
+        ) else {
+            self.set_message("Select a column first to jump to its empty cells".to_string());
+            return Ok(());
+        };
+        let Some(current_record_num) = self
+            .rows_view
+            .selected_record_num()
+            .or_else(|| self.rows_view.rows().first().map(|row| row.record_num))
+        else {
+            return Ok(());
+        };
+        let current = current_record_num.saturating_sub(1) as u64;
+        // Scanning to the end of the file does not need to know where it is
+        let end = self
+            .rows_view
+            .get_total_line_numbers()
+            .map_or(u64::MAX, |n| n as u64);
+        let column_index = origin_index as usize;
+
+        let (ahead, behind) = if forward {
+            (current.saturating_add(1)..end, 0..current)
+        } else {
+            (0..current, current.saturating_add(1)..end)
+        };
+        if self.scroll_to_empty_cell(ahead, forward, column_index)? {
+            return Ok(());
         }
-
-        fn filter_regex(mut self, filter: Option<String>) -> Self {
-            self.filter_regex = filter;
-            self
+        if self.wrap_search && self.scroll_to_empty_cell(behind, forward, column_index)? {
+            let message = if forward {
+                "Search wrapped to the top"
+            } else {
+                "Search wrapped to the bottom"
+            };
+            self.set_message(message.to_string());
+            return Ok(());
+        }
+        let direction = if forward { "below" } else { "above" };
+        let mut message = format!("No empty cells {direction} in column {column_name}");
+        if self.rows_view.get_total_line_numbers().is_none() {
+            message += " (still indexing)";
         }
+        self.set_message(message);
+        Ok(())
+    }
 
-        fn echo_column(mut self, column: &str) -> Self {
-            self.echo_column = Some(column.to_owned());
-            self
+    /// Scroll to the first (or last if not forward) record in the range with an empty value in
+    /// the column that is not filtered out. Returns whether there is one.
+    fn scroll_to_empty_cell(
+        &mut self,
+        records: Range<u64>,
+        forward: bool,
+        column_index: usize,
+    ) -> CsvlensResult<bool> {
+        const CHUNK_SIZE: u64 = 1000;
+        let is_empty = |row: &csv::Row| row.fields.get(column_index).is_none_or(|v| v.is_empty());
+        if forward {
+            let mut from = records.start;
+            while from < records.end {
+                let num_rows = min(CHUNK_SIZE, records.end - from);
+                let rows = self.rows_view.get_records(from, num_rows)?;
+                for row in rows.iter().filter(|row| is_empty(row)) {
+                    if self.scroll_to_record(row.record_num as u64)? {
+                        return Ok(true);
+                    }
+                }
+                if (rows.len() as u64) < num_rows {
+                    break;
+                }
+                from += num_rows;
+            }
+        } else {
+            // Without a known end, start from the last record indexed so far
+            let num_records = self
+                .rows_view
+                .get_total_line_numbers()
+                .or_else(|| self.rows_view.get_total_line_numbers_approx())
+                .map_or(0, |n| n as u64);
+            let mut to = min(records.end, num_records);
+            while to > records.start {
+                let from = max(records.start, to.saturating_sub(CHUNK_SIZE));
+                let rows = self.rows_view.get_records(from, to - from)?;
+                for row in rows.iter().rev().filter(|row| is_empty(row)) {
+                    if self.scroll_to_record(row.record_num as u64)? {
+                        return Ok(true);
+                    }
+                }
+                to = from;
+            }
         }
+        Ok(false)
+    }
+
+    /// Whether the runs of equal values in the collapsed column at the top and at the bottom of the
+    /// loaded rows go on beyond them, in the order rows are shown
+    fn collapsed_runs_continue(&mut self) -> CsvlensResult<(bool, bool)> {
+        let Some(column_index) = self.csv_table_state.collapsed_column else {
+            return Ok((false, false));
+        };
+        let finder = self.finder.as_ref();
+        let continues = |rows: Vec<csv::Row>| match &rows[..] {
+            [a, b] => a.fields.get(column_index) == b.fields.get(column_index),
+            _ => false,
+        };
+        let rows_from = self.rows_view.rows_from();
+        let rows_to = rows_from.saturating_add(self.rows_view.rows().len() as u64);
+        let continues_above = rows_from > 0
+            && continues(
+                self.rows_view
+                    .get_rows_in_view_order(finder, rows_from - 1, 2)?,
+            );
+        let continues_below = rows_to > rows_from
+            && continues(
+                self.rows_view
+                    .get_rows_in_view_order(finder, rows_to - 1, 2)?,
+            );
+        Ok((continues_above, continues_below))
     }
 
-    fn to_lines(buf: &Buffer) -> Vec<String> {
-        let mut symbols: String = "".to_owned();
-        let area = buf.area();
-        for y in 0..area.bottom() {
-            for x in 0..area.right() {
-                let symbol = buf[Position::new(x, y)].symbol();
-                symbols.push_str(symbol);
+    /// Jump to the first row of the next or previous run of equal values in the selected column,
+    /// in the order rows are shown
+    fn jump_to_group(&mut self, forward: bool) -> CsvlensResult<()> {
+        const CHUNK_SIZE: u64 = 1000;
+        let (Some(origin_index), Some(column_name), Some(selected)) = (
+            self.get_global_selected_column_index(),
+            self.rows_view.get_column_name_from_selection(),
+            self.rows_view.selection.row.index(),
+        ) else {
+            self.set_message("Select a column first to jump between its groups".to_string());
+            return Ok(());
+        };
+        let current = self.rows_view.rows_from().saturating_add(selected);
+        let column_index = origin_index as usize;
+        let finder = self.finder.as_ref();
+        let value_of = |row: &csv::Row| row.fields.get(column_index).cloned().unwrap_or_default();
+
+        let Some(current_value) = self
+            .rows_view
+            .get_rows_in_view_order(finder, current, 1)?
+            .first()
+            .map(value_of)
+        else {
+            return Ok(());
+        };
+
+        let mut found = None;
+        if forward {
+            let mut from = current.saturating_add(1);
+            'scan: loop {
+                let rows = self
+                    .rows_view
+                    .get_rows_in_view_order(finder, from, CHUNK_SIZE)?;
+                for (i, row) in rows.iter().enumerate() {
+                    if value_of(row) != current_value {
+                        found = Some(from + i as u64);
+                        break 'scan;
+                    }
+                }
+                if (rows.len() as u64) < CHUNK_SIZE {
+                    break;
+                }
+                from += CHUNK_SIZE;
             }
-            if y != area.bottom() - 1 {
-                symbols.push('\n');
+        } else {
+            // Find where the previous group ends, then keep going back to where it starts
+            let mut group_value: Option<String> = None;
+            let mut to = current;
+            'scan: while to > 0 {
+                let from = to.saturating_sub(CHUNK_SIZE);
+                let rows = self
+                    .rows_view
+                    .get_rows_in_view_order(finder, from, to - from)?;
+                for (i, row) in rows.iter().enumerate().rev() {
+                    let value = value_of(row);
+                    match &group_value {
+                        None if value != current_value => group_value = Some(value),
+                        Some(group_value) if value != *group_value => break 'scan,
+                        _ => {}
+                    }
+                    if group_value.is_some() {
+                        found = Some(from + i as u64);
+                    }
+                }
+                to = from;
             }
         }
 
-        symbols.split('\n').map(|s| s.to_string()).collect()
+        match found {
+            Some(row_order) => self.rows_view.scroll_to_row_order(row_order)?,
+            None => {
+                let direction = if forward { "below" } else { "above" };
+                self.set_message(format!(
+                    "No other values {direction} in column {column_name}"
+                ));
+            }
+        }
+        Ok(())
     }
 
-    fn step_and_draw<B: Backend>(app: &mut App, terminal: &mut Terminal<B>, control: Control) {
-        app.step(&control).unwrap();
+    fn close_command_palette(&mut self) {
+        self.command_palette_state.deactivate();
+        self.input_handler.exit_command_palette_mode();
+    }
 
-        // While it's possible to step multiple times before any draw when
-        // testing, App::render_frame() can update App's state (e.g. based on
-        // the current terminal frame size) with information that might be
-        // required for stepping to work correctly. Also, immediately drawing
-        // after each step is what App::main_loop() will be doing.
-        terminal.draw(|f| app.render_frame(f)).unwrap();
+    fn close_validation_page(&mut self) {
+        self.validation_page_state.deactivate();
+        self.input_handler.exit_validation_mode();
     }
 
-    fn till_app_ready(app: &App) {
-        app.rows_view.wait_internal();
-        if let Some(sorter) = &app.sorter {
-            sorter.wait_internal();
+    fn apply_initial_row(&mut self) -> CsvlensResult<()> {
+        let row = match self.pending_initial_row {
+            // Wait for the first frame so that the number of rows rendered is known
+            Some(row) if self.frame_width.is_some() => row,
+            _ => return Ok(()),
+        };
+        let target_row = if let Some(total) = self.rows_view.get_total_line_numbers() {
+            let total = total as u64;
+            if row > total {
+                self.set_message(format!(
+                    "Row {row} is out of range, showing row {total} instead"
+                ));
+            }
+            row.clamp(1, total.max(1))
+        } else if self
+            .rows_view
+            .get_total_line_numbers_approx()
+            .is_some_and(|n| n as u64 >= row)
+        {
+            row
+        } else {
+            // Not indexed far enough yet
+            return Ok(());
+        };
+        self.pending_initial_row = None;
+        self.rows_view
+            .scroll_to_row_order(target_row.saturating_sub(1))
+    }
+
+    /// Start in word wrap mode if a value in the first rows is wider than the whole terminal,
+    /// which is typical of files dominated by a long free-text column
+    fn apply_auto_wrap(&mut self) {
+        let frame_width = match self.frame_width {
+            // Wait for the first frame so that the terminal width and the first rows are known
+            Some(frame_width) if self.pending_auto_wrap && !self.rows_view.rows().is_empty() => {
+                frame_width
+            }
+            _ => return,
+        };
+        self.pending_auto_wrap = false;
+        if self.line_wrap_state.enable_line_wrap {
+            return;
         }
-        if let Some(finder) = &app.finder {
-            finder.wait_internal();
+        let widest = self
+            .rows_view
+            .rows()
+            .iter()
+            .flat_map(|row| row.fields.iter().zip(self.rows_view.headers()))
+            .map(|(field, header)| (Span::raw(field.as_str()).width(), header))
+            .max_by_key(|(width, _)| *width);
+        if let Some((width, header)) = widest {
+            if width > frame_width as usize {
+                let message = format!(
+                    "Word wrap enabled: column {} is wider than the terminal",
+                    header.name
+                );
+                self.line_wrap_state.toggle(true);
+                self.csv_table_state.enable_line_wrap = self.line_wrap_state.enable_line_wrap;
+                self.csv_table_state.is_word_wrap = self.line_wrap_state.is_word_wrap;
+                self.set_message(message);
+            }
         }
     }
 
-    #[test]
-    fn test_simple() {
-        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
-        till_app_ready(&app);
+    fn set_key_column(&mut self, column_name: &str) {
+        self.key_column = self
+            .rows_view
+            .raw_headers()
+            .iter()
+            .position(|name| name == column_name);
+        if self.key_column.is_none() {
+            self.set_message(format!("Key column not found: {column_name}"));
+        }
+    }
 
-        let backend = TestBackend::new(30, 10);
-        let mut terminal = Terminal::new(backend).unwrap();
+    fn row_order(&self) -> RowOrder {
+        RowOrder {
+            sort: self
+                .rows_view
+                .sorter()
+                .as_ref()
+                .map(|sorter| (sorter.column_index, self.sort_order)),
+            filter: self
+                .finder
+                .as_ref()
+                .filter(|_| self.rows_view.is_filter())
+                .map(|finder| finder.target().to_string()),
+        }
+    }
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        for _ in 0..7 {
-            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+    /// Select the row the selection was on before the rows were sorted or filtered, once sorting
+    /// and filtering are done. The selection is left where it is if the row is no longer shown.
+    fn apply_pending_record(&mut self) -> CsvlensResult<()> {
+        if self.pending_record.is_none() {
+            return Ok(());
+        }
+        let sorting = self
+            .sorter
+            .as_ref()
+            .is_some_and(|sorter| sorter.status() == SorterStatus::Running);
+        let filtering =
+            self.rows_view.is_filter() && self.finder.as_ref().is_some_and(|f| !f.done());
+        if sorting || filtering {
+            return Ok(());
+        }
+        if let Some(record_num) = self.pending_record.take() {
+            // The filter holds the number of rows found when it was last set, which bounds
+            // scrolling
+            if let (true, Some(finder)) = (self.rows_view.is_filter(), &self.finder) {
+                self.rows_view.set_filter(finder)?;
+            }
+            self.scroll_to_record(record_num)?;
         }
+        Ok(())
+    }
 
-        // TODO: need a less clunky way of checking expected output, and include
-        // checking of styles. Probably using automatically generated fixtures
-        // that are readable and can be easily updated
-        let expected = vec![
-            "──────────────────────────────",
-            "      a     b                 ",
-            "───┬──────────────┬───────────",
-            "4  │  A4    B4    │           ",
-            "5  │  A5    B5    │           ",
-            "6  │  A6    B6    │           ",
-            "7  │  A7    B7    │           ",
-            "8  │  A8    B8    │           ",
-            "───┴──────────────┴───────────",
-            "stdin [Row 8/5000, Col 1/2]   ",
-        ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+    /// Index among the shown columns of the column with the given name, or else the given
+    /// spreadsheet style letters counted from the first column of the file
+    fn find_column(&self, column: &str) -> Result<usize, String> {
+        let headers = self.rows_view.headers();
+        if let Some(index) = headers.iter().position(|h| h.name == column) {
+            return Ok(index);
+        }
+        match parse_column_letters(column) {
+            Some(origin_index) if origin_index < self.rows_view.raw_headers().len() => headers
+                .iter()
+                .position(|h| h.origin_index == origin_index)
+                .ok_or_else(|| format!("Column {column} is hidden by the columns filter")),
+            _ => Err(format!("Column name not found: {column}")),
+        }
     }
 
-    #[test]
-    fn test_scroll_horizontal() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
-        till_app_ready(&app);
+    fn goto_column(&mut self, column: &str) {
+        match self.find_column(column) {
+            Ok(index) => {
+                self.rows_view.set_cols_offset(index as u64);
+                if self.rows_view.selection.column.index().is_some() {
+                    self.rows_view.selection.column.set_index(0);
+                }
+            }
+            Err(message) => {
+                self.set_message(message);
+            }
+        }
+    }
 
-        let backend = TestBackend::new(30, 10);
-        let mut terminal = Terminal::new(backend).unwrap();
+    fn increase_cols_offset(&mut self) {
+        if self.csv_table_state.has_more_cols_to_show() {
+            let new_cols_offset = self.rows_view.cols_offset().saturating_add(1);
+            self.rows_view.set_cols_offset(new_cols_offset);
+        }
+    }
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        let expected = vec![
-            "──────────────────────────────",
-            "      La…    La…    La…    …  ",
-            "───┬──────────────────────────",
-            "1  │  41     5      59     …  ",
-            "2  │  42     52     48     …  ",
-            "3  │  46     35     59     …  ",
-            "4  │  42     16     12     …  ",
-            "5  │  43     37     48     …  ",
-            "───┴──────────────────────────",
-            "stdin [Row 1/128, Col 1/10]   ",
-        ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+    fn decrease_cols_offset(&mut self) {
+        let new_cols_offset = self.rows_view.cols_offset().saturating_sub(1);
+        self.rows_view.set_cols_offset(new_cols_offset);
+    }
 
-        step_and_draw(&mut app, &mut terminal, Control::ScrollPageRight);
-        let expected = vec![
-            "──────────────────────────────",
-            "      NS    Lo…    Lo…    …   ",
-            "───┬──────────────────────────",
-            "1  │  N     80     39     …   ",
-            "2  │  N     97     23     …   ",
-            "3  │  N     120    30     …   ",
-            "4  │  N     71     48     …   ",
-            "5  │  N     89     46     …   ",
-            "───┴──────────────────────────",
-            "stdin [Row 1/128, Col 4/10]   ",
+    fn adjust_column_width(&mut self, delta: i16) {
+        if let Some(column_index) = self.get_selected_column_index() {
+            if let Some(view_layout) = &mut self.csv_table_state.view_layout {
+                let current_width = view_layout.column_widths[column_index as usize];
+                let new_width = (current_width as i16).saturating_add(delta);
+
+                if new_width > 0 {
+                    let origin_index = self
+                        .rows_view
+                        .get_column_origin_index(column_index as usize);
+                    self.csv_table_state
+                        .column_width_overrides
+                        .set(origin_index, new_width as u16);
+                }
+            }
+        }
+    }
+
+    fn toggle_column_wrap(&mut self) {
+        let (Some(origin_index), Some(column_name)) = (
+            self.get_global_selected_column_index(),
+            self.rows_view.get_column_name_from_selection(),
+        ) else {
+            self.set_message("Select a column first to toggle its line wrapping".to_string());
+            return;
+        };
+        let wrapped_columns = &mut self.csv_table_state.wrapped_columns;
+        let message = if wrapped_columns.remove(&(origin_index as usize)) {
+            format!("Line wrapping disabled for column {column_name}")
+        } else {
+            wrapped_columns.insert(origin_index as usize);
+            format!("Line wrapping enabled for column {column_name}")
+        };
+        self.set_message(message);
+    }
+
+    fn toggle_collapse_column(&mut self) {
+        let (Some(origin_index), Some(column_name)) = (
+            self.get_global_selected_column_index(),
+            self.rows_view.get_column_name_from_selection(),
+        ) else {
+            self.set_message("Select a column first to collapse its duplicate values".to_string());
+            return;
+        };
+        let collapsed_column = &mut self.csv_table_state.collapsed_column;
+        let message = if *collapsed_column == Some(origin_index as usize) {
+            *collapsed_column = None;
+            format!("Showing all values of column {column_name}")
+        } else {
+            *collapsed_column = Some(origin_index as usize);
+            format!("Collapsing consecutive duplicate values of column {column_name}")
+        };
+        self.set_message(message);
+    }
+
+    fn get_selected_column_index(&self) -> Option<u64> {
+        // local index as in local to the view port
+        if let Some(local_column_index) = self.rows_view.selection.column.index() {
+            return Some(local_column_index.saturating_add(self.csv_table_state.cols_offset));
+        }
+        None
+    }
+
+    /// Local indices of the columns currently on screen, if searching is restricted to them
+    fn get_visible_columns(&self) -> Option<Range<usize>> {
+        let num_cols_rendered = self.csv_table_state.num_cols_rendered as usize;
+        if !self.search_visible_columns || num_cols_rendered == 0 {
+            return None;
+        }
+        let cols_offset = self.csv_table_state.cols_offset as usize;
+        Some(cols_offset..cols_offset + num_cols_rendered)
+    }
+
+    fn get_global_selected_column_index(&self) -> Option<u64> {
+        // TODO: maybe this and above should be methods provided by RowsView directly?
+        self.get_selected_column_index()
+            .map(|local_index| self.rows_view.get_column_origin_index(local_index as usize) as u64)
+    }
+
+    /// Positions in the view of the first and last rows of the range selection, in order
+    fn selected_range(&self) -> Option<(u64, u64)> {
+        let anchor = self.range_anchor?;
+        let current = self
+            .rows_view
+            .rows_from()
+            .saturating_add(self.rows_view.selection.row.index()?);
+        Some((anchor.min(current), anchor.max(current)))
+    }
+
+    /// Status of the range selection, with the sum and average of the selected column over it if
+    /// the column is numeric. The sum is computed in the background and only again once the range,
+    /// the column or the order of the rows changes.
+    fn range_summary(&mut self) -> Option<String> {
+        let Some((first, last)) = self.selected_range() else {
+            self.range_aggregator = None;
+            return None;
+        };
+        let num_rows = last - first + 1;
+        let Some(origin_index) = self.get_global_selected_column_index() else {
+            self.range_aggregator = None;
+            return Some(format!("{num_rows} rows"));
+        };
+        let key = RangeKey {
+            range: (first, last),
+            column_index: origin_index as usize,
+            row_order: self.row_order(),
+        };
+        if self.range_aggregator.as_ref().map(|(k, _)| k) != Some(&key) {
+            self.range_aggregator = self
+                .rows_view
+                .get_record_indices_in_view_order(self.finder.as_ref(), first, num_rows)
+                .map(|indices| {
+                    let aggregator = Aggregator::new(
+                        self.shared_config.clone(),
+                        Some(indices),
+                        vec![key.column_index],
+                    );
+                    (key, aggregator)
+                });
+        }
+        let summaries = self
+            .range_aggregator
+            .as_ref()
+            .and_then(|(_, aggregator)| aggregator.range_summaries());
+        let summary = match summaries.as_ref().map(|s| s.first()) {
+            Some(Some(Some(summary))) => format!("{num_rows} rows, {summary}"),
+            Some(_) => format!("{num_rows} rows, not numeric"),
+            None => format!("{num_rows} rows, summing..."),
+        };
+        Some(summary)
+    }
+
+    /// Text to copy for the selected cell, or else the selected row, and the message to show once
+    /// copied
+    fn selection_to_copy(&self) -> Option<(String, String)> {
+        let delimiter = self.shared_config.delimiter();
+        if let Some(selected) = self.rows_view.get_cell_value_from_selection() {
+            let text = self.copy_mode.format_cell(&selected, delimiter);
+            let message = format!("Copied {text} to clipboard");
+            return Some((text, message));
+        }
+        let row = self.rows_view.selected_row()?;
+        let text = self.copy_mode.format_row(&row.fields, delimiter);
+        let row_number = self.csv_table_state.row_number(row.record_num);
+        Some((text, format!("Copied row {row_number} to clipboard")))
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str, success_message: String) {
+        let message = if self.clipboard.is_disabled() {
+            "Copying is disabled by the clipboard backend none".to_string()
+        } else if let Some(reason) = self.clipboard.unavailable_reason() {
+            format!("Clipboard unavailable: {reason}")
+        } else {
+            match self.clipboard.set_text(text) {
+                Ok(_) => success_message,
+                Err(e) => format!("Failed to copy to clipboard: {e}"),
+            }
+        };
+        self.set_message(message);
+    }
+
+    /// Copy the absolute path of the file, or its URL if it was downloaded
+    fn copy_file_path(&mut self) {
+        let Some(filename) = self.csv_table_state.filename().map(str::to_string) else {
+            self.set_message("No file path to copy when reading from stdin".to_string());
+            return;
+        };
+        let path = if download::is_url(&filename) {
+            Ok(filename.clone())
+        } else {
+            std::fs::canonicalize(&filename).map(|path| path.display().to_string())
+        };
+        match path {
+            Ok(path) => self.copy_to_clipboard(&path, format!("Copied {path} to clipboard")),
+            Err(e) => {
+                self.set_message(format!("Failed to resolve the path of {filename}: {e}"));
+            }
+        }
+    }
+
+    /// Sort by the selected column, remembering it to be repeated later
+    fn toggle_sort(&mut self) -> CsvlensResult<()> {
+        if let Some(selected_column_index) = self.get_global_selected_column_index() {
+            self.toggle_sort_by(selected_column_index as usize)?;
+            self.last_command = Some(RepeatableCommand::ToggleSort(
+                selected_column_index as usize,
+            ));
+        } else {
+            self.set_message("Press TAB and select a column before sorting".to_string());
+        }
+        Ok(())
+    }
+
+    /// Sort by the column with the given origin index, or toggle the sort direction if already
+    /// sorted by it
+    fn toggle_sort_by(&mut self, selected_column_index: usize) -> CsvlensResult<()> {
+        let mut should_create_new_sorter = false;
+        if let Some(column_index) = self.sorter.as_ref().map(|s| s.column_index) {
+            if column_index.is_none() {
+                // Sort by the column instead of reversing, starting ascending
+                self.sort_order = SortOrder::Ascending;
+                self.rows_view.set_sort_order(self.sort_order)?;
+                should_create_new_sorter = true;
+            } else if Some(selected_column_index) != column_index {
+                should_create_new_sorter = true;
+            } else {
+                match self.sort_order {
+                    SortOrder::Ascending => {
+                        self.sort_order = SortOrder::Descending;
+                    }
+                    SortOrder::Descending => {
+                        self.sort_order = SortOrder::Ascending;
+                    }
+                }
+                self.rows_view.set_sort_order(self.sort_order)?;
+            }
+        } else {
+            should_create_new_sorter = true;
+        }
+        if should_create_new_sorter {
+            self.sorter = Some(self.new_sorter(Some(selected_column_index)));
+        }
+        Ok(())
+    }
+
+    /// Show the rows in reverse record order, or back in record order if already reversed
+    fn reverse_order(&mut self) -> CsvlensResult<()> {
+        // Same as going back to a view with or without the reversed order, which also rebuilds
+        // the finder for the new row order
+        let mut snapshot = self.view_snapshot();
+        snapshot.sort = match snapshot.sort {
+            Some((None, _)) => None,
+            _ => Some((None, SortOrder::Descending)),
+        };
+        self.restore_view_snapshot(snapshot)
+    }
+
+    /// Sorter by the given column, or keeping the record order if None, with the current sort
+    /// options
+    fn new_sorter(&self, column_index: Option<usize>) -> Arc<sort::Sorter> {
+        let sorter = match column_index {
+            Some(column_index) => sort::Sorter::new(
+                self.shared_config.clone(),
+                column_index,
+                self.rows_view
+                    .get_column_name_from_global_index(column_index),
+                self.sort_nulls_last,
+                self.ignore_case,
+            ),
+            None => sort::Sorter::record_order(
+                self.shared_config.clone(),
+                self.rows_view.get_total_line_numbers(),
+            ),
+        };
+        Arc::new(sorter)
+    }
+
+    fn reset_filter(&mut self) {
+        self.filter_stack.clear();
+        if self.finder.is_some() {
+            let was_filter = self.rows_view.is_filter();
+            self.finder = None;
+            self.csv_table_state.finder_state = FinderState::FinderInactive;
+            self.rows_view.reset_filter().unwrap();
+            self.aggregator = None;
+            if let (true, Some(record_num)) = (was_filter, self.filter_origin.take()) {
+                // Clamped to the bottom if the record is no longer there
+                self.scroll_to_record(record_num as u64).unwrap();
+            }
+        }
+    }
+
+    /// Stop finding or sorting that is still running and return to the previous view
+    fn cancel_background_tasks(&mut self) {
+        let mut cancelled = vec![];
+        if !self.macro_queue.is_empty() {
+            self.macro_queue.clear();
+            cancelled.push("Macro");
+        }
+        if self.finder.as_ref().is_some_and(|f| !f.done()) {
+            // Dropping the finder stops its background thread
+            self.reset_filter();
+            cancelled.push("Search");
+        }
+        if let Some(sorter) = &self.sorter {
+            if sorter.status() == SorterStatus::Running {
+                sorter.terminate();
+                self.reset_sorter();
+                cancelled.push("Sort");
+            }
+        }
+        if self
+            .duplicate_counter
+            .as_ref()
+            .is_some_and(|c| c.status() == DuplicateCounterStatus::Running)
+        {
+            // Dropping the counter stops its background thread
+            self.duplicate_counter = None;
+            self.show_distinct_count = false;
+            cancelled.push("Distinct count");
+        }
+        if !cancelled.is_empty() {
+            self.set_message(format!("{} cancelled", cancelled.join(" and ")));
+        }
+    }
+
+    /// Write the rows found by the current finder to a CSV file, keeping only the filtered columns
+    fn export_matches(&mut self, path: &Path) {
+        let message = match &self.finder {
+            Some(finder) if finder.done() => {
+                let row_indices = finder.get_subset_found(0, finder.count());
+                let column_indices = self
+                    .columns_filter
+                    .as_ref()
+                    .map(|columns_filter| columns_filter.indices().as_slice());
+                match export::export_rows(&self.shared_config, row_indices, column_indices, path) {
+                    Ok(n) => format!("Exported {} matches to {}", n, path.display()),
+                    Err(e) => format!("Failed to export matches: {e}"),
+                }
+            }
+            Some(_) => "Wait for finding to complete before exporting matches".to_string(),
+            None => "Find something first before exporting matches".to_string(),
+        };
+        self.set_message(message);
+    }
+
+    /// Start aggregating the visible columns over the rows currently shown for the footer
+    fn create_aggregator(&mut self) {
+        let row_indices = if self.rows_view.is_filter() {
+            match &self.finder {
+                // Aggregate once all the matches are known
+                Some(fdr) if fdr.done() => Some(fdr.get_subset_found(0, fdr.count())),
+                _ => return,
+            }
+        } else {
+            None
+        };
+        let column_indices = match &self.columns_filter {
+            Some(columns_filter) => columns_filter.indices().clone(),
+            None => (0..self.rows_view.raw_headers().len()).collect(),
+        };
+        self.aggregator = Some(Aggregator::new(
+            self.shared_config.clone(),
+            row_indices,
+            column_indices,
+        ));
+    }
+
+    /// Show a message in the status bar until the next key press, and add it to the message log
+    fn set_message(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.message_log.push(&message);
+        self.transient_message = Some(message);
+    }
+
+    fn finder_snapshot(&self) -> Option<FinderSnapshot> {
+        self.finder.as_ref().map(|finder| FinderSnapshot {
+            target: finder.target(),
+            duplicates: finder.duplicates(),
+            is_filter: self.rows_view.is_filter(),
+            column_index: finder.column_index(),
+            filter_stack: self.filter_stack.clone(),
+        })
+    }
+
+    /// Find or filter again as in the snapshot, replacing the current finder
+    fn restore_finder(&mut self, finder: FinderSnapshot) {
+        self.filter_stack = finder.filter_stack;
+        let sorter = self.finished_sorter();
+        self.create_finder_with_column_index(
+            finder.target,
+            finder.duplicates,
+            finder.is_filter,
+            finder.column_index,
+            sorter,
+        );
+    }
+
+    fn view_snapshot(&self) -> ViewSnapshot {
+        ViewSnapshot {
+            finder: self.finder_snapshot(),
+            sort: self
+                .sorter
+                .as_ref()
+                .map(|sorter| (sorter.column_index, self.sort_order)),
+            columns_filter: self.columns_filter.clone(),
+            column_width_overrides: self.csv_table_state.column_width_overrides.clone(),
+            wrapped_columns: self.csv_table_state.wrapped_columns.clone(),
+            collapsed_column: self.csv_table_state.collapsed_column,
+        }
+    }
+
+    /// Go back to a previous view, only rebuilding the finder and sorter if they changed
+    fn restore_view_snapshot(&mut self, snapshot: ViewSnapshot) -> CsvlensResult<()> {
+        let current = self.view_snapshot();
+
+        if current.columns_filter.as_ref().map(Arc::as_ptr)
+            != snapshot.columns_filter.as_ref().map(Arc::as_ptr)
+        {
+            match snapshot.columns_filter {
+                Some(columns_filter) => {
+                    self.rows_view.set_columns_filter(&columns_filter)?;
+                    self.columns_filter = Some(columns_filter);
+                    self.aggregator = None;
+                }
+                None => self.reset_columns_filter(),
+            }
+            self.csv_table_state.set_cols_offset(0);
+        }
+
+        match snapshot.sort {
+            Some((column_index, sort_order)) => {
+                if current.sort.map(|(c, _)| c) != Some(column_index) {
+                    self.sorter = Some(self.new_sorter(column_index));
+                }
+                self.sort_order = sort_order;
+                self.rows_view.set_sort_order(sort_order)?;
+            }
+            None => {
+                if self.sorter.is_some() {
+                    self.reset_sorter();
+                }
+            }
+        }
+
+        if current.finder != snapshot.finder || current.sort != snapshot.sort {
+            self.reset_filter();
+            if let Some(finder) = snapshot.finder {
+                self.restore_finder(finder);
+            }
+        }
+
+        self.csv_table_state.column_width_overrides = snapshot.column_width_overrides;
+        self.csv_table_state.wrapped_columns = snapshot.wrapped_columns;
+        self.csv_table_state.collapsed_column = snapshot.collapsed_column;
+        Ok(())
+    }
+
+    fn undo(&mut self) -> CsvlensResult<()> {
+        match self.undo_stack.undo(self.view_snapshot()) {
+            Some(snapshot) => {
+                self.restore_view_snapshot(snapshot)?;
+                self.set_message("Undid the last change".to_string());
+            }
+            None => {
+                self.set_message("Nothing to undo".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn redo(&mut self) -> CsvlensResult<()> {
+        match self.undo_stack.redo(self.view_snapshot()) {
+            Some(snapshot) => {
+                self.restore_view_snapshot(snapshot)?;
+                self.set_message("Redid the last undone change".to_string());
+            }
+            None => {
+                self.set_message("Nothing to redo".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn reset_sorter(&mut self) {
+        // TODO: consolidate rows_view reset
+        if self.sorter.take().is_some_and(|s| s.column_index.is_none()) {
+            // Reversed is not a sort direction to carry over to the next sort
+            self.sort_order = SortOrder::Ascending;
+            self.rows_view.set_sort_order(self.sort_order).unwrap();
+        }
+        self.rows_view.reset_sorter().unwrap();
+    }
+
+    fn render_frame(&mut self, f: &mut Frame) {
+        let size = f.area();
+
+        let border_type = self.csv_table_state.border_style.border_type();
+
+        // Render help; if so exit early.
+        if self.help_page_state.is_active() {
+            f.render_stateful_widget(
+                help::HelpPage::new(border_type, &self.csv_table_state.theme),
+                size,
+                &mut self.help_page_state,
+            );
+            return;
+        }
+        if self.message_log_page_state.is_active() {
+            f.render_stateful_widget(
+                MessageLogPage::new(&self.message_log, border_type, &self.csv_table_state.theme),
+                size,
+                &mut self.message_log_page_state,
+            );
+            return;
+        }
+        if self.command_palette_state.is_active() {
+            f.render_stateful_widget(
+                CommandPalette::new(border_type, &self.csv_table_state.theme),
+                size,
+                &mut self.command_palette_state,
+            );
+            return;
+        }
+        if let (true, Some(validator)) = (self.validation_page_state.is_active(), &self.validator) {
+            f.render_stateful_widget(
+                ValidationPage::new(validator, border_type, &self.csv_table_state.theme),
+                size,
+                &mut self.validation_page_state,
+            );
+            return;
+        }
+
+        // Render table
+        // TODO: check type of num_rows too big?
+        // Same lines as reserved by the table for the header and status bar. Wrapped column names
+        // can take up more than one line.
+        let header_height = CsvTable::new(self.rows_view.headers(), self.rows_view.rows())
+            .header_height(size, &self.csv_table_state);
+        let mut num_rows_not_visible =
+            self.csv_table_state.header_area_height(header_height) + STATUS_HEIGHT;
+        if self.csv_table_state.show_footer {
+            num_rows_not_visible += 1;
+        }
+        let num_rows_adjusted = size.height.saturating_sub(num_rows_not_visible) as u64;
+        if let Some(view_layout) = &self.csv_table_state.view_layout {
+            self.rows_view.set_num_rows_rendered(
+                view_layout
+                    .num_rows_renderable(num_rows_adjusted as u16, self.rows_view.rows().len())
+                    as u64,
+            );
+        } else {
+            // No layout yet in the first frame, assume every row takes up one line
+            self.rows_view.set_num_rows_rendered(num_rows_adjusted);
+        }
+        self.rows_view.set_num_rows(num_rows_adjusted).unwrap();
+        self.frame_width = Some(size.width);
+
+        // Not knowing, don't make the counts of the runs look complete
+        self.csv_table_state.collapsed_runs_continue =
+            self.collapsed_runs_continue().unwrap_or((true, true));
+
+        let rows = self.rows_view.rows();
+        let csv_table = CsvTable::new(self.rows_view.headers(), rows);
+        f.render_stateful_widget(csv_table, size, &mut self.csv_table_state);
+        if let Some((x, y)) = self.csv_table_state.cursor_xy {
+            f.set_cursor_position(Position::new(x, y));
+        }
+    }
+
+    fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> CsvlensResult<()> {
+        let start = Instant::now();
+        terminal.draw(|f| {
+            self.render_frame(f);
+        })?;
+        self.csv_table_state
+            .debug_stats
+            .render_elapsed(Some(Instant::now().saturating_duration_since(start)));
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::truncate::TruncationMode;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    struct AppBuilder {
+        source: csv::CsvSource,
+        options: AppOptions,
+    }
+
+    impl AppBuilder {
+        fn new(filename: &str) -> Self {
+            Self::from_source(csv::CsvSource::Path(filename.to_owned()))
+        }
+
+        fn from_source(source: csv::CsvSource) -> Self {
+            AppBuilder {
+                source,
+                // Keep tests off the system clipboard
+                options: AppOptions {
+                    clipboard_backend: ClipboardBackend::None,
+                    ..AppOptions::default()
+                },
+            }
+        }
+
+        fn build(self) -> CsvlensResult<App> {
+            App::new(self.source, self.options)
+        }
+
+        fn delimiter(mut self, delimiter: Delimiter) -> Self {
+            self.options.delimiter = delimiter;
+            self
+        }
+
+        fn terminator(mut self, terminator: u8) -> Self {
+            self.options.terminator = Some(terminator);
+            self
+        }
+
+        fn max_rows(mut self, max_rows: u64) -> Self {
+            self.options.max_rows = Some(max_rows);
+            self
+        }
+
+        fn original_filename(mut self, original_filename: &str) -> Self {
+            self.options.original_filename = Some(original_filename.to_owned());
+            self
+        }
+
+        fn column_spacing(mut self, column_spacing: u16) -> Self {
+            self.options.column_spacing = column_spacing;
+            self
+        }
+
+        fn line_number_spacing(mut self, line_number_spacing: u16) -> Self {
+            self.options.line_number_spacing = line_number_spacing;
+            self
+        }
+
+        fn border_style(mut self, border_style: BorderStyle) -> Self {
+            self.options.border_style = border_style;
+            self
+        }
+
+        fn sort_nulls_last(mut self, sort_nulls_last: bool) -> Self {
+            self.options.sort_nulls_last = sort_nulls_last;
+            self
+        }
+
+        fn ignore_case(mut self, ignore_case: bool) -> Self {
+            self.options.ignore_case = ignore_case;
+            self
+        }
+
+        fn no_headers(mut self, no_headers: bool) -> Self {
+            self.options.no_headers = no_headers;
+            self
+        }
+
+        fn columns_regex(mut self, columns: Option<String>) -> Self {
+            self.options.columns_regex = columns;
+            self
+        }
+
+        fn column_names(mut self, column_names: Vec<String>) -> Self {
+            self.options.column_names = Some(column_names);
+            self
+        }
+
+        fn truncation(mut self, truncation: Truncation) -> Self {
+            self.options.truncation = truncation;
+            self
+        }
+
+        fn key_column(mut self, key_column: &str) -> Self {
+            self.options.key_column = Some(key_column.to_owned());
+            self
+        }
+
+        fn max_row_height(mut self, max_row_height: u16) -> Self {
+            self.options.max_row_height = Some(max_row_height);
+            self
+        }
+
+        fn column_letters(mut self, column_letters: bool) -> Self {
+            self.options.column_letters = column_letters;
+            self
+        }
+
+        fn thousands_separator(mut self, thousands_separator: char) -> Self {
+            self.options.thousands_separator = Some(thousands_separator);
+            self
+        }
+
+        fn startup_message(mut self, startup_message: &str) -> Self {
+            self.options.startup_message = Some(startup_message.to_owned());
+            self
+        }
+
+        fn filter_regex(mut self, filter: Option<String>) -> Self {
+            self.options.filter_regex = filter;
+            self
+        }
+
+        fn scrolloff(mut self, scrolloff: u64) -> Self {
+            self.options.scrolloff = scrolloff;
+            self
+        }
+
+        fn on_selection_change(mut self, callback: view::SelectionCallback) -> Self {
+            self.options.on_selection_change = Some(callback);
+            self
+        }
+
+        fn initial_row(mut self, row: u64) -> Self {
+            self.options.initial_row = Some(row);
+            self
+        }
+
+        fn initial_column(mut self, column: &str) -> Self {
+            self.options.initial_column = Some(column.to_owned());
+            self
+        }
+
+        fn find_threads(mut self, find_threads: usize) -> Self {
+            self.options.find_threads = Some(find_threads);
+            self
+        }
+
+        fn output_mode(mut self, output_mode: OutputMode) -> Self {
+            self.options.output_mode = output_mode;
+            self
+        }
+
+        fn output_granularity(mut self, output_granularity: OutputGranularity) -> Self {
+            self.options.output_granularity = output_granularity;
+            self
+        }
+
+        fn column_color(mut self, column: &str, color: &str) -> Self {
+            self.options
+                .column_colors
+                .insert(column.to_string(), color.to_string());
+            self
+        }
+
+        fn hide_row_numbers(mut self, hide_row_numbers: bool) -> Self {
+            self.options.hide_row_numbers = hide_row_numbers;
+            self
+        }
+
+        fn zero_based_row_numbers(mut self, zero_based_row_numbers: bool) -> Self {
+            self.options.zero_based_row_numbers = zero_based_row_numbers;
+            self
+        }
+
+        fn monochrome(mut self, monochrome: bool) -> Self {
+            self.options.monochrome = monochrome;
+            self
+        }
+
+        fn auto_wrap(mut self, auto_wrap: bool) -> Self {
+            self.options.auto_wrap = auto_wrap;
+            self
+        }
+
+        fn copy_mode(mut self, copy_mode: CopyMode) -> Self {
+            self.options.copy_mode = copy_mode;
+            self
+        }
+
+        fn units_row(mut self, units_row: bool) -> Self {
+            self.options.units_row = units_row;
+            self
+        }
+
+        fn status_format(mut self, status_format: &str) -> Self {
+            self.options.status_format = Some(status_format.to_owned());
+            self
+        }
+
+        fn compact(mut self, compact: bool) -> Self {
+            self.options.compact = compact;
+            self
+        }
+
+        fn ignore_accents(mut self, ignore_accents: bool) -> Self {
+            self.options.ignore_accents = ignore_accents;
+            self
+        }
+
+        fn wrap_search(mut self, wrap_search: bool) -> Self {
+            self.options.wrap_search = wrap_search;
+            self
+        }
+
+        fn filter_context(mut self, filter_context: u64) -> Self {
+            self.options.filter_context = filter_context;
+            self
+        }
+
+        /// The diff needs to outlive the app, as its file backs the source
+        fn diff(mut self, diff: &diff::Diff) -> Self {
+            self.source = diff.source.clone();
+            self.options.diff_rows = Some(diff.rows.clone());
+            self
+        }
+
+        fn empty_placeholder(mut self, empty_placeholder: &str) -> Self {
+            self.options.empty_placeholder = Some(empty_placeholder.to_owned());
+            self
+        }
+
+        fn show_scrollbars(mut self, show_scrollbars: bool) -> Self {
+            self.options.show_scrollbars = show_scrollbars;
+            self
+        }
+
+        fn echo_column(mut self, column: &str) -> Self {
+            self.options.echo_column = Some(column.to_owned());
+            self
+        }
+    }
+
+    fn step_and_draw<B: Backend>(app: &mut App, terminal: &mut Terminal<B>, control: Control) {
+        app.step(&control).unwrap();
+
+        // While it's possible to step multiple times before any draw when
+        // testing, App::render_frame() can update App's state (e.g. based on
+        // the current terminal frame size) with information that might be
+        // required for stepping to work correctly. Also, immediately drawing
+        // after each step is what App::main_loop() will be doing.
+        terminal.draw(|f| app.render_frame(f)).unwrap();
+    }
+
+    fn press_key<B: Backend>(app: &mut App, terminal: &mut Terminal<B>, code: KeyCode) {
+        let control = app
+            .input_handler
+            .handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        step_and_draw(app, terminal, control);
+    }
+
+    /// Press the keys of the characters one after another, as if typed
+    fn type_keys<B: Backend>(app: &mut App, terminal: &mut Terminal<B>, keys: &str) {
+        for c in keys.chars() {
+            press_key(app, terminal, KeyCode::Char(c));
+        }
+    }
+
+    fn till_app_ready(app: &App) {
+        app.wait_until_ready();
+    }
+
+    /// Feed the controls of the macros being played as the run loop does, once the app is ready
+    fn till_macro_played(app: &mut App) {
+        while !app.macro_queue.is_empty() {
+            till_app_ready(app);
+            app.play_queued_macro().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        for _ in 0..7 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        }
+
+        // TODO: need a less clunky way of checking expected output, and include
+        // checking of styles. Probably using automatically generated fixtures
+        // that are readable and can be easily updated
+        let expected = vec![
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "4  │  A4    B4    │           ",
+            "5  │  A5    B5    │           ",
+            "6  │  A6    B6    │           ",
+            "7  │  A7    B7    │           ",
+            "8  │  A8    B8    │           ",
+            "───┴──────────────┴───────────",
+            "stdin [Row 8/5000, Col 1/2]   ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_scroll_half_page_horizontal() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let mut offsets = vec![];
+        for control in [
+            Control::ScrollHalfPageRight,
+            Control::ScrollHalfPageRight,
+            Control::ScrollHalfPageRight,
+            Control::ScrollHalfPageRight,
+            Control::ScrollHalfPageLeft,
+            Control::ScrollHalfPageLeft,
+            Control::ScrollHalfPageLeft,
+        ] {
+            step_and_draw(&mut app, &mut terminal, control);
+            offsets.push(app.csv_table_state.cols_offset);
+            if offsets.len() == 4 {
+                // Stops once the last column is in view
+                let lines = to_lines(terminal.backend().buffer());
+                let expected = vec![
+                    "──────────────────────────────────────────────────",
+                    "      LonM    LonS    EW    City         State    ",
+                    "───┬──────────────────────────────────────────────",
+                    "1  │  39      0       W     Youngsto…    OH       ",
+                    "2  │  23      23            Yankton      SD       ",
+                    "3  │  30      36      W     Yakima       WA       ",
+                    "4  │  48      0       W     Worcester    MA       ",
+                    "5  │  46      11      W     Wisconsi…    WI       ",
+                    "───┴──────────────────────────────────────────────",
+                    "stdin [Row 1/128, Col 6/10]                       ",
+                ];
+                assert_eq!(lines, expected);
+            }
+        }
+        assert_eq!(offsets, vec![2, 5, 5, 5, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_restore_position_after_filter() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(60));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        assert_eq!(app.rows_view.selected_record_num(), Some(62));
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^Wi".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::BufferReset);
+        assert_eq!(app.rows_view.rows_from(), 61);
+        assert_eq!(app.rows_view.selected_record_num(), Some(62));
+
+        // Filtering within a filter goes back to before the first one
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^W".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::FilterWithin("i".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        assert_eq!(app.rows_view.rows_from(), 61);
+        assert_eq!(app.rows_view.selected_record_num(), Some(62));
+    }
+
+    #[test]
+    fn test_scroll_horizontal() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "──────────────────────────────",
+            "      La…    La…    La…    …  ",
+            "───┬──────────────────────────",
+            "1  │  41     5      59     …  ",
+            "2  │  42     52     48     …  ",
+            "3  │  46     35     59     …  ",
+            "4  │  42     16     12     …  ",
+            "5  │  43     37     48     …  ",
+            "───┴──────────────────────────",
+            "stdin [Row 1/128, Col 1/10]   ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollPageRight);
+        let expected = vec![
+            "──────────────────────────────",
+            "      NS    Lo…    Lo…    …   ",
+            "───┬──────────────────────────",
+            "1  │  N     80     39     …   ",
+            "2  │  N     97     23     …   ",
+            "3  │  N     120    30     …   ",
+            "4  │  N     71     48     …   ",
+            "5  │  N     89     46     …   ",
+            "───┴──────────────────────────",
+            "stdin [Row 1/128, Col 4/10]   ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollPageLeft);
+        let expected = vec![
+            "──────────────────────────────",
+            "      La…    La…    La…    …  ",
+            "───┬──────────────────────────",
+            "1  │  41     5      59     …  ",
+            "2  │  42     52     48     …  ",
+            "3  │  46     35     59     …  ",
+            "4  │  42     16     12     …  ",
+            "5  │  43     37     48     …  ",
+            "───┴──────────────────────────",
+            "stdin [Row 1/128, Col 1/10]   ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_columns() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("Lon|City".into()),
+        );
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LonD    LonM    LonS    City                                              ",
+            "───┬─────────────────────────────────────────────┬──────────────────────────────",
+            "1  │  80      39      0       Youngstown         │                              ",
+            "2  │  97      23      23      Yankton            │                              ",
+            "3  │  120     30      36      Yakima             │                              ",
+            "4  │  71      48      0       Worcester          │                              ",
+            "5  │  89      46      11      Wisconsin Dells    │                              ",
+            "───┴─────────────────────────────────────────────┴──────────────────────────────",
+            "stdin [Row 1/128, Col 1/4] [Filter \"Lon|City\": 4/10 cols]                       ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_columns_irregular() {
+        let mut app = AppBuilder::new("tests/data/irregular.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("COL2".into()),
+        );
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       COL2                                                                     ",
+            "───┬───────────┬────────────────────────────────────────────────────────────────",
+            "1  │           │                                                                ",
+            "2  │   v2      │                                                                ",
+            "   │           │                                                                ",
+            "   │           │                                                                ",
+            "   │           │                                                                ",
+            "───┴───────────┴────────────────────────────────────────────────────────────────",
+            "stdin [Row 1/2, Col 1/1] [Filter \"COL2\": 1/2 cols]                              ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_columns_case_sensitive() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("city|state|wa".into()),
+        );
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
+            "───┬────────────────────────────────────────────────────────────────────────────",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
+            "2  │  42      52      48      N     97      23      23            Yankton       ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima        ",
+            "4  │  42      16      12      N     71      48      0       W     Worcester     ",
+            "5  │  43      37      48      N     89      46      11      W     Wisconsin…    ",
+            "───┴────────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10] [Filter \"city|state|wa\": no match, showing all colum",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_columns_ignore_case() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .ignore_case(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("city|state|wa".into()),
+        );
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      City               State                                                  ",
+            "───┬──────────────────────────────┬─────────────────────────────────────────────",
+            "1  │  Youngstown         OH       │                                             ",
+            "2  │  Yankton            SD       │                                             ",
+            "3  │  Yakima             WA       │                                             ",
+            "4  │  Worcester          MA       │                                             ",
+            "5  │  Wisconsin Dells    WI       │                                             ",
+            "───┴──────────────────────────────┴─────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/2] [Filter \"(?i)city|state|wa\": 2/10 cols] [ignore-case]",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_extra_fields_in_some_rows() {
+        // Test getting column widths should not fail on data with bad formatting (some rows having
+        // more fields than the header)
+        let mut app = AppBuilder::new("tests/data/bad_double_quote.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(35, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "───────────────────────────────────",
+            "      Column1     \"column2\"        ",
+            "───┬───────────────────────────┬───",
+            "1  │  1           \"quote\"      │   ",
+            "2  │  5           \"Comma       │   ",
+            "   │                           │   ",
+            "   │                           │   ",
+            "   │                           │   ",
+            "───┴───────────────────────────┴───",
+            "stdin [Row 1/2, Col 1/2]           ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_extra_fields_right_most_border() {
+        let mut app = AppBuilder::new("tests/data/bad_73.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(35, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "───────────────────────────────────",
+            "      COL1     COL2                ",
+            "───┬───────────────────┬───────────",
+            "1  │  c1               │           ",
+            "2  │  c2       v2      │           ",
+            "3  │  c2       4       │           ",
+            "4  │  c3               │           ",
+            "5  │  c4               │           ",
+            "───┴───────────────────┴───────────",
+            "stdin [Row 1/13, Col 1/2]          ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sniff_delimiter() {
+        let mut app = AppBuilder::new("tests/data/small.bsv")
+            .delimiter(Delimiter::Auto)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "──────────────────────────────",
+            "      COL1    COL2            ",
+            "───┬──────────────────┬───────",
+            "1  │  c1      v1      │       ",
+            "2  │  c2      v2      │       ",
+            "   │                  │       ",
+            "   │                  │       ",
+            "   │                  │       ",
+            "───┴──────────────────┴───────",
+            "stdin [Row 1/2, Col 1/2]      ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sniff_delimiter_decimal_comma() {
+        let mut app = AppBuilder::new("tests/data/decimal_comma.csv")
+            .delimiter(Delimiter::Auto)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      Product    Price    Weight        ",
+            "───┬────────────────────────────────┬───",
+            "1  │  Apple      1,25     0,2       │   ",
+            "2  │  Bread      2,5      0,75      │   ",
+            "3  │  Milk       0,99     1         │   ",
+            "4  │  Cheese     12,40    0,35      │   ",
+            "   │                                │   ",
+            "───┴────────────────────────────────┴───",
+            "stdin [Row 1/4, Col 1/3]                ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sorting_thousands_separator() {
+        let mut app = AppBuilder::new("tests/data/decimal_comma.csv")
+            .delimiter(Delimiter::Auto)
+            .thousands_separator('.')
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Sort by Price, which would put 12,40 before 2,5 if compared as text
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      Product    Price [▴]      Weight            ",
+            "───┬──────────────────────────────────────┬───────",
+            "3  │  Milk       0,99           1         │       ",
+            "1  │  Apple      1,25           0,2       │       ",
+            "2  │  Bread      2,5            0,75      │       ",
+            "4  │  Cheese     12,40          0,35      │       ",
+            "   │                                      │       ",
+            "───┴──────────────────────────────────────┴───────",
+            "stdin [Row 3/4, Col 1/3]                          ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_bytes_source() {
+        let bytes: Vec<u8> = b"COL1|COL2\nc1|v1\nc2|v2\n".to_vec();
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(bytes.into()))
+            .delimiter(Delimiter::Auto)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "──────────────────────────────",
+            "      COL1    COL2            ",
+            "───┬──────────────────┬───────",
+            "1  │  c1      v1      │       ",
+            "2  │  c2      v2      │       ",
+            "   │                  │       ",
+            "   │                  │       ",
+            "   │                  │       ",
+            "───┴──────────────────┴───────",
+            "stdin [Row 1/2, Col 1/2]      ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_terminator() {
+        let bytes: Vec<u8> = b"COL1,COL2;c1,v1\nmore;c2,v2;".to_vec();
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(bytes.into()))
+            .terminator(b';')
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────",
+            "      COL1    COL2            ",
+            "───┬──────────────────┬───────",
+            "1  │  c1      v…      │       ",
+            "2  │  c2      v2      │       ",
+            "   │                  │       ",
+            "   │                  │       ",
+            "   │                  │       ",
+            "───┴──────────────────┴───────",
+            "stdin [Row 1/2, Col 1/2]      ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_column_wrap() {
+        let mut app = AppBuilder::new("tests/data/multi_lines.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Wrapping a short column alone keeps the long column truncated
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleColumnWrap);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very lon…    12345          │",
+            "2  │  2    thi…                   678910         │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Line wrapping enabled for column a                ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleColumnWrap);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very long    12345          │",
+            "   │        text that surely w                   │",
+            "   │       ill not fit in your                   │",
+            "   │        small screen                         │",
+            "2  │  2    this                   678910         │",
+            "   │       is                                    │",
+            "   │       a…                                    │",
+            "───┴─────────────────────────────────────────────┴",
+            "Line wrapping enabled for column b                ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very lon…    12345          │",
+            "2  │  2    thi…                   678910         │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "stdin [Row 1/3, Col 1/3]                          ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_multi_lines() {
+        let mut app = AppBuilder::new("tests/data/multi_lines.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very lon…    12345          │",
+            "2  │  2    thi…                   678910         │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "stdin [Row 1/3, Col 1/3]                          ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(false));
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very long    12345          │",
+            "   │        text that surely w                   │",
+            "   │       ill not fit in your                   │",
+            "   │        small screen                         │",
+            "2  │  2    this                   678910         │",
+            "   │       is                                    │",
+            "   │       an                                    │",
+            "   │       even                                  │",
+            "   │       longer                                │",
+            "   │       text                                  │",
+            "   │       that                                  │",
+            "   │       surely                                │",
+            "   │       will                                  │",
+            "   │       not                                   │",
+            "   │       fit                                   │",
+            "   │       in                                    │",
+            "   │       your                                  │",
+            "   │       small                                 │",
+            "   │       screen                                │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Line wrap enabled                                 ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very         12345          │",
+            "   │       long text that                        │",
+            "   │       surely will not                       │",
+            "   │       fit in your small                     │",
+            "   │       screen                                │",
+            "2  │  2    this                   678910         │",
+            "   │       is                                    │",
+            "   │       an                                    │",
+            "   │       even                                  │",
+            "   │       longer                                │",
+            "   │       text                                  │",
+            "   │       that                                  │",
+            "   │       surely                                │",
+            "   │       will                                  │",
+            "   │       not                                   │",
+            "   │       fit                                   │",
+            "   │       in                                    │",
+            "   │       your                                  │",
+            "   │       small                                 │",
+            "   │       screen                                │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Word wrap enabled                                 ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_multi_lines_degenerate_width() {
+        let mut app = AppBuilder::new("tests/data/multi_lines.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(false));
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    …   c                                  ",
+            "───┬──────────────────────────┬───────────────────",
+            "1  │  1    …   12345          │                   ",
+            "2  │  2    …   678910         │                   ",
+            "3  │  3    …   123,456,789    │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "   │                          │                   ",
+            "───┴──────────────────────────┴───────────────────",
+            "stdin [Row 1/3, Col 1/3]                          ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_multiple_newlines() {
+        let mut app = AppBuilder::new("tests/data/multiple_newlines.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 45);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very         12345          │",
+            "   │       long text that                        │",
+            "   │       surely will not                       │",
+            "   │       fit in your small                     │",
+            "   │       screen                                │",
+            "2  │  2    this                   678910         │",
+            "   │                                             │",
+            "   │       is                                    │",
+            "   │                                             │",
+            "   │       an                                    │",
+            "   │                                             │",
+            "   │       even                                  │",
+            "   │                                             │",
+            "   │       longer                                │",
+            "   │                                             │",
+            "   │       text                                  │",
+            "   │                                             │",
+            "   │       that                                  │",
+            "   │                                             │",
+            "   │       surely                                │",
+            "   │                                             │",
+            "   │       will                                  │",
+            "   │                                             │",
+            "   │       not                                   │",
+            "   │                                             │",
+            "   │       fit                                   │",
+            "   │                                             │",
+            "   │       in                                    │",
+            "   │                                             │",
+            "   │       your                                  │",
+            "   │                                             │",
+            "   │       small                                 │",
+            "   │                                             │",
+            "   │       screen                                │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Word wrap enabled                                 ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_empty_placeholder() {
+        let mut app = AppBuilder::new("tests/data/duplicate_values.csv")
+            .empty_placeholder("NULL")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      id    name                        ",
+            "───┬────────────────┬───────────────────",
+            "1  │  1     a       │                   ",
+            "2  │  2     b       │                   ",
+            "3  │  1     a       │                   ",
+            "4  │  3     c       │                   ",
+            "5  │  2     b       │                   ",
+            "6  │  1a    NULL    │                   ",
+            "7  │  4     c       │                   ",
+            "───┴────────────────┴───────────────────",
+            "stdin [Row 1/7, Col 1/2]                ",
+        ];
+        assert_eq!(lines, expected);
+        assert!(terminal.backend().buffer()[(12, 8)]
+            .modifier
+            .contains(ratatui::style::Modifier::DIM));
+
+        // Found as empty, not as the placeholder
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^$".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      id    name                        ",
+            "───┬────────────────┬───────────────────",
+            "6  │  1a    NULL    │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "───┴────────────────┴───────────────────",
+            "stdin [Row 6/7, Col 1/2] [Filter \"^$\": 1",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleEmptyPlaceholder);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      id    name                        ",
+            "───┬────────────────┬───────────────────",
+            "6  │  1a            │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "   │                │                   ",
+            "───┴────────────────┴───────────────────",
+            "Showing empty cells as blank            ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_show_whitespace() {
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(
+            b"a,b\n1,\"x  \"\n2,\"y\tz\r\nw\"\n".to_vec().into(),
+        ))
+        .build()
+        .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 9);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleShowWhitespace);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(false));
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────",
+            "      a    b                  ",
+            "───┬────────────────┬─────────",
+            "1  │  1    x··      │         ",
+            "2  │  2    y→z¤↵    │         ",
+            "   │       w        │         ",
+            "   │                │         ",
+            "───┴────────────────┴─────────",
+            "Line wrap enabled             ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Copying is not affected
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        assert_eq!(app.get_selection(), Some("x  ".to_string()));
+
+        // Matches in the original content are highlighted where they are shown
+        step_and_draw(&mut app, &mut terminal, Control::Find("\tz".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines[4], "2  │  2    y→z¤↵    │         ");
+        let highlight = ratatui::style::Color::Rgb(200, 0, 0);
+        let x_y = lines[4].chars().position(|c| c == 'y').unwrap() as u16;
+        let highlighted: Vec<bool> = (x_y..x_y + 5)
+            .map(|x| actual_buffer[(x, 4)].fg == highlight)
+            .collect();
+        assert_eq!(highlighted, vec![false, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_carriage_returns() {
+        let mut app = AppBuilder::new("tests/data/multi_lines_carriage_return.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 45);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very         12345          │",
+            "   │       long text that                        │",
+            "   │       surely will not                       │",
+            "   │       fit in your small                     │",
+            "   │       screen                                │",
+            "2  │  2    this                   678910         │",
+            "   │                                             │",
+            "   │       is                                    │",
+            "   │                                             │",
+            "   │       an                                    │",
+            "   │                                             │",
+            "   │       even                                  │",
+            "   │                                             │",
+            "   │       longer                                │",
+            "   │                                             │",
+            "   │       text                                  │",
+            "   │                                             │",
+            "   │       that                                  │",
+            "   │                                             │",
+            "   │       surely                                │",
+            "   │                                             │",
+            "   │       will                                  │",
+            "   │                                             │",
+            "   │       not                                   │",
+            "   │                                             │",
+            "   │       fit                                   │",
+            "   │                                             │",
+            "   │       in                                    │",
+            "   │                                             │",
+            "   │       your                                  │",
+            "   │                                             │",
+            "   │       small                                 │",
+            "   │                                             │",
+            "   │       screen                                │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Word wrap enabled                                 ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_starts_with_newline() {
+        let mut app = AppBuilder::new("tests/data/starts_with_newline.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 20);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very lon…    12345          │",
+            "2  │  2    …                      678910         │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "stdin [Row 1/3, Col 1/3]                          ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a    b                      c               ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1    this is a very         12345          │",
+            "   │       long text that                        │",
+            "   │       surely will not                       │",
+            "   │       fit in your small                     │",
+            "   │       screen                                │",
+            "2  │  2                           678910         │",
+            "   │       starts with new                       │",
+            "   │       line                                  │",
+            "3  │  3    normal text now        123,456,789    │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Word wrap enabled                                 ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_column_widths_boundary_condition() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(120, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::Filter("Salt Lake City".into()),
+        );
+        till_app_ready(&app);
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("City".into()),
+        );
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "       City                                                                                                             ",
+            "────┬────────────────────┬──────────────────────────────────────────────────────────────────────────────────────────────",
+            "97  │  Salt Lake City    │                                                                                              ",
+            "    │                    │                                                                                              ",
+            "    │                    │                                                                                              ",
+            "    │                    │                                                                                              ",
+            "    │                    │                                                                                              ",
+            "────┴────────────────────┴──────────────────────────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 97/128, Col 1/1] [Filter \"Salt Lake City\": 1/1] [Filter \"City\": 1/10 cols]                                   "];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_scroll_right_most() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // TODO: why is this first nothing step needed?
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRightMost);
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      LonS    EW    City      State     ",
+            "───┬───────────────────────────────────┬",
+            "1  │  0       W     Young…    OH       │",
+            "2  │  23            Yankt…    SD       │",
+            "3  │  36      W     Yakima    WA       │",
+            "4  │  0       W     Worce…    MA       │",
+            "5  │  11      W     Wisco…    WI       │",
+            "───┴───────────────────────────────────┴",
+            "stdin [Row 1/128, Col 7/10]             ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_scroll_left_most() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // TODO: why is this first nothing step needed?
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRightMost);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollLeftMost);
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    …   ",
+            "───┬────────────────────────────────────",
+            "1  │  41      5       59      N     …   ",
+            "2  │  42      52      48      N     …   ",
+            "3  │  46      35      59      N     …   ",
+            "4  │  42      16      12      N     …   ",
+            "5  │  43      37      48      N     …   ",
+            "───┴────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10]             ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_scroll_half_page() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // TODO: since some states are updated late only during rendering, sometimes some extra
+        // no-ops are required to warm up the states. I don't like it, but this is how it has to be
+        // in tests for now.
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageDown);
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    …   ",
+            "───┬────────────────────────────────────",
+            "5  │  43      37      48      N     …   ",
+            "6  │  36      5       59      N     …   ",
+            "7  │  49      52      48      N     …   ",
+            "8  │  39      11      23      N     …   ",
+            "9  │  34      14      24      N     …   ",
+            "───┴────────────────────────────────────",
+            "stdin [Row 5/128, Col 1/10]             ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageUp);
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    …   ",
+            "───┬────────────────────────────────────",
+            "3  │  46      35      59      N     …   ",
+            "4  │  42      16      12      N     …   ",
+            "5  │  43      37      48      N     …   ",
+            "6  │  36      5       59      N     …   ",
+            "7  │  49      52      48      N     …   ",
+            "───┴────────────────────────────────────",
+            "stdin [Row 3/128, Col 1/10]             ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_resize_column() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Check column widths are adjusted correctly
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::IncreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::IncreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollLeft);
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    …   LatS            NS    LonD    LonM    LonS    EW    City      ",
+            "───┬────────────────────────────────────────────────────────────────────────────",
+            "1  │  41      …   59              N     80      39      0       W     Young…    ",
+            "2  │  42      …   48              N     97      23      23            Yankt…    ",
+            "3  │  46      …   59              N     120     30      36      W     Yakima    ",
+            "4  │  42      …   12              N     71      48      0       W     Worce…    ",
+            "5  │  43      …   48              N     89      46      11      W     Wisco…    ",
+            "───┴────────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10]                                                     ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Check overridden column widths still have  when columns are filtered
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("Lat".into()),
+        );
+        till_app_ready(&app);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    …   LatS                                                          ",
+            "───┬──────────────────────────────┬─────────────────────────────────────────────",
+            "1  │  41      …   59              │                                             ",
+            "2  │  42      …   48              │                                             ",
+            "3  │  46      …   59              │                                             ",
+            "4  │  42      …   12              │                                             ",
+            "5  │  43      …   48              │                                             ",
+            "───┴──────────────────────────────┴─────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/3] [Filter \"Lat\": 3/10 cols]                            ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Check reset
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
+            "───┬────────────────────────────────────────────────────────────────────────────",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
+            "2  │  42      52      48      N     97      23      23            Yankton       ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima        ",
+            "4  │  42      16      12      N     71      48      0       W     Worcester     ",
+            "5  │  43      37      48      N     89      46      11      W     Wisconsin…    ",
+            "───┴────────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10]                                                     ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sorting() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(100, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        // Sort by City (no tie)
+        for _ in 0..8 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "        LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▴]      State             ",
+            "─────┬─────────────────────────────────────────────────────────────────────────────────────┬────────",
+            "128  │  41      9       35      N     81      14      23      W     Ravenna       OH       │        ",
+            "127  │  40      19      48      N     75      55      48      W     Reading       PA       │        ",
+            "126  │  40      10      48      N     122     14      23      W     Red Bluff     CA       │        ",
+            "125  │  50      25      11      N     104     39      0       W     Regina        SA       │        ",
+            "124  │  39      31      12      N     119     48      35      W     Reno          NV       │        ",
+            "─────┴─────────────────────────────────────────────────────────────────────────────────────┴────────",
+            "stdin [Row 128/128, Col 1/10]                                                                       ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Check descending
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▾]           State          ",
+            "───┬──────────────────────────────────────────────────────────────────────────────────────────┬─────",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown         OH       │     ",
+            "2  │  42      52      48      N     97      23      23            Yankton            SD       │     ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima             WA       │     ",
+            "4  │  42      16      12      N     71      48      0       W     Worcester          MA       │     ",
+            "5  │  43      37      48      N     89      46      11      W     Wisconsin Dells    WI       │     ",
+            "───┴──────────────────────────────────────────────────────────────────────────────────────────┴─────",
+            "stdin [Row 1/128, Col 1/10]                                                                         ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sort_nulls_last() {
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(
+            b"a,b\n3,x\n,y\n1,z\n,w\n2,v\n".to_vec().into(),
+        ))
+        .sort_nulls_last(true)
+        .build()
+        .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        app.sorter.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a [▴]      b                                ",
+            "───┬──────────────────┬───────────────────────────",
+            "3  │  1          z    │                           ",
+            "5  │  2          v    │                           ",
+            "1  │  3          x    │                           ",
+            "2  │             y    │                           ",
+            "4  │             w    │                           ",
+            "───┴──────────────────┴───────────────────────────",
+            "stdin [Row 3/5, Col 1/2]                          ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a [▾]      b                                ",
+            "───┬──────────────────┬───────────────────────────",
+            "1  │  3          x    │                           ",
+            "5  │  2          v    │                           ",
+            "3  │  1          z    │                           ",
+            "2  │             y    │                           ",
+            "4  │             w    │                           ",
+            "───┴──────────────────┴───────────────────────────",
+            "stdin [Row 1/5, Col 1/2]                          ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSortNullsLast);
+        app.sorter.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      a [▾]      b                                ",
+            "───┬──────────────────┬───────────────────────────",
+            "1  │  3          x    │                           ",
+            "5  │  2          v    │                           ",
+            "3  │  1          z    │                           ",
+            "2  │             y    │                           ",
+            "4  │             w    │                           ",
+            "───┴──────────────────┴───────────────────────────",
+            "Sorting empty values first when ascending         ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sort_ignore_case() {
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(
+            b"City,State\nboston,MA\nAustin,TX\nBoston,GA\naustin,MN\nBOSTON,KY\n"
+                .to_vec()
+                .into(),
+        ))
+        .ignore_case(true)
+        .build()
+        .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        app.sorter.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      City [▴i]      State              ",
+            "───┬──────────────────────────┬─────────",
+            "2  │  Austin         TX       │         ",
+            "4  │  austin         MN       │         ",
+            "1  │  boston         MA       │         ",
+            "3  │  Boston         GA       │         ",
+            "5  │  BOSTON         KY       │         ",
+            "───┴──────────────────────────┴─────────",
+            "stdin [Row 2/5, Col 1/2] [ignore-case]  ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Sorted again with case breaking the ties once case is no longer ignored
+        step_and_draw(&mut app, &mut terminal, Control::ToggleIgnoreCase);
+        app.sorter.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      City [▴]      State               ",
+            "───┬─────────────────────────┬──────────",
+            "2  │  Austin        TX       │          ",
+            "4  │  austin        MN       │          ",
+            "5  │  BOSTON        KY       │          ",
+            "3  │  Boston        GA       │          ",
+            "1  │  boston        MA       │          ",
+            "───┴─────────────────────────┴──────────",
+            "Matching case in sort, find and filter  ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_reverse_order() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ReverseOrder);
+        app.sorter.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "        LatD    LatM    LatS    NS    LonD    LonM    L…    ",
+            "─────┬──────────────────────────────────────────────────────",
+            "128  │  41      9       35      N     81      14      23    ",
+            "127  │  40      19      48      N     75      55      48    ",
+            "126  │  40      10      48      N     122     14      23    ",
+            "125  │  50      25      11      N     104     39      0     ",
+            "124  │  39      31      12      N     119     48      35    ",
+            "─────┴──────────────────────────────────────────────────────",
+            "stdin [Row 128/128, Col 1/10] [Reversed]                    ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Composes with filters
+        step_and_draw(&mut app, &mut terminal, Control::Filter("OH".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "        LatD    LatM    LatS    NS    LonD    LonM    L…    ",
+            "─────┬──────────────────────────────────────────────────────",
+            "128  │  41      9       35      N     81      14      23    ",
+            "92   │  41      27      0       N     82      42      35    ",
+            "65   │  39      55      11      N     83      48      35    ",
+            "62   │  40      21      36      N     80      37      12    ",
+            "50   │  41      39      0       N     83      32      24    ",
+            "─────┴──────────────────────────────────────────────────────",
+            "stdin [Row 128/128, Col 1/10] [Filter \"OH\": 1/6] [Reversed] ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::Undo);
+        app.sorter.as_ref().unwrap().wait_internal();
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "        LatD    LatM    LatS    NS    LonD    LonM    L…    ",
+            "─────┬──────────────────────────────────────────────────────",
+            "128  │  41      9       35      N     81      14      23    ",
+            "92   │  41      27      0       N     82      42      35    ",
+            "65   │  39      55      11      N     83      48      35    ",
+            "62   │  40      21      36      N     80      37      12    ",
+            "50   │  41      39      0       N     83      32      24    ",
+            "─────┴──────────────────────────────────────────────────────",
+            "Undid the last change                                       ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Reversing again goes back to the record order
+        step_and_draw(&mut app, &mut terminal, Control::ReverseOrder);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    Lo…    ",
+            "────┬───────────────────────────────────────────────────────",
+            "1   │  41      5       59      N     80      39      0      ",
+            "50  │  41      39      0       N     83      32      24     ",
+            "62  │  40      21      36      N     80      37      12     ",
+            "65  │  39      55      11      N     83      48      35     ",
+            "92  │  41      27      0       N     82      42      35     ",
+            "────┴───────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10] [Filter \"OH\": 1/6]              ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_click_header_to_sort() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(100, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        // Clicks on the borders above and below the header are ignored
+        for y in [0, 2] {
+            step_and_draw(&mut app, &mut terminal, Control::MouseClick(70, y));
+            assert!(app.sorter.is_none());
+        }
+
+        // Click on "City"
+        step_and_draw(&mut app, &mut terminal, Control::MouseClick(70, 1));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(
+            lines[1],
+            "        LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▴]      State             "
+        );
+        assert_eq!(
+            lines[3],
+            "128  │  41      9       35      N     81      14      23      W     Ravenna       OH       │        "
+        );
+
+        // Clicking again flips the order
+        step_and_draw(&mut app, &mut terminal, Control::MouseClick(70, 1));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(
+            lines[1],
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▾]           State          "
+        );
+    }
+
+    #[test]
+    fn test_cancel_find_and_sort() {
+        // Large enough for background tasks to be still running when cancelled
+        let mut bytes = b"a,b\n".to_vec();
+        for i in 0..500_000 {
+            bytes.extend(format!("{i},x{i}\n").as_bytes());
+        }
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(bytes.into()))
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("x1".into()));
+        assert!(!app.finder.as_ref().unwrap().done());
+        step_and_draw(&mut app, &mut terminal, Control::Cancel);
+        assert!(app.finder.is_none());
+        assert_eq!(app.transient_message, Some("Search cancelled".to_string()));
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        let sorter = app.sorter.clone().unwrap();
+        assert_eq!(sorter.status(), SorterStatus::Running);
+        step_and_draw(&mut app, &mut terminal, Control::Cancel);
+        assert!(app.sorter.is_none());
+        assert_eq!(app.transient_message, Some("Sort cancelled".to_string()));
+        // The background thread stops early
+        sorter.wait_internal();
+
+        // Nothing to cancel
+        step_and_draw(&mut app, &mut terminal, Control::Cancel);
+        assert_eq!(app.transient_message, None);
+
+        // Finished tasks are kept
+        step_and_draw(&mut app, &mut terminal, Control::Filter("x1".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Cancel);
+        assert!(app.finder.is_some());
+        assert_eq!(app.transient_message, None);
+    }
+
+    #[test]
+    fn test_max_rows() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .max_rows(100)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollBottom);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "        a       b                                           ",
+            "─────┬──────────────────┬───────────────────────────────────",
+            "96   │  A96     B96     │                                   ",
+            "97   │  A97     B97     │                                   ",
+            "98   │  A98     B98     │                                   ",
+            "99   │  A99     B99     │                                   ",
+            "100  │  A100    B100    │                                   ",
+            "─────┴──────────────────┴───────────────────────────────────",
+            "stdin [Row 100/100, Col 1/2]                                ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Only the first 100 rows are searched
+        step_and_draw(&mut app, &mut terminal, Control::Filter("A9".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "       a      b                                             ",
+            "────┬────────────────┬──────────────────────────────────────",
+            "9   │  A9     B9     │                                      ",
+            "90  │  A90    B90    │                                      ",
+            "91  │  A91    B91    │                                      ",
+            "92  │  A92    B92    │                                      ",
+            "93  │  A93    B93    │                                      ",
+            "────┴────────────────┴──────────────────────────────────────",
+            "stdin [Row 93/100, Col 1/2] [Filter \"A9\": 5/11]             ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_goto_byte() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleByteOffset);
+        // Within row 14, which starts at byte 90
+        step_and_draw(&mut app, &mut terminal, Control::GotoByte(93));
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "       a      b                                             ",
+            "────┬────────────────┬──────────────────────────────────────",
+            "14  │  A14    B14    │                                      ",
+            "15  │  A15    B15    │                                      ",
+            "16  │  A16    B16    │                                      ",
+            "17  │  A17    B17    │                                      ",
+            "18  │  A18    B18    │                                      ",
+            "────┴────────────────┴──────────────────────────────────────",
+            "stdin [Row 14/5000, Col 1/2] [Byte 90]                      ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("A1".into()));
+        step_and_draw(&mut app, &mut terminal, Control::GotoByte(90));
+        assert_eq!(
+            app.transient_message,
+            Some("Going to a byte offset needs the rows unfiltered and unsorted".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parallel_filter() {
+        // Large enough to be searched on multiple threads
+        let mut bytes = b"a,b\n".to_vec();
+        for i in 0..400_000 {
+            bytes.extend(format!("{i},x{i}\n").as_bytes());
+        }
+        let source = csv::CsvSource::Bytes(bytes.into());
+
+        let mut results = vec![];
+        for find_threads in [1, 4] {
+            let mut app = AppBuilder::from_source(source.clone())
+                .find_threads(find_threads)
+                .build()
+                .unwrap();
+            till_app_ready(&app);
+
+            let backend = TestBackend::new(30, 10);
+            let mut terminal = Terminal::new(backend).unwrap();
+
+            step_and_draw(&mut app, &mut terminal, Control::Filter("^x.*99$".into()));
+            till_app_ready(&app);
+            let finder = app.finder.as_ref().unwrap();
+            let count = finder.count();
+            let found = finder.get_subset_found(0, count);
+
+            // Pick up all the found rows before scrolling to the bottom
+            step_and_draw(&mut app, &mut terminal, Control::Nothing);
+            step_and_draw(&mut app, &mut terminal, Control::ScrollBottom);
+            step_and_draw(&mut app, &mut terminal, Control::Nothing);
+            let record_nums: Vec<usize> = app.visible_rows().iter().map(|r| r.record_num).collect();
+            results.push((count, found, record_nums));
+        }
+        assert_eq!(results[0].0, 4000);
+        assert_eq!(results[0].1.len(), 4000);
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    fn test_collapse_column() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(100, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Sort by State and collapse it
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        for _ in 0..9 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleCollapseColumn);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          State [▴]          ",
+            "────┬───────────────────────────────────────────────────────────────────────────────────────────┬───",
+            "41  │  33      12      35      N     87      34      11      W     Tuscaloosa    AL (×2)        │   ",
+            "77  │  32      25      11      N     87      1       11      W     Selma                        │   ",
+            "44  │  32      13      12      N     110     58      12      W     Tucson        AZ             │   ",
+            "34  │  49      16      12      N     123     7       12      W     Vancouver     BC             │   ",
+            "19  │  41      25      11      N     122     23      23      W     Weed          CA (×3+)       │   ",
+            "60  │  37      57      35      N     121     17      24      W     Stockton                     │   ",
+            "86  │  38      26      23      N     122     43      12      W     Santa Rosa                   │   ",
+            "────┴───────────────────────────────────────────────────────────────────────────────────────────┴───",
+            "Collapsing consecutive duplicate values of column State                                             ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Only part of the run is counted when it goes on above the rows shown
+        step_and_draw(&mut app, &mut terminal, Control::ScrollPageDown);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(
+            lines[3],
+            "88  │  34      25      11      N     119     41      59      W     Santa Barbara     CA (×7+)       "
+        );
+        step_and_draw(&mut app, &mut terminal, Control::ScrollPageUp);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleCollapseColumn);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          State [▴]          ",
+            "────┬───────────────────────────────────────────────────────────────────────────────────────────┬───",
+            "41  │  33      12      35      N     87      34      11      W     Tuscaloosa    AL             │   ",
+            "77  │  32      25      11      N     87      1       11      W     Selma         AL             │   ",
+            "44  │  32      13      12      N     110     58      12      W     Tucson        AZ             │   ",
+            "34  │  49      16      12      N     123     7       12      W     Vancouver     BC             │   ",
+            "19  │  41      25      11      N     122     23      23      W     Weed          CA             │   ",
+            "60  │  37      57      35      N     121     17      24      W     Stockton      CA             │   ",
+            "86  │  38      26      23      N     122     43      12      W     Santa Rosa    CA             │   ",
+            "────┴───────────────────────────────────────────────────────────────────────────────────────────┴───",
+            "Showing all values of column State                                                                  ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_sorting_with_filter() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        // Sort by City (no tie)
+        for _ in 0..8 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+
+        // Toggle back to row selection mode before filtering
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("San".into()));
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("Lat|City".into()),
+        );
+
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    City [▴]                                         ",
+            "────┬────────────────────────────────────────────┬──────────────────────────────",
+            "96  │  31      27      35      San Angelo        │                              ",
+            "95  │  29      25      12      San Antonio       │                              ",
+            "94  │  34      6       36      San Bernardino    │                              ",
+            "93  │  32      42      35      San Diego         │                              ",
+            "91  │  37      46      47      San Francisco     │                              ",
+            "────┴────────────────────────────────────────────┴──────────────────────────────",
+            "stdin [Row 96/128, Col 1/4] [Filter \"San\": 1/11] [Filter \"Lat|City\": 4/10 cols] ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Check descending
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    City [▾]                                         ",
+            "────┬───────────────────────────────────────────┬───────────────────────────────",
+            "86  │  38      26      23      Santa Rosa       │                               ",
+            "87  │  35      40      48      Santa Fe         │                               ",
+            "88  │  34      25      11      Santa Barbara    │                               ",
+            "89  │  33      45      35      Santa Ana        │                               ",
+            "92  │  41      27      0       Sandusky         │                               ",
+            "────┴───────────────────────────────────────────┴───────────────────────────────",
+            "stdin [Row 86/128, Col 1/4] [Filter \"San\": -/11] [Filter \"Lat|City\": 4/10 cols] ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_no_headers() {
+        let mut app = AppBuilder::new("tests/data/no_headers.csv")
+            .no_headers(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        for _ in 0..7 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        }
+
+        let expected = vec![
+            "──────────────────────────────",
+            "      1     2                 ",
+            "───┬──────────────┬───────────",
+            "4  │  A4    B4    │           ",
+            "5  │  A5    B5    │           ",
+            "6  │  A6    B6    │           ",
+            "7  │  A7    B7    │           ",
+            "8  │  A8    B8    │           ",
+            "───┴──────────────┴───────────",
+            "stdin [Row 8/20, Col 1/2]     ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_cli_columns_option() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .columns_regex(Some("Lat".to_string()))
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS                                                      ",
+            "───┬──────────────────────────┬─────────────────────────────────────────────────",
+            "1  │  41      5       59      │                                                 ",
+            "2  │  42      52      48      │                                                 ",
+            "3  │  46      35      59      │                                                 ",
+            "4  │  42      16      12      │                                                 ",
+            "5  │  43      37      48      │                                                 ",
+            "───┴──────────────────────────┴─────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/3] [Filter \"Lat\": 3/10 cols]                            ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_cli_filter_option() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .filter_regex(Some("San".to_string()))
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
+            "87  │  35      40      48      N     105     56      59      W     Santa Fe     ",
+            "88  │  34      25      11      N     119     41      59      W     Santa Ba…    ",
+            "89  │  33      45      35      N     117     52      12      W     Santa Ana    ",
+            "90  │  37      20      24      N     121     52      47      W     San Jose     ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 86/128, Col 1/10] [Filter \"San\": 1/11]                               ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_rows_for_specific_column() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^1".into()));
+
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "4   │  42      16      12      N     71      48      0       W     Worcester    ",
+            "8   │  39      11      23      N     78      9       36      W     Winchest…    ",
+            "9   │  34      14      24      N     77      55      11      W     Wilmingt…    ",
+            "12  │  41      15      0       N     77      0       0       W     Williams…    ",
+            "20  │  31      13      11      N     82      20      59      W     Waycross     ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 4/128, Col 1/10] [Filter \"^1\" in LatM: -/19]                         ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_ignore_accents() {
+        let mut app = AppBuilder::new("tests/data/accents.csv")
+            .ignore_accents(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(70, 7);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("Cafe".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────────────────────────",
+            "      name           city                                             ",
+            "───┬──────────────────────────────┬───────────────────────────────────",
+            "1  │  Café Über      Montréal     │                                   ",
+            "2  │  Cafe Uber      Montreal     │                                   ",
+            "───┴──────────────────────────────┴───────────────────────────────────",
+            "stdin [Row 1/3, Col 1/2] [Filter \"Cafe\": 1/2] [ignore-accents]        ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Highlighting is aligned with the original text
+        let cell = actual_buffer
+            .content()
+            .iter()
+            .position(|c| c.symbol() == "é")
+            .unwrap();
+        assert_eq!(
+            actual_buffer.content()[cell].fg,
+            ratatui::style::Color::Rgb(200, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_highlight_all_columns() {
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(
+            b"a,b\nx1,x2\ny1,x3\n".to_vec().into(),
+        ))
+        .build()
+        .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Filter on the first column only
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("x".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────",
+            "      a     b                           ",
+            "───┬──────────────┬─────────────────────",
+            "1  │  x1    x2    │                     ",
+            "   │              │                     ",
+            "   │              │                     ",
+            "───┴──────────────┴─────────────────────",
+            "stdin [Row 1/2, Col 1/2] [Filter \"x\" in ",
+        ];
+        assert_eq!(lines, expected);
+        let highlight = ratatui::style::Color::Rgb(200, 0, 0);
+        let x_a = lines[3].chars().position(|c| c == 'x').unwrap() as u16;
+        let x_b = x_a + "x1    ".len() as u16;
+        assert_eq!(actual_buffer[(x_a, 3)].fg, highlight);
+        assert_ne!(actual_buffer[(x_b, 3)].fg, highlight);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleHighlightAllColumns);
+        let actual_buffer = terminal.backend().buffer().clone();
+        assert_eq!(actual_buffer[(x_a, 3)].fg, highlight);
+        assert_eq!(actual_buffer[(x_b, 3)].fg, highlight);
+    }
+
+    #[test]
+    fn test_filter_visible_columns() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 6);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // City is not on screen
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSearchVisibleColumns);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^Wa".into()));
+
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      LatM    LatS    NS    LonD    LonM    L…    ",
+            "───┬──────────────────────────────────────────────",
+            "   │                                              ",
+            "───┴──────────────────────────────────────────────",
+            "stdin [Row -/128, Col 2/10] [Filter \"^Wa\" in cols ",
+        ];
+        assert_eq!(lines, expected);
+        assert_eq!(app.finder.as_ref().unwrap().visible_columns(), Some(1..7));
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSearchVisibleColumns);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^Wa".into()));
+        till_app_ready(&app);
+        assert_eq!(app.finder.as_ref().unwrap().count(), 11);
+    }
+
+    #[test]
+    fn test_find_wrap_search() {
+        let found_row_index = |app: &App| match app.finder.as_ref().unwrap().current() {
+            Some(find::FoundEntry::Row(entry)) => Some(entry.row_index()),
+            _ => None,
+        };
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .wrap_search(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Find("^Ya".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        assert_eq!(found_row_index(&app), Some(1));
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToNextFound);
+        assert_eq!(found_row_index(&app), Some(2));
+        assert_eq!(app.transient_message, None);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToNextFound);
+        assert_eq!(found_row_index(&app), Some(1));
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("Search wrapped to the top")
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToPrevFound);
+        assert_eq!(found_row_index(&app), Some(2));
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("Search wrapped to the bottom")
+        );
+
+        // Stops at the last match when disabled
+        step_and_draw(&mut app, &mut terminal, Control::ToggleWrapSearch);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToNextFound);
+        assert_eq!(found_row_index(&app), Some(2));
+        assert_eq!(app.transient_message, None);
+    }
+
+    #[test]
+    fn test_select_columns_by_names() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .column_names(vec!["City".into(), "Nope".into(), "LatD".into()])
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      City          LatD                          ",
+            "───┬────────────────────────┬─────────────────────",
+            "1  │  Youngstown    41      │                     ",
+            "2  │  Yankton       42      │                     ",
+            "3  │  Yakima        46      │                     ",
+            "───┴────────────────────────┴─────────────────────",
+            "Column names not found: Nope                      ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      City          LatD                          ",
+            "───┬────────────────────────┬─────────────────────",
+            "1  │  Youngstown    41      │                     ",
+            "2  │  Yankton       42      │                     ",
+            "3  │  Yakima        46      │                     ",
+            "───┴────────────────────────┴─────────────────────",
+            "stdin [Row 2/128, Col 1/2] [Filter \"City,Nope,LatD",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_columns_filter_preview() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        app.input_handler
+            .run_action(crate::input::Action::FilterColumns);
+        let mut status_lines = vec![];
+        for pat in ["L", "Lat", "Lat|City", "xyz", "Lat("] {
+            let input = tui_input::Input::new(pat.to_string());
+            step_and_draw(&mut app, &mut terminal, Control::BufferContent(input));
+            let lines = to_lines(terminal.backend().buffer());
+            status_lines.push(lines[9].trim_end().to_string());
+        }
+        assert_eq!(
+            status_lines,
+            vec![
+                "Columns regex: L (6/10 cols)",
+                "Columns regex: Lat (3/10 cols)",
+                "Columns regex: Lat|City (4/10 cols)",
+                "Columns regex: xyz (0/10 cols)",
+                "Columns regex: Lat( (invalid)",
+            ]
+        );
+
+        // Nothing is filtered until the regex is submitted
+        assert!(app.columns_filter.is_none());
+    }
+
+    #[test]
+    fn test_macro() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::RecordMacro('a'));
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines[7].trim_end(),
+            "stdin [Row 1/128, Col 1/10] [Recording 'a']"
+        );
+
+        // Only the submitted filter is recorded, not what is typed
+        step_and_draw(&mut app, &mut terminal, Control::BufferContent("^W".into()));
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^W".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::StopRecordingMacro);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[7].trim_end(), "Recorded macro 'a' of 2 commands");
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        let recorded = to_lines(terminal.backend().buffer())[7].clone();
+        assert_eq!(
+            recorded.trim_end(),
+            "stdin [Row 4/128, Col 1/10] [Filter \"^W\": 3/127]"
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::PlayMacro('a'));
+        till_macro_played(&mut app);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        let played = to_lines(terminal.backend().buffer())[7].clone();
+        assert_eq!(played, recorded);
+
+        step_and_draw(&mut app, &mut terminal, Control::PlayMacro('b'));
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[7].trim_end(), "Macro 'b' is not recorded");
+
+        // Playing a macro while recording records the key playing it, not what it plays
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::RecordMacro('b'));
+        step_and_draw(&mut app, &mut terminal, Control::PlayMacro('a'));
+        till_macro_played(&mut app);
+        step_and_draw(&mut app, &mut terminal, Control::StopRecordingMacro);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[7].trim_end(), "Recorded macro 'b' of 1 commands");
+        assert!(matches!(
+            app.macros.get(&'b').map(Vec::as_slice),
+            Some([Control::PlayMacro('a')])
+        ));
+
+        // and a macro can't play itself
+        step_and_draw(&mut app, &mut terminal, Control::RecordMacro('a'));
+        step_and_draw(&mut app, &mut terminal, Control::PlayMacro('b'));
+        step_and_draw(&mut app, &mut terminal, Control::StopRecordingMacro);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines[7].trim_end(),
+            "Macro 'a' not recorded, it would play itself"
+        );
+        assert_eq!(app.macros.get(&'a').map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn test_macro_cancel() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::RecordMacro('a'));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::StopRecordingMacro);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTop);
+
+        // Queued controls are left for the run loop to feed, which Esc stops
+        step_and_draw(&mut app, &mut terminal, Control::PlayMacro('a'));
+        assert_eq!(app.macro_queue.len(), 2);
+        step_and_draw(&mut app, &mut terminal, Control::Cancel);
+        till_macro_played(&mut app);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[7].trim_end(), "Macro cancelled");
+        assert_eq!(app.rows_view.selected_record_num(), Some(1));
+
+        // Keys that do not name a macro only close the prompt, keeping the filter
+        step_and_draw(&mut app, &mut terminal, Control::Filter("Salt".into()));
+        till_app_ready(&app);
+        type_keys(&mut app, &mut terminal, "Q1!2");
+        assert!(app.rows_view.is_filter());
+        let lines = to_lines(terminal.backend().buffer());
+        assert!(lines[7].starts_with("stdin [Row "));
+    }
+
+    #[test]
+    fn test_find_preview() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        app.find_preview_delay = Duration::ZERO;
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        type_keys(&mut app, &mut terminal, "/Ya");
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        // Matches are highlighted without leaving the prompt or scrolling
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
+            "───┬────────────────────────────────────────────────────────────────────────────",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
+            "2  │  42      52      48      N     97      23      23            Yankton       ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima        ",
+            "───┴────────────────────────────────────────────────────────────────────────────",
+            "Find: Ya                                                                        ",
+        ];
+        assert_eq!(lines, expected);
+        assert!(app.finder.is_none());
+        assert_eq!(app.preview_finder.as_ref().unwrap().count(), 2);
+        let x = lines[4].chars().position(|c| c == 'Y').unwrap() as u16;
+        let highlight = ratatui::style::Color::Rgb(200, 0, 0);
+        assert_eq!(actual_buffer[(x, 4)].fg, highlight);
+        assert_ne!(actual_buffer[(x, 3)].fg, highlight);
+
+        // Discarded on Esc
+        press_key(&mut app, &mut terminal, KeyCode::Esc);
+        assert!(app.preview_finder.is_none());
+        assert!(matches!(
+            app.csv_table_state.finder_state,
+            FinderState::FinderInactive
+        ));
+    }
+
+    #[test]
+    fn test_find_preview_keeps_filter() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        app.find_preview_delay = Duration::ZERO;
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^Y".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::FilterWithin("n".into()));
+        till_app_ready(&app);
+        type_keys(&mut app, &mut terminal, "/to");
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        // Only the rows shown by the filters are searched, and the filters stay as they are
+        assert!(app.rows_view.is_filter());
+        assert_eq!(app.filter_stack.len(), 1);
+        assert_eq!(app.finder.as_ref().unwrap().target().as_str(), "n");
+        assert_eq!(app.preview_finder.as_ref().unwrap().count(), 2);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
+            "───┬────────────────────────────────────────────────────────────────────────────",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
+            "2  │  42      52      48      N     97      23      23            Yankton       ",
+            "   │                                                                            ",
+            "───┴────────────────────────────────────────────────────────────────────────────",
+            "Find: to                                                                        ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Submitting the find replaces the filters like any other find
+        press_key(&mut app, &mut terminal, KeyCode::Enter);
+        assert!(app.preview_finder.is_none());
+        assert!(!app.rows_view.is_filter());
+    }
+
+    #[test]
+    fn test_export_matches() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("City|State".into()),
+        );
+        step_and_draw(&mut app, &mut terminal, Control::Find("^Wa".into()));
+        till_app_ready(&app);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("matches.csv");
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::ExportMatches(path.clone()),
+        );
+        assert_eq!(
+            app.transient_message,
+            Some(format!("Exported 11 matches to {}", path.display()))
+        );
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[0], "City,State");
+        assert!(lines[1..].iter().all(|line| line.starts_with("Wa")));
+
+        // Exporting again does not overwrite the file
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::ExportMatches(path.clone()),
+        );
+        assert_eq!(
+            app.transient_message,
+            Some(format!(
+                "Failed to export matches: File already exists: {}",
+                path.display()
+            ))
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_filter_context() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .filter_context(1)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(90, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::Filter("Yankton|Yakima|Wichita".into()),
+        );
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City             S…    ",
+            "────┬─────────────────────────────────────────────────────────────────────────────────────",
+            "1   │  41      5       59      N     80      39      0       W     Youngstown       OH    ",
+            "2   │  42      52      48      N     97      23      23            Yankton          SD    ",
+            "3   │  46      35      59      N     120     30      36      W     Yakima           WA    ",
+            "4   │  42      16      12      N     71      48      0       W     Worcester        MA    ",
+            "13  │  37      40      48      N     82      16      47      W     Williamson       WV    ",
+            "14  │  33      54      0       N     98      29      23      W     Wichita Falls    TX    ",
+            "15  │  37      41      23      N     97      20      23      W     Wichita          KS    ",
+            "────┴─────────────────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10] [Filter \"Yankton|Yakima|Wichita\" ±1: 4 matches]               ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Context rows are dimmed
+        let is_dim = |y: u16| {
+            actual_buffer[(7, y)]
+                .modifier
+                .contains(ratatui::style::Modifier::DIM)
+        };
+        assert_eq!(
+            (3..10).map(is_dim).collect::<Vec<bool>>(),
+            vec![true, false, false, true, true, false, false]
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseFilterContext);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City             S…    ",
+            "────┬─────────────────────────────────────────────────────────────────────────────────────",
+            "2   │  42      52      48      N     97      23      23            Yankton          SD    ",
+            "3   │  46      35      59      N     120     30      36      W     Yakima           WA    ",
+            "14  │  33      54      0       N     98      29      23      W     Wichita Falls    TX    ",
+            "15  │  37      41      23      N     97      20      23      W     Wichita          KS    ",
+            "    │                                                                                     ",
+            "    │                                                                                     ",
+            "    │                                                                                     ",
+            "────┴─────────────────────────────────────────────────────────────────────────────────────",
+            "Showing 0 rows of context around filtered matches                                         ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_duplicates() {
+        let mut app = AppBuilder::new("tests/data/duplicate_values.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Same values in all columns
+        step_and_draw(&mut app, &mut terminal, Control::FilterDuplicates);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      id    name                                                                ",
+            "───┬────────────────┬───────────────────────────────────────────────────────────",
+            "1  │  1     a       │                                                           ",
+            "2  │  2     b       │                                                           ",
+            "3  │  1     a       │                                                           ",
+            "5  │  2     b       │                                                           ",
+            "   │                │                                                           ",
+            "───┴────────────────┴───────────────────────────────────────────────────────────",
+            "stdin [Row 1/7, Col 1/2] [Filter duplicates: 1/4]                               ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Same value in the selected column only
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::FilterDuplicates);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      id    name                                                                ",
+            "───┬────────────────┬───────────────────────────────────────────────────────────",
+            "1  │  1     a       │                                                           ",
+            "2  │  2     b       │                                                           ",
+            "3  │  1     a       │                                                           ",
+            "4  │  3     c       │                                                           ",
+            "5  │  2     b       │                                                           ",
+            "───┴────────────────┴───────────────────────────────────────────────────────────",
+            "stdin [Row 1/7, Col 1/2] [Filter duplicates in name: -/6]                       ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::FilterWithin("c".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      id    name                                                                ",
+            "───┬────────────────┬───────────────────────────────────────────────────────────",
+            "4  │  3     c       │                                                           ",
+            "7  │  4     c       │                                                           ",
+            "   │                │                                                           ",
+            "   │                │                                                           ",
+            "   │                │                                                           ",
+            "───┴────────────────┴───────────────────────────────────────────────────────────",
+            "stdin [Row 4/7, Col 1/2] [Filter duplicates] [Filter \"c\" in name: -/2]          ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_filter_within() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^CA$".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::FilterWithin("San".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
+            "88  │  34      25      11      N     119     41      59      W     Santa Ba…    ",
+            "89  │  33      45      35      N     117     52      12      W     Santa Ana    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 86/128, Col 1/10] [Filter \"^CA$\"] [Filter \"San\": 1/7]                ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Back to the first filter only
+        step_and_draw(&mut app, &mut terminal, Control::BufferReset);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "19  │  41      25      11      N     122     23      23      W     Weed         ",
+            "60  │  37      57      35      N     121     17      24      W     Stockton     ",
+            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 19/128, Col 1/10] [Filter \"^CA$\": 1/12]                              ",
+        ];
+        assert_eq!(lines, expected);
+
+        // A new filter replaces the whole stack
+        step_and_draw(&mut app, &mut terminal, Control::FilterWithin("San".into()));
+        step_and_draw(&mut app, &mut terminal, Control::Filter("Salt".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "97  │  40      45      35      N     111     52      47      W     Salt Lak…    ",
+            "    │                                                                           ",
+            "    │                                                                           ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 97/128, Col 1/10] [Filter \"Salt\": 1/1]                               ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_repeat_last_command() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::RepeatLastCommand);
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("No find, filter or sort to repeat yet")
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("San".into()));
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::RepeatLastCommand);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
+            "87  │  35      40      48      N     105     56      59      W     Santa Fe     ",
+            "88  │  34      25      11      N     119     41      59      W     Santa Ba…    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 86/128, Col 1/10] [Filter \"San\": 1/11]                               ",
+        ];
+        assert_eq!(lines, expected);
+
+        // A sort after the filter is repeated instead, toggling the sort direction
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::RepeatLastCommand);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD [▾]      LatM    LatS    NS    LonD    LonM    LonS    EW    Ci…    ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "92  │  41            27      0       N     82      42      35      W     Sa…    ",
+            "86  │  38            26      23      N     122     43      12      W     Sa…    ",
+            "90  │  37            20      24      N     121     52      47      W     Sa…    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 92/128, Col 1/10] [Filter \"San\": -/11]                               ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Repeated on the same column with the same options, whatever is selected by then
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::RepeatLastCommand);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD [▾]      LatM    LatS    NS    LonD    LonM    LonS    EW    Ci…    ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "1   │  41            5       59      N     80      39      0       W     Yo…    ",
+            "12  │  41            15      0       N     77      0       0       W     Wi…    ",
+            "19  │  41            25      11      N     122     23      23      W     We…    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10]                                                     ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleMatchMode);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("2".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Reset);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleMatchMode);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::RepeatLastCommand);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City        …",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "49  │  39      2       59      N     95      40      11      W     Topeka      …",
+            "56  │  43      2       59      N     76      9       0       W     Syracuse    …",
+            "    │                                                                           ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "stdin [Row 49/128, Col 1/10] [Filter \"\\b(?:2)\\b\" in LatM: -/2] [whole-cell]     ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("San".into()));
+        till_app_ready(&app);
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::FilterColumns("City|State".into()),
+        );
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       City             State                                                   ",
+            "────┬────────────────────────────┬──────────────────────────────────────────────",
+            "86  │  Santa Rosa       CA       │                                              ",
+            "87  │  Santa Fe         NM       │                                              ",
+            "88  │  Santa Barbara    CA       │                                              ",
+            "────┴────────────────────────────┴──────────────────────────────────────────────",
+            "stdin [Row 86/128, Col 1/2] [Filter \"San\": 1/11] [Filter \"City|State\": 2/10 cols",
+        ];
+        assert_eq!(lines, expected);
+
+        // Back to all columns, still filtered
+        step_and_draw(&mut app, &mut terminal, Control::Undo);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
+            "87  │  35      40      48      N     105     56      59      W     Santa Fe     ",
+            "88  │  34      25      11      N     119     41      59      W     Santa Ba…    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "Undid the last change                                                           ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Back to no filter, with nothing more to undo
+        step_and_draw(&mut app, &mut terminal, Control::Undo);
+        step_and_draw(&mut app, &mut terminal, Control::Undo);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
+            "───┬────────────────────────────────────────────────────────────────────────────",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
+            "2  │  42      52      48      N     97      23      23            Yankton       ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima        ",
+            "───┴────────────────────────────────────────────────────────────────────────────",
+            "Nothing to undo                                                                 ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Filter is rebuilt
+        step_and_draw(&mut app, &mut terminal, Control::Redo);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
+            "────┬───────────────────────────────────────────────────────────────────────────",
+            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
+            "87  │  35      40      48      N     105     56      59      W     Santa Fe     ",
+            "88  │  34      25      11      N     119     41      59      W     Santa Ba…    ",
+            "────┴───────────────────────────────────────────────────────────────────────────",
+            "Redid the last undone change                                                    ",
+        ];
+        assert_eq!(lines, expected);
+
+        // A new change cannot be followed by a redo
+        step_and_draw(&mut app, &mut terminal, Control::Filter("Salt".into()));
+        step_and_draw(&mut app, &mut terminal, Control::Redo);
+        assert_eq!(app.transient_message.as_deref(), Some("Nothing to redo"));
+    }
+
+    #[test]
+    fn test_text_stats() {
+        let bytes: Vec<u8> = "id,text\n1,héllo  wörld\n2,\n".as_bytes().to_vec();
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(bytes.into()))
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleTextStats);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollUp);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      id    text                                                                ",
+            "───┬──────────────────────────┬─────────────────────────────────────────────────",
+            "1  │  1     héllo  wörld      │                                                 ",
+            "2  │  2                       │                                                 ",
+            "   │                          │                                                 ",
+            "───┴──────────────────────────┴─────────────────────────────────────────────────",
+            "stdin [Row 1/2, Col 1/2] [row 13 chars]                                         ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      id    text                                                                ",
+            "───┬──────────────────────────┬─────────────────────────────────────────────────",
+            "1  │  1     héllo  wörld      │                                                 ",
+            "2  │  2                       │                                                 ",
+            "   │                          │                                                 ",
+            "───┴──────────────────────────┴─────────────────────────────────────────────────",
+            "stdin [Row 1/2, Col 1/2] [len 12, text] [2 words, 12 chars, row 13 chars]       ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_monochrome() {
+        use ratatui::style::Modifier;
+
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .monochrome(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::Find("A".into()));
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "1  │  A1    B1    │           ",
+            "2  │  A2    B2    │           ",
+            "3  │  A3    B3    │           ",
+            "───┴──────────────┴───────────",
+            "stdin [Row 1/5000, Col 1/2] [F",
+        ];
+        assert_eq!(lines, expected);
+
+        let buffer = terminal.backend().buffer();
+        for cell in buffer.content() {
+            assert_eq!(cell.fg, Color::Reset);
+            assert_eq!(cell.bg, Color::Reset);
+        }
+        let modifier = |x, y| buffer[(x, y)].modifier;
+        // Selected cell and the match the finder is at
+        assert!(modifier(6, 3).contains(Modifier::REVERSED));
+        // Other matches
+        assert!(modifier(6, 4).contains(Modifier::BOLD | Modifier::UNDERLINED));
+        assert!(!modifier(6, 4).contains(Modifier::REVERSED));
+        // No match
+        assert_eq!(modifier(12, 4), Modifier::empty());
+
+        // Pages shown over the table too
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        for control in [
+            Control::Help,
+            Control::ShowMessages,
+            Control::CommandPalette,
+            Control::Validate,
+        ] {
+            step_and_draw(&mut app, &mut terminal, control);
+            // Highlight matches of a find in the help page
+            step_and_draw(&mut app, &mut terminal, Control::Find("csv".into()));
+            till_app_ready(&app);
+            step_and_draw(&mut app, &mut terminal, Control::Nothing);
+            for cell in terminal.backend().buffer().content() {
+                assert_eq!(cell.fg, Color::Reset);
+                assert_eq!(cell.bg, Color::Reset);
+            }
+            step_and_draw(&mut app, &mut terminal, Control::Quit);
+        }
+    }
+
+    #[test]
+    fn test_crosshair() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleCrosshair);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "1  │  A1    B1    │           ",
+            "2  │  A2    B2    │           ",
+            "3  │  A3    B3    │           ",
+            "───┴──────────────┴───────────",
+            "Crosshair enabled             ",
+        ];
+        assert_eq!(lines, expected);
+
+        let bg = |x, y| terminal.backend().buffer()[(x, y)].bg;
+        let crosshair = ratatui::style::Color::Rgb(36, 36, 36);
+        assert_eq!(bg(6, 4), ratatui::style::Color::Rgb(64, 64, 64));
+        assert_eq!(bg(12, 4), crosshair);
+        assert_eq!(bg(6, 3), crosshair);
+        assert_eq!(bg(6, 5), crosshair);
+        assert_eq!(bg(12, 3), ratatui::style::Color::Reset);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = csv::CsvConfig::new("tests/data/diff_old.csv", b',', false);
+        let new = csv::CsvConfig::new("tests/data/diff_new.csv", b',', false);
+        let diff = diff::Diff::new(&old, &new, Some("id")).unwrap();
+        let mut app = AppBuilder::from_source(csv::CsvSource::Bytes(Arc::from(&b""[..])))
+            .diff(&diff)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      ±     id    name          price             ",
+            "───┬─────────────────────────────────────┬────────",
+            "1  │        1     apple         3        │        ",
+            "2  │  -     2     banana        1        │        ",
+            "3  │  ~     3     cherry        9        │        ",
+            "4  │        4     date          5        │        ",
+            "5  │  +     5     elderberry    12       │        ",
+            "───┴─────────────────────────────────────┴────────",
+            "1 added, 1 removed, 1 changed                     ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Added rows are green, removed rows red and only the changed cells of changed rows yellow
+        let fg = |x: u16, y: u16| actual_buffer[(x, y)].fg;
+        assert_eq!(fg(18, 4), ratatui::style::Color::Red);
+        assert_eq!(fg(18, 5), ratatui::style::Color::Reset);
+        assert_eq!(fg(32, 5), ratatui::style::Color::Yellow);
+        assert_eq!(fg(18, 6), ratatui::style::Color::Reset);
+        assert_eq!(fg(18, 7), ratatui::style::Color::Green);
+    }
+    #[test]
+    fn test_filter_like_cell() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Enter cell selection mode
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+
+        // Select the State column
+        for _ in 0..10 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        }
+        step_and_draw(&mut app, &mut terminal, Control::FilterLikeCell);
+
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "       LatS    NS    LonD    LonM    LonS    EW    City            State        ",
+            "────┬───────────────────────────────────────────────────────────────────────┬───",
+            "1   │  59      N     80      39      0       W     Youngstown      OH       │   ",
+            "50  │  0       N     83      32      24      W     Toledo          OH       │   ",
+            "62  │  36      N     80      37      12      W     Steubenville    OH       │   ",
+            "65  │  11      N     83      48      35      W     Springfield     OH       │   ",
+            "92  │  0       N     82      42      35      W     Sandusky        OH       │   ",
+            "────┴───────────────────────────────────────────────────────────────────────┴───",
+            "stdin [Row 1/128, Col 3/10] [Filter \"^OH$\" in State: 1/6] [len 2, text]         ",
         ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_match_mode() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        let mut counts = vec![];
+        for pattern in ["San", "San", "San", "San Jose"] {
+            step_and_draw(&mut app, &mut terminal, Control::Filter(pattern.into()));
+            till_app_ready(&app);
+            counts.push(app.finder.as_ref().unwrap().count());
+            step_and_draw(&mut app, &mut terminal, Control::ToggleMatchMode);
+        }
+        // Substring, whole word, whole cell, then back to substring
+        assert_eq!(counts, vec![11, 6, 0, 1]);
+
+        // The indicator shows up once the transient message is gone
+        step_and_draw(&mut app, &mut terminal, Control::ScrollUp);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap(),
+            "stdin [Row 90/128, Col 1/10] [Filter \"San Jose\": 1/1] [whole-word]              "
+        );
+    }
+
+    #[test]
+    fn test_filter_like_cell_escape() {
+        let mut app = AppBuilder::new("tests/data/filter.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // Enter cell selection mode
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+
+        // Filter like the selected cell
+        step_and_draw(&mut app, &mut terminal, Control::FilterLikeCell);
+        till_app_ready(&app);
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      a             b                                                           ",
+            "───┬─────────────────────┬──────────────────────────────────────────────────────",
+            "1  │  $(#1#2#.3)    1    │                                                      ",
+            "   │                     │                                                      ",
+            "   │                     │                                                      ",
+            "   │                     │                                                      ",
+            "   │                     │                                                      ",
+            "───┴─────────────────────┴──────────────────────────────────────────────────────",
+            "stdin [Row 1/3, Col 1/2] [Filter \"^\\$\\(\\#1\\#2\\#\\.3\\)$\" in a: 1/1] [len 10, text]",
+        ];
         assert_eq!(lines, expected);
+    }
 
-        step_and_draw(&mut app, &mut terminal, Control::ScrollPageLeft);
+    #[test]
+    fn test_input_cursor() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::BufferContent("abc".into()),
+        );
+        assert_eq!(app.csv_table_state.cursor_xy, Some((3, 9)));
         let expected = vec![
             "──────────────────────────────",
             "      La…    La…    La…    …  ",
@@ -1061,67 +6681,406 @@ mod tests {
             "4  │  42     16     12     …  ",
             "5  │  43     37     48     …  ",
             "───┴──────────────────────────",
-            "stdin [Row 1/128, Col 1/10]   ",
+            "abc                           ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_auto_wrap() {
+        let mut app = AppBuilder::new("tests/data/one_wide_column.txt")
+            .auto_wrap(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "      id    text                             label          ",
+            "───┬───────────────────────────────────────────────────────┬",
+            "1  │  1     this is a very very very         hotdog        │",
+            "   │        very very very very very                       │",
+            "   │        very very very very very                       │",
+            "   │        very very very very very                       │",
+            "   │        very very very very very                       │",
+            "   │        long thing                                     │",
+            "2  │  2     this is a very very very…        not_hotdog    │",
+            "───┴───────────────────────────────────────────────────────┴",
+            "Word wrap enabled: column text is wider than the terminal   ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Narrow columns are left alone
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .auto_wrap(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        assert!(!app.csv_table_state.enable_line_wrap);
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .truncation(Truncation::new(
+                TruncationMode::Middle,
+                Some("~".to_string()),
+            ))
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        for _ in 0..4 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageRight);
+        }
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      LonM    LonS    EW    City         State    ",
+            "───┬──────────────────────────────────────────────",
+            "1  │  39      0       W     Youn~town    OH       ",
+            "2  │  23      23            Yankton      SD       ",
+            "3  │  30      36      W     Yakima       WA       ",
+            "4  │  48      0       W     Worcester    MA       ",
+            "5  │  46      11      W     Wisc~ells    WI       ",
+            "───┴──────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 6/10]                       ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_truncate_wide_indicator() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .truncation(Truncation::new(
+                TruncationMode::End,
+                Some("...".to_string()),
+            ))
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        for _ in 0..4 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageRight);
+        }
+        // The highlighted match leaves a short span at the end of the value
+        step_and_draw(&mut app, &mut terminal, Control::Find("ngsto".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      LonM    LonS    EW    City         State    ",
+            "───┬──────────────────────────────────────────────",
+            "1  │  39      0       W     Youngs...    OH       ",
+            "2  │  23      23            Yankton      SD       ",
+            "3  │  30      36      W     Yakima       WA       ",
+            "4  │  48      0       W     Worcester    MA       ",
+            "5  │  46      11      W     Wiscon...    WI       ",
+            "───┴──────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 6/10] [Find \"ngsto\": 1/1]   ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_key_column() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .key_column("City")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(100, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        assert_eq!(app.rows_view.selected_record_num(), Some(3));
+
+        // Yakima stays selected after sorting by City
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        for _ in 0..8 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▴]           State          ",
+            "───┬──────────────────────────────────────────────────────────────────────────────────────────┬─────",
+            "5  │  43      37      48      N     89      46      11      W     Wisconsin Dells    WI       │     ",
+            "4  │  42      16      12      N     71      48      0       W     Worcester          MA       │     ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima             WA       │     ",
+            "2  │  42      52      48      N     97      23      23            Yankton            SD       │     ",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown         OH       │     ",
+            "───┴──────────────────────────────────────────────────────────────────────────────────────────┴─────",
+            "stdin [Row 3/128, Col 1/10] [len 6, text]                                                           ",
+        ];
+        assert_eq!(lines, expected);
+
+        // and after filtering if it is still shown
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^(W|Y)".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▴]           State          ",
+            "───┬──────────────────────────────────────────────────────────────────────────────────────────┬─────",
+            "5  │  43      37      48      N     89      46      11      W     Wisconsin Dells    WI       │     ",
+            "4  │  42      16      12      N     71      48      0       W     Worcester          MA       │     ",
+            "3  │  46      35      59      N     120     30      36      W     Yakima             WA       │     ",
+            "2  │  42      52      48      N     97      23      23            Yankton            SD       │     ",
+            "1  │  41      5       59      N     80      39      0       W     Youngstown         OH       │     ",
+            "───┴──────────────────────────────────────────────────────────────────────────────────────────┴─────",
+            "stdin [Row 3/128, Col 1/10] [Filter \"^(W|Y)\" in City: 28/30] [len 6, text]                          ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_key_column_duplicates() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .key_column("City")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(100, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        // The second of the two Wilmingtons stays selected, not the first one in the file
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(10));
+        assert_eq!(app.rows_view.selected_record_num(), Some(10));
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        for _ in 0..8 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        assert_eq!(app.rows_view.selected_record_num(), Some(10));
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("Wil".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        assert_eq!(app.rows_view.selected_record_num(), Some(10));
+    }
+
+    #[test]
+    fn test_key_column_not_found() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .key_column("Nope")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap().trim_end(),
+            "Key column not found: Nope"
+        );
+    }
+
+    #[test]
+    fn test_max_row_height() {
+        let mut app = AppBuilder::new("tests/data/one_wide_column.txt")
+            .max_row_height(2)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      id    text                   label          ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1     this is a very         hotdog        │",
+            "   │        very very very…                      │",
+            "2  │  2     this is a very         not_hotdog    │",
+            "   │        very very very…                      │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Word wrap enabled                                 ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::IncreaseMaxRowHeight);
+        step_and_draw(&mut app, &mut terminal, Control::IncreaseMaxRowHeight);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      id    text                   label          ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1     this is a very         hotdog        │",
+            "   │        very very very                       │",
+            "   │        very very very                       │",
+            "   │        very very very…                      │",
+            "2  │  2     this is a very         not_hotdog    │",
+            "   │        very very very                       │",
+            "   │        very very very…                      │",
+            "───┴─────────────────────────────────────────────┴",
+            "Showing at most 4 lines of each wrapped row       ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+        assert_eq!(app.csv_table_state.max_row_height, Some(4));
+
+        // Lifted once every row shown fits
+        for _ in 0..10 {
+            step_and_draw(&mut app, &mut terminal, Control::IncreaseMaxRowHeight);
+        }
+        assert_eq!(app.csv_table_state.max_row_height, None);
+
+        // and started again from the tallest row shown
+        step_and_draw(&mut app, &mut terminal, Control::DecreaseMaxRowHeight);
+        assert_eq!(
+            app.csv_table_state.max_row_height,
+            Some(app.csv_table_state.tallest_row_height - 1)
+        );
     }
 
     #[test]
-    fn test_filter_columns() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_column_letters() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .column_letters(true)
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────────────────────",
+            "      A       B       C       D     E       F       G       ",
+            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    ",
+            "───┬────────────────────────────────────────────────────────",
+            "1  │  41      5       59      N     80      39      0       ",
+            "2  │  42      52      48      N     97      23      23      ",
+            "3  │  46      35      59      N     120     30      36      ",
+            "4  │  42      16      12      N     71      48      0       ",
+            "───┴────────────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10]                                 ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Letters follow the columns in the file, not the ones shown
         step_and_draw(
             &mut app,
             &mut terminal,
-            Control::FilterColumns("Lon|City".into()),
+            Control::FilterColumns("^Lon".into()),
         );
+        step_and_draw(&mut app, &mut terminal, Control::GotoColumn("g".into()));
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      LonD    LonM    LonS    City                                              ",
-            "───┬─────────────────────────────────────────────┬──────────────────────────────",
-            "1  │  80      39      0       Youngstown         │                              ",
-            "2  │  97      23      23      Yankton            │                              ",
-            "3  │  120     30      36      Yakima             │                              ",
-            "4  │  71      48      0       Worcester          │                              ",
-            "5  │  89      46      11      Wisconsin Dells    │                              ",
-            "───┴─────────────────────────────────────────────┴──────────────────────────────",
-            "stdin [Row 1/128, Col 1/4] [Filter \"Lon|City\": 4/10 cols]                       ",
+            "────────────────────────────────────────────────────────────",
+            "      G                                                     ",
+            "      LonS                                                  ",
+            "───┬──────────┬─────────────────────────────────────────────",
+            "1  │  0       │                                             ",
+            "2  │  23      │                                             ",
+            "3  │  36      │                                             ",
+            "4  │  0       │                                             ",
+            "───┴──────────┴─────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 3/3] [Filter \"^Lon\": 3/10 cols]       ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::GotoColumn("B".into()));
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap().trim_end(),
+            "Column B is hidden by the columns filter"
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::GotoColumn("ZZ".into()));
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap().trim_end(),
+            "Column name not found: ZZ"
+        );
     }
 
     #[test]
-    fn test_filter_columns_irregular() {
-        let mut app = AppBuilder::new("tests/data/irregular.csv").build().unwrap();
+    fn test_goto_column() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::FilterColumns("COL2".into()),
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::GotoColumn("City".into()));
+        assert_eq!(
+            app.rows_view.get_column_name_from_selection(),
+            Some("City".to_string())
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::GotoColumn("e".into()));
+        assert_eq!(
+            app.rows_view.get_column_name_from_selection(),
+            Some("LonD".to_string())
         );
+    }
+
+    #[test]
+    fn test_one_wide_column_but_terminal_is_wider() {
+        let mut app = AppBuilder::new("tests/data/one_wide_column.txt")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(180, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "       COL2                                                                     ",
-            "───┬───────────┬────────────────────────────────────────────────────────────────",
-            "1  │           │                                                                ",
-            "2  │   v2      │                                                                ",
-            "   │           │                                                                ",
-            "   │           │                                                                ",
-            "   │           │                                                                ",
-            "───┴───────────┴────────────────────────────────────────────────────────────────",
-            "stdin [Row 1/2, Col 1/1] [Filter \"COL2\": 1/2 cols]                              ",
+            "────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────",
+            "      id    text                                                                                                                                        label                       ",
+            "───┬──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┬─────────────",
+            "1  │  1     this is a very very very very very very very very very very very very very very very very very very very very very very very long thing     hotdog        │             ",
+            "2  │  2     this is a very very very very very very very very very very very very very very very very very very very very very very very short thing    not_hotdog    │             ",
+            "   │                                                                                                                                                                  │             ",
+            "───┴──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┴─────────────",
+            "stdin [Row 1/2, Col 1/3]                                                                                                                                                            ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
@@ -1129,29 +7088,306 @@ mod tests {
     }
 
     #[test]
-    fn test_filter_columns_case_sensitive() {
+    fn test_echo_column() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .echo_column("City")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(180, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+
+        let selection = app.get_selection();
+        assert_eq!(selection, Some("Worcester".to_string()));
+    }
+
+    #[test]
+    fn test_echo_column_with_bom() {
+        let mut app = AppBuilder::new("tests/data/bom.csv")
+            .echo_column("id")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(80, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+
+        let selection = app.get_selection();
+        assert_eq!(selection, Some("2".to_string()));
+    }
+
+    #[test]
+    fn test_output_mode() {
+        let source = csv::CsvSource::Bytes(b"a,b\n1,x\n2,\"y \"\"z\"\"\"\n".to_vec().into());
+        let mut lines = vec![];
+        for output_mode in [OutputMode::Value, OutputMode::Json, OutputMode::JsonRow] {
+            let mut app = AppBuilder::from_source(source.clone())
+                .output_mode(output_mode)
+                .build()
+                .unwrap();
+            till_app_ready(&app);
+
+            let backend = TestBackend::new(80, 8);
+            let mut terminal = Terminal::new(backend).unwrap();
+
+            step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+            step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+            lines.push(app.get_selection().unwrap());
+        }
+        assert_eq!(
+            lines,
+            vec![
+                r#"y "z""#,
+                r#"{"record_num":2,"column":"b","value":"y \"z\""}"#,
+                r#"{"record_num":2,"column":"b","value":"y \"z\"","row":{"a":"2","b":"y \"z\""}}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_granularity() {
+        let source = csv::CsvSource::Bytes(b"a,b\n1,x\n2,\"y \"\"z\"\"\"\n".to_vec().into());
+        let mut lines = vec![];
+        for output_mode in [OutputMode::Value, OutputMode::Json] {
+            let mut app = AppBuilder::from_source(source.clone())
+                .output_mode(output_mode)
+                .output_granularity(OutputGranularity::Row)
+                .build()
+                .unwrap();
+            till_app_ready(&app);
+
+            let backend = TestBackend::new(80, 8);
+            let mut terminal = Terminal::new(backend).unwrap();
+
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+            lines.push(app.get_selection().unwrap());
+
+            // Still only the cell in cell selection mode
+            step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+            step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+            lines.push(app.get_selection().unwrap());
+        }
+        assert_eq!(
+            lines,
+            vec![
+                r#"2,"y ""z""""#,
+                "2",
+                r#"{"record_num":2,"row":{"a":"2","b":"y \"z\""}}"#,
+                r#"{"record_num":2,"column":"a","value":"2"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copy_mode() {
+        let source = csv::CsvSource::Bytes(b"a;b\n1;x,y\n2;\"say \"\"hi\"\"\"\n".to_vec().into());
+        let mut copied = vec![];
+        for copy_mode in [CopyMode::Value, CopyMode::Csv] {
+            let mut app = AppBuilder::from_source(source.clone())
+                .delimiter(Delimiter::Character(b';'))
+                .copy_mode(copy_mode)
+                .build()
+                .unwrap();
+            till_app_ready(&app);
+
+            let backend = TestBackend::new(80, 8);
+            let mut terminal = Terminal::new(backend).unwrap();
+
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+            copied.push(app.selection_to_copy().unwrap().0);
+
+            step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+            step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+            copied.push(app.selection_to_copy().unwrap().0);
+        }
+        assert_eq!(
+            copied,
+            vec![
+                "2\tsay \"hi\"",
+                "say \"hi\"",
+                r#"2;"say ""hi""""#,
+                r#""say ""hi""""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_echo_multiple_columns() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .echo_column("City,State")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(180, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+
+        let selection = app.get_selection();
+        assert_eq!(selection, Some("Worcester\tMA".to_string()));
+
+        let app = AppBuilder::new("tests/data/cities.csv")
+            .echo_column("City,Statez")
+            .build();
+        assert_eq!(
+            app.err().unwrap().to_string(),
+            "Column name not found: Statez"
+        );
+    }
+
+    #[test]
+    fn test_status_format() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .status_format("{row} of {total} {unknown}{filter}{sort}")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Filter("Salt".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap(),
+            "97 of 128 {unknown} [Filter \"Salt\": 1/1]          "
+        );
+    }
+
+    #[test]
+    fn test_indexing_progress() {
         let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(50, 8);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::FilterColumns("city|state|wa".into()),
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap(),
+            "stdin [Row 1/128, Col 1/10]                       "
+        );
+
+        // Small files are indexed too quickly to see the progress, so it is set as if the
+        // indexing thread was still running
+        app.csv_table_state.indexing_progress = Some(0.42);
+        terminal.draw(|f| app.render_frame(f)).unwrap();
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines.last().unwrap(),
+            "stdin [Row 1/128, Col 1/10] [Indexing 42%]        "
         );
+    }
+
+    #[test]
+    fn test_echo_column_not_found() {
+        let app = AppBuilder::new("tests/data/cities.csv")
+            .echo_column("Cityz")
+            .build();
+        if let Err(e) = app {
+            assert_eq!(e.to_string(), "Column name not found: Cityz");
+        } else {
+            panic!("Expected error");
+        }
+    }
+
+    #[test]
+    fn test_column_colors() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .column_color("b", "red")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let x = lines[3][..lines[3].find("B1").unwrap()].chars().count() as u16;
+        let fg = |x, y| terminal.backend().buffer()[(x, y)].fg;
+        // The selected first row keeps its own style
+        assert_eq!(fg(x, 4), Color::Red);
+        assert_eq!(fg(x, 5), Color::Red);
+        // Only the values are colored
+        assert_eq!(fg(x, 1), Color::Reset);
+        assert_eq!(fg(x - 6, 4), Color::Reset);
+
+        let app = AppBuilder::new("tests/data/simple.csv")
+            .column_color("b", "reddish")
+            .build();
+        if let Err(e) = app {
+            assert_eq!(e.to_string(), "Invalid color for column b: 'reddish'");
+        } else {
+            panic!("Expected error");
+        }
+
+        let app = AppBuilder::new("tests/data/simple.csv")
+            .column_color("c", "red")
+            .build();
+        if let Err(e) = app {
+            assert_eq!(e.to_string(), "Column name not found: c");
+        } else {
+            panic!("Expected error");
+        }
+    }
+
+    #[test]
+    fn test_irregular_columns_scrolling() {
+        let mut app = AppBuilder::new("tests/data/irregular_last_row.csv")
+            .no_headers(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
-            "───┬────────────────────────────────────────────────────────────────────────────",
-            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
-            "2  │  42      52      48      N     97      23      23            Yankton       ",
-            "3  │  46      35      59      N     120     30      36      W     Yakima        ",
-            "4  │  42      16      12      N     71      48      0       W     Worcester     ",
-            "5  │  43      37      48      N     89      46      11      W     Wisconsin…    ",
-            "───┴────────────────────────────────────────────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/10] [Filter \"city|state|wa\": no match, showing all colum",
+            "──────────────────────────────────────────────────",
+            "      1            2            3            4    ",
+            "───┬──────────────────────────────────────────────",
+            "1  │  AAAAAAAA…    BBBBBBBB…    AAAAAAAA…    …    ",
+            "2  │  AAAAAAAA…    BBBBBBBB…    AAAAAAAA…    …    ",
+            "3  │  A                                           ",
+            "   │                                              ",
+            "   │                                              ",
+            "───┴──────────────────────────────────────────────",
+            "stdin [Row 1/3, Col 1/10]                         ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      2            3            4            5    ",
+            "───┬──────────────────────────────────────────────",
+            "1  │  BBBBBBBB…    AAAAAAAA…    BBBBBBBB…    …    ",
+            "2  │  BBBBBBBB…    AAAAAAAA…    BBBBBBBB…    …    ",
+            "3  │                                              ",
+            "   │                                              ",
+            "   │                                              ",
+            "───┴──────────────────────────────────────────────",
+            "stdin [Row 1/3, Col 2/10]                         ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
@@ -1159,863 +7395,1051 @@ mod tests {
     }
 
     #[test]
-    fn test_filter_columns_ignore_case() {
-        let mut app = AppBuilder::new("tests/data/cities.csv")
-            .ignore_case(true)
+    fn test_irregular_filter_columns_then_rows() {
+        let mut app = AppBuilder::new("tests/data/irregular_more_fields.csv")
             .build()
             .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(50, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         step_and_draw(
             &mut app,
             &mut terminal,
-            Control::FilterColumns("city|state|wa".into()),
+            Control::FilterColumns("COL1".into()),
         );
+        step_and_draw(&mut app, &mut terminal, Control::Filter("x1".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        // Toggle to cell selection
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      City               State                                                  ",
-            "───┬──────────────────────────────┬─────────────────────────────────────────────",
-            "1  │  Youngstown         OH       │                                             ",
-            "2  │  Yankton            SD       │                                             ",
-            "3  │  Yakima             WA       │                                             ",
-            "4  │  Worcester          MA       │                                             ",
-            "5  │  Wisconsin Dells    WI       │                                             ",
-            "───┴──────────────────────────────┴─────────────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/2] [Filter \"(?i)city|state|wa\": 2/10 cols] [ignore-case]",
+            "──────────────────────────────────────────────────",
+            "      COL1                                        ",
+            "───┬──────────┬───────────────────────────────────",
+            "1  │  x1      │                                   ",
+            "   │          │                                   ",
+            "   │          │                                   ",
+            "   │          │                                   ",
+            "   │          │                                   ",
+            "───┴──────────┴───────────────────────────────────",
+            "stdin [Row 1/2, Col 1/1] [Filter \"x1\": 1/1] [Filte",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+
+        let selection = app.get_selection();
+        assert_eq!(selection, Some("x1".to_string()));
     }
 
     #[test]
-    fn test_extra_fields_in_some_rows() {
-        // Test getting column widths should not fail on data with bad formatting (some rows having
-        // more fields than the header)
-        let mut app = AppBuilder::new("tests/data/bad_double_quote.csv")
-            .build()
-            .unwrap();
+    fn test_select_range() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(35, 10);
+        let backend = TestBackend::new(120, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        let expected = vec![
-            "───────────────────────────────────",
-            "      Column1     \"column2\"        ",
-            "───┬───────────────────────────┬───",
-            "1  │  1           \"quote\"      │   ",
-            "2  │  5           \"Comma       │   ",
-            "   │                           │   ",
-            "   │                           │   ",
-            "   │                           │   ",
-            "───┴───────────────────────────┴───",
-            "stdin [Row 1/2, Col 1/2]           ",
-        ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
-    }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::SelectRange);
+        for _ in 0..3 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        }
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines[9].trim_end(),
+            "stdin [Row 4/128, Col 1/10] [len 2, integer] [Range 4 rows, sum: 171, avg: 42.75]"
+        );
 
-    #[test]
-    fn test_extra_fields_right_most_border() {
-        let mut app = AppBuilder::new("tests/data/bad_73.csv").build().unwrap();
+        // Extends beyond the rows in view
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(20));
         till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines[9].trim_end(),
+            "stdin [Row 20/128, Col 1/10] [len 2, integer] [Range 20 rows, sum: 792, avg: 39.6]"
+        );
 
-        let backend = TestBackend::new(35, 10);
-        let mut terminal = Terminal::new(backend).unwrap();
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(
+            lines[9].trim_end(),
+            "stdin [Row 20/128, Col 1/10] [len 1, text] [Range 20 rows, not numeric]"
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::SelectRange);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[9].trim_end(), "Range selection cleared");
 
+        // Sorting puts other rows at the positions of the range, so it is cleared
+        step_and_draw(&mut app, &mut terminal, Control::SelectRange);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        let expected = vec![
-            "───────────────────────────────────",
-            "      COL1     COL2                ",
-            "───┬───────────────────┬───────────",
-            "1  │  c1               │           ",
-            "2  │  c2       v2      │           ",
-            "3  │  c2       4       │           ",
-            "4  │  c3               │           ",
-            "5  │  c4               │           ",
-            "───┴───────────────────┴───────────",
-            "stdin [Row 1/13, Col 1/2]          ",
-        ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        let lines = to_lines(terminal.backend().buffer());
+        assert!(!lines[9].contains("Range"));
     }
 
     #[test]
-    fn test_sniff_delimiter() {
-        let mut app = AppBuilder::new("tests/data/small.bsv")
-            .delimiter(Delimiter::Auto)
-            .build()
-            .unwrap();
+    fn test_scroll_to_from_end() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(30, 10);
+        let backend = TestBackend::new(40, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToFromEnd(1));
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "──────────────────────────────",
-            "      COL1    COL2            ",
-            "───┬──────────────────┬───────",
-            "1  │  c1      v1      │       ",
-            "2  │  c2      v2      │       ",
-            "   │                  │       ",
-            "   │                  │       ",
-            "   │                  │       ",
-            "───┴──────────────────┴───────",
-            "stdin [Row 1/2, Col 1/2]      ",
+            "────────────────────────────────────────",
+            "        LatD    LatM    LatS    NS    … ",
+            "─────┬──────────────────────────────────",
+            "124  │  39      31      12      N     … ",
+            "125  │  50      25      11      N     … ",
+            "126  │  40      10      48      N     … ",
+            "127  │  40      19      48      N     … ",
+            "128  │  41      9       35      N     … ",
+            "─────┴──────────────────────────────────",
+            "stdin [Row 128/128, Col 1/10]           ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToFromEnd(20));
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────",
+            "        LatD    LatM    LatS    NS    … ",
+            "─────┬──────────────────────────────────",
+            "109  │  29      53      23      N     … ",
+            "110  │  43      25      48      N     … ",
+            "111  │  38      35      24      N     … ",
+            "112  │  43      36      36      N     … ",
+            "113  │  33      24      0       N     … ",
+            "─────┴──────────────────────────────────",
+            "stdin [Row 109/128, Col 1/10]           ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_multi_lines() {
-        let mut app = AppBuilder::new("tests/data/multi_lines.csv")
-            .build()
-            .unwrap();
+    fn test_marks() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(50, 30);
+        let backend = TestBackend::new(40, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::SetMark('a'));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(100));
+        step_and_draw(&mut app, &mut terminal, Control::JumpToMark('a'));
         let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very lon…    12345          │",
-            "2  │  2    thi…                   678910         │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "stdin [Row 1/3, Col 1/3]                          ",
+            "────────────────────────────────────────",
+            "      a     b                           ",
+            "───┬──────────────┬─────────────────────",
+            "3  │  A3    B3    │                     ",
+            "4  │  A4    B4    │                     ",
+            "5  │  A5    B5    │                     ",
+            "6  │  A6    B6    │                     ",
+            "7  │  A7    B7    │                     ",
+            "───┴──────────────┴─────────────────────",
+            "stdin [Row 3/5000, Col 1/2]             ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
 
-        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(false));
-        let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very long    12345          │",
-            "   │        text that surely w                   │",
-            "   │       ill not fit in your                   │",
-            "   │        small screen                         │",
-            "2  │  2    this                   678910         │",
-            "   │       is                                    │",
-            "   │       an                                    │",
-            "   │       even                                  │",
-            "   │       longer                                │",
-            "   │       text                                  │",
-            "   │       that                                  │",
-            "   │       surely                                │",
-            "   │       will                                  │",
-            "   │       not                                   │",
-            "   │       fit                                   │",
-            "   │       in                                    │",
-            "   │       your                                  │",
-            "   │       small                                 │",
-            "   │       screen                                │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "Line wrap enabled                                 ",
-        ];
+        step_and_draw(&mut app, &mut terminal, Control::JumpToMark('b'));
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        assert_eq!(lines[9], "Mark 'b' is not set                     ");
 
-        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
-        let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very         12345          │",
-            "   │       long text that                        │",
-            "   │       surely will not                       │",
-            "   │       fit in your small                     │",
-            "   │       screen                                │",
-            "2  │  2    this                   678910         │",
-            "   │       is                                    │",
-            "   │       an                                    │",
-            "   │       even                                  │",
-            "   │       longer                                │",
-            "   │       text                                  │",
-            "   │       that                                  │",
-            "   │       surely                                │",
-            "   │       will                                  │",
-            "   │       not                                   │",
-            "   │       fit                                   │",
-            "   │       in                                    │",
-            "   │       your                                  │",
-            "   │       small                                 │",
-            "   │       screen                                │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "Word wrap enabled                                 ",
-        ];
+        step_and_draw(&mut app, &mut terminal, Control::Filter("A1".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::JumpToMark('a'));
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        assert_eq!(lines[9], "Row 3 marked as 'a' is filtered out     ");
+
+        // Keys that are not a mark only close the prompt, keeping the filter
+        type_keys(&mut app, &mut terminal, "m1");
+        press_key(&mut app, &mut terminal, KeyCode::Char('\''));
+        press_key(&mut app, &mut terminal, KeyCode::Esc);
+        assert!(app.rows_view.is_filter());
+        let lines = to_lines(terminal.backend().buffer());
+        assert!(lines[9].starts_with("stdin [Row 1/"));
     }
 
     #[test]
-    fn test_multi_lines_degenerate_width() {
-        let mut app = AppBuilder::new("tests/data/multi_lines.csv")
-            .build()
-            .unwrap();
+    fn test_copy_with_clipboard_disabled() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(50, 30);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(false));
-        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
-        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
-        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
-        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
-        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
-
-        let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    …   c                                  ",
-            "───┬──────────────────────────┬───────────────────",
-            "1  │  1    …   12345          │                   ",
-            "2  │  2    …   678910         │                   ",
-            "3  │  3    …   123,456,789    │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "   │                          │                   ",
-            "───┴──────────────────────────┴───────────────────",
-            "stdin [Row 1/3, Col 1/3]                          ",
-        ];
+        step_and_draw(&mut app, &mut terminal, Control::CopySelection);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        assert_eq!(
+            lines[9],
+            "Copying is disabled by the clipboard backend none           "
+        );
     }
 
     #[test]
-    fn test_multiple_newlines() {
-        let mut app = AppBuilder::new("tests/data/multiple_newlines.csv")
-            .build()
-            .unwrap();
+    fn test_copy_column_name() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(50, 45);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
-        let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very         12345          │",
-            "   │       long text that                        │",
-            "   │       surely will not                       │",
-            "   │       fit in your small                     │",
-            "   │       screen                                │",
-            "2  │  2    this                   678910         │",
-            "   │                                             │",
-            "   │       is                                    │",
-            "   │                                             │",
-            "   │       an                                    │",
-            "   │                                             │",
-            "   │       even                                  │",
-            "   │                                             │",
-            "   │       longer                                │",
-            "   │                                             │",
-            "   │       text                                  │",
-            "   │                                             │",
-            "   │       that                                  │",
-            "   │                                             │",
-            "   │       surely                                │",
-            "   │                                             │",
-            "   │       will                                  │",
-            "   │                                             │",
-            "   │       not                                   │",
-            "   │                                             │",
-            "   │       fit                                   │",
-            "   │                                             │",
-            "   │       in                                    │",
-            "   │                                             │",
-            "   │       your                                  │",
-            "   │                                             │",
-            "   │       small                                 │",
-            "   │                                             │",
-            "   │       screen                                │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "Word wrap enabled                                 ",
-        ];
+        step_and_draw(&mut app, &mut terminal, Control::CopyColumnName);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        assert_eq!(
+            lines[9],
+            "Select a column first to copy its name                      "
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        assert_eq!(
+            app.rows_view.get_column_name_from_selection(),
+            Some("b".to_string())
+        );
+        step_and_draw(&mut app, &mut terminal, Control::CopyColumnName);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(
+            lines[9],
+            "Copying is disabled by the clipboard backend none           "
+        );
     }
 
     #[test]
-    fn test_carriage_returns() {
-        let mut app = AppBuilder::new("tests/data/multi_lines_carriage_return.csv")
+    fn test_copy_file_path() {
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut copy_file_path = |app: &mut App| {
+            step_and_draw(app, &mut terminal, Control::CopyFilePath);
+            let actual_buffer = terminal.backend().buffer().clone();
+            to_lines(&actual_buffer)[9].trim_end().to_string()
+        };
+
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
+        assert_eq!(
+            copy_file_path(&mut app),
+            "No file path to copy when reading from stdin"
+        );
+
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .original_filename("tests/data/simple.csv")
             .build()
             .unwrap();
-        till_app_ready(&app);
-
-        let backend = TestBackend::new(50, 45);
-        let mut terminal = Terminal::new(backend).unwrap();
+        assert_eq!(
+            copy_file_path(&mut app),
+            "Copying is disabled by the clipboard backend none"
+        );
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
-        let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very         12345          │",
-            "   │       long text that                        │",
-            "   │       surely will not                       │",
-            "   │       fit in your small                     │",
-            "   │       screen                                │",
-            "2  │  2    this                   678910         │",
-            "   │                                             │",
-            "   │       is                                    │",
-            "   │                                             │",
-            "   │       an                                    │",
-            "   │                                             │",
-            "   │       even                                  │",
-            "   │                                             │",
-            "   │       longer                                │",
-            "   │                                             │",
-            "   │       text                                  │",
-            "   │                                             │",
-            "   │       that                                  │",
-            "   │                                             │",
-            "   │       surely                                │",
-            "   │                                             │",
-            "   │       will                                  │",
-            "   │                                             │",
-            "   │       not                                   │",
-            "   │                                             │",
-            "   │       fit                                   │",
-            "   │                                             │",
-            "   │       in                                    │",
-            "   │                                             │",
-            "   │       your                                  │",
-            "   │                                             │",
-            "   │       small                                 │",
-            "   │                                             │",
-            "   │       screen                                │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "Word wrap enabled                                 ",
-        ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .original_filename("missing.csv")
+            .build()
+            .unwrap();
+        assert!(copy_file_path(&mut app).starts_with("Failed to resolve the path of missing.csv"));
     }
 
     #[test]
-    fn test_starts_with_newline() {
-        let mut app = AppBuilder::new("tests/data/starts_with_newline.csv")
+    fn test_scrolloff() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .scrolloff(2)
             .build()
             .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(50, 20);
+        let backend = TestBackend::new(30, 12);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        for _ in 0..6 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        }
         let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very lon…    12345          │",
-            "2  │  2    …                      678910         │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "stdin [Row 1/3, Col 1/3]                          ",
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "3  │  A3    B3    │           ",
+            "4  │  A4    B4    │           ",
+            "5  │  A5    B5    │           ",
+            "6  │  A6    B6    │           ",
+            "7  │  A7    B7    │           ",
+            "8  │  A8    B8    │           ",
+            "9  │  A9    B9    │           ",
+            "───┴──────────────┴───────────",
+            "stdin [Row 7/5000, Col 1/2]   ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
 
-        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(true));
+        for _ in 0..3 {
+            step_and_draw(&mut app, &mut terminal, Control::ScrollUp);
+        }
         let expected = vec![
-            "──────────────────────────────────────────────────",
-            "      a    b                      c               ",
-            "───┬─────────────────────────────────────────────┬",
-            "1  │  1    this is a very         12345          │",
-            "   │       long text that                        │",
-            "   │       surely will not                       │",
-            "   │       fit in your small                     │",
-            "   │       screen                                │",
-            "2  │  2                           678910         │",
-            "   │       starts with new                       │",
-            "   │       line                                  │",
-            "3  │  3    normal text now        123,456,789    │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "   │                                             │",
-            "───┴─────────────────────────────────────────────┴",
-            "Word wrap enabled                                 ",
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "2  │  A2    B2    │           ",
+            "3  │  A3    B3    │           ",
+            "4  │  A4    B4    │           ",
+            "5  │  A5    B5    │           ",
+            "6  │  A6    B6    │           ",
+            "7  │  A7    B7    │           ",
+            "8  │  A8    B8    │           ",
+            "───┴──────────────┴───────────",
+            "stdin [Row 4/5000, Col 1/2]   ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_mouse_click() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(44, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::MouseClick(15, 5));
+        assert_eq!(
+            app.rows_view.get_cell_value_from_selection(),
+            Some("B3".to_string())
+        );
+        let expected = vec![
+            "────────────────────────────────────────────",
+            "      a     b                               ",
+            "───┬──────────────┬─────────────────────────",
+            "1  │  A1    B1    │                         ",
+            "2  │  A2    B2    │                         ",
+            "3  │  A3    B3    │                         ",
+            "4  │  A4    B4    │                         ",
+            "5  │  A5    B5    │                         ",
+            "───┴──────────────┴─────────────────────────",
+            "stdin [Row 3/5000, Col 1/2] [len 2, text]   ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+
+        // Clicks on row numbers or the status bar are ignored
+        for (x, y) in [(1, 4), (8, 9)] {
+            step_and_draw(&mut app, &mut terminal, Control::MouseClick(x, y));
+            assert_eq!(
+                app.rows_view.get_cell_value_from_selection(),
+                Some("B3".to_string())
+            );
+        }
+
+        step_and_draw(&mut app, &mut terminal, Control::MouseClick(8, 3));
+        assert_eq!(
+            app.rows_view.get_cell_value_from_selection(),
+            Some("A1".to_string())
+        );
     }
 
     #[test]
-    fn test_column_widths_boundary_condition() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_compact() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .compact(true)
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(120, 10);
+        let backend = TestBackend::new(44, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::Filter("Salt Lake City".into()),
-        );
-        till_app_ready(&app);
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::FilterColumns("City".into()),
-        );
-        till_app_ready(&app);
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::MouseClick(10, 3));
+        assert_eq!(
+            app.rows_view.get_cell_value_from_selection(),
+            Some("B3".to_string())
+        );
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────",
-            "       City                                                                                                             ",
-            "────┬────────────────────┬──────────────────────────────────────────────────────────────────────────────────────────────",
-            "97  │  Salt Lake City    │                                                                                              ",
-            "    │                    │                                                                                              ",
-            "    │                    │                                                                                              ",
-            "    │                    │                                                                                              ",
-            "    │                    │                                                                                              ",
-            "────┴────────────────────┴──────────────────────────────────────────────────────────────────────────────────────────────",
-            "stdin [Row 97/128, Col 1/1] [Filter \"Salt Lake City\": 1/1] [Filter \"City\": 1/10 cols]                                   "];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
+            "   a     b                                  ",
+            "1  A1    B1                                 ",
+            "2  A2    B2                                 ",
+            "3  A3    B3                                 ",
+            "4  A4    B4                                 ",
+            "5  A5    B5                                 ",
+            "6  A6    B6                                 ",
+            "7  A7    B7                                 ",
+            "                                            ",
+            "stdin [Row 3/5000, Col 1/2] [len 2, text]   ",
+        ];
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleCompact);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "────────────────────────────────────────────",
+            "      a     b                               ",
+            "───┬──────────────┬─────────────────────────",
+            "1  │  A1    B1    │                         ",
+            "2  │  A2    B2    │                         ",
+            "3  │  A3    B3    │                         ",
+            "4  │  A4    B4    │                         ",
+            "5  │  A5    B5    │                         ",
+            "───┴──────────────┴─────────────────────────",
+            "Compact mode disabled                       ",
+        ];
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_scroll_right_most() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_row_numbers() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .hide_row_numbers(true)
+            .zero_based_row_numbers(true)
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(40, 10);
+        let backend = TestBackend::new(30, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        // TODO: why is this first nothing step needed?
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollRightMost);
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "────────────────────────────────────────",
-            "      LonS    EW    City      State     ",
-            "───┬───────────────────────────────────┬",
-            "1  │  0       W     Young…    OH       │",
-            "2  │  23            Yankt…    SD       │",
-            "3  │  36      W     Yakima    WA       │",
-            "4  │  0       W     Worce…    MA       │",
-            "5  │  11      W     Wisco…    WI       │",
-            "───┴───────────────────────────────────┴",
-            "stdin [Row 1/128, Col 7/10]             ",
+            "──────────────────────────────",
+            "a     b                       ",
+            "────────────┬─────────────────",
+            "A1    B1    │                 ",
+            "A2    B2    │                 ",
+            "A3    B3    │                 ",
+            "A4    B4    │                 ",
+            "A5    B5    │                 ",
+            "────────────┴─────────────────",
+            "stdin [Row 0/5000, Col 1/2]   ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Rows are gone to by their zero-based numbers
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(3));
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[3], "A4    B4    │                 ");
+        assert_eq!(lines[9], "stdin [Row 3/5000, Col 1/2]   ");
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(0));
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleRowNumbers);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "0  │  A1    B1    │           ",
+            "1  │  A2    B2    │           ",
+            "2  │  A3    B3    │           ",
+            "3  │  A4    B4    │           ",
+            "4  │  A5    B5    │           ",
+            "───┴──────────────┴───────────",
+            "Row numbers shown             ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_scroll_left_most() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_scrollbars() {
+        use ratatui::style::Modifier;
+
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .show_scrollbars(true)
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(40, 10);
+        let backend = TestBackend::new(50, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        // TODO: why is this first nothing step needed?
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollRightMost);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollLeftMost);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(59));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
         let expected = vec![
-            "────────────────────────────────────────",
-            "      LatD    LatM    LatS    NS    …   ",
-            "───┬────────────────────────────────────",
-            "1  │  41      5       59      N     …   ",
-            "2  │  42      52      48      N     …   ",
-            "3  │  46      35      59      N     …   ",
-            "4  │  42      16      12      N     …   ",
-            "5  │  43      37      48      N     …   ",
-            "───┴────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/10]             ",
+            "──────────────────────────────────────────────────",
+            "       LatM    LatS    NS    LonD    LonM    …    ",
+            "────┬─────────────────────────────────────────────",
+            "59  │  59      24      N     75      11      …   │",
+            "60  │  57      35      N     121     17      …   │",
+            "61  │  31      12      N     89      34      …   █",
+            "62  │  21      36      N     80      37      …   │",
+            "63  │  37      11      N     103     13      …   │",
+            "────┴───━━━━━━━━━━━━━━━━━━────────────────────────",
+            "stdin [Row 59/128, Col 2/10]                      ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+        let theme = Theme::default();
+        assert_eq!(actual_buffer[(49, 4)].fg, theme.border.fg.unwrap());
+        assert_eq!(actual_buffer[(49, 5)].fg, theme.scrollbar_thumb.fg.unwrap());
+        assert_eq!(actual_buffer[(10, 8)].fg, theme.scrollbar_thumb.fg.unwrap());
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleScrollbars);
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "       LatM    LatS    NS    LonD    LonM    …    ",
+            "────┬─────────────────────────────────────────────",
+            "59  │  59      24      N     75      11      …    ",
+            "60  │  57      35      N     121     17      …    ",
+            "61  │  31      12      N     89      34      …    ",
+            "62  │  21      36      N     80      37      …    ",
+            "63  │  37      11      N     103     13      …    ",
+            "────┴─────────────────────────────────────────────",
+            "Scrollbars disabled                               ",
+        ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        // Tracks follow the border style, and thumbs the theme
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .show_scrollbars(true)
+            .border_style(BorderStyle::Double)
+            .monochrome(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(59));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        let expected = vec![
+            "══════════════════════════════════════════════════",
+            "       LatM    LatS    NS    LonD    LonM    …    ",
+            "════╦═════════════════════════════════════════════",
+            "59  ║  59      24      N     75      11      …   ║",
+            "60  ║  57      35      N     121     17      …   ║",
+            "61  ║  31      12      N     89      34      …   █",
+            "62  ║  21      36      N     80      37      …   ║",
+            "63  ║  37      11      N     103     13      …   ║",
+            "════╩═══━━━━━━━━━━━━━━━━━━════════════════════════",
+            "stdin [Row 59/128, Col 2/10]                      ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+        assert_eq!(actual_buffer[(49, 4)].modifier, Modifier::empty());
+        assert_eq!(actual_buffer[(49, 5)].modifier, Modifier::BOLD);
+        assert_eq!(actual_buffer[(10, 8)].modifier, Modifier::BOLD);
     }
 
     #[test]
-    fn test_scroll_half_page() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_footer() {
+        let source = csv::CsvSource::Bytes(b"n,s\n1,x\n2,y\n3.5,z\n".to_vec().into());
+        let mut app = AppBuilder::from_source(source).build().unwrap();
         till_app_ready(&app);
 
         let backend = TestBackend::new(40, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        // TODO: since some states are updated late only during rendering, sometimes some extra
-        // no-ops are required to warm up the states. I don't like it, but this is how it has to be
-        // in tests for now.
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageDown);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageDown);
+        // The aggregator is started on the next step once the rows to aggregate are known
+        let wait_for_footer = |app: &mut App, terminal: &mut Terminal<TestBackend>| {
+            step_and_draw(app, terminal, Control::Nothing);
+            till_app_ready(app);
+            step_and_draw(app, terminal, Control::Nothing);
+        };
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleFooter);
+        wait_for_footer(&mut app, &mut terminal);
         let expected = vec![
             "────────────────────────────────────────",
-            "      LatD    LatM    LatS    NS    …   ",
-            "───┬────────────────────────────────────",
-            "5  │  43      37      48      N     …   ",
-            "6  │  36      5       59      N     …   ",
-            "7  │  49      52      48      N     …   ",
-            "8  │  39      11      23      N     …   ",
-            "9  │  34      14      24      N     …   ",
-            "───┴────────────────────────────────────",
-            "stdin [Row 5/128, Col 1/10]             ",
+            "      n           s                     ",
+            "───┬──────────────────────────┬─────────",
+            "1  │  1           x           │         ",
+            "2  │  2           y           │         ",
+            "3  │  3.5         z           │         ",
+            "   │                          │         ",
+            "Σ  │  sum: 6.5    count: 3    │         ",
+            "───┴──────────────────────────┴─────────",
+            "Footer enabled                          ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollHalfPageUp);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("x|z".into()));
+        app.finder.as_ref().unwrap().wait_internal();
+        wait_for_footer(&mut app, &mut terminal);
         let expected = vec![
             "────────────────────────────────────────",
-            "      LatD    LatM    LatS    NS    …   ",
-            "───┬────────────────────────────────────",
-            "3  │  46      35      59      N     …   ",
-            "4  │  42      16      12      N     …   ",
-            "5  │  43      37      48      N     …   ",
-            "6  │  36      5       59      N     …   ",
-            "7  │  49      52      48      N     …   ",
-            "───┴────────────────────────────────────",
-            "stdin [Row 3/128, Col 1/10]             ",
+            "      n           s                     ",
+            "───┬──────────────────────────┬─────────",
+            "1  │  1           x           │         ",
+            "3  │  3.5         z           │         ",
+            "   │                          │         ",
+            "   │                          │         ",
+            "Σ  │  sum: 4.5    count: 2    │         ",
+            "───┴──────────────────────────┴─────────",
+            "stdin [Row 1/3, Col 1/2] [Filter \"x|z\": ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleFooter);
+        assert!(app.csv_table_state.footer.is_none());
     }
 
     #[test]
-    fn test_resize_column() {
+    fn test_selected_cell_info() {
         let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(60, 8);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        // Check column widths are adjusted correctly
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let last_line =
+            |terminal: &Terminal<TestBackend>| to_lines(terminal.backend().buffer()).pop().unwrap();
+        // Only shown in cell selection mode
+        assert_eq!(
+            last_line(&terminal),
+            "stdin [Row 1/128, Col 1/10]                                 "
+        );
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
         step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        assert_eq!(
+            last_line(&terminal),
+            "stdin [Row 1/128, Col 1/10] [len 2, integer]                "
+        );
         step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
         step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
-        step_and_draw(&mut app, &mut terminal, Control::IncreaseWidth);
-        step_and_draw(&mut app, &mut terminal, Control::IncreaseWidth);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollLeft);
-        step_and_draw(&mut app, &mut terminal, Control::DecreaseWidth);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        assert_eq!(
+            last_line(&terminal),
+            "stdin [Row 1/128, Col 1/10] [len 1, text]                   "
+        );
+    }
+
+    #[test]
+    fn test_count_distinct() {
+        let mut app = AppBuilder::new("tests/data/duplicates.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(60, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::CountDistinct);
+        app.duplicate_counter.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      LatD    …   LatS            NS    LonD    LonM    LonS    EW    City      ",
-            "───┬────────────────────────────────────────────────────────────────────────────",
-            "1  │  41      …   59              N     80      39      0       W     Young…    ",
-            "2  │  42      …   48              N     97      23      23            Yankt…    ",
-            "3  │  46      …   59              N     120     30      36      W     Yakima    ",
-            "4  │  42      …   12              N     71      48      0       W     Worce…    ",
-            "5  │  43      …   48              N     89      46      11      W     Wisco…    ",
-            "───┴────────────────────────────────────────────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/10]                                                     ",
+            "────────────────────────────────────────────────────────────",
+            "      id    name                                            ",
+            "───┬────────────────┬───────────────────────────────────────",
+            "1  │  1     a       │                                       ",
+            "2  │  2     b       │                                       ",
+            "3  │  1     a       │                                       ",
+            "4  │  3     c       │                                       ",
+            "5  │  2     b       │                                       ",
+            "───┴────────────────┴───────────────────────────────────────",
+            "4 distinct of 6 rows (2 duplicates)                         ",
         ];
         assert_eq!(lines, expected);
 
-        // Check overridden column widths still have  when columns are filtered
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::FilterColumns("Lat".into()),
-        );
-        till_app_ready(&app);
+        // Gone on the next action, with the count kept for next time
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      LatD    …   LatS                                                          ",
-            "───┬──────────────────────────────┬─────────────────────────────────────────────",
-            "1  │  41      …   59              │                                             ",
-            "2  │  42      …   48              │                                             ",
-            "3  │  46      …   59              │                                             ",
-            "4  │  42      …   12              │                                             ",
-            "5  │  43      …   48              │                                             ",
-            "───┴──────────────────────────────┴─────────────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/3] [Filter \"Lat\": 3/10 cols]                            ",
+            "────────────────────────────────────────────────────────────",
+            "      id    name                                            ",
+            "───┬────────────────┬───────────────────────────────────────",
+            "1  │  1     a       │                                       ",
+            "2  │  2     b       │                                       ",
+            "3  │  1     a       │                                       ",
+            "4  │  3     c       │                                       ",
+            "5  │  2     b       │                                       ",
+            "───┴────────────────┴───────────────────────────────────────",
+            "stdin [Row 2/6, Col 1/2]                                    ",
         ];
         assert_eq!(lines, expected);
+        assert!(app.duplicate_counter.is_some());
+    }
 
-        // Check reset
-        step_and_draw(&mut app, &mut terminal, Control::Reset);
+    #[test]
+    fn test_validate() {
+        let mut app = AppBuilder::new("tests/data/irregular_more_fields.csv")
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(50, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::Validate);
+        app.validator.as_ref().unwrap().wait_internal();
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        let expected = vec![
+            "┌Validation──────────────────────────────────────┐",
+            "│2 invalid rows.                                 │",
+            "│Enter: jump to the selected row, q: close       │",
+            "│                                                │",
+            "│Row 1: Expected 2 fields, found 3               │",
+            "│Row 2: Expected 2 fields, found 4               │",
+            "│                                                │",
+            "│                                                │",
+            "│                                                │",
+            "└────────────────────────────────────────────────┘",
+        ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::Select);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City          ",
-            "───┬────────────────────────────────────────────────────────────────────────────",
-            "1  │  41      5       59      N     80      39      0       W     Youngstown    ",
-            "2  │  42      52      48      N     97      23      23            Yankton       ",
-            "3  │  46      35      59      N     120     30      36      W     Yakima        ",
-            "4  │  42      16      12      N     71      48      0       W     Worcester     ",
-            "5  │  43      37      48      N     89      46      11      W     Wisconsin…    ",
-            "───┴────────────────────────────────────────────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/10]                                                     ",
+            "──────────────────────────────────────────────────",
+            "      COL1    COL2                                ",
+            "───┬──────────────────┬───────────────────────────",
+            "1  │  x1      x2      │                           ",
+            "2  │  y1      y2      │                           ",
+            "   │                  │                           ",
+            "   │                  │                           ",
+            "   │                  │                           ",
+            "───┴──────────────────┴───────────────────────────",
+            "stdin [Row 2/2, Col 1/2] [2 invalid rows]         ",
         ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_sorting() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_jump_to_ragged_row() {
+        let source = csv::CsvSource::Bytes(b"a,b\n1,2\n3\n4,5\n6,7,8\n".to_vec().into());
+        let mut app = AppBuilder::from_source(source).build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::NextRaggedRow);
+        assert_eq!(app.rows_view.selected_record_num(), Some(2));
+        step_and_draw(&mut app, &mut terminal, Control::NextRaggedRow);
+        assert_eq!(app.rows_view.selected_record_num(), Some(4));
+        step_and_draw(&mut app, &mut terminal, Control::NextRaggedRow);
+        assert_eq!(app.rows_view.selected_record_num(), Some(4));
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("No rows below with a different number of fields than the header")
+        );
+        step_and_draw(&mut app, &mut terminal, Control::PrevRaggedRow);
+        assert_eq!(app.rows_view.selected_record_num(), Some(2));
+    }
+
+    #[test]
+    fn test_jump_to_empty_cell() {
+        let source = csv::CsvSource::Bytes(b"a,b\n1,x\n2,\n3,y\n4,\n5\n6,z\n".to_vec().into());
+        let mut app = AppBuilder::from_source(source).build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(40, 12);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("Select a column first to jump to its empty cells")
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(2));
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(4));
+        // A missing field counts as empty
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(5));
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(5));
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("No empty cells below in column b")
+        );
+        step_and_draw(&mut app, &mut terminal, Control::PrevEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(4));
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleWrapSearch);
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(2));
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("Search wrapped to the top")
+        );
+
+        // Rows filtered out are skipped
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^[1456]$".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTop);
+        step_and_draw(&mut app, &mut terminal, Control::NextEmptyInColumn);
+        assert_eq!(app.rows_view.selected_record_num(), Some(4));
+    }
+
+    #[test]
+    fn test_jump_to_group() {
+        let source = csv::CsvSource::Bytes(b"k,v\nb,1\na,2\nb,3\na,4\nc,5\n".to_vec().into());
+        let mut app = AppBuilder::from_source(source).build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(100, 10);
+        let backend = TestBackend::new(40, 12);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        // Sort by City (no tie)
-        for _ in 0..8 {
-            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
-        }
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
         step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
         till_app_ready(&app);
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
 
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
+        // Sorted as a, a, b, b, c
+        step_and_draw(&mut app, &mut terminal, Control::NextGroup);
+        assert_eq!(app.rows_view.selected_record_num(), Some(1));
+        step_and_draw(&mut app, &mut terminal, Control::NextGroup);
+        assert_eq!(app.rows_view.selected_record_num(), Some(5));
+        step_and_draw(&mut app, &mut terminal, Control::NextGroup);
+        assert_eq!(app.rows_view.selected_record_num(), Some(5));
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("No other values below in column k")
+        );
+
+        step_and_draw(&mut app, &mut terminal, Control::PrevGroup);
+        assert_eq!(app.rows_view.selected_record_num(), Some(1));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        assert_eq!(app.rows_view.selected_record_num(), Some(3));
+        // From within a group, to the start of the one before
+        step_and_draw(&mut app, &mut terminal, Control::PrevGroup);
+        assert_eq!(app.rows_view.selected_record_num(), Some(2));
+        step_and_draw(&mut app, &mut terminal, Control::PrevGroup);
+        assert_eq!(
+            app.transient_message.as_deref(),
+            Some("No other values above in column k")
+        );
+
+        // Over the filtered rows only
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^[ac]$".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTop);
+        step_and_draw(&mut app, &mut terminal, Control::NextGroup);
+        assert_eq!(app.rows_view.selected_record_num(), Some(5));
+    }
+
+    #[test]
+    fn test_scroll_selection_position() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
+        till_app_ready(&app);
+
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollTo(20));
+        step_and_draw(&mut app, &mut terminal, Control::CenterSelection);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────────────────────────",
-            "        LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▴]      State             ",
-            "─────┬─────────────────────────────────────────────────────────────────────────────────────┬────────",
-            "128  │  41      9       35      N     81      14      23      W     Ravenna       OH       │        ",
-            "127  │  40      19      48      N     75      55      48      W     Reading       PA       │        ",
-            "126  │  40      10      48      N     122     14      23      W     Red Bluff     CA       │        ",
-            "125  │  50      25      11      N     104     39      0       W     Regina        SA       │        ",
-            "124  │  39      31      12      N     119     48      35      W     Reno          NV       │        ",
-            "─────┴─────────────────────────────────────────────────────────────────────────────────────┴────────",
-            "stdin [Row 128/128, Col 1/10]                                                                       ",
+            "──────────────────────────────",
+            "       a      b               ",
+            "────┬────────────────┬────────",
+            "18  │  A18    B18    │        ",
+            "19  │  A19    B19    │        ",
+            "20  │  A20    B20    │        ",
+            "21  │  A21    B21    │        ",
+            "22  │  A22    B22    │        ",
+            "────┴────────────────┴────────",
+            "stdin [Row 20/5000, Col 1/2]  ",
         ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
 
-        // Check descending
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
-        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::SelectionToBottom);
+        let expected = vec![
+            "──────────────────────────────",
+            "       a      b               ",
+            "────┬────────────────┬────────",
+            "16  │  A16    B16    │        ",
+            "17  │  A17    B17    │        ",
+            "18  │  A18    B18    │        ",
+            "19  │  A19    B19    │        ",
+            "20  │  A20    B20    │        ",
+            "────┴────────────────┴────────",
+            "stdin [Row 20/5000, Col 1/2]  ",
+        ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
+        assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::SelectionToTop);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────────────────────────",
-            "      LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City [▾]           State          ",
-            "───┬──────────────────────────────────────────────────────────────────────────────────────────┬─────",
-            "1  │  41      5       59      N     80      39      0       W     Youngstown         OH       │     ",
-            "2  │  42      52      48      N     97      23      23            Yankton            SD       │     ",
-            "3  │  46      35      59      N     120     30      36      W     Yakima             WA       │     ",
-            "4  │  42      16      12      N     71      48      0       W     Worcester          MA       │     ",
-            "5  │  43      37      48      N     89      46      11      W     Wisconsin Dells    WI       │     ",
-            "───┴──────────────────────────────────────────────────────────────────────────────────────────┴─────",
-            "stdin [Row 1/128, Col 1/10]                                                                         ",
+            "──────────────────────────────",
+            "       a      b               ",
+            "────┬────────────────┬────────",
+            "20  │  A20    B20    │        ",
+            "21  │  A21    B21    │        ",
+            "22  │  A22    B22    │        ",
+            "23  │  A23    B23    │        ",
+            "24  │  A24    B24    │        ",
+            "────┴────────────────┴────────",
+            "stdin [Row 20/5000, Col 1/2]  ",
         ];
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+
+        // Other keys only cancel the pending z, keeping the filter
+        step_and_draw(&mut app, &mut terminal, Control::Filter("A2".into()));
+        till_app_ready(&app);
+        type_keys(&mut app, &mut terminal, "zq");
+        assert!(app.rows_view.is_filter());
+        let lines = to_lines(terminal.backend().buffer());
+        assert!(lines[9].starts_with("stdin [Row "));
     }
 
     #[test]
-    fn test_sorting_with_filter() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_visible_rows() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .columns_regex(Some("City|State".into()))
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
         let backend = TestBackend::new(80, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        // Sort by City (no tie)
-        for _ in 0..8 {
-            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
-        }
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
-        till_app_ready(&app);
-
-        // Toggle back to row selection mode before filtering
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollPageDown);
 
-        step_and_draw(&mut app, &mut terminal, Control::Filter("San".into()));
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::FilterColumns("Lat|City".into()),
-        );
+        let headers: Vec<&str> = app.headers().iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(headers, vec!["City", "State"]);
+
+        let rows = app.visible_rows();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0].record_num, 6);
+        assert_eq!(rows[0].fields, vec!["Winston-Salem", "NC"]);
+    }
 
+    #[test]
+    fn test_on_selection_change() {
+        let events = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        let events_clone = events.clone();
+        let callback = view::SelectionCallback(Box::new(move |event| {
+            events_clone.borrow_mut().push(event);
+        }));
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .on_selection_change(callback)
+            .build()
+            .unwrap();
         till_app_ready(&app);
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
 
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
-        let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "       LatD    LatM    LatS    City [▴]                                         ",
-            "────┬────────────────────────────────────────────┬──────────────────────────────",
-            "96  │  31      27      35      San Angelo        │                              ",
-            "95  │  29      25      12      San Antonio       │                              ",
-            "94  │  34      6       36      San Bernardino    │                              ",
-            "93  │  32      42      35      San Diego         │                              ",
-            "91  │  37      46      47      San Francisco     │                              ",
-            "────┴────────────────────────────────────────────┴──────────────────────────────",
-            "stdin [Row 96/128, Col 1/4] [Filter \"San\": 1/11] [Filter \"Lat|City\": 4/10 cols] ",
-        ];
-        assert_eq!(lines, expected);
+        let backend = TestBackend::new(30, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
 
-        // Check descending
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
-        till_app_ready(&app);
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "       LatD    LatM    LatS    City [▾]                                         ",
-            "────┬───────────────────────────────────────────┬───────────────────────────────",
-            "86  │  38      26      23      Santa Rosa       │                               ",
-            "87  │  35      40      48      Santa Fe         │                               ",
-            "88  │  34      25      11      Santa Barbara    │                               ",
-            "89  │  33      45      35      Santa Ana        │                               ",
-            "92  │  41      27      0       Sandusky         │                               ",
-            "────┴───────────────────────────────────────────┴───────────────────────────────",
-            "stdin [Row 86/128, Col 1/4] [Filter \"San\": -/11] [Filter \"Lat|City\": 4/10 cols] ",
+            view::SelectionEvent {
+                record_num: Some(1),
+                column_name: None,
+                value: None,
+            },
+            view::SelectionEvent {
+                record_num: Some(2),
+                column_name: None,
+                value: None,
+            },
+            view::SelectionEvent {
+                record_num: None,
+                column_name: Some("a".to_string()),
+                value: None,
+            },
+            view::SelectionEvent {
+                record_num: Some(2),
+                column_name: Some("a".to_string()),
+                value: Some("A2".to_string()),
+            },
+            view::SelectionEvent {
+                record_num: Some(2),
+                column_name: Some("b".to_string()),
+                value: Some("B2".to_string()),
+            },
         ];
-        assert_eq!(lines, expected);
+        assert_eq!(*events.borrow(), expected);
     }
 
     #[test]
-    fn test_no_headers() {
-        let mut app = AppBuilder::new("tests/data/no_headers.csv")
-            .no_headers(true)
+    fn test_initial_position() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .initial_row(20)
+            .initial_column("LonD")
             .build()
             .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(30, 10);
+        let backend = TestBackend::new(40, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        for _ in 0..7 {
-            step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
-        }
-
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         let expected = vec![
-            "──────────────────────────────",
-            "      1     2                 ",
-            "───┬──────────────┬───────────",
-            "4  │  A4    B4    │           ",
-            "5  │  A5    B5    │           ",
-            "6  │  A6    B6    │           ",
-            "7  │  A7    B7    │           ",
-            "8  │  A8    B8    │           ",
-            "───┴──────────────┴───────────",
-            "stdin [Row 8/20, Col 1/2]     ",
+            "────────────────────────────────────────",
+            "       LonD    LonM    LonS    EW    …  ",
+            "────┬───────────────────────────────────",
+            "20  │  82      20      59      W     …  ",
+            "21  │  89      38      23      W     …  ",
+            "22  │  87      49      48      W     …  ",
+            "23  │  97      6       36      W     …  ",
+            "24  │  75      55      11      W     …  ",
+            "────┴───────────────────────────────────",
+            "stdin [Row 20/128, Col 5/10]            ",
         ];
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
@@ -2023,341 +8447,584 @@ mod tests {
     }
 
     #[test]
-    fn test_cli_columns_option() {
+    fn test_initial_position_out_of_range() {
         let mut app = AppBuilder::new("tests/data/cities.csv")
-            .columns_regex(Some("Lat".to_string()))
+            .initial_row(1000)
+            .initial_column("Nope")
             .build()
             .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
 
-        let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      LatD    LatM    LatS                                                      ",
-            "───┬──────────────────────────┬─────────────────────────────────────────────────",
-            "1  │  41      5       59      │                                                 ",
-            "2  │  42      52      48      │                                                 ",
-            "3  │  46      35      59      │                                                 ",
-            "4  │  42      16      12      │                                                 ",
-            "5  │  43      37      48      │                                                 ",
-            "───┴──────────────────────────┴─────────────────────────────────────────────────",
-            "stdin [Row 1/128, Col 1/3] [Filter \"Lat\": 3/10 cols]                            ",
-        ];
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
-        assert_eq!(lines, expected);
+        assert_eq!(
+            lines[9],
+            "Row 1000 is out of range, showing row 128 instead           "
+        );
+        assert_eq!(app.rows_view.selected_record_num(), Some(128));
     }
 
     #[test]
-    fn test_cli_filter_option() {
+    fn test_render_to_lines() {
         let mut app = AppBuilder::new("tests/data/cities.csv")
-            .filter_regex(Some("San".to_string()))
+            .columns_regex(Some("City|State".into()))
+            .filter_regex(Some("^Wi".into()))
             .build()
             .unwrap();
+
+        let lines = app.render_to_lines(50, 8).unwrap();
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      City               State                    ",
+            "───┬──────────────────────────────┬───────────────",
+            "5  │  Wisconsin Dells    WI       │               ",
+            "6  │  Winston-Salem      NC       │               ",
+            "7  │  Winnipeg           MB       │               ",
+            "───┴──────────────────────────────┴───────────────",
+            "stdin [Row 5/128, Col 1/2] [Filter \"^Wi\": 1/11] [F",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_command_palette() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(90, 10);
         let mut terminal = Terminal::new(backend).unwrap();
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
 
+        step_and_draw(&mut app, &mut terminal, Control::CommandPalette);
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::BufferContent("order ROWS".into()),
+        );
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
-            "────┬───────────────────────────────────────────────────────────────────────────",
-            "86  │  38      26      23      N     122     43      12      W     Santa Ro…    ",
-            "87  │  35      40      48      N     105     56      59      W     Santa Fe     ",
-            "88  │  34      25      11      N     119     41      59      W     Santa Ba…    ",
-            "89  │  33      45      35      N     117     52      12      W     Santa Ana    ",
-            "90  │  37      20      24      N     121     52      47      W     San Jose     ",
-            "────┴───────────────────────────────────────────────────────────────────────────",
-            "stdin [Row 86/128, Col 1/10] [Filter \"San\": 1/11]                               ",
+            "┌Commands────────────────────────────────────────────────────────────────────────────────┐",
+            "│> order ROWS                                                                            │",
+            "│Type to filter, Enter: run the selected command, Esc: close                             │",
+            "│                                                                                        │",
+            "│reverse_order           ~                  Reverse the order of the rows                │",
+            "│                                                                                        │",
+            "│                                                                                        │",
+            "│                                                                                        │",
+            "│                                                                                        │",
+            "└────────────────────────────────────────────────────────────────────────────────────────┘",
         ];
+        assert_eq!(lines, expected);
+
+        // Enter runs the highlighted command and closes the palette
+        step_and_draw(&mut app, &mut terminal, Control::Select);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "──────────────────────────────────────────────────────────────────────────────────────────",
+            "         a        b                                                                       ",
+            "──────┬────────────────────┬──────────────────────────────────────────────────────────────",
+            "5000  │  A5000    B5000    │                                                              ",
+            "4999  │  A4999    B4999    │                                                              ",
+            "4998  │  A4998    B4998    │                                                              ",
+            "4997  │  A4997    B4997    │                                                              ",
+            "4996  │  A4996    B4996    │                                                              ",
+            "──────┴────────────────────┴──────────────────────────────────────────────────────────────",
+            "stdin [Row 5000/5000, Col 1/2] [Reversed]                                                 ",
+        ];
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_filter_rows_for_specific_column() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_command_palette_every_command() {
+        let num_commands = palette::commands(&KeyMap::default()).len();
+        for i in 0..num_commands {
+            let mut app = AppBuilder::new("tests/data/small.csv").build().unwrap();
+            till_app_ready(&app);
+            app.step(&Control::CommandPalette).unwrap();
+            for _ in 0..i {
+                app.step(&Control::ScrollDown).unwrap();
+            }
+            let action = app.command_palette_state.selected_action().unwrap();
+            let control = InputHandler::new(KeyMap::default()).run_action(action);
+            assert!(
+                !matches!(control, Control::Nothing),
+                "{action:?} runs nothing"
+            );
+            app.step(&Control::Select).unwrap();
+            assert!(!app.command_palette_state.is_active());
+        }
+    }
+
+    #[test]
+    fn test_help_search() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(80, 8);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
-        step_and_draw(&mut app, &mut terminal, Control::Filter("^1".into()));
-
-        till_app_ready(&app);
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        app.help_page_state.activate();
+        app.input_handler.enter_help_mode();
+        step_and_draw(
+            &mut app,
+            &mut terminal,
+            Control::BufferContent("Sort".into()),
+        );
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        assert_eq!(
+            lines[7],
+            "└Find: Sort────────────────────────────────────────────────────────────────────┘"
+        );
 
+        step_and_draw(&mut app, &mut terminal, Control::Find("Sort".into()));
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToNextFound);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "       LatD    LatM    LatS    NS    LonD    LonM    LonS    EW    City         ",
-            "────┬───────────────────────────────────────────────────────────────────────────",
-            "4   │  42      16      12      N     71      48      0       W     Worcester    ",
-            "8   │  39      11      23      N     78      9       36      W     Winchest…    ",
-            "9   │  34      14      24      N     77      55      11      W     Wilmingt…    ",
-            "12  │  41      15      0       N     77      0       0       W     Williams…    ",
-            "20  │  31      13      11      N     82      20      59      W     Waycross     ",
-            "────┴───────────────────────────────────────────────────────────────────────────",
-            "stdin [Row 4/128, Col 1/10] [Filter \"^1\" in LatM: -/19]                         ",
+            "┌Help──────────────────────────────────────────────────────────────────────────┐",
+            "│Shift + ↓ (or J)        : Sort rows by the selected column                    │",
+            "│~                       : Reverse the order of the rows                       │",
+            "│# (in Cell mode)        : Find and highlight rows like the selected cell      │",
+            "│@ (in Cell mode)        : Filter rows like the selected cell                  │",
+            "│y                       : Copy the selected row or cell to clipboard          │",
+            "│Y (in Column/Cell mode) : Copy the name of the selected column to clipboard   │",
+            "└Find \"Sort\": 2/2 (n: next, N: previous)───────────────────────────────────────┘",
+        ];
+        assert_eq!(lines, expected);
+        // Matches are highlighted
+        let x = lines[1][..lines[1].find("Sort").unwrap()].chars().count() as u16;
+        assert_eq!(
+            actual_buffer[(x, 1)].fg,
+            ratatui::style::Color::Rgb(200, 0, 0)
+        );
+        assert_eq!(actual_buffer[(x - 1, 1)].fg, ratatui::style::Color::Reset);
+
+        // Wraps around to the first match
+        step_and_draw(&mut app, &mut terminal, Control::ScrollToNextFound);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "┌Help──────────────────────────────────────────────────────────────────────────┐",
+            "│Mouse click (on header) : Sort rows by the clicked column                     │",
+            "│Mouse wheel             : Scroll one row up or down                           │",
+            "│>                       : Increase selected column's width                    │",
+            "│<                       : Decrease selected column's width                    │",
+            "│Shift + ↓ (or J)        : Sort rows by the selected column                    │",
+            "│~                       : Reverse the order of the rows                       │",
+            "└Find \"Sort\": 1/2 (n: next, N: previous)───────────────────────────────────────┘",
         ];
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_filter_like_cell() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_message_log() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        // Enter cell selection mode
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-
-        // Select the State column
-        for _ in 0..10 {
-            step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
-        }
-        step_and_draw(&mut app, &mut terminal, Control::FilterLikeCell);
-
-        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::CopyColumnName);
+        step_and_draw(&mut app, &mut terminal, Control::Undo);
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ShowMessages);
+        let actual_buffer = terminal.backend().buffer().clone();
+        let lines = to_lines(&actual_buffer);
+        let expected = vec![
+            "┌Messages──────────────────────────────────────────────────┐",
+            "│Recent messages, newest first                             │",
+            "│q: close                                                  │",
+            "│                                                          │",
+            "│ 0s ago  Nothing to undo                                  │",
+            "│ 0s ago  Select a column first to copy its name           │",
+            "│                                                          │",
+            "│                                                          │",
+            "│                                                          │",
+            "└──────────────────────────────────────────────────────────┘",
+        ];
+        assert_eq!(lines, expected);
 
+        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
         let actual_buffer = terminal.backend().buffer().clone();
         let lines = to_lines(&actual_buffer);
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "       LatS    NS    LonD    LonM    LonS    EW    City            State        ",
-            "────┬───────────────────────────────────────────────────────────────────────┬───",
-            "1   │  59      N     80      39      0       W     Youngstown      OH       │   ",
-            "50  │  0       N     83      32      24      W     Toledo          OH       │   ",
-            "62  │  36      N     80      37      12      W     Steubenville    OH       │   ",
-            "65  │  11      N     83      48      35      W     Springfield     OH       │   ",
-            "92  │  0       N     82      42      35      W     Sandusky        OH       │   ",
-            "────┴───────────────────────────────────────────────────────────────────────┴───",
-            "stdin [Row 1/128, Col 3/10] [Filter \"^OH$\" in State: 1/6]                       ",
+            "┌Messages──────────────────────────────────────────────────┐",
+            "│Recent messages, newest first                             │",
+            "│q: close                                                  │",
+            "│                                                          │",
+            "│ 0s ago  Select a column first to copy its name           │",
+            "│                                                          │",
+            "│                                                          │",
+            "│                                                          │",
+            "│                                                          │",
+            "└──────────────────────────────────────────────────────────┘",
         ];
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_filter_like_cell_escape() {
-        let mut app = AppBuilder::new("tests/data/filter.csv").build().unwrap();
+    fn test_message_log_every_message() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .startup_message("Columns differ")
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(80, 10);
+        let backend = TestBackend::new(60, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        // Enter cell selection mode
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        // Every message is logged as it is set, starting with the one shown at startup
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::RecordMacro('a'));
+        step_and_draw(&mut app, &mut terminal, Control::PlayMacro('a'));
+        step_and_draw(&mut app, &mut terminal, Control::StopRecordingMacro);
+        assert_eq!(
+            app.message_log.messages().collect::<Vec<_>>(),
+            vec![
+                "Macro 'a' not recorded, it would play itself",
+                "Macro 'a' is not recorded",
+                "Columns differ"
+            ]
+        );
+    }
 
-        // Filter like the selected cell
-        step_and_draw(&mut app, &mut terminal, Control::FilterLikeCell);
+    #[test]
+    fn test_column_focus() {
+        let mut app = AppBuilder::new("tests/data/simple.csv").build().unwrap();
         till_app_ready(&app);
 
-        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let backend = TestBackend::new(30, 8);
+        let mut terminal = Terminal::new(backend).unwrap();
 
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleColumnFocus);
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────",
-            "      a             b                                                           ",
-            "───┬─────────────────────┬──────────────────────────────────────────────────────",
-            "1  │  $(#1#2#.3)    1    │                                                      ",
-            "   │                     │                                                      ",
-            "   │                     │                                                      ",
-            "   │                     │                                                      ",
-            "   │                     │                                                      ",
-            "───┴─────────────────────┴──────────────────────────────────────────────────────",
-            "stdin [Row 1/3, Col 1/2] [Filter \"^\\$\\(\\#1\\#2\\#\\.3\\)$\" in a: 1/1]               ",
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "1  │  A1    B1    │           ",
+            "2  │  A2    B2    │           ",
+            "3  │  A3    B3    │           ",
+            "───┴──────────────┴───────────",
+            "Select a column first to focus",
         ];
         assert_eq!(lines, expected);
+
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::Find("1".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────",
+            "      a     b                 ",
+            "───┬──────────────┬───────────",
+            "1  │  A1    B1    │           ",
+            "2  │  A2    B2    │           ",
+            "3  │  A3    B3    │           ",
+            "───┴──────────────┴───────────",
+            "stdin [Row 1/5000, Col 1/2] [F",
+        ];
+        assert_eq!(lines, expected);
+
+        let fg = |x, y| terminal.backend().buffer()[(x, y)].fg;
+        let dimmed = ratatui::style::Color::Rgb(96, 96, 96);
+        // Other columns are dimmed, except for matches
+        assert_eq!(fg(6, 1), dimmed);
+        assert_eq!(fg(6, 4), dimmed);
+        assert_eq!(fg(7, 3), ratatui::style::Color::Rgb(200, 0, 0));
+        assert_eq!(fg(12, 1), ratatui::style::Color::Reset);
+
+        // Back to normal without a selected column
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        let fg = |x, y| terminal.backend().buffer()[(x, y)].fg;
+        assert_eq!(fg(6, 4), ratatui::style::Color::Reset);
     }
 
     #[test]
-    fn test_input_cursor() {
-        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
+    fn test_column_spacing() {
+        let mut app = AppBuilder::new("tests/data/cities.csv")
+            .column_spacing(1)
+            .line_number_spacing(0)
+            .build()
+            .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(30, 10);
+        let backend = TestBackend::new(50, 8);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::BufferContent("abc".into()),
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "  LatD LatM LatS NS LonD LonM LonS EW City       …",
+            "─┬────────────────────────────────────────────────",
+            "1│41   5    59   N  80   39   0    W  Youngstown …",
+            "2│42   52   48   N  97   23   23      Yankton    …",
+            "3│46   35   59   N  120  30   36   W  Yakima     …",
+            "─┴────────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10]                       ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Clicks land on the columns where they are drawn
+        let x = lines[3][..lines[3].find("59").unwrap()].chars().count() as u16;
+        step_and_draw(&mut app, &mut terminal, Control::MouseClick(x, 3));
+        assert_eq!(
+            app.rows_view.get_cell_value_from_selection(),
+            Some("59".to_string())
         );
-        assert_eq!(app.csv_table_state.cursor_xy, Some((3, 9)));
+
+        // Changing the spacing lays out the columns again
+        app.csv_table_state.column_spacing = 6;
+        app.csv_table_state.line_number_spacing = 3;
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "──────────────────────────────",
-            "      La…    La…    La…    …  ",
-            "───┬──────────────────────────",
-            "1  │  41     5      59     …  ",
-            "2  │  42     52     48     …  ",
-            "3  │  46     35     59     …  ",
-            "4  │  42     16     12     …  ",
-            "5  │  43     37     48     …  ",
-            "───┴──────────────────────────",
-            "abc                           ",
+            "──────────────────────────────────────────────────",
+            "        LatD      LatM      LatS      NS      …   ",
+            "────┬─────────────────────────────────────────────",
+            "1   │   41        5         59        N       …   ",
+            "2   │   42        52        48        N       …   ",
+            "3   │   46        35        59        N       …   ",
+            "────┴─────────────────────────────────────────────",
+            "stdin [Row 1/128, Col 1/10] [len 2, integer]      ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_one_wide_column_but_terminal_is_wider() {
-        let mut app = AppBuilder::new("tests/data/one_wide_column.txt")
+    fn test_border_style() {
+        let mut app = AppBuilder::new("tests/data/simple.csv")
+            .border_style(BorderStyle::Double)
             .build()
             .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(180, 8);
+        let backend = TestBackend::new(30, 8);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
-            "────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────",
-            "      id    text                                                                                                                                        label                       ",
-            "───┬──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┬─────────────",
-            "1  │  1     this is a very very very very very very very very very very very very very very very very very very very very very very very long thing     hotdog        │             ",
-            "2  │  2     this is a very very very very very very very very very very very very very very very very very very very very very very very short thing    not_hotdog    │             ",
-            "   │                                                                                                                                                                  │             ",
-            "───┴──────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────────┴─────────────",
-            "stdin [Row 1/2, Col 1/3]                                                                                                                                                            ",
+            "══════════════════════════════",
+            "      a     b                 ",
+            "═══╦══════════════╦═══════════",
+            "1  ║  A1    B1    ║           ",
+            "2  ║  A2    B2    ║           ",
+            "3  ║  A3    B3    ║           ",
+            "═══╩══════════════╩═══════════",
+            "stdin [Row 1/5000, Col 1/2]   ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+
+        // Pages shown over the table are framed in the same style
+        app.csv_table_state.border_style = BorderStyle::Rounded;
+        app.help_page_state.activate();
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        assert_eq!(lines[0].chars().next(), Some('╭'));
+        assert_eq!(lines[7].chars().last(), Some('╯'));
     }
 
     #[test]
-    fn test_echo_column() {
-        let mut app = AppBuilder::new("tests/data/cities.csv")
-            .echo_column("City")
+    fn test_units_row() {
+        let mut app = AppBuilder::new("tests/data/units.csv")
+            .units_row(true)
             .build()
             .unwrap();
         till_app_ready(&app);
 
-        let backend = TestBackend::new(180, 8);
+        let backend = TestBackend::new(50, 9);
         let mut terminal = Terminal::new(backend).unwrap();
 
-        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
-        step_and_draw(&mut app, &mut terminal, Control::ScrollDown);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      station    depth    temperature             ",
+            "                 m        degC                    ",
+            "───┬─────────────────────────────────────┬────────",
+            "1  │  B          30       4.5            │        ",
+            "2  │  A          10       12.25          │        ",
+            "3  │  C          20       8.5            │        ",
+            "───┴─────────────────────────────────────┴────────",
+            "stdin [Row 1/3, Col 1/3]                          ",
+        ];
+        assert_eq!(lines, expected);
 
-        let selection = app.get_selection();
-        assert_eq!(selection, Some("Worcester".to_string()));
-    }
+        // The units stay in place when sorting
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ToggleSort);
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      station    depth    temperature [▴]         ",
+            "                 m        degC                    ",
+            "───┬───────────────────────────────────────────┬──",
+            "1  │  B          30       4.5                  │  ",
+            "3  │  C          20       8.5                  │  ",
+            "2  │  A          10       12.25                │  ",
+            "───┴───────────────────────────────────────────┴──",
+            "stdin [Row 1/3, Col 1/3]                          ",
+        ];
+        assert_eq!(lines, expected);
 
-    #[test]
-    fn test_echo_column_not_found() {
-        let app = AppBuilder::new("tests/data/cities.csv")
-            .echo_column("Cityz")
-            .build();
-        if let Err(e) = app {
-            assert_eq!(e.to_string(), "Column name not found: Cityz");
-        } else {
-            panic!("Expected error");
-        }
+        // Nor are they filtered out
+        let mut app = AppBuilder::new("tests/data/units.csv")
+            .units_row(true)
+            .build()
+            .unwrap();
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Filter("^(m|A)$".into()));
+        till_app_ready(&app);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      station    depth    temperature             ",
+            "                 m        degC                    ",
+            "───┬─────────────────────────────────────┬────────",
+            "2  │  A          10       12.25          │        ",
+            "   │                                     │        ",
+            "   │                                     │        ",
+            "───┴─────────────────────────────────────┴────────",
+            "stdin [Row 2/3, Col 1/3] [Filter \"^(m|A)$\": 1/1]  ",
+        ];
+        assert_eq!(lines, expected);
     }
 
     #[test]
-    fn test_irregular_columns_scrolling() {
-        let mut app = AppBuilder::new("tests/data/irregular_last_row.csv")
-            .no_headers(true)
-            .build()
-            .unwrap();
+    fn test_wrap_header() {
+        let source = csv::CsvSource::Bytes(
+            b"id,very_long_descriptive_column_name,another_long_descriptive_name\n1,x,y\n2,z,w\n"
+                .to_vec()
+                .into(),
+        );
+        let mut app = AppBuilder::from_source(source).build().unwrap();
         till_app_ready(&app);
 
         let backend = TestBackend::new(50, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
             "──────────────────────────────────────────────────",
-            "      1            2            3            4    ",
-            "───┬──────────────────────────────────────────────",
-            "1  │  AAAAAAAA…    BBBBBBBB…    AAAAAAAA…    …    ",
-            "2  │  AAAAAAAA…    BBBBBBBB…    AAAAAAAA…    …    ",
-            "3  │  A                                           ",
-            "   │                                              ",
-            "   │                                              ",
-            "───┴──────────────────────────────────────────────",
-            "stdin [Row 1/3, Col 1/10]                         ",
+            "      id    very_long_desc…    another_long_…     ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1     x                  y                 │",
+            "2  │  2     z                  w                 │",
+            "   │                                             │",
+            "   │                                             │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "stdin [Row 1/2, Col 1/3]                          ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
 
-        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        // Column names are wrapped along with the values
+        step_and_draw(&mut app, &mut terminal, Control::ToggleLineWrap(false));
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
             "──────────────────────────────────────────────────",
-            "      2            3            4            5    ",
-            "───┬──────────────────────────────────────────────",
-            "1  │  BBBBBBBB…    AAAAAAAA…    BBBBBBBB…    …    ",
-            "2  │  BBBBBBBB…    AAAAAAAA…    BBBBBBBB…    …    ",
-            "3  │                                              ",
-            "   │                                              ",
-            "   │                                              ",
-            "───┴──────────────────────────────────────────────",
-            "stdin [Row 1/3, Col 2/10]                         ",
+            "      id    very_long_descr    another_long_d     ",
+            "            iptive_column_n    escriptive_nam     ",
+            "            ame                e                  ",
+            "───┬─────────────────────────────────────────────┬",
+            "1  │  1     x                  y                 │",
+            "2  │  2     z                  w                 │",
+            "   │                                             │",
+            "───┴─────────────────────────────────────────────┴",
+            "Line wrap enabled                                 ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
+        // Rows are sized for the wrapped header in the same frame
+        assert_eq!(app.rows_view.num_rows(), 3);
     }
 
     #[test]
-    fn test_irregular_filter_columns_then_rows() {
-        let mut app = AppBuilder::new("tests/data/irregular_more_fields.csv")
-            .build()
-            .unwrap();
+    fn test_column_types() {
+        let mut app = AppBuilder::new("tests/data/cities.csv").build().unwrap();
         till_app_ready(&app);
 
         let backend = TestBackend::new(50, 10);
         let mut terminal = Terminal::new(backend).unwrap();
 
+        step_and_draw(&mut app, &mut terminal, Control::ToggleColumnTypes);
         step_and_draw(&mut app, &mut terminal, Control::Nothing);
-        step_and_draw(
-            &mut app,
-            &mut terminal,
-            Control::FilterColumns("COL1".into()),
-        );
-        step_and_draw(&mut app, &mut terminal, Control::Filter("x1".into()));
-        // Toggle to cell selection
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-        step_and_draw(&mut app, &mut terminal, Control::ToggleSelectionType);
-
+        let lines = to_lines(terminal.backend().buffer());
         let expected = vec![
             "──────────────────────────────────────────────────",
-            "      COL1                                        ",
-            "───┬──────────┬───────────────────────────────────",
-            "1  │  x1      │                                   ",
-            "   │          │                                   ",
-            "   │          │                                   ",
-            "   │          │                                   ",
-            "   │          │                                   ",
-            "───┴──────────┴───────────────────────────────────",
-            "stdin [Row 1/2, Col 1/1] [Filter \"x1\": 1/1] [Filte",
+            "      LatD    LatM    LatS    NS        LonD    … ",
+            "      int     int     int     string    int     … ",
+            "───┬──────────────────────────────────────────────",
+            "1  │  41      5       59      N         80      … ",
+            "2  │  42      52      48      N         97      … ",
+            "3  │  46      35      59      N         120     … ",
+            "4  │  42      16      12      N         71      … ",
+            "───┴──────────────────────────────────────────────",
+            "Showing column types inferred from the first rows ",
         ];
-        let actual_buffer = terminal.backend().buffer().clone();
-        let lines = to_lines(&actual_buffer);
         assert_eq!(lines, expected);
 
-        let selection = app.get_selection();
-        assert_eq!(selection, Some("x1".to_string()));
+        // Types are shown dimmed
+        let x = lines[2][..lines[2].find("string").unwrap()].chars().count() as u16;
+        let cell = &terminal.backend().buffer()[(x, 2)];
+        assert!(cell.modifier.contains(ratatui::style::Modifier::DIM));
+
+        // The row follows the columns shown
+        app.csv_table_state.compact = true;
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        step_and_draw(&mut app, &mut terminal, Control::ScrollRight);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "   LatS    NS        LonD    LonM    LonS    …    ",
+            "   int     string    int     int     int     …    ",
+            "1  59      N         80      39      0       W    ",
+            "2  48      N         97      23      23           ",
+            "3  59      N         120     30      36      W    ",
+            "4  12      N         71      48      0       W    ",
+            "5  48      N         89      46      11      W    ",
+            "6  59      N         80      15      0       W    ",
+            "                                                  ",
+            "stdin [Row 1/128, Col 3/10]                       ",
+        ];
+        assert_eq!(lines, expected);
+
+        // Hidden again without taking up a line
+        app.csv_table_state.compact = false;
+        step_and_draw(&mut app, &mut terminal, Control::ToggleColumnTypes);
+        step_and_draw(&mut app, &mut terminal, Control::Nothing);
+        let lines = to_lines(terminal.backend().buffer());
+        let expected = vec![
+            "──────────────────────────────────────────────────",
+            "      LatS    NS    LonD    LonM    LonS    EW    ",
+            "───┬──────────────────────────────────────────────",
+            "1  │  59      N     80      39      0       W     ",
+            "2  │  48      N     97      23      23            ",
+            "3  │  59      N     120     30      36      W     ",
+            "4  │  12      N     71      48      0       W     ",
+            "5  │  48      N     89      46      11      W     ",
+            "───┴──────────────────────────────────────────────",
+            "Column types hidden                               ",
+        ];
+        assert_eq!(lines, expected);
     }
 }
@@ -0,0 +1,35 @@
+use ratatui::symbols::line;
+use ratatui::widgets::BorderType;
+
+/// Glyphs used to draw the table borders and the frames of the pages shown over it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum BorderStyle {
+    /// Plain lines with square corners
+    #[default]
+    Square,
+    /// Plain lines with rounded corners
+    Rounded,
+    /// Double lines
+    Double,
+}
+
+impl BorderStyle {
+    /// Symbols for lines and the junctions between them
+    pub fn line_set(self) -> line::Set {
+        match self {
+            BorderStyle::Square => line::NORMAL,
+            BorderStyle::Rounded => line::ROUNDED,
+            BorderStyle::Double => line::DOUBLE,
+        }
+    }
+
+    /// Border type of blocks drawn in this style
+    pub fn border_type(self) -> BorderType {
+        match self {
+            BorderStyle::Square => BorderType::Plain,
+            BorderStyle::Rounded => BorderType::Rounded,
+            BorderStyle::Double => BorderType::Double,
+        }
+    }
+}
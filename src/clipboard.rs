@@ -0,0 +1,224 @@
+use anyhow::{anyhow, Result};
+#[cfg(all(
+    feature = "clipboard",
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+use arboard::{LinuxClipboardKind, SetExtLinux};
+use std::io::Write;
+
+/// Where copied content should be sent to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ClipboardBackend {
+    /// Use the system clipboard, falling back to OSC 52 if it is not reachable
+    #[default]
+    Auto,
+    /// Use the system clipboard only
+    Native,
+    /// Ask the terminal to set the clipboard via the OSC 52 escape sequence. Works over SSH if the
+    /// terminal emulator supports it.
+    Osc52,
+    /// Use the PRIMARY selection only, pasted with middle-click (Linux only)
+    Primary,
+    /// Disable copying to clipboard
+    None,
+}
+
+pub struct Clipboard {
+    backend: ClipboardBackend,
+    // Whether to also set the PRIMARY selection, only applicable to the system clipboard on Linux
+    #[cfg_attr(not(feature = "clipboard"), allow(dead_code))]
+    copy_to_primary: bool,
+    #[cfg(feature = "clipboard")]
+    native: Result<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn new(backend: ClipboardBackend, copy_to_primary: bool) -> Self {
+        #[cfg(feature = "clipboard")]
+        let native = match backend {
+            ClipboardBackend::Auto | ClipboardBackend::Native | ClipboardBackend::Primary => {
+                arboard::Clipboard::new().map_err(|e| anyhow!(e))
+            }
+            _ => Err(anyhow!("System clipboard not in use")),
+        };
+        Clipboard {
+            backend,
+            copy_to_primary,
+            #[cfg(feature = "clipboard")]
+            native,
+        }
+    }
+
+    /// Why copying can never succeed with the chosen backend, if the system clipboard it relies on
+    /// failed to initialize. The Auto backend falls back to OSC 52, so it is always available.
+    pub fn unavailable_reason(&self) -> Option<String> {
+        if !matches!(
+            self.backend,
+            ClipboardBackend::Native | ClipboardBackend::Primary
+        ) {
+            return None;
+        }
+        #[cfg(feature = "clipboard")]
+        return self.native.as_ref().err().map(|e| e.to_string());
+        #[cfg(not(feature = "clipboard"))]
+        return Some(NATIVE_NOT_ENABLED.to_string());
+    }
+
+    /// Whether copying was turned off by choosing the None backend
+    pub fn is_disabled(&self) -> bool {
+        self.backend == ClipboardBackend::None
+    }
+
+    pub fn set_text(&mut self, text: &str) -> Result<()> {
+        match self.backend {
+            ClipboardBackend::Auto => self.set_text_native(text).or_else(|_| set_text_osc52(text)),
+            ClipboardBackend::Native => self.set_text_native(text),
+            ClipboardBackend::Osc52 => set_text_osc52(text),
+            ClipboardBackend::Primary => self.set_text_primary(text),
+            ClipboardBackend::None => Err(anyhow!("clipboard is disabled")),
+        }
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn set_text_native(&mut self, text: &str) -> Result<()> {
+        match self.native.as_mut() {
+            Ok(clipboard) => {
+                clipboard.set_text(text).map_err(|e| anyhow!(e))?;
+                if self.copy_to_primary {
+                    // Best effort only: the regular clipboard is already set at this point
+                    let _ = Self::set_primary_selection(clipboard, text);
+                }
+                Ok(())
+            }
+            Err(e) => Err(anyhow!(e.to_string())),
+        }
+    }
+
+    #[cfg(all(
+        feature = "clipboard",
+        unix,
+        not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+    ))]
+    fn set_primary_selection(clipboard: &mut arboard::Clipboard, text: &str) -> Result<()> {
+        clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+            .map_err(|e| anyhow!(e))
+    }
+
+    #[cfg(all(
+        feature = "clipboard",
+        not(all(
+            unix,
+            not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+        ))
+    ))]
+    fn set_primary_selection(_clipboard: &mut arboard::Clipboard, _text: &str) -> Result<()> {
+        Err(anyhow!("there is no PRIMARY selection on this platform"))
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn set_text_primary(&mut self, text: &str) -> Result<()> {
+        match self.native.as_mut() {
+            Ok(clipboard) => Self::set_primary_selection(clipboard, text),
+            Err(e) => Err(anyhow!(e.to_string())),
+        }
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn set_text_native(&mut self, _text: &str) -> Result<()> {
+        Err(anyhow!(NATIVE_NOT_ENABLED))
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn set_text_primary(&mut self, _text: &str) -> Result<()> {
+        Err(anyhow!(NATIVE_NOT_ENABLED))
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+const NATIVE_NOT_ENABLED: &str =
+    "system clipboard is not enabled. Compile with the 'clipboard' feature to use it.";
+
+fn set_text_osc52(text: &str) -> Result<()> {
+    // The terminal UI is drawn on stderr, so the escape sequence needs to go there too
+    let mut output = std::io::stderr();
+    output.write_all(osc52_sequence(text).as_bytes())?;
+    output.flush()?;
+    Ok(())
+}
+
+fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).cloned().unwrap_or(0),
+            chunk.get(2).cloned().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode("é,ü".as_bytes()), "w6ksw7w=");
+    }
+
+    #[test]
+    #[cfg(feature = "clipboard")]
+    fn test_unavailable_reason() {
+        let failed_native = |backend| Clipboard {
+            backend,
+            copy_to_primary: false,
+            native: Err(anyhow!("no display")),
+        };
+        assert_eq!(
+            failed_native(ClipboardBackend::Native).unavailable_reason(),
+            Some("no display".to_string())
+        );
+        assert_eq!(
+            failed_native(ClipboardBackend::Primary).unavailable_reason(),
+            Some("no display".to_string())
+        );
+        assert_eq!(
+            failed_native(ClipboardBackend::Auto).unavailable_reason(),
+            None
+        );
+        assert_eq!(
+            failed_native(ClipboardBackend::Osc52).unavailable_reason(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("A1"), "\x1b]52;c;QTE=\x07");
+    }
+}
@@ -0,0 +1,55 @@
+/// Spreadsheet style label of the column at the given index among all columns: A to Z, then AA,
+/// AB and so on
+pub fn column_letters(index: usize) -> String {
+    let mut letters = vec![];
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        letters.push((b'A' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Index of the column labelled by the given letters, in either case. None if it isn't made of
+/// letters only.
+pub fn parse_column_letters(s: &str) -> Option<usize> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut n: usize = 0;
+    for c in s.chars() {
+        let digit = (c.to_ascii_uppercase() as u8 - b'A') as usize + 1;
+        n = n.checked_mul(26)?.checked_add(digit)?;
+    }
+    Some(n - 1)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_column_letters() {
+        assert_eq!(column_letters(0), "A");
+        assert_eq!(column_letters(25), "Z");
+        assert_eq!(column_letters(26), "AA");
+        assert_eq!(column_letters(51), "AZ");
+        assert_eq!(column_letters(52), "BA");
+        assert_eq!(column_letters(701), "ZZ");
+        assert_eq!(column_letters(702), "AAA");
+    }
+
+    #[test]
+    fn test_parse_column_letters() {
+        for i in [0, 25, 26, 51, 52, 701, 702, 12345] {
+            assert_eq!(parse_column_letters(&column_letters(i)), Some(i));
+        }
+        assert_eq!(parse_column_letters("q"), Some(16));
+        assert_eq!(parse_column_letters("aA"), Some(26));
+        assert_eq!(parse_column_letters(""), None);
+        assert_eq!(parse_column_letters("A1"), None);
+        assert_eq!(parse_column_letters("é"), None);
+    }
+}
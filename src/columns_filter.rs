@@ -2,36 +2,60 @@ use regex::Regex;
 
 #[derive(Debug)]
 pub struct ColumnsFilter {
-    pattern: Regex,
+    /// Regex or list of names the columns were selected with, as shown in the status line
+    pattern: String,
     indices: Vec<usize>,
     filtered_headers: Vec<String>,
     filtered_flags: Vec<bool>,
     num_columns_before_filter: usize,
     disabled_because_no_match: bool,
+    /// Names given to select columns by that no column has
+    missing_names: Vec<String>,
 }
 
 impl ColumnsFilter {
     pub fn new(pattern: Regex, headers: &[String]) -> Self {
+        let indices = headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| pattern.is_match(header))
+            .map(|(i, _)| i)
+            .collect();
+        Self::from_indices(pattern.to_string(), indices, headers, vec![])
+    }
+
+    /// Keep the columns with the given names, in the order of the names rather than of the
+    /// columns in the file. Names that no column has are skipped.
+    pub fn from_names(names: &[String], headers: &[String]) -> Self {
         let mut indices = vec![];
-        let mut filtered_headers: Vec<String> = vec![];
-        let mut filtered_flags: Vec<bool> = vec![];
-        for (i, header) in headers.iter().enumerate() {
-            if pattern.is_match(header) {
-                indices.push(i);
-                filtered_headers.push(header.clone());
-                filtered_flags.push(true);
-            } else {
-                filtered_flags.push(false);
+        let mut missing_names = vec![];
+        for name in names {
+            match headers.iter().position(|header| header == name) {
+                Some(i) if !indices.contains(&i) => indices.push(i),
+                Some(_) => {}
+                None => missing_names.push(name.clone()),
             }
         }
-        let disabled_because_no_match;
-        if indices.is_empty() {
-            indices = (0..headers.len()).collect();
-            filtered_headers = headers.into();
-            disabled_because_no_match = true;
-        } else {
-            disabled_because_no_match = false;
+        Self::from_indices(names.join(","), indices, headers, missing_names)
+    }
+
+    fn from_indices(
+        pattern: String,
+        indices: Vec<usize>,
+        headers: &[String],
+        missing_names: Vec<String>,
+    ) -> Self {
+        let mut filtered_flags = vec![false; headers.len()];
+        for &i in &indices {
+            filtered_flags[i] = true;
         }
+        let disabled_because_no_match = indices.is_empty();
+        let indices = if disabled_because_no_match {
+            (0..headers.len()).collect()
+        } else {
+            indices
+        };
+        let filtered_headers = indices.iter().map(|&i| headers[i].clone()).collect();
         Self {
             pattern,
             indices,
@@ -39,6 +63,7 @@ impl ColumnsFilter {
             filtered_flags,
             num_columns_before_filter: headers.len(),
             disabled_because_no_match,
+            missing_names,
         }
     }
 
@@ -50,8 +75,8 @@ impl ColumnsFilter {
         &self.indices
     }
 
-    pub fn pattern(&self) -> Regex {
-        self.pattern.to_owned()
+    pub fn pattern(&self) -> &str {
+        &self.pattern
     }
 
     pub fn num_filtered(&self) -> usize {
@@ -62,10 +87,23 @@ impl ColumnsFilter {
         self.num_columns_before_filter
     }
 
+    /// Number of columns matching the pattern, which is 0 if the filter is disabled because of it
+    pub fn num_matched(&self) -> usize {
+        if self.disabled_because_no_match {
+            0
+        } else {
+            self.num_filtered()
+        }
+    }
+
     pub fn disabled_because_no_match(&self) -> bool {
         self.disabled_because_no_match
     }
 
+    pub fn missing_names(&self) -> &[String] {
+        &self.missing_names
+    }
+
     pub fn is_column_filtered(&self, index: usize) -> bool {
         self.filtered_flags.get(index).cloned().unwrap_or(false)
     }
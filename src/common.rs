@@ -6,9 +6,21 @@ pub enum InputMode {
     GotoLine,
     Find,
     Filter,
+    FilterWithin,
     FilterColumns,
     Option,
+    Mark,
+    JumpToMark,
+    RecordMacro,
+    PlayMacro,
+    ScrollSelection,
+    ExportMatches,
+    GotoColumn,
     Help,
+    HelpFind,
+    Validation,
+    MessageLog,
+    CommandPalette,
 }
 
 impl fmt::Display for InputMode {
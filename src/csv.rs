@@ -1,13 +1,17 @@
 extern crate csv;
 
-use csv::{Position, Reader, ReaderBuilder};
+use csv::{Position, Reader, ReaderBuilder, Terminator};
 use std::cmp::max;
 use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time;
 
 use crate::errors::CsvlensResult;
+#[cfg(feature = "parquet")]
+use crate::io::GrowingFile;
+use crate::stack::Stack;
 
 fn string_record_to_vec(record: &csv::StringRecord) -> Vec<String> {
     let mut string_vec = Vec::with_capacity(record.len());
@@ -17,38 +21,249 @@ fn string_record_to_vec(record: &csv::StringRecord) -> Vec<String> {
     string_vec
 }
 
+/// Column names from the header of the reader, or numbers from 1 if the file has no headers
+fn read_headers<R: Read>(reader: &mut Reader<R>, no_headers: bool) -> CsvlensResult<Vec<String>> {
+    let headers_record = if no_headers {
+        let mut dummy_headers = csv::StringRecord::new();
+        for (i, _) in reader.headers()?.into_iter().enumerate() {
+            dummy_headers.push_field((i + 1).to_string().as_str());
+        }
+        dummy_headers
+    } else {
+        reader.headers()?.clone()
+    };
+    Ok(string_record_to_vec(&headers_record))
+}
+
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Where the csv content is read from
+#[derive(Clone, Debug)]
+pub enum CsvSource {
+    Path(String),
+    Bytes(Arc<[u8]>),
+    /// Records of several files one after another, generated as they are read
+    Stack(Arc<Stack>),
+    /// Content written in the background, e.g. converted from parquet, read as it is written
+    #[cfg(feature = "parquet")]
+    Growing(Arc<GrowingFile>),
+}
+
+impl CsvSource {
+    /// Open a new independent reader positioned at the start of the content
+    pub fn open(&self) -> CsvlensResult<Box<dyn ReadSeek>> {
+        match self {
+            CsvSource::Path(path) => Ok(Box::new(File::open(path)?)),
+            CsvSource::Bytes(bytes) => Ok(Box::new(Cursor::new(bytes.clone()))),
+            CsvSource::Stack(stack) => Ok(Box::new(stack.open())),
+            #[cfg(feature = "parquet")]
+            CsvSource::Growing(file) => Ok(Box::new(file.open()?)),
+        }
+    }
+
+    /// Size of the content in bytes, or of what is written so far if it is written in the
+    /// background
+    pub fn len(&self) -> CsvlensResult<u64> {
+        match self {
+            CsvSource::Path(path) => Ok(File::open(path)?.metadata()?.len()),
+            CsvSource::Bytes(bytes) => Ok(bytes.len() as u64),
+            CsvSource::Stack(stack) => stack.len(),
+            #[cfg(feature = "parquet")]
+            CsvSource::Growing(file) => Ok(file.written_len()),
+        }
+    }
+
+    /// Why the content is incomplete, if writing it in the background stopped short
+    pub fn error(&self) -> Option<String> {
+        match self {
+            CsvSource::Path(_) | CsvSource::Bytes(_) => None,
+            CsvSource::Stack(stack) => stack.error(),
+            #[cfg(feature = "parquet")]
+            CsvSource::Growing(file) => file.error(),
+        }
+    }
+}
+
+/// How densely record positions are indexed in the background for seeking to arbitrary rows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PosTableConfig {
+    /// Target number of positions to keep for the whole file
+    pub num_entries: u64,
+    /// Minimum number of bytes between two positions, so that small files are not indexed at
+    /// every record
+    pub minimum_interval: u64,
+}
+
+impl Default for PosTableConfig {
+    fn default() -> Self {
+        PosTableConfig {
+            num_entries: 10000,
+            minimum_interval: 500,
+        }
+    }
+}
+
+impl PosTableConfig {
+    /// Number of bytes between two positions for a file of the given size
+    fn interval(&self, filesize: u64) -> u64 {
+        max(
+            max(self.minimum_interval, 1),
+            filesize / max(self.num_entries, 1),
+        )
+    }
+}
+
 pub struct CsvConfig {
-    path: String,
+    source: CsvSource,
     delimiter: u8,
     no_headers: bool,
+    /// Byte ending each record. None for the default of any of \r\n, \n or \r.
+    terminator: Option<u8>,
+    /// Only the first this many records are read, if set
+    max_rows: Option<u64>,
+    /// Whether the first record holds the units of the columns rather than data
+    units_row: bool,
+    /// Separator grouping the digits of numbers, left out when they are sorted or summed
+    thousands_separator: Option<char>,
+    pos_table_config: PosTableConfig,
 }
 
 impl CsvConfig {
+    #[cfg(test)]
     pub fn new(path: &str, delimiter: u8, no_headers: bool) -> CsvConfig {
+        CsvConfig::from_source(CsvSource::Path(path.to_string()), delimiter, no_headers)
+    }
+
+    pub fn from_source(source: CsvSource, delimiter: u8, no_headers: bool) -> CsvConfig {
         CsvConfig {
-            path: path.to_string(),
+            source,
             delimiter,
             no_headers,
+            terminator: None,
+            max_rows: None,
+            units_row: false,
+            thousands_separator: None,
+            pos_table_config: PosTableConfig::default(),
+        }
+    }
+
+    pub fn with_terminator(mut self, terminator: Option<u8>) -> CsvConfig {
+        self.terminator = terminator;
+        self
+    }
+
+    pub fn with_max_rows(mut self, max_rows: Option<u64>) -> CsvConfig {
+        self.max_rows = max_rows;
+        self
+    }
+
+    pub fn with_units_row(mut self, units_row: bool) -> CsvConfig {
+        self.units_row = units_row;
+        self
+    }
+
+    pub fn with_thousands_separator(mut self, thousands_separator: Option<char>) -> CsvConfig {
+        self.thousands_separator = thousands_separator;
+        self
+    }
+
+    pub fn with_pos_table_config(mut self, pos_table_config: PosTableConfig) -> CsvConfig {
+        self.pos_table_config = pos_table_config;
+        self
+    }
+
+    /// New reader of the records, positioned after the header and the units row if any, that
+    /// stops after the maximum number of records to read
+    pub fn new_reader(&self) -> CsvlensResult<RecordsReader> {
+        Ok(RecordsReader {
+            reader: self.new_seekable_reader()?,
+            num_records_left: self.max_records(),
+        })
+    }
+
+    /// New reader like [`CsvConfig::new_reader`], for seeking to the positions of records found
+    /// earlier. It reads past the maximum number of records to read.
+    pub fn new_seekable_reader(&self) -> CsvlensResult<Reader<Box<dyn ReadSeek>>> {
+        let mut reader = self.new_reader_with_units()?;
+        if self.units_row {
+            reader.read_byte_record(&mut csv::ByteRecord::new())?;
         }
+        Ok(reader)
     }
 
-    pub fn new_reader(&self) -> CsvlensResult<Reader<File>> {
+    fn new_reader_with_units(&self) -> CsvlensResult<Reader<Box<dyn ReadSeek>>> {
         let reader = ReaderBuilder::new()
             .flexible(true)
             .delimiter(self.delimiter)
             .has_headers(!self.no_headers)
-            .from_path(self.path.as_str())?;
+            .terminator(self.terminator.map_or(Terminator::CRLF, Terminator::Any))
+            .from_reader(self.source.open()?);
         Ok(reader)
     }
 
-    pub fn filename(&self) -> &str {
-        self.path.as_str()
+    /// Open the raw content with the units row left out, for parsers other than the csv reader.
+    /// The header line, if any, is kept.
+    pub fn open_without_units(&self) -> CsvlensResult<Box<dyn Read + Send>> {
+        let mut source = self.source.open()?;
+        if !self.units_row {
+            return Ok(Box::new(source));
+        }
+        let mut reader = self.new_reader_with_units()?;
+        let header_end = if self.no_headers {
+            0
+        } else {
+            reader.byte_headers()?;
+            reader.position().byte()
+        };
+        reader.read_byte_record(&mut csv::ByteRecord::new())?;
+        let units_end = reader.position().byte();
+
+        let mut header = vec![0; header_end as usize];
+        source.read_exact(&mut header)?;
+        source.seek(SeekFrom::Start(units_end))?;
+        Ok(Box::new(Cursor::new(header).chain(source)))
+    }
+
+    /// Values of the units row, or None if there is no units row
+    pub fn read_units(&self) -> CsvlensResult<Option<Vec<String>>> {
+        if !self.units_row {
+            return Ok(None);
+        }
+        let mut record = csv::StringRecord::new();
+        if !self.new_reader_with_units()?.read_record(&mut record)? {
+            return Ok(None);
+        }
+        Ok(Some(string_record_to_vec(&record)))
+    }
+
+    /// Column names, without setting up a reader of the records
+    pub fn read_headers(&self) -> CsvlensResult<Vec<String>> {
+        read_headers(&mut self.new_reader_with_units()?, self.no_headers)
+    }
+
+    pub fn source(&self) -> &CsvSource {
+        &self.source
     }
 
     pub fn delimiter(&self) -> u8 {
         self.delimiter
     }
 
+    pub fn terminator(&self) -> Option<u8> {
+        self.terminator
+    }
+
+    /// Number of records to read at most
+    pub fn max_records(&self) -> usize {
+        self.max_rows.map_or(usize::MAX, |n| n as usize)
+    }
+
+    pub fn thousands_separator(&self) -> Option<char> {
+        self.thousands_separator
+    }
+
     pub fn no_headers(&self) -> bool {
         self.no_headers
     }
@@ -57,29 +272,84 @@ impl CsvConfig {
         !self.no_headers
     }
 
+    /// Number of records at the start that are not data: the header and the units row
+    pub fn num_leading_records(&self) -> u64 {
+        self.has_headers() as u64 + self.units_row as u64
+    }
+
     /// Convert position to a 0-based record index
     pub fn position_to_record_index(&self, position: u64) -> u64 {
-        if self.no_headers {
-            position
-        } else {
-            position - 1
-        }
+        position - self.num_leading_records()
     }
 
     /// Convert position to a 1-based record number
     pub fn position_to_record_num(&self, position: u64) -> u64 {
-        if self.no_headers {
-            position + 1
-        } else {
-            position
+        position + 1 - self.num_leading_records()
+    }
+}
+
+/// Reader of the records from the start of a source, stopping after the maximum number of records
+/// to read
+pub struct RecordsReader {
+    reader: Reader<Box<dyn ReadSeek>>,
+    num_records_left: usize,
+}
+
+impl RecordsReader {
+    pub fn headers(&mut self) -> csv::Result<&csv::StringRecord> {
+        self.reader.headers()
+    }
+
+    pub fn byte_headers(&mut self) -> csv::Result<&csv::ByteRecord> {
+        self.reader.byte_headers()
+    }
+
+    /// Position of the next record
+    pub fn position(&self) -> &Position {
+        self.reader.position()
+    }
+
+    pub fn read_record(&mut self, record: &mut csv::StringRecord) -> csv::Result<bool> {
+        if self.num_records_left == 0 {
+            return Ok(false);
         }
+        self.num_records_left -= 1;
+        self.reader.read_record(record)
+    }
+
+    pub fn read_byte_record(&mut self, record: &mut csv::ByteRecord) -> csv::Result<bool> {
+        if self.num_records_left == 0 {
+            return Ok(false);
+        }
+        self.num_records_left -= 1;
+        self.reader.read_byte_record(record)
+    }
+
+    pub fn records(&mut self) -> impl Iterator<Item = csv::Result<csv::StringRecord>> + '_ {
+        std::iter::from_fn(move || {
+            let mut record = csv::StringRecord::new();
+            self.read_record(&mut record)
+                .map(|more| more.then_some(record))
+                .transpose()
+        })
+    }
+
+    pub fn byte_records(&mut self) -> impl Iterator<Item = csv::Result<csv::ByteRecord>> + '_ {
+        std::iter::from_fn(move || {
+            let mut record = csv::ByteRecord::new();
+            self.read_byte_record(&mut record)
+                .map(|more| more.then_some(record))
+                .transpose()
+        })
     }
 }
 
 pub struct CsvLensReader {
     config: Arc<CsvConfig>,
-    reader: Reader<File>,
+    reader: Reader<Box<dyn ReadSeek>>,
     pub headers: Vec<String>,
+    /// Values of the units row, which is not part of the records
+    pub units: Option<Vec<String>>,
     internal: Arc<Mutex<ReaderInternalState>>,
 }
 
@@ -122,18 +392,9 @@ struct GetRowIndex {
 
 impl CsvLensReader {
     pub fn new(config: Arc<CsvConfig>) -> CsvlensResult<Self> {
-        let mut reader = config.new_reader()?;
-
-        let headers_record = if config.no_headers() {
-            let mut dummy_headers = csv::StringRecord::new();
-            for (i, _) in reader.headers()?.into_iter().enumerate() {
-                dummy_headers.push_field((i + 1).to_string().as_str());
-            }
-            dummy_headers
-        } else {
-            reader.headers()?.clone()
-        };
-        let headers = string_record_to_vec(&headers_record);
+        let mut reader = config.new_seekable_reader()?;
+        let headers = read_headers(&mut reader, config.no_headers())?;
+        let units = config.read_units()?;
 
         let (m_internal, _handle) = ReaderInternalState::init_internal(config.clone());
 
@@ -141,6 +402,7 @@ impl CsvLensReader {
             config: config.clone(),
             reader,
             headers,
+            units,
             internal: m_internal,
         };
         Ok(reader)
@@ -163,15 +425,17 @@ impl CsvLensReader {
     }
 
     fn get_rows_impl(&mut self, indices: &[u64]) -> CsvlensResult<(Vec<Row>, GetRowsStats)> {
+        let max_records = self.config.max_records() as u64;
         let mut get_row_indices = indices
             .iter()
             .enumerate()
+            .filter(|x| *x.1 < max_records)
             .map(|x| GetRowIndex {
                 record_index: *x.1,
                 order_index: x.0,
             })
             .collect::<Vec<_>>();
-        get_row_indices.sort_by(|a, b| a.record_index.cmp(&b.record_index));
+        get_row_indices.sort_by_key(|a| a.record_index);
         self._get_rows_impl_sorted(&get_row_indices)
     }
 
@@ -187,7 +451,7 @@ impl CsvLensReader {
 
         let tic = time::Instant::now();
         let pos_table = self.get_pos_table();
-        stats.pos_table_elapsed = Some(tic.elapsed());
+        stats.pos_table_elapsed = Some(time::Instant::now().saturating_duration_since(tic));
         stats.pos_table_entry = pos_table.len();
 
         let mut pos_iter = pos_table.iter();
@@ -235,8 +499,8 @@ impl CsvLensReader {
                 let record_position = records.reader().position().record();
                 if let Some(r) = records.next() {
                     stats.log_parsed_record();
-                    // no effective pre-seeking happened, this is still the header
-                    if self.config.has_headers() && record_position == 0 {
+                    // no effective pre-seeking happened, this is still the header or the units
+                    if record_position < self.config.num_leading_records() {
                         continue;
                     }
                     if self.config.position_to_record_index(record_position) == wanted.record_index
@@ -285,6 +549,58 @@ impl CsvLensReader {
         Ok((res, stats))
     }
 
+    /// Index of the record the given byte offset falls in, clamped to the first and last records.
+    /// None if there are no records.
+    pub fn record_index_at_byte(&mut self, byte: u64) -> CsvlensResult<Option<u64>> {
+        let pos_table = self.get_pos_table();
+        self.reader
+            .seek(last_position_where(&pos_table, |pos| pos.byte() <= byte))?;
+        let max_records = self.config.max_records() as u64;
+        let mut found = None;
+        let mut records = self.reader.byte_records();
+        loop {
+            let position = records.reader().position().clone();
+            if records.next().is_none() {
+                break;
+            }
+            if self.config.has_headers() && position.record() == 0 {
+                continue;
+            }
+            let record_index = self.config.position_to_record_index(position.record());
+            if record_index >= max_records || (found.is_some() && position.byte() > byte) {
+                break;
+            }
+            found = Some(record_index);
+        }
+        Ok(found)
+    }
+
+    /// Byte offset where the given record starts, or None if there is no such record
+    pub fn byte_at_record_index(&mut self, record_index: u64) -> CsvlensResult<Option<u64>> {
+        let pos_table = self.get_pos_table();
+        let config = &self.config;
+        self.reader.seek(last_position_where(&pos_table, |pos| {
+            config.position_to_record_index(pos.record()) <= record_index
+        }))?;
+        let mut records = self.reader.byte_records();
+        loop {
+            let position = records.reader().position().clone();
+            if records.next().is_none() {
+                return Ok(None);
+            }
+            if config.has_headers() && position.record() == 0 {
+                continue;
+            }
+            let i = config.position_to_record_index(position.record());
+            if i == record_index {
+                return Ok(Some(position.byte()));
+            }
+            if i > record_index {
+                return Ok(None);
+            }
+        }
+    }
+
     pub fn get_total_line_numbers(&self) -> Option<usize> {
         let res = self.internal.lock().unwrap().total_line_number;
         res
@@ -301,12 +617,38 @@ impl CsvLensReader {
         res
     }
 
+    /// Fraction of the file indexed so far, or None if indexing is done or the file size is not
+    /// known
+    pub fn get_indexing_progress(&self) -> Option<f64> {
+        let m = self.internal.lock().unwrap();
+        if m.done || m.filesize == 0 {
+            return None;
+        }
+        Some(m.bytes_indexed as f64 / m.filesize as f64)
+    }
+
+    /// Index of the first record after the given one with a different number of fields than the
+    /// header, among those indexed so far
+    pub fn get_next_ragged_record(&self, record_index: u64) -> Option<u64> {
+        let m = self.internal.lock().unwrap();
+        let i = m.ragged_records.partition_point(|&x| x <= record_index);
+        m.ragged_records.get(i).cloned()
+    }
+
+    /// Index of the last record before the given one with a different number of fields than the
+    /// header
+    pub fn get_prev_ragged_record(&self, record_index: u64) -> Option<u64> {
+        let m = self.internal.lock().unwrap();
+        let i = m.ragged_records.partition_point(|&x| x < record_index);
+        i.checked_sub(1).map(|i| m.ragged_records[i])
+    }
+
     pub fn get_pos_table(&self) -> Vec<Position> {
         let res = self.internal.lock().unwrap().pos_table.clone();
         res
     }
 
-    #[cfg(test)]
+    /// Block until the background thread is done
     pub fn wait_internal(&self) {
         loop {
             if self.internal.lock().unwrap().done {
@@ -317,6 +659,16 @@ impl CsvLensReader {
     }
 }
 
+/// Last position in the pos table meeting the condition, or the start of the content if none
+/// does. The table is in file order and the condition is expected to hold for a prefix of it.
+fn last_position_where(pos_table: &[Position], condition: impl Fn(&Position) -> bool) -> Position {
+    let i = pos_table.partition_point(condition);
+    match i.checked_sub(1) {
+        Some(i) => pos_table[i].clone(),
+        None => Position::new(),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct GetRowsStats {
     pub num_seek: u64,
@@ -347,6 +699,10 @@ impl GetRowsStats {
 struct ReaderInternalState {
     total_line_number: Option<usize>,
     pos_table: Vec<Position>,
+    filesize: u64,
+    bytes_indexed: u64,
+    // Indices of records with a different number of fields than the header, in file order
+    ragged_records: Vec<u64>,
     done: bool,
 }
 
@@ -355,6 +711,9 @@ impl ReaderInternalState {
         let internal = ReaderInternalState {
             total_line_number: None,
             pos_table: vec![],
+            filesize: 0,
+            bytes_indexed: 0,
+            ragged_records: vec![],
             done: false,
         };
 
@@ -362,32 +721,40 @@ impl ReaderInternalState {
 
         let _m = m_state.clone();
         let handle = thread::spawn(move || {
-            let filesize = File::open(config.filename())
-                .unwrap()
-                .metadata()
-                .unwrap()
-                .len();
-            let pos_table_num_entries = 10000;
-            let minimum_interval = 500; // handle small csv (don't keep pos every byte)
-            let pos_table_update_every = max(minimum_interval, filesize / pos_table_num_entries);
+            let mut filesize = config.source().len().unwrap();
+            _m.lock().unwrap().filesize = filesize;
+            let mut pos_table_update_every = config.pos_table_config.interval(filesize);
 
             // full csv parsing
-            let bg_reader = config.new_reader().unwrap();
+            let mut bg_reader = config.new_reader().unwrap();
+            let num_header_fields = bg_reader.byte_headers().map(|h| h.len()).unwrap_or(0);
             let mut n_lines = 0;
             let mut n_bytes: u64 = 0;
             let mut last_updated_at = 0;
-            let mut iter = bg_reader.into_records();
+            let mut record = csv::StringRecord::new();
             loop {
-                let next_pos = iter.reader().position().clone();
-                if iter.next().is_none() {
-                    break;
+                let next_pos = bg_reader.position().clone();
+                match bg_reader.read_record(&mut record) {
+                    Ok(false) => break,
+                    Ok(true) if record.len() != num_header_fields => {
+                        _m.lock().unwrap().ragged_records.push(n_lines as u64);
+                    }
+                    _ => {}
                 }
                 // must not include headers position here (n > 0)
                 let cur = n_bytes / pos_table_update_every;
                 if n_bytes > 0 && cur > last_updated_at {
+                    // Content written in the background keeps growing while it is indexed
+                    let len = config.source().len().unwrap_or(filesize);
+                    if len > filesize {
+                        filesize = len;
+                        pos_table_update_every = config.pos_table_config.interval(filesize);
+                    }
                     let mut m = _m.lock().unwrap();
                     m.pos_table.push(next_pos.clone());
-                    last_updated_at = cur;
+                    m.bytes_indexed = next_pos.byte();
+                    m.filesize = filesize;
+                    last_updated_at = n_bytes / pos_table_update_every;
                 }
                 n_lines += 1;
                 n_bytes = next_pos.byte();
@@ -484,6 +851,193 @@ mod tests {
         assert_eq!(rows, vec![]);
     }
 
+    #[test]
+    fn test_indexing_progress() {
+        let config = Arc::new(CsvConfig::new("tests/data/simple.csv", b',', false));
+        let r = CsvLensReader::new(config).unwrap();
+        if let Some(progress) = r.get_indexing_progress() {
+            assert!((0.0..=1.0).contains(&progress));
+        }
+        r.wait_internal();
+        assert_eq!(r.get_indexing_progress(), None);
+    }
+
+    #[test]
+    fn test_ragged_records() {
+        let config = Arc::new(CsvConfig::from_source(
+            CsvSource::Bytes(b"a,b\n1,2\n3\n4,5\n6,7,8\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        assert_eq!(r.get_next_ragged_record(0), Some(1));
+        assert_eq!(r.get_next_ragged_record(1), Some(3));
+        assert_eq!(r.get_next_ragged_record(3), None);
+        assert_eq!(r.get_prev_ragged_record(3), Some(1));
+        assert_eq!(r.get_prev_ragged_record(1), None);
+    }
+
+    #[test]
+    fn test_terminator_seek_agrees_with_sequential() {
+        // Records end at \r only, so the \n are part of the values
+        let mut content = "a,b\r".to_string();
+        for i in 0..1000 {
+            content.push_str(&format!("A{i},B\n{i}\r"));
+        }
+        let config =
+            CsvConfig::from_source(CsvSource::Bytes(content.into_bytes().into()), b',', false)
+                .with_terminator(Some(b'\r'))
+                .with_pos_table_config(PosTableConfig {
+                    num_entries: 10000,
+                    minimum_interval: 100,
+                });
+        let config = Arc::new(config);
+
+        let mut sequential = vec![];
+        for record in config.new_reader().unwrap().records() {
+            sequential.push(string_record_to_vec(&record.unwrap()));
+        }
+        assert_eq!(sequential.len(), 1000);
+        assert_eq!(sequential[999], vec!["A999", "B\n999"]);
+
+        let mut r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        assert!(r.get_pos_table().len() > 10);
+        assert_eq!(r.get_total_line_numbers(), Some(1000));
+        let indices = vec![0, 1, 345, 346, 678, 998, 999];
+        let (rows, stats) = r.get_rows_for_indices(&indices).unwrap();
+        assert!(stats.num_seek > 0);
+        for (row, index) in rows.iter().zip(indices) {
+            assert_eq!(row.record_num, index as usize + 1);
+            assert_eq!(row.fields, sequential[index as usize]);
+        }
+    }
+
+    #[test]
+    fn test_bom() {
+        let config = Arc::new(CsvConfig::new("tests/data/bom.csv", b',', false));
+        let mut r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        assert_eq!(r.headers, vec!["id", "name"]);
+        let rows = r.get_rows(0, 2).unwrap().0;
+        let expected = vec![Row::new(1, vec!["1", "a"]), Row::new(2, vec!["2", "b"])];
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_bom_no_headers() {
+        let config = Arc::new(CsvConfig::new("tests/data/bom.csv", b',', true));
+        let mut r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        assert_eq!(r.headers, vec!["1", "2"]);
+        let rows = r.get_rows(0, 2).unwrap().0;
+        let expected = vec![Row::new(1, vec!["id", "name"]), Row::new(2, vec!["1", "a"])];
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_units_row() {
+        let config =
+            Arc::new(CsvConfig::new("tests/data/units.csv", b',', false).with_units_row(true));
+        let mut r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        assert_eq!(r.headers, vec!["station", "depth", "temperature"]);
+        assert_eq!(
+            r.units,
+            Some(vec!["".to_string(), "m".to_string(), "degC".to_string()])
+        );
+        let rows = r.get_rows(0, 2).unwrap().0;
+        let expected = vec![
+            Row::new(1, vec!["B", "30", "4.5"]),
+            Row::new(2, vec!["A", "10", "12.25"]),
+        ];
+        assert_eq!(rows, expected);
+        assert_eq!(r.get_total_line_numbers(), Some(3));
+    }
+
+    #[test]
+    fn test_max_rows() {
+        let config = CsvConfig::new("tests/data/simple.csv", b',', false).with_max_rows(Some(1000));
+        let mut r = CsvLensReader::new(Arc::new(config)).unwrap();
+        r.wait_internal();
+        assert_eq!(r.get_total_line_numbers(), Some(1000));
+        assert!(r.get_pos_table().iter().all(|pos| pos.record() <= 1000));
+        let rows = r.get_rows(998, 5).unwrap().0;
+        let expected = vec![
+            Row::new(999, vec!["A999", "B999"]),
+            Row::new(1000, vec!["A1000", "B1000"]),
+        ];
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn test_byte_offsets() {
+        let config = Arc::new(CsvConfig::from_source(
+            CsvSource::Bytes(b"a,b\n1,2\n33,44\n5,6\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let mut r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        let at_byte = |r: &mut CsvLensReader, byte| r.record_index_at_byte(byte).unwrap();
+        // Within the header, clamped to the first record
+        assert_eq!(at_byte(&mut r, 0), Some(0));
+        assert_eq!(at_byte(&mut r, 4), Some(0));
+        assert_eq!(at_byte(&mut r, 7), Some(0));
+        assert_eq!(at_byte(&mut r, 8), Some(1));
+        assert_eq!(at_byte(&mut r, 13), Some(1));
+        assert_eq!(at_byte(&mut r, 14), Some(2));
+        // Past the end, clamped to the last record
+        assert_eq!(at_byte(&mut r, 100), Some(2));
+        assert_eq!(r.byte_at_record_index(0).unwrap(), Some(4));
+        assert_eq!(r.byte_at_record_index(2).unwrap(), Some(14));
+        assert_eq!(r.byte_at_record_index(3).unwrap(), None);
+    }
+
+    #[test]
+    fn test_byte_offsets_with_pos_table() {
+        let config = Arc::new(CsvConfig::new("tests/data/simple.csv", b',', false));
+        let mut r = CsvLensReader::new(config).unwrap();
+        r.wait_internal();
+        assert!(!r.get_pos_table().is_empty());
+        for record_index in [0, 1234, 3456, 4999] {
+            let byte = r.byte_at_record_index(record_index).unwrap().unwrap();
+            assert_eq!(r.record_index_at_byte(byte).unwrap(), Some(record_index));
+            assert_eq!(
+                r.record_index_at_byte(byte + 1).unwrap(),
+                Some(record_index)
+            );
+        }
+        // Reading rows is not affected by the seeks above
+        let rows = r.get_rows(1234, 1).unwrap().0;
+        assert_eq!(rows, vec![Row::new(1235, vec!["A1235", "B1235"])]);
+    }
+
+    #[test]
+    fn test_pos_table_config() {
+        let num_entries = |pos_table_config| {
+            let config = CsvConfig::new("tests/data/simple.csv", b',', false)
+                .with_pos_table_config(pos_table_config);
+            let r = CsvLensReader::new(Arc::new(config)).unwrap();
+            r.wait_internal();
+            r.get_pos_table().len()
+        };
+        let default = num_entries(PosTableConfig::default());
+        let denser = num_entries(PosTableConfig {
+            num_entries: 10000,
+            minimum_interval: 100,
+        });
+        assert!(denser > default);
+
+        // Degenerate values should not panic
+        let zero = num_entries(PosTableConfig {
+            num_entries: 0,
+            minimum_interval: 0,
+        });
+        assert!(zero <= default);
+    }
+
     #[test]
     fn test_simple_get_rows_impl_1() {
         let config = Arc::new(CsvConfig::new("tests/data/simple.csv", b',', false));
@@ -600,7 +1154,7 @@ mod tests {
         let config = Arc::new(CsvConfig::new("tests/data/simple.csv", b',', false));
         let mut r = CsvLensReader::new(config).unwrap();
         r.wait_internal();
-        let rows = r.get_rows_for_indices(&vec![1235, 1234]).unwrap().0;
+        let rows = r.get_rows_for_indices(&[1235, 1234]).unwrap().0;
         let expected = vec![
             Row::new(1236, vec!["A1236", "B1236"]),
             Row::new(1235, vec!["A1235", "B1235"]),
@@ -1,10 +1,20 @@
+use crate::csv::CsvSource;
 use crate::errors::{CsvlensError, CsvlensResult};
 
+use std::path::Path;
+
+/// Environment variable with the delimiter to use when it is neither specified nor implied by the
+/// file extension
+pub const DELIMITER_ENV_VAR: &str = "CSVLENS_DELIMITER";
+
 /// Delimiter behaviour as specified in the command line
 pub enum Delimiter {
     /// Use the default delimiter (comma)
     Default,
 
+    /// Like Default, but with this delimiter instead of comma
+    Fallback(u8),
+
     /// Use tab as the delimiter
     Tab,
 
@@ -46,14 +56,227 @@ impl Delimiter {
             Ok(Delimiter::Default)
         }
     }
+
+    /// Fall back to the delimiter in `env_value`, the value of the CSVLENS_DELIMITER environment
+    /// variable, if the delimiter is not specified. Only a single character or \t is accepted.
+    pub fn with_env_fallback(self, env_value: Option<String>) -> CsvlensResult<Self> {
+        let Delimiter::Default = self else {
+            return Ok(self);
+        };
+        match env_value {
+            Some(value) if !value.is_empty() => {
+                match Delimiter::from_arg(&Some(value.clone()), false) {
+                    Ok(Delimiter::Character(d)) => Ok(Delimiter::Fallback(d)),
+                    Ok(Delimiter::Tab) => Ok(Delimiter::Fallback(b'\t')),
+                    _ => Err(CsvlensError::DelimiterEnvInvalid(value)),
+                }
+            }
+            _ => Ok(self),
+        }
+    }
+
+    /// The delimiter to parse the source with, sniffing it from the content if auto. By default,
+    /// it goes by the extension of `filename` (or the path of the source), e.g. tab for ".tsv",
+    /// and is otherwise comma.
+    pub fn resolve(&self, source: &CsvSource, filename: Option<&str>) -> u8 {
+        match self {
+            Delimiter::Default | Delimiter::Fallback(_) => {
+                let path = filename.or(match source {
+                    CsvSource::Path(path) => Some(path.as_str()),
+                    CsvSource::Bytes(_) | CsvSource::Stack(_) => None,
+                    #[cfg(feature = "parquet")]
+                    CsvSource::Growing(_) => None,
+                });
+                let fallback = match self {
+                    Delimiter::Fallback(d) => *d,
+                    _ => b',',
+                };
+                path.and_then(delimiter_from_extension).unwrap_or(fallback)
+            }
+            Delimiter::Tab => b'\t',
+            Delimiter::Character(d) => *d,
+            Delimiter::Auto => sniff_delimiter(source).unwrap_or(b','),
+        }
+    }
+}
+
+/// Parse the command line argument for the record terminator. None (also given as "crlf") ends
+/// records at any of \r\n, \n or \r.
+pub fn terminator_from_arg(terminator_arg: &Option<String>) -> CsvlensResult<Option<u8>> {
+    let Some(s) = terminator_arg else {
+        return Ok(None);
+    };
+    match s.as_str() {
+        "crlf" => Ok(None),
+        r"\r" => Ok(Some(b'\r')),
+        r"\n" => Ok(Some(b'\n')),
+        r"\0" => Ok(Some(b'\0')),
+        _ => match s.as_bytes() {
+            [b] if b.is_ascii() => Ok(Some(*b)),
+            _ => Err(CsvlensError::TerminatorInvalid(s.clone())),
+        },
+    }
+}
+
+/// Delimiter conventionally used by files with the extension of `path`, if not comma
+fn delimiter_from_extension(path: &str) -> Option<u8> {
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "tsv" | "tab" => Some(b'\t'),
+        "psv" | "bsv" => Some(b'|'),
+        _ => None,
+    }
+}
+
+/// Delimiters checked for giving the same number of fields on every line
+const CANDIDATE_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// Number of records read to check the consistency of the number of fields
+const CONSISTENCY_SAMPLE_SIZE: usize = 200;
+
+/// Sniff the delimiter from the csv content. Delimiters giving the same number of fields on every
+/// sampled line are preferred, so that e.g. a file delimited by ';' with ',' as decimal separator
+/// is not split on the commas. Ties and files with no consistent delimiter go by the sniffer.
+pub fn sniff_delimiter(source: &CsvSource) -> Option<u8> {
+    let sniffed = sniff_with_csv_sniffer(source);
+    let consistent = consistent_delimiters(source);
+    match consistent.as_slice() {
+        [] => sniffed,
+        [delimiter] => Some(*delimiter),
+        delimiters => sniffed
+            .filter(|d| delimiters.contains(d))
+            .or(Some(delimiters[0])),
+    }
 }
 
-/// Sniff the delimiter from the file
-pub fn sniff_delimiter(filename: &str) -> Option<u8> {
+fn sniff_with_csv_sniffer(source: &CsvSource) -> Option<u8> {
     let mut sniffer = csv_sniffer::Sniffer::new();
     sniffer.sample_size(csv_sniffer::SampleSize::Records(200));
-    if let Ok(metadata) = sniffer.sniff_path(filename) {
+    let reader = source.open().ok()?;
+    if let Ok(metadata) = sniffer.sniff_reader(reader) {
         return Some(metadata.dialect.delimiter);
     }
     None
 }
+
+/// Candidate delimiters splitting every sampled record into the same number (more than one) of
+/// fields, the ones giving the most fields first
+fn consistent_delimiters(source: &CsvSource) -> Vec<u8> {
+    let mut consistent = vec![];
+    for delimiter in CANDIDATE_DELIMITERS {
+        let Ok(reader) = source.open() else {
+            return vec![];
+        };
+        let mut reader = ::csv::ReaderBuilder::new()
+            .flexible(true)
+            .has_headers(false)
+            .delimiter(delimiter)
+            .from_reader(reader);
+        let mut num_fields = None;
+        let mut is_consistent = true;
+        for record in reader.records().take(CONSISTENCY_SAMPLE_SIZE) {
+            let Ok(record) = record else {
+                is_consistent = false;
+                break;
+            };
+            if *num_fields.get_or_insert(record.len()) != record.len() {
+                is_consistent = false;
+                break;
+            }
+        }
+        match num_fields {
+            Some(n) if is_consistent && n > 1 => consistent.push((delimiter, n)),
+            _ => {}
+        }
+    }
+    // Stable, so that ties keep the order of the candidates
+    consistent.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    consistent
+        .into_iter()
+        .map(|(delimiter, _)| delimiter)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::Arc;
+
+    fn bytes(content: &str) -> CsvSource {
+        CsvSource::Bytes(Arc::from(content.as_bytes()))
+    }
+
+    #[test]
+    fn test_resolve_by_extension() {
+        let resolve = |filename| Delimiter::Default.resolve(&bytes(""), Some(filename));
+        assert_eq!(resolve("data.tsv"), b'\t');
+        assert_eq!(resolve("data.TAB"), b'\t');
+        assert_eq!(resolve("data.psv"), b'|');
+        assert_eq!(resolve("data.csv"), b',');
+        assert_eq!(resolve("data"), b',');
+        // Only when the delimiter is not specified
+        assert_eq!(
+            Delimiter::Character(b';').resolve(&bytes(""), Some("data.tsv")),
+            b';'
+        );
+        let source = CsvSource::Path("tests/data/small.bsv".to_string());
+        assert_eq!(Delimiter::Default.resolve(&source, None), b'|');
+    }
+
+    #[test]
+    fn test_env_fallback() {
+        let from_env = |arg: Option<&str>, value: &str| {
+            Delimiter::from_arg(&arg.map(str::to_string), false)
+                .unwrap()
+                .with_env_fallback(Some(value.to_string()))
+        };
+        let resolve =
+            |delimiter: Delimiter, filename| delimiter.resolve(&bytes(""), Some(filename));
+        assert_eq!(resolve(from_env(None, "|").unwrap(), "data.csv"), b'|');
+        assert_eq!(resolve(from_env(None, r"\t").unwrap(), "data"), b'\t');
+        // The extension and the arguments take precedence
+        assert_eq!(resolve(from_env(None, "|").unwrap(), "data.tsv"), b'\t');
+        assert_eq!(resolve(from_env(Some(";"), "|").unwrap(), "data.csv"), b';');
+        assert_eq!(resolve(from_env(None, "").unwrap(), "data.csv"), b',');
+        assert!(matches!(
+            from_env(None, "||"),
+            Err(CsvlensError::DelimiterEnvInvalid(_))
+        ));
+        assert!(matches!(
+            from_env(None, "auto"),
+            Err(CsvlensError::DelimiterEnvInvalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_terminator_from_arg() {
+        let parse = |arg: &str| terminator_from_arg(&Some(arg.to_string())).ok();
+        assert_eq!(terminator_from_arg(&None).ok(), Some(None));
+        assert_eq!(parse("crlf"), Some(None));
+        assert_eq!(parse(r"\r"), Some(Some(b'\r')));
+        assert_eq!(parse(r"\n"), Some(Some(b'\n')));
+        assert_eq!(parse(";"), Some(Some(b';')));
+        assert_eq!(parse("é"), None);
+        assert_eq!(parse("ab"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn test_sniff_decimal_comma() {
+        let source = CsvSource::Path("tests/data/decimal_comma.csv".to_string());
+        assert_eq!(sniff_delimiter(&source), Some(b';'));
+    }
+
+    #[test]
+    fn test_consistent_delimiters() {
+        assert_eq!(consistent_delimiters(&bytes("a,b,c\n1,2,3\n")), vec![b',']);
+        assert_eq!(consistent_delimiters(&bytes("a\tb\n1\t2\n")), vec![b'\t']);
+        // Both split every line into two fields
+        assert_eq!(
+            consistent_delimiters(&bytes("a;b,c\n1;2,3\n")),
+            vec![b',', b';']
+        );
+        assert!(consistent_delimiters(&bytes("a\nb\n")).is_empty());
+    }
+}
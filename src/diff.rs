@@ -0,0 +1,335 @@
+use crate::csv::{CsvConfig, CsvSource, ReadSeek};
+use crate::errors::{CsvlensError, CsvlensResult};
+use crate::io::SeekableFile;
+
+use csv::{Position, Reader, StringRecord};
+use std::collections::{HashMap, VecDeque};
+use tempfile::NamedTempFile;
+
+/// Name of the column added in front of the diff, showing the status of each row
+pub const STATUS_COLUMN_NAME: &str = "±";
+
+/// How a row of the diff differs between the two files
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffStatus {
+    Unchanged,
+    /// Only in the second file
+    Added,
+    /// Only in the first file
+    Removed,
+    /// In both files, with different values
+    Changed,
+}
+
+impl DiffStatus {
+    fn marker(&self) -> &'static str {
+        match self {
+            DiffStatus::Unchanged => "",
+            DiffStatus::Added => "+",
+            DiffStatus::Removed => "-",
+            DiffStatus::Changed => "~",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffRow {
+    pub status: DiffStatus,
+    /// Indices of the columns of the diff whose values changed
+    pub changed_columns: Vec<usize>,
+}
+
+/// Rows of two csv files aligned on a key column, combined into a single csv
+pub struct Diff {
+    /// The combined csv, with a status column followed by the columns of the second file and then
+    /// any columns only in the first file. Has a units row if the files do.
+    pub source: CsvSource,
+    /// Status of each record of the combined csv
+    pub rows: Vec<DiffRow>,
+    /// Temp file the combined csv is written to, which needs to outlive the source
+    pub file: SeekableFile,
+}
+
+impl Diff {
+    /// Align the records of `old` and `new` on the values of `key_column`, or the first column if
+    /// not specified. Changed rows show the values from `new`, and removed rows are placed close
+    /// to where they were in `old`. The records are read as they are needed rather than all at
+    /// once, and the combined csv is written to a temp file.
+    pub fn new(old: &CsvConfig, new: &CsvConfig, key_column: Option<&str>) -> CsvlensResult<Diff> {
+        let old_headers = old.read_headers()?;
+        let new_headers = new.read_headers()?;
+
+        let key_index = |headers: &[String]| match key_column {
+            Some(name) => headers
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| CsvlensError::ColumnNameNotFound(name.to_string())),
+            None => Ok(0),
+        };
+        let old_key_index = key_index(&old_headers)?;
+        let new_key_index = key_index(&new_headers)?;
+        let key =
+            |record: &StringRecord, index: usize| record.get(index).unwrap_or_default().to_string();
+
+        // Columns of the second file first, then those only in the first file
+        let mut headers = new_headers.clone();
+        for header in &old_headers {
+            if !headers.contains(header) {
+                headers.push(header.clone());
+            }
+        }
+        let column_in = |file_headers: &[String]| -> Vec<Option<usize>> {
+            headers
+                .iter()
+                .map(|h| file_headers.iter().position(|fh| fh == h))
+                .collect()
+        };
+        let old_columns = column_in(&old_headers);
+        let new_columns = column_in(&new_headers);
+
+        // Only the keys and positions of the old records are kept, to read them again when written
+        let mut old_by_key: HashMap<String, VecDeque<usize>> = HashMap::new();
+        let mut old_positions = vec![];
+        let mut reader = old.new_reader()?;
+        let mut record = StringRecord::new();
+        loop {
+            let position = reader.position().clone();
+            if !reader.read_record(&mut record)? {
+                break;
+            }
+            old_by_key
+                .entry(key(&record, old_key_index))
+                .or_default()
+                .push_back(old_positions.len());
+            old_positions.push(position);
+        }
+
+        // Match each new record with the first unmatched old record with the same key
+        let mut matches: Vec<Option<usize>> = vec![];
+        for r in new.new_reader()?.records() {
+            let record = r?;
+            matches.push(
+                old_by_key
+                    .get_mut(&key(&record, new_key_index))
+                    .and_then(|indices| indices.pop_front()),
+            );
+        }
+        drop(old_by_key);
+        let mut old_matched = vec![false; old_positions.len()];
+        for i in matches.iter().flatten() {
+            old_matched[*i] = true;
+        }
+
+        let mut writer = ::csv::WriterBuilder::new()
+            .flexible(true)
+            .from_writer(NamedTempFile::new()?);
+        let mut header_record = vec![STATUS_COLUMN_NAME.to_string()];
+        header_record.extend(headers.iter().cloned());
+        writer.write_record(header_record)?;
+        // Units of the columns only in the first file are taken from it
+        if let Some(new_units) = new.read_units()? {
+            let old_units = old.read_units()?.unwrap_or_default();
+            let mut units_record = vec![String::new()];
+            units_record.extend(new_columns.iter().zip(&old_columns).map(|(n, o)| {
+                n.and_then(|c| new_units.get(c))
+                    .or_else(|| o.and_then(|c| old_units.get(c)))
+                    .cloned()
+                    .unwrap_or_default()
+            }));
+            writer.write_record(units_record)?;
+        }
+
+        let mut rows = vec![];
+        let mut write_row = |status: DiffStatus,
+                             fields: Vec<String>,
+                             changed_columns: Vec<usize>|
+         -> CsvlensResult<()> {
+            let mut record = vec![status.marker().to_string()];
+            record.extend(fields);
+            writer.write_record(record)?;
+            rows.push(DiffRow {
+                status,
+                changed_columns,
+            });
+            Ok(())
+        };
+        let values = |record: &StringRecord, columns: &[Option<usize>]| -> Vec<String> {
+            columns
+                .iter()
+                .map(|c| {
+                    c.and_then(|c| record.get(c))
+                        .unwrap_or_default()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let mut old_records = OldRecords::new(old, old_positions)?;
+        let mut next_old = 0;
+        for (r, matched) in new.new_reader()?.records().zip(matches) {
+            let new_record = r?;
+            let Some(old_index) = matched else {
+                write_row(DiffStatus::Added, values(&new_record, &new_columns), vec![])?;
+                continue;
+            };
+            // Removed records up to the matched one go before it
+            while next_old < old_index {
+                if !old_matched[next_old] {
+                    let old_values = values(old_records.get(next_old)?, &old_columns);
+                    write_row(DiffStatus::Removed, old_values, vec![])?;
+                }
+                next_old += 1;
+            }
+            next_old = next_old.max(old_index + 1);
+
+            let old_values = values(old_records.get(old_index)?, &old_columns);
+            let new_values = values(&new_record, &new_columns);
+            // Shifted by one for the status column
+            let changed_columns: Vec<usize> = old_values
+                .iter()
+                .zip(new_values.iter())
+                .enumerate()
+                .filter(|(_, (o, n))| o != n)
+                .map(|(i, _)| i + 1)
+                .collect();
+            let status = if changed_columns.is_empty() {
+                DiffStatus::Unchanged
+            } else {
+                DiffStatus::Changed
+            };
+            write_row(status, new_values, changed_columns)?;
+        }
+        for (i, matched) in old_matched.into_iter().enumerate().skip(next_old) {
+            if !matched {
+                let old_values = values(old_records.get(i)?, &old_columns);
+                write_row(DiffStatus::Removed, old_values, vec![])?;
+            }
+        }
+
+        let temp_file = writer
+            .into_inner()
+            .map_err(|e| CsvlensError::Io(e.into_error()))?;
+        let file = SeekableFile::from_temp_file("diff", temp_file);
+        Ok(Diff {
+            source: CsvSource::Path(file.filename().to_owned()),
+            rows,
+            file,
+        })
+    }
+}
+
+/// Number of added, removed and changed rows
+pub fn summary(rows: &[DiffRow]) -> String {
+    let count = |status| rows.iter().filter(|r| r.status == status).count();
+    format!(
+        "{} added, {} removed, {} changed",
+        count(DiffStatus::Added),
+        count(DiffStatus::Removed),
+        count(DiffStatus::Changed)
+    )
+}
+
+/// Records of the first file read again by index, mostly one after another
+struct OldRecords {
+    reader: Reader<Box<dyn ReadSeek>>,
+    positions: Vec<Position>,
+    /// Index of the record the reader is at
+    next: usize,
+    record: StringRecord,
+}
+
+impl OldRecords {
+    fn new(config: &CsvConfig, positions: Vec<Position>) -> CsvlensResult<Self> {
+        Ok(OldRecords {
+            reader: config.new_seekable_reader()?,
+            positions,
+            next: 0,
+            record: StringRecord::new(),
+        })
+    }
+
+    fn get(&mut self, index: usize) -> CsvlensResult<&StringRecord> {
+        if index != self.next {
+            self.reader.seek(self.positions[index].clone())?;
+        }
+        self.reader.read_record(&mut self.record)?;
+        self.next = index + 1;
+        Ok(&self.record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    fn config(content: &str) -> CsvConfig {
+        CsvConfig::from_source(CsvSource::Bytes(Arc::from(content.as_bytes())), b',', false)
+    }
+
+    fn combined(diff: &Diff) -> String {
+        let mut out = String::new();
+        diff.source
+            .open()
+            .unwrap()
+            .read_to_string(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = config("id,name\n1,a\n2,b\n3,c\n4,d\n");
+        let new = config("id,name\n1,a\n3,C\n4,d\n5,e\n");
+        let diff = Diff::new(&old, &new, Some("id")).unwrap();
+        assert_eq!(
+            combined(&diff),
+            "±,id,name\n,1,a\n-,2,b\n~,3,C\n,4,d\n+,5,e\n"
+        );
+        assert_eq!(diff.rows[2].changed_columns, vec![2]);
+        assert_eq!(summary(&diff.rows), "1 added, 1 removed, 1 changed");
+    }
+
+    #[test]
+    fn test_diff_different_columns() {
+        let old = config("name,id,x\na,1,old\n");
+        let new = config("id,name,y\n1,a,new\n");
+        let diff = Diff::new(&old, &new, Some("id")).unwrap();
+        assert_eq!(combined(&diff), "±,id,name,y,x\n~,1,a,new,\n");
+        assert_eq!(diff.rows[0].changed_columns, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_diff_units_row() {
+        let units_config = |content: &str| config(content).with_units_row(true);
+        let old = units_config("id,t,x\n,s,m\n1,10,a\n");
+        let new = units_config("id,t\n,ms\n1,20\n");
+        let diff = Diff::new(&old, &new, None).unwrap();
+        assert_eq!(combined(&diff), "±,id,t,x\n,,ms,m\n~,1,20,\n");
+        assert_eq!(diff.rows.len(), 1);
+    }
+
+    #[test]
+    fn test_diff_reordered() {
+        let old = config("id,name\n1,a\n2,b\n3,c\n");
+        let new = config("id,name\n3,c\n1,A\n");
+        let diff = Diff::new(&old, &new, None).unwrap();
+        assert_eq!(combined(&diff), "±,id,name\n-,2,b\n,3,c\n~,1,A\n");
+    }
+
+    #[test]
+    fn test_diff_max_rows() {
+        let old = config("id,name\n1,a\n2,b\n3,c\n").with_max_rows(Some(2));
+        let new = config("id,name\n1,a\n2,B\n").with_max_rows(Some(1));
+        let diff = Diff::new(&old, &new, Some("id")).unwrap();
+        assert_eq!(combined(&diff), "±,id,name\n,1,a\n-,2,b\n");
+    }
+
+    #[test]
+    fn test_diff_key_not_found() {
+        let old = config("id\n1\n");
+        assert!(Diff::new(&old, &old, Some("key")).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::errors::{CsvlensError, CsvlensResult};
+use crate::io::SeekableFile;
+
+#[cfg(feature = "url")]
+use std::io::{IsTerminal, Read, Write};
+#[cfg(feature = "url")]
+use std::time::{Duration, Instant};
+
+/// Minimum time between two updates of the download progress
+#[cfg(feature = "url")]
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the file argument is to be downloaded rather than opened
+pub fn is_url(filename: &str) -> bool {
+    let lower = filename.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Download the content at `url` to a temp file, showing the progress on stderr
+#[cfg(feature = "url")]
+pub fn download(url: &str) -> CsvlensResult<SeekableFile> {
+    let failed = |reason: String| CsvlensError::Download(url.to_string(), reason);
+    let response = ureq::get(url).call().map_err(|e| match e {
+        ureq::Error::StatusCode(status) => failed(format!("HTTP status {status}")),
+        e => failed(e.to_string()),
+    })?;
+    let body = response.into_body();
+    let mut reader = ProgressReader {
+        total: body.content_length(),
+        inner: body.into_reader(),
+        num_read: 0,
+        last_shown: None,
+        show: std::io::stderr().is_terminal(),
+    };
+    let file = SeekableFile::from_reader(url, &mut reader).map_err(|e| failed(e.to_string()))?;
+    reader.clear();
+    Ok(file)
+}
+
+#[cfg(not(feature = "url"))]
+pub fn download(url: &str) -> CsvlensResult<SeekableFile> {
+    Err(CsvlensError::Download(
+        url.to_string(),
+        "csvlens was built without the url feature".to_string(),
+    ))
+}
+
+/// Shows how much has been read so far on stderr while reading
+#[cfg(feature = "url")]
+struct ProgressReader<R: Read> {
+    inner: R,
+    total: Option<u64>,
+    num_read: u64,
+    last_shown: Option<Instant>,
+    show: bool,
+}
+
+#[cfg(feature = "url")]
+impl<R: Read> ProgressReader<R> {
+    fn clear(&self) {
+        if self.show && self.last_shown.is_some() {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+#[cfg(feature = "url")]
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.num_read += n as u64;
+        if self.show
+            && self
+                .last_shown
+                .is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL)
+        {
+            eprint!("\r\x1b[K{}", progress_message(self.num_read, self.total));
+            let _ = std::io::stderr().flush();
+            self.last_shown = Some(Instant::now());
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "url")]
+fn progress_message(num_read: u64, total: Option<u64>) -> String {
+    match total {
+        Some(total) if total > 0 => format!(
+            "Downloading... {} / {} ({}%)",
+            format_bytes(num_read),
+            format_bytes(total),
+            num_read * 100 / total
+        ),
+        _ => format!("Downloading... {}", format_bytes(num_read)),
+    }
+}
+
+#[cfg(feature = "url")]
+fn format_bytes(n: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{n} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/data.csv"));
+        assert!(is_url("HTTP://example.com/data.csv"));
+        assert!(!is_url("data.csv"));
+        assert!(!is_url("ftp://example.com/data.csv"));
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn test_progress_message() {
+        assert_eq!(progress_message(512, None), "Downloading... 512 B");
+        assert_eq!(
+            progress_message(1536, Some(3 * 1024 * 1024)),
+            "Downloading... 1.5 KB / 3.0 MB (0%)"
+        );
+        assert_eq!(
+            progress_message(1024 * 1024, Some(2 * 1024 * 1024)),
+            "Downloading... 1.0 MB / 2.0 MB (50%)"
+        );
+    }
+}
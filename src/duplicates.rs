@@ -0,0 +1,152 @@
+use crate::csv;
+use crate::errors::CsvlensResult;
+
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::{self};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DuplicateCounterStatus {
+    Running,
+    Finished,
+    Error(String),
+}
+
+/// Counts the distinct rows of the whole file in the background
+#[derive(Debug)]
+pub struct DuplicateCounter {
+    internal: Arc<Mutex<DuplicateCounterInternalState>>,
+}
+
+impl DuplicateCounter {
+    pub fn new(config: Arc<csv::CsvConfig>) -> Self {
+        let internal = DuplicateCounterInternalState::init(config);
+        DuplicateCounter { internal }
+    }
+
+    pub fn status(&self) -> DuplicateCounterStatus {
+        (self.internal.lock().unwrap()).status.clone()
+    }
+
+    /// Summary of the counts, marked as partial while still scanning
+    pub fn message(&self) -> String {
+        let (status, num_rows, num_distinct) = {
+            let m = self.internal.lock().unwrap();
+            (m.status.clone(), m.num_rows, m.hashes.len())
+        };
+        let counts = format!(
+            "{num_distinct} distinct of {num_rows} rows ({} duplicates)",
+            num_rows - num_distinct
+        );
+        match status {
+            DuplicateCounterStatus::Running => format!("Counting distinct rows... {counts} so far"),
+            DuplicateCounterStatus::Finished => counts,
+            DuplicateCounterStatus::Error(e) => format!("Counting distinct rows failed: {e}"),
+        }
+    }
+
+    pub fn terminate(&self) {
+        let mut m = self.internal.lock().unwrap();
+        m.should_terminate = true;
+    }
+
+    /// Block until the background thread is done
+    #[cfg(test)]
+    pub fn wait_internal(&self) {
+        loop {
+            if self.internal.lock().unwrap().done {
+                break;
+            }
+            thread::sleep(core::time::Duration::from_millis(100));
+        }
+    }
+}
+
+impl Drop for DuplicateCounter {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
+
+#[derive(Debug)]
+struct DuplicateCounterInternalState {
+    /// Hashes of the rows seen so far. Collisions are unlikely enough to not matter for a count.
+    hashes: HashSet<u64>,
+    num_rows: usize,
+    status: DuplicateCounterStatus,
+    should_terminate: bool,
+    done: bool,
+}
+
+impl DuplicateCounterInternalState {
+    fn init(config: Arc<csv::CsvConfig>) -> Arc<Mutex<DuplicateCounterInternalState>> {
+        let internal = DuplicateCounterInternalState {
+            hashes: HashSet::new(),
+            num_rows: 0,
+            status: DuplicateCounterStatus::Running,
+            should_terminate: false,
+            done: false,
+        };
+
+        let m_state = Arc::new(Mutex::new(internal));
+
+        let _m = m_state.clone();
+
+        let _handle = thread::spawn(move || {
+            fn run(
+                m: &Arc<Mutex<DuplicateCounterInternalState>>,
+                config: Arc<csv::CsvConfig>,
+            ) -> CsvlensResult<()> {
+                let mut reader = config.new_reader()?;
+                for record in reader.byte_records() {
+                    let record = record?;
+                    let mut hasher = DefaultHasher::new();
+                    for field in record.iter() {
+                        field.hash(&mut hasher);
+                    }
+                    let hash = hasher.finish();
+
+                    let mut m = m.lock().unwrap();
+                    if m.should_terminate {
+                        break;
+                    }
+                    m.hashes.insert(hash);
+                    m.num_rows += 1;
+                }
+                Ok(())
+            }
+
+            let res = run(&_m, config);
+
+            let mut m = _m.lock().unwrap();
+            m.status = match res {
+                Ok(_) => DuplicateCounterStatus::Finished,
+                Err(e) => DuplicateCounterStatus::Error(e.to_string()),
+            };
+            m.done = true;
+        });
+
+        m_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_count_distinct() {
+        let config = Arc::new(csv::CsvConfig::new(
+            "tests/data/duplicates.csv",
+            b',',
+            false,
+        ));
+        let counter = DuplicateCounter::new(config);
+        counter.wait_internal();
+        assert_eq!(counter.status(), DuplicateCounterStatus::Finished);
+        assert_eq!(counter.message(), "4 distinct of 6 rows (2 duplicates)");
+    }
+}
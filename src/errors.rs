@@ -8,6 +8,15 @@ pub enum CsvlensError {
     #[error("File not found: {0}")]
     FileNotFound(String),
 
+    #[error("File already exists: {0}")]
+    FileExists(String),
+
+    #[error("Failed to download {0}: {1}")]
+    Download(String, String),
+
+    #[error("Failed to read {0} as parquet: {1}")]
+    Parquet(String, String),
+
     #[error("Column name not found: {0}")]
     ColumnNameNotFound(String),
 
@@ -20,6 +29,32 @@ pub enum CsvlensError {
     #[error("Delimiter should be exactly one character (or \\t), got '{0}'")]
     DelimiterMultipleCharacters(String),
 
+    #[error("CSVLENS_DELIMITER should be exactly one ASCII character (or \\t), got '{0}'")]
+    DelimiterEnvInvalid(String),
+
+    #[error(
+        "Terminator should be exactly one ASCII character (or \\r, \\n, \\0 or crlf), got '{0}'"
+    )]
+    TerminatorInvalid(String),
+
+    #[error(
+        "Whitespace symbols should be exactly 4 characters (trailing space, tab, newline and other \
+         control characters), got '{0}'"
+    )]
+    WhitespaceSymbolsLength(String),
+
+    #[error("Invalid key in key binding: '{0}'")]
+    InvalidKey(String),
+
+    #[error("Unknown action in key binding: '{0}'")]
+    UnknownAction(String),
+
+    #[error("Invalid color for column {0}: '{1}'")]
+    InvalidColor(String, String),
+
+    #[error("Thousands separator should not be part of numbers itself: '{0}'")]
+    ThousandsSeparatorInvalid(char),
+
     #[error(transparent)]
     DelimiterParsing(#[from] std::char::TryFromCharError),
 
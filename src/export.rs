@@ -0,0 +1,108 @@
+use crate::csv::CsvConfig;
+use crate::errors::{CsvlensError, CsvlensResult};
+
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Write the header and the records with the given indices to a new CSV file at `path`, keeping
+/// only the columns with the given origin indices. An existing file is never overwritten. Returns
+/// the number of records written.
+pub fn export_rows(
+    config: &CsvConfig,
+    row_indices: Vec<u64>,
+    column_indices: Option<&[usize]>,
+    path: &Path,
+) -> CsvlensResult<usize> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| match e.kind() {
+            ErrorKind::AlreadyExists => CsvlensError::FileExists(path.display().to_string()),
+            _ => e.into(),
+        })?;
+    let mut reader = config.new_reader()?;
+    let mut writer = ::csv::WriterBuilder::new()
+        .flexible(true)
+        .delimiter(config.delimiter())
+        .from_writer(file);
+
+    let select = |record: &::csv::StringRecord| -> Vec<String> {
+        match column_indices {
+            Some(indices) => indices
+                .iter()
+                .map(|i| record.get(*i).unwrap_or_default().to_string())
+                .collect(),
+            None => record.iter().map(|field| field.to_string()).collect(),
+        }
+    };
+
+    if config.has_headers() {
+        let headers = reader.headers()?.clone();
+        writer.write_record(select(&headers))?;
+    }
+    if let Some(units) = config.read_units()? {
+        writer.write_record(select(&::csv::StringRecord::from(units)))?;
+    }
+
+    // Found rows are not necessarily in file order, e.g. when sorted
+    let mut wanted = row_indices;
+    wanted.sort_unstable();
+    let mut wanted = wanted.into_iter().peekable();
+
+    let mut num_written = 0;
+    for (row_index, record) in reader.records().enumerate() {
+        let Some(next) = wanted.peek() else {
+            break;
+        };
+        if *next != row_index as u64 {
+            continue;
+        }
+        wanted.next();
+        writer.write_record(select(&record?))?;
+        num_written += 1;
+    }
+    writer.flush()?;
+
+    Ok(num_written)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_export_rows() {
+        let config = CsvConfig::new("tests/data/simple.csv", b',', false);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        let num_written = export_rows(&config, vec![3, 1], Some(&[1]), &path).unwrap();
+        assert_eq!(num_written, 2);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "b\nB2\nB4\n");
+    }
+
+    #[test]
+    fn test_export_rows_units_row() {
+        let config = CsvConfig::new("tests/data/units.csv", b',', false).with_units_row(true);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        let num_written = export_rows(&config, vec![0], None, &path).unwrap();
+        assert_eq!(num_written, 1);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "station,depth,temperature\n,m,degC\nB,30,4.5\n");
+    }
+
+    #[test]
+    fn test_export_rows_existing_file() {
+        let config = CsvConfig::new("tests/data/simple.csv", b',', false);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        std::fs::write(&path, "keep me").unwrap();
+        let result = export_rows(&config, vec![1], None, &path);
+        assert!(matches!(result, Err(CsvlensError::FileExists(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    }
+}
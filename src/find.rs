@@ -1,15 +1,57 @@
 use crate::columns_filter;
 use crate::csv;
 use crate::errors::CsvlensResult;
+use crate::normalize;
 use crate::sort;
 use crate::sort::SortOrder;
+use ::csv::{Position, StringRecord};
 use regex::Regex;
 use sorted_vec::SortedVec;
 use std::cmp::min;
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread::{self};
 use std::time::{Duration, Instant};
 
+/// How a find or filter pattern has to match the content of a cell
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchMode {
+    #[default]
+    Substring,
+    WholeWord,
+    WholeCell,
+}
+
+impl MatchMode {
+    pub fn next(&self) -> MatchMode {
+        match self {
+            MatchMode::Substring => MatchMode::WholeWord,
+            MatchMode::WholeWord => MatchMode::WholeCell,
+            MatchMode::WholeCell => MatchMode::Substring,
+        }
+    }
+
+    /// Wrap a user supplied pattern so that it only matches according to this mode
+    pub fn wrap_pattern(&self, pattern: &str) -> String {
+        match self {
+            MatchMode::Substring => pattern.to_string(),
+            MatchMode::WholeWord => format!(r"\b(?:{pattern})\b"),
+            MatchMode::WholeCell => format!("^(?:{pattern})$"),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            MatchMode::Substring => "substring",
+            MatchMode::WholeWord => "whole-word",
+            MatchMode::WholeCell => "whole-cell",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RowPos {
     Header,
@@ -92,14 +134,51 @@ impl FinderCursor {
     }
 }
 
+/// A previous filter that records also have to match when filters are stacked
+#[derive(Clone)]
+pub struct FilterCondition {
+    target: Regex,
+    duplicates: bool,
+    column_index: Option<usize>,
+    visible_columns: Option<Range<usize>>,
+    ignore_accents: bool,
+}
+
+impl FilterCondition {
+    pub fn target(&self) -> &Regex {
+        &self.target
+    }
+
+    pub fn column_index(&self) -> Option<usize> {
+        self.column_index
+    }
+
+    pub fn duplicates(&self) -> bool {
+        self.duplicates
+    }
+}
+
+/// Id of the next Finder created
+static NEXT_FINDER_ID: AtomicUsize = AtomicUsize::new(0);
+
 pub struct Finder {
+    /// Unique to each finder, to tell whether results come from the same search
+    id: usize,
     internal: Arc<Mutex<FinderInternalState>>,
+    /// Set to stop the background search, checked for every record without taking the lock
+    should_terminate: Arc<AtomicBool>,
     pub cursor: Option<FinderCursor>,
     row_hint: RowPos,
     target: Regex,
+    duplicates: bool,
     column_index: Option<usize>,
+    visible_columns: Option<Range<usize>>,
+    ignore_accents: bool,
     sorter: Option<Arc<sort::Sorter>>,
     pub sort_order: SortOrder,
+    wrap_around: bool,
+    wrapped: bool,
+    conditions: Vec<FilterCondition>,
 }
 
 pub enum FoundEntry {
@@ -197,7 +276,7 @@ impl Ord for FoundRow {
 
 impl PartialOrd for FoundRow {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.row_order.cmp(&other.row_order))
+        Some(self.cmp(other))
     }
 }
 
@@ -210,34 +289,83 @@ impl PartialEq for FoundRow {
 impl Eq for FoundRow {}
 
 impl Finder {
+    /// With `ignore_accents`, fields have their diacritics stripped before being matched, so the
+    /// target should be stripped as well. If `visible_columns` is set, only columns with local
+    /// indices in that range are searched. Only records that also match all of `conditions` are
+    /// found.
+    ///
+    /// With `duplicates`, the target is ignored and records are found if their value in the
+    /// target column, or their values in all columns if there is none, also appear in another
+    /// record.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: Arc<csv::CsvConfig>,
         target: Regex,
+        duplicates: bool,
         column_index: Option<usize>,
+        visible_columns: Option<Range<usize>>,
+        ignore_accents: bool,
+        conditions: Vec<FilterCondition>,
         sorter: Option<Arc<sort::Sorter>>,
         sort_order: SortOrder,
         columns_filter: Option<Arc<columns_filter::ColumnsFilter>>,
+        parallel_scan: Option<ParallelScan>,
     ) -> CsvlensResult<Self> {
+        let matcher = RecordMatcher {
+            target: target.clone(),
+            duplicates,
+            duplicate_keys: HashSet::new(),
+            target_local_column_index: column_index,
+            visible_columns: visible_columns.clone(),
+            ignore_accents,
+            columns_filter: columns_filter.clone(),
+            conditions: conditions
+                .iter()
+                .map(|c| RecordMatcher {
+                    target: c.target.clone(),
+                    duplicates: c.duplicates,
+                    duplicate_keys: HashSet::new(),
+                    target_local_column_index: c.column_index,
+                    visible_columns: c.visible_columns.clone(),
+                    ignore_accents: c.ignore_accents,
+                    columns_filter: columns_filter.clone(),
+                    conditions: vec![],
+                })
+                .collect(),
+        };
+        let should_terminate = Arc::new(AtomicBool::new(false));
         let internal = FinderInternalState::init(
             config,
-            target.clone(),
-            column_index,
+            matcher,
             sorter.clone(),
             sort_order,
-            columns_filter,
+            parallel_scan,
+            should_terminate.clone(),
         );
         let finder = Finder {
+            id: NEXT_FINDER_ID.fetch_add(1, Ordering::Relaxed),
             internal,
+            should_terminate,
             cursor: None,
             row_hint: RowPos::Header,
             target,
+            duplicates,
             column_index,
+            visible_columns,
+            ignore_accents,
             sorter: sorter.clone(),
             sort_order,
+            wrap_around: false,
+            wrapped: false,
+            conditions,
         };
         Ok(finder)
     }
 
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     pub fn count(&self) -> usize {
         (self.internal.lock().unwrap()).count
     }
@@ -260,6 +388,11 @@ impl Finder {
         (self.internal.lock().unwrap()).done
     }
 
+    /// Why finding stopped short, if it did
+    pub fn error(&self) -> Option<String> {
+        (self.internal.lock().unwrap()).error.clone()
+    }
+
     pub fn cursor(&self) -> Option<FinderCursor> {
         self.cursor.as_ref().cloned()
     }
@@ -277,10 +410,39 @@ impl Finder {
         self.target.clone()
     }
 
+    /// Whether this finds duplicated records rather than the target
+    pub fn duplicates(&self) -> bool {
+        self.duplicates
+    }
+
     pub fn column_index(&self) -> Option<usize> {
         self.column_index
     }
 
+    pub fn visible_columns(&self) -> Option<Range<usize>> {
+        self.visible_columns.clone()
+    }
+
+    pub fn ignore_accents(&self) -> bool {
+        self.ignore_accents
+    }
+
+    /// Previous filters that the found records also match
+    pub fn conditions(&self) -> &[FilterCondition] {
+        &self.conditions
+    }
+
+    /// What this finder matches, to stack another filter on top of it
+    pub fn as_condition(&self) -> FilterCondition {
+        FilterCondition {
+            target: self.target.clone(),
+            duplicates: self.duplicates,
+            column_index: self.column_index,
+            visible_columns: self.visible_columns.clone(),
+            ignore_accents: self.ignore_accents,
+        }
+    }
+
     pub fn sorter(&self) -> &Option<Arc<sort::Sorter>> {
         &self.sorter
     }
@@ -293,19 +455,46 @@ impl Finder {
         self.row_hint = row_hint;
     }
 
+    /// Whether moving past the last match goes back to the first one and vice versa, once finding
+    /// is done
+    pub fn set_wrap_around(&mut self, wrap_around: bool) {
+        self.wrap_around = wrap_around;
+    }
+
+    /// Whether the last call to `next` or `prev` wrapped around
+    pub fn wrapped(&self) -> bool {
+        self.wrapped
+    }
+
     pub fn next(&mut self) -> Option<FoundEntry> {
         let m_guard = self.internal.lock().unwrap();
         let count = m_guard.count;
         let founds = &m_guard.founds;
+        self.wrapped = false;
         if let Some(cursor) = &self.cursor {
             let column_indices = match cursor.row {
                 RowPos::Header => m_guard.found_header.as_ref().map(|x| x.column_indices()),
                 RowPos::Row(n) => founds.get(n).map(|x| x.column_indices()),
             };
             if let Some(column_indices) = column_indices {
+                let is_last_row = match cursor.row {
+                    RowPos::Header => count == 0,
+                    RowPos::Row(n) => n + 1 >= count,
+                };
                 if cursor.column + 1 < column_indices.len() {
                     // Try next column first if available
                     self.cursor = Some(cursor.next_column());
+                } else if self.wrap_around && is_last_row && m_guard.done {
+                    // Only once the last match is known for sure
+                    self.cursor = Some(FinderCursor {
+                        row: if m_guard.found_header.is_some() {
+                            RowPos::Header
+                        } else {
+                            RowPos::Row(0)
+                        },
+                        column: 0,
+                    });
+                    self.wrapped = true;
                 } else {
                     // Next row if available
                     self.cursor = Some(cursor.next_row(count));
@@ -333,10 +522,25 @@ impl Finder {
 
     pub fn prev(&mut self) -> Option<FoundEntry> {
         let m_guard = self.internal.lock().unwrap();
+        self.wrapped = false;
         if let Some(cursor) = &self.cursor {
+            let is_first_row = match cursor.row {
+                RowPos::Header => true,
+                RowPos::Row(n) => n == 0 && m_guard.found_header.is_none(),
+            };
             if cursor.column > 0 {
                 // Try previous column first if available
                 self.cursor = Some(cursor.prev_column());
+            } else if self.wrap_around && is_first_row && m_guard.done {
+                // Only once the last match to wrap to is known
+                self.cursor = Some(FinderCursor {
+                    row: match m_guard.count {
+                        0 => RowPos::Header,
+                        n => RowPos::Row(n - 1),
+                    },
+                    column: 0,
+                });
+                self.wrapped = true;
             } else {
                 // Previous row if available
                 self.cursor = Some(cursor.prev_row(m_guard.found_header.is_some()));
@@ -385,8 +589,7 @@ impl Finder {
     }
 
     fn terminate(&self) {
-        let mut m_guard = self.internal.lock().unwrap();
-        m_guard.terminate();
+        self.should_terminate.store(true, Ordering::Relaxed);
     }
 
     pub fn elapsed(&self) -> Option<Duration> {
@@ -394,6 +597,15 @@ impl Finder {
         m_guard.elapsed()
     }
 
+    /// Position of the given record index among the found rows, if it is one of them
+    pub fn get_found_position(&self, row_index: usize) -> Option<usize> {
+        let m_guard = self.internal.lock().unwrap();
+        m_guard
+            .founds
+            .iter()
+            .position(|x| x.row_index() == row_index)
+    }
+
     pub fn get_subset_found(&self, offset: usize, num_rows: usize) -> Vec<u64> {
         let m_guard = self.internal.lock().unwrap();
         let founds = &m_guard.founds;
@@ -407,7 +619,7 @@ impl Finder {
         indices
     }
 
-    #[cfg(test)]
+    /// Block until the background thread is done
     pub fn wait_internal(&self) {
         loop {
             if self.internal.lock().unwrap().done {
@@ -424,118 +636,298 @@ impl Drop for Finder {
     }
 }
 
+/// Files smaller than this are always searched on a single thread
+const PARALLEL_SCAN_MIN_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Record positions to split the file at for searching on multiple threads
+pub struct ParallelScan {
+    pub pos_table: Vec<Position>,
+    pub num_threads: usize,
+}
+
+impl ParallelScan {
+    /// Split the file into (start, end) ranges of records, one per thread. Returns None if the
+    /// file is too small to be worth it.
+    fn ranges(&self) -> Option<Vec<(Option<Position>, Option<Position>)>> {
+        let last = self.pos_table.last()?;
+        if self.num_threads < 2
+            || last.byte() < PARALLEL_SCAN_MIN_BYTES
+            || self.pos_table.len() < self.num_threads
+        {
+            return None;
+        }
+        let mut starts = vec![None];
+        for k in 1..self.num_threads {
+            let index = self.pos_table.len() * k / self.num_threads;
+            starts.push(Some(self.pos_table[index].clone()));
+        }
+        let mut ranges = vec![];
+        for (i, start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).cloned().flatten();
+            ranges.push((start.clone(), end));
+        }
+        Some(ranges)
+    }
+}
+
+/// Matches fields of a record against the target
+struct RecordMatcher {
+    target: Regex,
+    /// Match records with the same key as another record instead of the target
+    duplicates: bool,
+    /// Keys appearing in more than one record, once collected
+    duplicate_keys: HashSet<u64>,
+    target_local_column_index: Option<usize>,
+    visible_columns: Option<Range<usize>>,
+    ignore_accents: bool,
+    columns_filter: Option<Arc<columns_filter::ColumnsFilter>>,
+    /// Stacked filters the record has to match first
+    conditions: Vec<RecordMatcher>,
+}
+
+impl RecordMatcher {
+    fn meets_conditions(&self, record: &StringRecord) -> bool {
+        self.conditions
+            .iter()
+            .all(|c| !c.matching_columns(record, true).is_empty())
+    }
+
+    /// Collect the keys of the duplicated records by scanning the whole file, for this matcher
+    /// and any of its conditions that look for duplicates
+    fn collect_duplicate_keys(
+        &mut self,
+        config: &csv::CsvConfig,
+        should_terminate: &AtomicBool,
+    ) -> CsvlensResult<()> {
+        for condition in self.conditions.iter_mut() {
+            condition.collect_duplicate_keys(config, should_terminate)?;
+        }
+        if !self.duplicates {
+            return Ok(());
+        }
+        let mut seen = HashSet::new();
+        let mut reader = config.new_reader()?;
+        for r in reader.records() {
+            if let Ok(record) = r {
+                let key = self.duplicate_key(&record);
+                if !seen.insert(key) {
+                    self.duplicate_keys.insert(key);
+                }
+            }
+            if should_terminate.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Hash of the value in the target column, or of the values in all columns if there is none
+    fn duplicate_key(&self, record: &StringRecord) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let fields = record.iter().enumerate().filter(|(column_index, _)| {
+            self.columns_filter
+                .as_ref()
+                .is_none_or(|f| f.is_column_filtered(*column_index))
+        });
+        for (local_column_index, (_, field)) in fields.enumerate() {
+            if self
+                .target_local_column_index
+                .is_none_or(|i| i == local_column_index)
+            {
+                field.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Local indices of the matching columns. If `target_column_only` is set, only the target
+    /// column is checked if there is one.
+    fn matching_columns(&self, record: &StringRecord, target_column_only: bool) -> Vec<usize> {
+        if self.duplicates && !self.duplicate_keys.contains(&self.duplicate_key(record)) {
+            return vec![];
+        }
+        let mut column_indices = vec![];
+        let mut local_column_index = 0;
+        for (column_index, field) in record.iter().enumerate() {
+            if let Some(columns_filter) = &self.columns_filter {
+                if !columns_filter.is_column_filtered(column_index) {
+                    continue;
+                }
+            }
+            let should_check_regex = match self.target_local_column_index {
+                Some(target_local_column_index) if target_column_only => {
+                    local_column_index == target_local_column_index
+                }
+                _ => true,
+            };
+            let is_visible = self
+                .visible_columns
+                .as_ref()
+                .is_none_or(|r| r.contains(&local_column_index));
+            if should_check_regex && is_visible && (self.duplicates || self.is_match(field)) {
+                column_indices.push(local_column_index);
+            }
+            local_column_index += 1;
+        }
+        column_indices
+    }
+
+    fn is_match(&self, field: &str) -> bool {
+        if self.ignore_accents {
+            self.target.is_match(&normalize::strip_diacritics(field))
+        } else {
+            self.target.is_match(field)
+        }
+    }
+}
+
 struct FinderInternalState {
     count: usize,
     found_header: Option<FoundHeader>,
     founds: SortedVec<FoundRow>,
     done: bool,
-    should_terminate: bool,
+    error: Option<String>,
     elapsed: Option<Duration>,
 }
 
 impl FinderInternalState {
     pub fn init(
         config: Arc<csv::CsvConfig>,
-        target: Regex,
-        target_local_column_index: Option<usize>,
+        mut matcher: RecordMatcher,
         sorter: Option<Arc<sort::Sorter>>,
         sort_order: SortOrder,
-        columns_filter: Option<Arc<columns_filter::ColumnsFilter>>,
+        parallel_scan: Option<ParallelScan>,
+        should_terminate: Arc<AtomicBool>,
     ) -> Arc<Mutex<FinderInternalState>> {
         let internal = FinderInternalState {
             count: 0,
             found_header: None,
             founds: SortedVec::new(),
             done: false,
-            should_terminate: false,
+            error: None,
             elapsed: None,
         };
 
         let m_state = Arc::new(Mutex::new(internal));
 
         let _m = m_state.clone();
-        let _filename = config.filename().to_owned();
 
         let _handle = thread::spawn(move || {
             let mut bg_reader = config.new_reader().unwrap();
 
-            // search header
-            let mut column_indices = vec![];
-            if let Ok(header) = bg_reader.headers() {
-                let mut local_column_index = 0;
-                for (column_index, field) in header.iter().enumerate() {
-                    if let Some(columns_filter) = &columns_filter {
-                        if !columns_filter.is_column_filtered(column_index) {
-                            continue;
-                        }
-                    }
-                    if target.is_match(field) {
-                        column_indices.push(local_column_index);
-                    }
-                    local_column_index += 1;
-                }
-            }
-            if !column_indices.is_empty() {
-                let found = FoundHeader { column_indices };
+            let start = Instant::now();
+            // Finding with only some of the duplicated values known would miss rows
+            if let Err(e) = matcher.collect_duplicate_keys(&config, &should_terminate) {
                 let mut m = _m.lock().unwrap();
-                m.found_header = Some(found);
+                m.error = Some(e.to_string());
+                m.done = true;
+                return;
             }
 
-            // note that records() excludes header
-            let records = bg_reader.records();
-
-            let start = Instant::now();
-            for (row_index, r) in records.enumerate() {
-                let mut column_indices = vec![];
-                if let Ok(valid_record) = r {
-                    let mut local_column_index = 0;
-                    for (column_index, field) in valid_record.iter().enumerate() {
-                        if let Some(columns_filter) = &columns_filter {
-                            if !columns_filter.is_column_filtered(column_index) {
-                                continue;
-                            }
-                        }
-                        let should_check_regex =
-                            if let Some(target_local_column_index) = target_local_column_index {
-                                local_column_index == target_local_column_index
-                            } else {
-                                true
-                            };
-                        if should_check_regex && target.is_match(field) {
-                            column_indices.push(local_column_index);
-                        }
-                        local_column_index += 1;
-                    }
-                }
+            // search header, which cannot be a duplicate of a record
+            if let Some(header) = bg_reader
+                .headers()
+                .ok()
+                .filter(|h| !matcher.duplicates && matcher.meets_conditions(h))
+            {
+                let column_indices = matcher.matching_columns(header, false);
                 if !column_indices.is_empty() {
-                    let row_order = match &sorter {
-                        Some(s) => {
-                            s.get_record_order(row_index as u64, sort_order).unwrap() as usize
-                        }
-                        _ => row_index,
-                    };
-                    let found = FoundRow {
-                        row_index,
-                        row_order,
-                        column_indices,
-                    };
+                    let found = FoundHeader { column_indices };
                     let mut m = _m.lock().unwrap();
-                    (*m).found_one(found);
-                }
-                let m = _m.lock().unwrap();
-                if m.should_terminate {
-                    break;
+                    m.found_header = Some(found);
                 }
             }
 
+            let ranges = parallel_scan
+                .and_then(|p| p.ranges())
+                .unwrap_or_else(|| vec![(None, None)]);
+
+            thread::scope(|scope| {
+                for range in ranges {
+                    let m = &_m;
+                    let config = &config;
+                    let matcher = &matcher;
+                    let sorter = &sorter;
+                    let should_terminate = &should_terminate;
+                    scope.spawn(move || {
+                        // Matches of the range are missing from then on, which the error tells
+                        if let Err(e) = Self::search_range(
+                            m,
+                            should_terminate,
+                            config,
+                            range,
+                            matcher,
+                            sorter,
+                            sort_order,
+                        ) {
+                            let mut m = m.lock().unwrap();
+                            m.error.get_or_insert(e.to_string());
+                        }
+                    });
+                }
+            });
+
             let mut m = _m.lock().unwrap();
             m.done = true;
-            m.elapsed = Some(start.elapsed());
+            m.elapsed = Some(Instant::now().saturating_duration_since(start));
         });
 
         m_state
     }
 
+    /// Search records in the (start, end) range: from `start` (or the first record if None) until
+    /// just before `end` (or the last record if None)
+    fn search_range(
+        m: &Mutex<FinderInternalState>,
+        should_terminate: &AtomicBool,
+        config: &csv::CsvConfig,
+        (start, end): (Option<Position>, Option<Position>),
+        matcher: &RecordMatcher,
+        sorter: &Option<Arc<sort::Sorter>>,
+        sort_order: SortOrder,
+    ) -> CsvlensResult<()> {
+        let mut reader = config.new_seekable_reader()?;
+        let mut row_index = 0;
+        if let Some(pos) = start {
+            row_index = config.position_to_record_index(pos.record()) as usize;
+            reader.seek(pos)?;
+        }
+        let end_row_index = end.map_or(config.max_records(), |pos| {
+            config.position_to_record_index(pos.record()) as usize
+        });
+
+        // note that records() excludes header unless seeked
+        for r in reader.records() {
+            if row_index >= end_row_index {
+                break;
+            }
+            let column_indices = match r {
+                Ok(valid_record) if matcher.meets_conditions(&valid_record) => {
+                    matcher.matching_columns(&valid_record, true)
+                }
+                _ => vec![],
+            };
+            if !column_indices.is_empty() {
+                let row_order = match sorter {
+                    Some(s) => s.get_record_order(row_index as u64, sort_order).unwrap() as usize,
+                    _ => row_index,
+                };
+                let found = FoundRow {
+                    row_index,
+                    row_order,
+                    column_indices,
+                };
+                let mut m = m.lock().unwrap();
+                (*m).found_one(found);
+            }
+            if should_terminate.load(Ordering::Relaxed) {
+                break;
+            }
+            row_index += 1;
+        }
+        Ok(())
+    }
+
     fn found_one(&mut self, found: FoundRow) {
         self.founds.push(found);
         self.count += 1;
@@ -558,10 +950,6 @@ impl FinderInternalState {
         }
     }
 
-    fn terminate(&mut self) {
-        self.should_terminate = true;
-    }
-
     fn elapsed(&self) -> Option<Duration> {
         self.elapsed
     }
@@ -1,15 +1,19 @@
+use regex::Regex;
+
+use crate::theme::Theme;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
+    widgets::{Block, BorderType, Borders, Paragraph, StatefulWidget, Widget, Wrap},
 };
 
 const HELP_CONTENT: &str = "
 csvlens is an interactive CSV file viewer in the command line.
 
-These are the key bindings. Press q to exit.
+These are the key bindings. Press / to search them and q to exit.
 
 # Moving
 
@@ -20,50 +24,121 @@ Ctrl + d (or d)         : Scroll half a window down
 Ctrl + u (or u)         : Scroll half a window up
 Ctrl + h                : Scroll one window left
 Ctrl + l                : Scroll one window right
+Alt + h                 : Scroll half a window left
+Alt + l                 : Scroll half a window right
 Ctrl + ←                : Scroll left to first column
 Ctrl + →                : Scroll right to last column
 G (or End)              : Go to bottom
 g (or Home)             : Go to top
 <n>G                    : Go to line n
+-<n>G                   : Go to the n-th line from the end
+<n>o                    : Go to the row at byte offset n
+|<column>               : Go to a column by its name or spreadsheet style letters, e.g. Q
+m<letter>               : Mark the selected row with a letter
+'<letter>               : Jump to the row marked with a letter
+v                       : Start selecting a range of rows from the selected row, or clear it.
+                          In Cell mode, the sum and average of the selected column over the
+                          range are shown in the status bar.
+Q<letter>               : Record commands as a macro until Q is pressed again
+!<letter>               : Play the macro named by a letter
+zz                      : Scroll to put the selected row in the middle of the window
+zt                      : Scroll to put the selected row at the top of the window
+zb                      : Scroll to put the selected row at the bottom of the window
 
 # Search
 
 /<regex>                : Find content matching regex and highlight matches
 n (in Find mode)        : Jump to next result
 N (in Find mode)        : Jump to previous result
+] (or [)                : Jump to the next (or previous) row with a different number of fields
+} (or {)                : Jump to the next (or previous) empty cell in the selected column
+) (or ()                : Jump to the next (or previous) group of equal values in the column
 &<regex>                : Filter rows using regex (show only matches)
++<regex>                : Filter the filtered rows further (Esc to go back one filter)
+=                       : Filter rows duplicated in the selected column (or all columns)
 *<regex>                : Filter columns using regex (show only matches)
+E<path>                 : Export the rows found to a new CSV file
+Esc                     : Cancel a find, filter or sort that is still running
+V                       : Validate the file and list rows with structural errors
+D                       : Count the distinct rows of the file
 
 # Selection modes
 
 TAB                     : Toggle between row, column or cell selection modes
+Mouse click             : Select the clicked cell
+Mouse click (on header) : Sort rows by the clicked column
+Mouse wheel             : Scroll one row up or down
 >                       : Increase selected column's width
 <                       : Decrease selected column's width
 Shift + ↓ (or J)        : Sort rows by the selected column
+~                       : Reverse the order of the rows
 # (in Cell mode)        : Find and highlight rows like the selected cell
 @ (in Cell mode)        : Filter rows like the selected cell
 y                       : Copy the selected row or cell to clipboard
+Y (in Column/Cell mode) : Copy the name of the selected column to clipboard
+Ctrl + y                : Copy the absolute path of the file to clipboard
 Enter (in Cell mode)    : Print the selected cell to stdout and exit
 
 # Other options
 
 -S                      : Toggle line wrapping
 -W                      : Toggle line wrapping by words
+-C                      : Toggle line wrapping of the selected column
+-U                      : Toggle collapsing repeated values of the selected column
+-L                      : Toggle showing whitespace and control characters
+-E                      : Toggle showing empty cells as a placeholder
+-P                      : Toggle compact mode without borders
+-i                      : Toggle ignoring case in sorting, find and filter
+-A                      : Toggle ignoring accents in find and filter
+-V                      : Toggle searching only the columns on screen
+-N                      : Toggle wrapping around at the first and last match
+-Z                      : Toggle sorting empty values last in both orders
+-> (or -<)              : Show more (or fewer) context rows around filtered matches
+-] (or -[)              : Show more (or fewer) lines of each row when wrapping
+-B                      : Toggle scrollbars
+-T                      : Toggle word and character counts of the selected cell and row
+-O                      : Toggle showing the byte offset of the top row
+-X                      : Toggle highlighting the row and column of the selected cell
+-H                      : Toggle highlighting matches in all columns
+-D                      : Toggle dimming the columns other than the selected one
+-F                      : Toggle footer with column aggregates
+-M                      : Cycle find and filter match mode (substring, word, cell)
+-I                      : Toggle showing the inferred type of each column
+-R                      : Toggle showing the row numbers
+-G                      : Toggle showing spreadsheet style column letters above the header
+-0                      : Toggle numbering the rows from 0
 r                       : Reset to default view (clear all filters and custom column widths)
+U (or R)                : Undo (or redo) the last change to filters, sort or columns
+.                       : Repeat the last find, filter or sort, whichever came last
+M                       : Show recent messages
 H (or ?)                : Display this help
+:                       : Search all actions with their keys and run one
 q                       : Exit";
 
-pub struct HelpPage {}
+pub struct HelpPage<'a> {
+    border_type: BorderType,
+    theme: &'a Theme,
+}
 
 pub struct HelpPageState {
     active: bool,
     offset: u16,
     render_complete: bool,
+    prompt: Option<String>,
+    search: Option<HelpSearch>,
 }
 
-impl HelpPage {
-    pub fn new() -> Self {
-        HelpPage {}
+/// Lines of the help page matching a find
+struct HelpSearch {
+    pattern: String,
+    regex: Regex,
+    lines: Vec<u16>,
+    current: usize,
+}
+
+impl<'a> HelpPage<'a> {
+    pub fn new(border_type: BorderType, theme: &'a Theme) -> Self {
+        HelpPage { border_type, theme }
     }
 }
 
@@ -73,6 +148,8 @@ impl HelpPageState {
             active: false,
             offset: 0,
             render_complete: true,
+            prompt: None,
+            search: None,
         }
     }
 
@@ -85,6 +162,8 @@ impl HelpPageState {
     pub fn deactivate(&mut self) -> &Self {
         self.active = false;
         self.offset = 0;
+        self.prompt = None;
+        self.search = None;
         self
     }
 
@@ -105,37 +184,159 @@ impl HelpPageState {
         }
         self
     }
+
+    /// Show the query being typed, or hide the prompt with None
+    pub fn set_prompt(&mut self, prompt: Option<String>) -> &Self {
+        self.prompt = prompt;
+        self
+    }
+
+    /// Highlight lines matching the regex and scroll to the first one
+    pub fn find(&mut self, pattern: &str, regex: Regex) -> &Self {
+        let lines = HELP_CONTENT
+            .split('\n')
+            .enumerate()
+            .filter(|(_, line)| regex.is_match(line))
+            .map(|(i, _)| i as u16)
+            .collect();
+        self.prompt = None;
+        self.search = Some(HelpSearch {
+            pattern: pattern.to_string(),
+            regex,
+            lines,
+            current: 0,
+        });
+        self.scroll_to_current_match();
+        self
+    }
+
+    /// Scroll to the next matching line, wrapping around at the end
+    pub fn next_match(&mut self) -> &Self {
+        if let Some(search) = &mut self.search {
+            if !search.lines.is_empty() {
+                search.current = (search.current + 1) % search.lines.len();
+            }
+        }
+        self.scroll_to_current_match();
+        self
+    }
+
+    /// Scroll to the previous matching line, wrapping around at the start
+    pub fn prev_match(&mut self) -> &Self {
+        if let Some(search) = &mut self.search {
+            if !search.lines.is_empty() {
+                search.current = search
+                    .current
+                    .checked_sub(1)
+                    .unwrap_or(search.lines.len() - 1);
+            }
+        }
+        self.scroll_to_current_match();
+        self
+    }
+
+    fn scroll_to_current_match(&mut self) {
+        if let Some(line) = self
+            .search
+            .as_ref()
+            .and_then(|search| search.lines.get(search.current))
+        {
+            self.offset = *line;
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        if let Some(prompt) = &self.prompt {
+            return Some(format!("Find: {prompt}"));
+        }
+        let search = self.search.as_ref()?;
+        let status = if search.lines.is_empty() {
+            format!("Find \"{}\": no matches", search.pattern)
+        } else {
+            format!(
+                "Find \"{}\": {}/{} (n: next, N: previous)",
+                search.pattern,
+                search.current + 1,
+                search.lines.len()
+            )
+        };
+        Some(status)
+    }
 }
 
-impl StatefulWidget for HelpPage {
+/// Split the text into spans, highlighting the parts matching the regex
+fn highlight(
+    text: String,
+    style: Style,
+    regex: Option<&Regex>,
+    highlight_style: Style,
+) -> Line<'static> {
+    let Some(regex) = regex else {
+        return Line::from(Span::styled(text, style));
+    };
+    let mut spans = vec![];
+    let mut last = 0;
+    for m in regex.find_iter(&text) {
+        if m.is_empty() {
+            continue;
+        }
+        if m.start() > last {
+            spans.push(Span::styled(text[last..m.start()].to_string(), style));
+        }
+        spans.push(Span::styled(m.as_str().to_string(), highlight_style));
+        last = m.end();
+    }
+    if last < text.len() {
+        spans.push(Span::styled(text[last..].to_string(), style));
+    }
+    Line::from(spans)
+}
+
+impl StatefulWidget for HelpPage<'_> {
     type State = HelpPageState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        fn line_to_span(line: &str) -> Span {
-            if line.starts_with("# ") && !line.contains(':') {
-                let header_style = Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::Rgb(200, 200, 200));
-                let header_formatted = format!("[{}]", line.strip_prefix("# ").unwrap());
-                Span::styled(header_formatted, header_style)
-            } else {
-                Span::raw(line)
-            }
-        }
+        let header_style = self.theme.heading;
+        let match_style = self.theme.found;
+        let regex = state.search.as_ref().map(|search| &search.regex);
+        let current_line = state
+            .search
+            .as_ref()
+            .and_then(|search| search.lines.get(search.current));
 
         let text: Vec<Line> = HELP_CONTENT
             .split('\n')
-            .map(|s| Line::from(line_to_span(s)))
+            .enumerate()
+            .map(|(i, line)| {
+                let match_style = if current_line == Some(&(i as u16)) {
+                    match_style.patch(self.theme.found_current)
+                } else {
+                    match_style
+                };
+                if line.starts_with("# ") && !line.contains(':') {
+                    let header_formatted = format!("[{}]", line.strip_prefix("# ").unwrap());
+                    highlight(header_formatted, header_style, regex, match_style)
+                } else {
+                    highlight(line.to_string(), Style::default(), regex, match_style)
+                }
+            })
             .collect();
 
         // Minus 2 to account for borders.
         let num_lines_to_be_rendered = (text.len() as u16).saturating_sub(state.offset);
         state.render_complete = area.height.saturating_sub(2) >= num_lines_to_be_rendered;
 
-        let paragraph = Paragraph::new(text)
-            .block(Block::default().title("Help").borders(Borders::ALL))
-            .wrap(Wrap { trim: true })
-            .scroll((state.offset, 0));
+        let mut block = Block::default()
+            .title("Help")
+            .borders(Borders::ALL)
+            .border_type(self.border_type);
+        if let Some(status) = state.status() {
+            block = block.title_bottom(status);
+        }
+        // Skip whole lines rather than scrolling the paragraph, so that the offset stays a line
+        // number even when lines wrap
+        let text: Vec<Line> = text.into_iter().skip(state.offset as usize).collect();
+        let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: true });
 
         paragraph.render(area, buf);
     }
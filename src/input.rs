@@ -1,10 +1,16 @@
 use crate::common::InputMode;
+use crate::errors::{CsvlensError, CsvlensResult};
 use crate::history::BufferHistoryContainer;
 use crate::util::events::{CsvlensEvent, CsvlensEvents};
-use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tui_input::backend::crossterm::EventHandler;
 use tui_input::Input;
 
+#[derive(Clone)]
 pub enum Control {
     ScrollUp,
     ScrollDown,
@@ -18,28 +24,93 @@ pub enum Control {
     ScrollHalfPageDown,
     ScrollPageLeft,
     ScrollPageRight,
+    ScrollHalfPageLeft,
+    ScrollHalfPageRight,
     ScrollLeftMost,
     ScrollRightMost,
     ScrollTo(usize),
+    /// Scroll to the n-th row counting back from the last one, which is 1
+    ScrollToFromEnd(usize),
+    GotoByte(u64),
+    MouseClick(u16, u16),
+    CenterSelection,
+    SelectionToTop,
+    SelectionToBottom,
     ScrollToNextFound,
     ScrollToPrevFound,
+    NextRaggedRow,
+    PrevRaggedRow,
+    NextEmptyInColumn,
+    PrevEmptyInColumn,
+    NextGroup,
+    PrevGroup,
     IncreaseWidth,
     DecreaseWidth,
     Find(String),
     FindLikeCell,
     Filter(String),
+    FilterWithin(String),
+    FilterDuplicates,
     FilterColumns(String),
     FilterLikeCell,
+    ExportMatches(PathBuf),
+    /// Scroll to the column with the given name, or else spreadsheet style letters
+    GotoColumn(String),
     Quit,
     BufferContent(Input),
     BufferReset,
+    /// Close the input buffer without acting on it, leaving filters as they are
+    BufferCancel,
     Select,
     CopySelection,
+    CopyColumnName,
+    CopyFilePath,
     ToggleSelectionType,
     ToggleLineWrap(bool),
+    ToggleColumnWrap,
+    ToggleCollapseColumn,
+    ToggleShowWhitespace,
+    ToggleEmptyPlaceholder,
+    ToggleCompact,
+    ToggleIgnoreCase,
+    ToggleIgnoreAccents,
+    ToggleSearchVisibleColumns,
+    ToggleWrapSearch,
+    ToggleSortNullsLast,
+    IncreaseFilterContext,
+    DecreaseFilterContext,
+    IncreaseMaxRowHeight,
+    DecreaseMaxRowHeight,
+    ToggleScrollbars,
+    ToggleCrosshair,
+    ToggleColumnFocus,
+    ToggleHighlightAllColumns,
+    ToggleByteOffset,
+    ToggleTextStats,
+    ToggleFooter,
+    ToggleColumnTypes,
+    ToggleColumnLetters,
+    ToggleRowNumbers,
+    ToggleZeroBasedRowNumbers,
+    ToggleMatchMode,
     ToggleSort,
+    ReverseOrder,
+    Cancel,
+    Validate,
+    CountDistinct,
+    SetMark(char),
+    JumpToMark(char),
+    SelectRange,
+    RecordMacro(char),
+    StopRecordingMacro,
+    PlayMacro(char),
     Reset,
+    Undo,
+    Redo,
+    RepeatLastCommand,
     Help,
+    CommandPalette,
+    ShowMessages,
     UnknownOption(String),
     Nothing,
 }
@@ -48,6 +119,430 @@ impl Control {
     fn empty_buffer() -> Control {
         Control::BufferContent("".into())
     }
+
+    /// Whether this may change the filters, sort or columns shown, which can then be undone
+    pub fn is_undoable(&self) -> bool {
+        matches!(
+            self,
+            Control::Find(_)
+                | Control::FindLikeCell
+                | Control::Filter(_)
+                | Control::FilterWithin(_)
+                | Control::FilterDuplicates
+                | Control::FilterLikeCell
+                | Control::FilterColumns(_)
+                | Control::BufferReset
+                | Control::ToggleSort
+                | Control::ReverseOrder
+                | Control::MouseClick(_, _)
+                | Control::IncreaseWidth
+                | Control::DecreaseWidth
+                | Control::ToggleColumnWrap
+                | Control::ToggleCollapseColumn
+                | Control::Reset
+                | Control::RepeatLastCommand
+        )
+    }
+
+    /// Whether this is kept when recording a macro. What is typed into a prompt is left out, only
+    /// the submitted find, filter or other input is kept.
+    pub fn is_recorded_in_macro(&self) -> bool {
+        !matches!(
+            self,
+            Control::Nothing
+                | Control::BufferContent(_)
+                | Control::Select
+                | Control::Help
+                | Control::CommandPalette
+                | Control::ShowMessages
+                | Control::Quit
+                | Control::RecordMacro(_)
+                | Control::StopRecordingMacro
+        )
+    }
+}
+
+/// Actions that can be bound to a key in the default mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    ScrollTop,
+    ScrollBottom,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollHalfPageUp,
+    ScrollHalfPageDown,
+    ScrollPageLeft,
+    ScrollPageRight,
+    ScrollHalfPageLeft,
+    ScrollHalfPageRight,
+    ScrollLeftMost,
+    ScrollRightMost,
+    NextFound,
+    PrevFound,
+    NextRaggedRow,
+    PrevRaggedRow,
+    NextEmptyInColumn,
+    PrevEmptyInColumn,
+    NextGroup,
+    PrevGroup,
+    Find,
+    Filter,
+    FilterWithin,
+    FilterDuplicates,
+    FilterColumns,
+    Option,
+    Mark,
+    JumpToMark,
+    SelectRange,
+    RecordMacro,
+    PlayMacro,
+    ScrollSelection,
+    Select,
+    ToggleSelectionType,
+    IncreaseWidth,
+    DecreaseWidth,
+    Reset,
+    Help,
+    FindLikeCell,
+    FilterLikeCell,
+    CopySelection,
+    CopyColumnName,
+    CopyFilePath,
+    ToggleSort,
+    ReverseOrder,
+    Cancel,
+    Validate,
+    CountDistinct,
+    ExportMatches,
+    GotoColumn,
+    Undo,
+    Redo,
+    RepeatLastCommand,
+    CommandPalette,
+    ShowMessages,
+    None,
+}
+
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("quit", Action::Quit),
+    ("scroll_up", Action::ScrollUp),
+    ("scroll_down", Action::ScrollDown),
+    ("scroll_left", Action::ScrollLeft),
+    ("scroll_right", Action::ScrollRight),
+    ("scroll_top", Action::ScrollTop),
+    ("scroll_bottom", Action::ScrollBottom),
+    ("scroll_page_up", Action::ScrollPageUp),
+    ("scroll_page_down", Action::ScrollPageDown),
+    ("scroll_half_page_up", Action::ScrollHalfPageUp),
+    ("scroll_half_page_down", Action::ScrollHalfPageDown),
+    ("scroll_page_left", Action::ScrollPageLeft),
+    ("scroll_page_right", Action::ScrollPageRight),
+    ("scroll_half_page_left", Action::ScrollHalfPageLeft),
+    ("scroll_half_page_right", Action::ScrollHalfPageRight),
+    ("scroll_left_most", Action::ScrollLeftMost),
+    ("scroll_right_most", Action::ScrollRightMost),
+    ("next_found", Action::NextFound),
+    ("prev_found", Action::PrevFound),
+    ("next_ragged_row", Action::NextRaggedRow),
+    ("prev_ragged_row", Action::PrevRaggedRow),
+    ("next_empty_in_column", Action::NextEmptyInColumn),
+    ("prev_empty_in_column", Action::PrevEmptyInColumn),
+    ("next_group", Action::NextGroup),
+    ("prev_group", Action::PrevGroup),
+    ("find", Action::Find),
+    ("filter", Action::Filter),
+    ("filter_within", Action::FilterWithin),
+    ("filter_duplicates", Action::FilterDuplicates),
+    ("filter_columns", Action::FilterColumns),
+    ("option", Action::Option),
+    ("mark", Action::Mark),
+    ("jump_to_mark", Action::JumpToMark),
+    ("select_range", Action::SelectRange),
+    ("record_macro", Action::RecordMacro),
+    ("play_macro", Action::PlayMacro),
+    ("scroll_selection", Action::ScrollSelection),
+    ("select", Action::Select),
+    ("toggle_selection_type", Action::ToggleSelectionType),
+    ("increase_width", Action::IncreaseWidth),
+    ("decrease_width", Action::DecreaseWidth),
+    ("reset", Action::Reset),
+    ("help", Action::Help),
+    ("find_like_cell", Action::FindLikeCell),
+    ("filter_like_cell", Action::FilterLikeCell),
+    ("copy_selection", Action::CopySelection),
+    ("copy_column_name", Action::CopyColumnName),
+    ("copy_file_path", Action::CopyFilePath),
+    ("toggle_sort", Action::ToggleSort),
+    ("reverse_order", Action::ReverseOrder),
+    ("cancel", Action::Cancel),
+    ("validate", Action::Validate),
+    ("count_distinct", Action::CountDistinct),
+    ("export_matches", Action::ExportMatches),
+    ("goto_column", Action::GotoColumn),
+    ("undo", Action::Undo),
+    ("redo", Action::Redo),
+    ("repeat_last_command", Action::RepeatLastCommand),
+    ("command_palette", Action::CommandPalette),
+    ("show_messages", Action::ShowMessages),
+    ("none", Action::None),
+];
+
+impl Action {
+    fn from_name(name: &str) -> Option<Action> {
+        ACTION_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, action)| *action)
+    }
+
+    /// Name of the action as used in key binding overrides
+    pub fn name(self) -> &'static str {
+        ACTION_NAMES
+            .iter()
+            .find(|(_, action)| *action == self)
+            .map(|(name, _)| *name)
+            .unwrap_or("none")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Keep in sync with the shift key normalization in InputHandler::next
+        let mut modifiers = modifiers;
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            _ => code,
+        };
+        if let KeyCode::Char(c) = code {
+            modifiers.set(KeyModifiers::SHIFT, c.is_ascii_uppercase());
+        }
+        KeyBinding { code, modifiers }
+    }
+
+    fn from_event(key_event: &KeyEvent) -> Self {
+        KeyBinding::new(key_event.code, key_event.modifiers)
+    }
+
+    /// Parse a key such as "j", "G", "ctrl-f", "shift-down" or "pagedown"
+    fn parse(s: &str) -> CsvlensResult<Self> {
+        let invalid = || CsvlensError::InvalidKey(s.to_string());
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if lower.starts_with("ctrl-") && rest.len() > 5 {
+                modifiers.insert(KeyModifiers::CONTROL);
+                rest = &rest[5..];
+            } else if lower.starts_with("alt-") && rest.len() > 4 {
+                modifiers.insert(KeyModifiers::ALT);
+                rest = &rest[4..];
+            } else if lower.starts_with("shift-") && rest.len() > 6 {
+                modifiers.insert(KeyModifiers::SHIFT);
+                rest = &rest[6..];
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "esc" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "space" => KeyCode::Char(' '),
+                _ => return Err(invalid()),
+            },
+        };
+        Ok(KeyBinding::new(code, modifiers))
+    }
+
+    /// Format the key the same way it is parsed, e.g. "ctrl-f" or "G"
+    fn describe(&self) -> String {
+        let mut s = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            s.push_str("ctrl-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            s.push_str("alt-");
+        }
+        // Upper case characters already imply shift
+        if self.modifiers.contains(KeyModifiers::SHIFT) && !matches!(self.code, KeyCode::Char(_)) {
+            s.push_str("shift-");
+        }
+        match self.code {
+            KeyCode::Char(' ') => s.push_str("space"),
+            KeyCode::Char(c) => s.push(c),
+            KeyCode::Up => s.push_str("up"),
+            KeyCode::Down => s.push_str("down"),
+            KeyCode::Left => s.push_str("left"),
+            KeyCode::Right => s.push_str("right"),
+            KeyCode::Home => s.push_str("home"),
+            KeyCode::End => s.push_str("end"),
+            KeyCode::PageUp => s.push_str("pageup"),
+            KeyCode::PageDown => s.push_str("pagedown"),
+            KeyCode::Enter => s.push_str("enter"),
+            KeyCode::Tab => s.push_str("tab"),
+            KeyCode::Esc => s.push_str("esc"),
+            KeyCode::Backspace => s.push_str("backspace"),
+            code => s.push_str(&format!("{code:?}").to_ascii_lowercase()),
+        }
+        s
+    }
+}
+
+/// Table resolving keys pressed in the default mode to actions
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let none = KeyModifiers::NONE;
+        let shift = KeyModifiers::SHIFT;
+        let ctrl = KeyModifiers::CONTROL;
+        let alt = KeyModifiers::ALT;
+        let defaults = [
+            (KeyCode::Char('q'), none, Action::Quit),
+            (KeyCode::Char('j'), none, Action::ScrollDown),
+            (KeyCode::Down, none, Action::ScrollDown),
+            (KeyCode::Char('k'), none, Action::ScrollUp),
+            (KeyCode::Up, none, Action::ScrollUp),
+            (KeyCode::Char('l'), none, Action::ScrollRight),
+            (KeyCode::Right, none, Action::ScrollRight),
+            (KeyCode::Char('h'), none, Action::ScrollLeft),
+            (KeyCode::Left, none, Action::ScrollLeft),
+            (KeyCode::Char('g'), none, Action::ScrollTop),
+            (KeyCode::Home, none, Action::ScrollTop),
+            (KeyCode::End, none, Action::ScrollBottom),
+            (KeyCode::Char('n'), none, Action::NextFound),
+            (KeyCode::Char(']'), none, Action::NextRaggedRow),
+            (KeyCode::Char('['), none, Action::PrevRaggedRow),
+            (KeyCode::Char('}'), none, Action::NextEmptyInColumn),
+            (KeyCode::Char('{'), none, Action::PrevEmptyInColumn),
+            (KeyCode::Char(')'), none, Action::NextGroup),
+            (KeyCode::Char('('), none, Action::PrevGroup),
+            (KeyCode::PageDown, none, Action::ScrollPageDown),
+            (KeyCode::PageUp, none, Action::ScrollPageUp),
+            (KeyCode::Char('d'), none, Action::ScrollHalfPageDown),
+            (KeyCode::Char('u'), none, Action::ScrollHalfPageUp),
+            (KeyCode::Char('/'), none, Action::Find),
+            (KeyCode::Char('&'), none, Action::Filter),
+            (KeyCode::Char('+'), none, Action::FilterWithin),
+            (KeyCode::Char('='), none, Action::FilterDuplicates),
+            (KeyCode::Char('*'), none, Action::FilterColumns),
+            (KeyCode::Char('-'), none, Action::Option),
+            (KeyCode::Char('m'), none, Action::Mark),
+            (KeyCode::Char('\''), none, Action::JumpToMark),
+            (KeyCode::Char('v'), none, Action::SelectRange),
+            (KeyCode::Char('Q'), shift, Action::RecordMacro),
+            (KeyCode::Char('!'), none, Action::PlayMacro),
+            (KeyCode::Char('z'), none, Action::ScrollSelection),
+            (KeyCode::Enter, none, Action::Select),
+            (KeyCode::Tab, none, Action::ToggleSelectionType),
+            (KeyCode::Char('>'), none, Action::IncreaseWidth),
+            (KeyCode::Char('<'), none, Action::DecreaseWidth),
+            (KeyCode::Char('r'), none, Action::Reset),
+            (KeyCode::Char('?'), none, Action::Help),
+            (KeyCode::Char('#'), none, Action::FindLikeCell),
+            (KeyCode::Char('@'), none, Action::FilterLikeCell),
+            (KeyCode::Char('y'), none, Action::CopySelection),
+            (KeyCode::Char('Y'), shift, Action::CopyColumnName),
+            (KeyCode::Char('M'), shift, Action::ShowMessages),
+            (KeyCode::Esc, none, Action::Cancel),
+            (KeyCode::Char('G'), shift, Action::ScrollBottom),
+            (KeyCode::End, shift, Action::ScrollBottom),
+            (KeyCode::Char('N'), shift, Action::PrevFound),
+            (KeyCode::Char('H'), shift, Action::Help),
+            (KeyCode::Char('J'), shift, Action::ToggleSort),
+            (KeyCode::Char('V'), shift, Action::Validate),
+            (KeyCode::Char('D'), shift, Action::CountDistinct),
+            (KeyCode::Char('E'), shift, Action::ExportMatches),
+            (KeyCode::Char('|'), none, Action::GotoColumn),
+            (KeyCode::Char('U'), shift, Action::Undo),
+            (KeyCode::Char('R'), shift, Action::Redo),
+            (KeyCode::Char('.'), none, Action::RepeatLastCommand),
+            (KeyCode::Down, shift, Action::ToggleSort),
+            (KeyCode::Char('~'), none, Action::ReverseOrder),
+            (KeyCode::Char(':'), none, Action::CommandPalette),
+            (KeyCode::Char('f'), ctrl, Action::ScrollPageDown),
+            (KeyCode::Char('b'), ctrl, Action::ScrollPageUp),
+            (KeyCode::Char('d'), ctrl, Action::ScrollHalfPageDown),
+            (KeyCode::Char('u'), ctrl, Action::ScrollHalfPageUp),
+            (KeyCode::Char('y'), ctrl, Action::CopyFilePath),
+            (KeyCode::Char('h'), ctrl, Action::ScrollPageLeft),
+            (KeyCode::Char('l'), ctrl, Action::ScrollPageRight),
+            (KeyCode::Char('h'), alt, Action::ScrollHalfPageLeft),
+            (KeyCode::Char('l'), alt, Action::ScrollHalfPageRight),
+            (KeyCode::Left, ctrl, Action::ScrollLeftMost),
+            (KeyCode::Right, ctrl, Action::ScrollRightMost),
+        ];
+        let bindings = defaults
+            .into_iter()
+            .map(|(code, modifiers, action)| (KeyBinding::new(code, modifiers), action))
+            .collect();
+        KeyMap { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Create a key map from the defaults, with the given key to action name overrides applied.
+    /// Binding a key to "none" removes its default binding.
+    pub fn with_overrides(overrides: &HashMap<String, String>) -> CsvlensResult<Self> {
+        let mut keymap = KeyMap::default();
+        for (key, action_name) in overrides {
+            let binding = KeyBinding::parse(key)?;
+            let action = Action::from_name(action_name)
+                .ok_or_else(|| CsvlensError::UnknownAction(action_name.clone()))?;
+            if action == Action::None {
+                keymap.bindings.remove(&binding);
+            } else {
+                keymap.bindings.insert(binding, action);
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Keys bound to the action, shortest first
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(binding, _)| binding.describe())
+            .collect();
+        keys.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        keys
+    }
+
+    fn action(&self, key_event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .get(&KeyBinding::from_event(key_event))
+            .cloned()
+    }
 }
 
 enum BufferState {
@@ -60,125 +555,189 @@ pub struct InputHandler {
     mode: InputMode,
     buffer_state: BufferState,
     buffer_history_container: BufferHistoryContainer,
+    keymap: KeyMap,
+    /// Whether a macro is being recorded, so that the record key stops it instead
+    recording_macro: bool,
 }
 
 impl InputHandler {
-    pub fn new() -> InputHandler {
+    pub fn new(keymap: KeyMap) -> InputHandler {
         InputHandler {
+            keymap,
             events: CsvlensEvents::new(),
             mode: InputMode::Default,
             buffer_state: BufferState::Inactive,
             buffer_history_container: BufferHistoryContainer::new(),
+            recording_macro: false,
         }
     }
 
     pub fn next(&mut self) -> Control {
-        if let CsvlensEvent::Input(mut key) = self.events.next().unwrap() {
-            /*
-            The shift key modifier is not consistent across platforms.
+        let event = self.events.next().unwrap();
+        if let CsvlensEvent::Mouse(mouse_event) = event {
+            return self.handler_mouse(mouse_event);
+        }
+        if let CsvlensEvent::Input(key) = event {
+            return self.handle_key(key);
+        }
+        // tick event, no need to distinguish it for now
+        Control::Nothing
+    }
 
-            For upper case alphabets, e.g. 'A'
+    /// Control for a key press in the current mode
+    pub fn handle_key(&mut self, mut key: KeyEvent) -> Control {
+        /*
+        The shift key modifier is not consistent across platforms.
 
-            Unix: Char("A") + SHIFT
-            Windows: Char("A") + SHIFT
+        For upper case alphabets, e.g. 'A'
 
-            For non-alphabets, e.g. '>'
+        Unix: Char("A") + SHIFT
+        Windows: Char("A") + SHIFT
 
-            Unix: Char(">") + NULL
-            Windows: Char(">") + SHIFT
+        For non-alphabets, e.g. '>'
 
-            But the key event handling below assumes that the shift key modifier is only added for
-            alphabets. To satisfy the assumption, the following ensures that the presence or absence
-            of shift modifier is consistent across platforms.
+        Unix: Char(">") + NULL
+        Windows: Char(">") + SHIFT
 
-            Idea borrowed from: https://github.com/sxyazi/yazi/pull/174
-            */
-            let platform_consistent_shift = match (key.code, key.modifiers) {
-                (KeyCode::Char(c), _) => c.is_ascii_uppercase(),
-                (_, m) => m.contains(KeyModifiers::SHIFT),
-            };
-            if platform_consistent_shift {
-                key.modifiers.insert(KeyModifiers::SHIFT);
-            } else {
-                key.modifiers.remove(KeyModifiers::SHIFT);
-            }
-            if self.is_help_mode() {
-                return self.handler_help(key);
-            } else if self.is_input_buffering() {
-                return self.handler_buffering(key);
-            } else {
-                return self.handler_default(key);
-            }
+        But the key event handling below assumes that the shift key modifier is only added for
+        alphabets. To satisfy the assumption, the following ensures that the presence or absence
+        of shift modifier is consistent across platforms.
+
+        Idea borrowed from: https://github.com/sxyazi/yazi/pull/174
+        */
+        let platform_consistent_shift = match (key.code, key.modifiers) {
+            (KeyCode::Char(c), _) => c.is_ascii_uppercase(),
+            (_, m) => m.contains(KeyModifiers::SHIFT),
+        };
+        if platform_consistent_shift {
+            key.modifiers.insert(KeyModifiers::SHIFT);
+        } else {
+            key.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        if self.is_help_mode() {
+            self.handler_help(key)
+        } else if self.is_validation_mode() {
+            self.handler_validation(key)
+        } else if self.is_message_log_mode() {
+            self.handler_message_log(key)
+        } else if self.is_command_palette_mode() {
+            self.handler_command_palette(key)
+        } else if self.is_input_buffering() {
+            self.handler_buffering(key)
+        } else {
+            self.handler_default(key)
         }
-        // tick event, no need to distinguish it for now
-        Control::Nothing
     }
 
     fn handler_default(&mut self, key_event: KeyEvent) -> Control {
-        match key_event.modifiers {
-            KeyModifiers::NONE => match key_event.code {
-                KeyCode::Char('q') => Control::Quit,
-                KeyCode::Char('j') | KeyCode::Down => Control::ScrollDown,
-                KeyCode::Char('k') | KeyCode::Up => Control::ScrollUp,
-                KeyCode::Char('l') | KeyCode::Right => Control::ScrollRight,
-                KeyCode::Char('h') | KeyCode::Left => Control::ScrollLeft,
-                KeyCode::Char('g') | KeyCode::Home => Control::ScrollTop,
-                KeyCode::End => Control::ScrollBottom,
-                KeyCode::Char('n') => Control::ScrollToNextFound,
-                KeyCode::PageDown => Control::ScrollPageDown,
-                KeyCode::PageUp => Control::ScrollPageUp,
-                KeyCode::Char('d') => Control::ScrollHalfPageDown,
-                KeyCode::Char('u') => Control::ScrollHalfPageUp,
-                KeyCode::Char(x) if "0123456789".contains(x.to_string().as_str()) => {
-                    self.buffer_state = BufferState::Active(Input::new(x.to_string()));
-                    self.mode = InputMode::GotoLine;
-                    Control::BufferContent(Input::new(x.to_string()))
-                }
-                KeyCode::Char('/') => {
-                    self.init_buffer(InputMode::Find);
-                    Control::empty_buffer()
-                }
-                KeyCode::Char('&') => {
-                    self.init_buffer(InputMode::Filter);
-                    Control::empty_buffer()
-                }
-                KeyCode::Char('*') => {
-                    self.init_buffer(InputMode::FilterColumns);
-                    Control::empty_buffer()
-                }
-                KeyCode::Char('-') => {
-                    self.init_buffer(InputMode::Option);
-                    Control::empty_buffer()
-                }
-                KeyCode::Enter => Control::Select,
-                KeyCode::Tab => Control::ToggleSelectionType,
-                KeyCode::Char('>') => Control::IncreaseWidth,
-                KeyCode::Char('<') => Control::DecreaseWidth,
-                KeyCode::Char('r') => Control::Reset,
-                KeyCode::Char('?') => Control::Help,
-                KeyCode::Char('#') => Control::FindLikeCell,
-                KeyCode::Char('@') => Control::FilterLikeCell,
-                KeyCode::Char('y') => Control::CopySelection,
-                _ => Control::Nothing,
-            },
-            KeyModifiers::SHIFT => match key_event.code {
-                KeyCode::Char('G') | KeyCode::End => Control::ScrollBottom,
-                KeyCode::Char('N') => Control::ScrollToPrevFound,
-                KeyCode::Char('H') => Control::Help,
-                KeyCode::Char('J') | KeyCode::Down => Control::ToggleSort,
-                _ => Control::Nothing,
-            },
-            KeyModifiers::CONTROL => match key_event.code {
-                KeyCode::Char('f') => Control::ScrollPageDown,
-                KeyCode::Char('b') => Control::ScrollPageUp,
-                KeyCode::Char('d') => Control::ScrollHalfPageDown,
-                KeyCode::Char('u') => Control::ScrollHalfPageUp,
-                KeyCode::Char('h') => Control::ScrollPageLeft,
-                KeyCode::Char('l') => Control::ScrollPageRight,
-                KeyCode::Left => Control::ScrollLeftMost,
-                KeyCode::Right => Control::ScrollRightMost,
-                _ => Control::Nothing,
-            },
+        let action = match self.keymap.action(&key_event) {
+            Some(action) => action,
+            None => {
+                return match (key_event.modifiers, key_event.code) {
+                    (KeyModifiers::NONE, KeyCode::Char(x)) if x.is_ascii_digit() => {
+                        self.buffer_state = BufferState::Active(Input::new(x.to_string()));
+                        self.mode = InputMode::GotoLine;
+                        Control::BufferContent(Input::new(x.to_string()))
+                    }
+                    _ => Control::Nothing,
+                };
+            }
+        };
+        self.run_action(action)
+    }
+
+    /// Control for the action as if its key was pressed in the default mode
+    pub fn run_action(&mut self, action: Action) -> Control {
+        if action == Action::RecordMacro && self.recording_macro {
+            self.recording_macro = false;
+            return Control::StopRecordingMacro;
+        }
+        let buffer_mode = match action {
+            Action::Find => Some(InputMode::Find),
+            Action::Filter => Some(InputMode::Filter),
+            Action::FilterWithin => Some(InputMode::FilterWithin),
+            Action::FilterColumns => Some(InputMode::FilterColumns),
+            Action::Option => Some(InputMode::Option),
+            Action::Mark => Some(InputMode::Mark),
+            Action::JumpToMark => Some(InputMode::JumpToMark),
+            Action::RecordMacro => Some(InputMode::RecordMacro),
+            Action::PlayMacro => Some(InputMode::PlayMacro),
+            Action::ScrollSelection => Some(InputMode::ScrollSelection),
+            Action::ExportMatches => Some(InputMode::ExportMatches),
+            Action::GotoColumn => Some(InputMode::GotoColumn),
+            _ => None,
+        };
+        if let Some(mode) = buffer_mode {
+            self.init_buffer(mode);
+            return Control::empty_buffer();
+        }
+        match action {
+            Action::Quit => Control::Quit,
+            Action::ScrollUp => Control::ScrollUp,
+            Action::ScrollDown => Control::ScrollDown,
+            Action::ScrollLeft => Control::ScrollLeft,
+            Action::ScrollRight => Control::ScrollRight,
+            Action::ScrollTop => Control::ScrollTop,
+            Action::ScrollBottom => Control::ScrollBottom,
+            Action::ScrollPageUp => Control::ScrollPageUp,
+            Action::ScrollPageDown => Control::ScrollPageDown,
+            Action::ScrollHalfPageUp => Control::ScrollHalfPageUp,
+            Action::ScrollHalfPageDown => Control::ScrollHalfPageDown,
+            Action::ScrollPageLeft => Control::ScrollPageLeft,
+            Action::ScrollPageRight => Control::ScrollPageRight,
+            Action::ScrollHalfPageLeft => Control::ScrollHalfPageLeft,
+            Action::ScrollHalfPageRight => Control::ScrollHalfPageRight,
+            Action::ScrollLeftMost => Control::ScrollLeftMost,
+            Action::ScrollRightMost => Control::ScrollRightMost,
+            Action::NextFound => Control::ScrollToNextFound,
+            Action::PrevFound => Control::ScrollToPrevFound,
+            Action::NextRaggedRow => Control::NextRaggedRow,
+            Action::PrevRaggedRow => Control::PrevRaggedRow,
+            Action::NextEmptyInColumn => Control::NextEmptyInColumn,
+            Action::PrevEmptyInColumn => Control::PrevEmptyInColumn,
+            Action::NextGroup => Control::NextGroup,
+            Action::PrevGroup => Control::PrevGroup,
+            Action::Select => Control::Select,
+            Action::SelectRange => Control::SelectRange,
+            Action::ToggleSelectionType => Control::ToggleSelectionType,
+            Action::IncreaseWidth => Control::IncreaseWidth,
+            Action::DecreaseWidth => Control::DecreaseWidth,
+            Action::Reset => Control::Reset,
+            Action::Help => Control::Help,
+            Action::FindLikeCell => Control::FindLikeCell,
+            Action::FilterLikeCell => Control::FilterLikeCell,
+            Action::FilterDuplicates => Control::FilterDuplicates,
+            Action::CopySelection => Control::CopySelection,
+            Action::CopyColumnName => Control::CopyColumnName,
+            Action::CopyFilePath => Control::CopyFilePath,
+            Action::ToggleSort => Control::ToggleSort,
+            Action::ReverseOrder => Control::ReverseOrder,
+            Action::Cancel => Control::Cancel,
+            Action::Validate => Control::Validate,
+            Action::CountDistinct => Control::CountDistinct,
+            Action::Undo => Control::Undo,
+            Action::Redo => Control::Redo,
+            Action::RepeatLastCommand => Control::RepeatLastCommand,
+            Action::CommandPalette => Control::CommandPalette,
+            Action::ShowMessages => Control::ShowMessages,
+            _ => Control::Nothing,
+        }
+    }
+
+    fn handler_mouse(&mut self, mouse_event: MouseEvent) -> Control {
+        if self.is_input_buffering() {
+            return Control::Nothing;
+        }
+        match mouse_event.kind {
+            MouseEventKind::ScrollUp => Control::ScrollUp,
+            MouseEventKind::ScrollDown => Control::ScrollDown,
+            MouseEventKind::Down(MouseButton::Left)
+                if !self.is_help_mode()
+                    && !self.is_validation_mode()
+                    && !self.is_message_log_mode() =>
+            {
+                Control::MouseClick(mouse_event.column, mouse_event.row)
+            }
             _ => Control::Nothing,
         }
     }
@@ -191,6 +750,15 @@ impl InputHandler {
         if self.mode == InputMode::Option {
             return self.handler_buffering_option_mode(key_event);
         }
+        if matches!(
+            self.mode,
+            InputMode::Mark | InputMode::JumpToMark | InputMode::RecordMacro | InputMode::PlayMacro
+        ) {
+            return self.handler_buffering_mark_mode(key_event);
+        }
+        if self.mode == InputMode::ScrollSelection {
+            return self.handler_buffering_scroll_selection_mode(key_event);
+        }
         match key_event.code {
             KeyCode::Esc => {
                 self.reset_buffer();
@@ -199,20 +767,29 @@ impl InputHandler {
             KeyCode::Char('g' | 'G') | KeyCode::Enter if self.mode == InputMode::GotoLine => {
                 self.buffer_history_container.set(self.mode, input.value());
                 let goto_line = match &self.buffer_state {
-                    BufferState::Active(input) => input.value().parse::<usize>().ok(),
+                    BufferState::Active(input) => input.value().parse::<i64>().ok(),
                     BufferState::Inactive => None,
                 };
-                let res = if let Some(n) = goto_line {
-                    Control::ScrollTo(n)
-                } else {
-                    Control::BufferReset
+                let res = match goto_line {
+                    Some(n) if n < 0 => Control::ScrollToFromEnd(n.unsigned_abs() as usize),
+                    Some(n) => Control::ScrollTo(n as usize),
+                    None => Control::BufferReset,
+                };
+                self.reset_buffer();
+                res
+            }
+            KeyCode::Char('o') if self.mode == InputMode::GotoLine => {
+                self.buffer_history_container.set(self.mode, input.value());
+                let res = match input.value().parse::<u64>() {
+                    Ok(n) => Control::GotoByte(n),
+                    Err(_) => Control::BufferReset,
                 };
                 self.reset_buffer();
                 res
             }
             KeyCode::Up => {
                 let mode = match self.mode {
-                    InputMode::Filter => InputMode::Find,
+                    InputMode::Filter | InputMode::FilterWithin => InputMode::Find,
                     _ => self.mode,
                 };
                 if let Some(buf) = self.buffer_history_container.prev(mode) {
@@ -224,7 +801,7 @@ impl InputHandler {
             }
             KeyCode::Down => {
                 let mode = match self.mode {
-                    InputMode::Filter => InputMode::Find,
+                    InputMode::Filter | InputMode::FilterWithin => InputMode::Find,
                     _ => self.mode,
                 };
                 if let Some(buf) = self.buffer_history_container.next(mode) {
@@ -243,12 +820,18 @@ impl InputHandler {
                     control = Control::Find(input.value().to_string());
                 } else if self.mode == InputMode::Filter {
                     control = Control::Filter(input.value().to_string());
+                } else if self.mode == InputMode::FilterWithin {
+                    control = Control::FilterWithin(input.value().to_string());
                 } else if self.mode == InputMode::FilterColumns {
                     control = Control::FilterColumns(input.value().to_string());
+                } else if self.mode == InputMode::ExportMatches {
+                    control = Control::ExportMatches(PathBuf::from(input.value()));
+                } else if self.mode == InputMode::GotoColumn {
+                    control = Control::GotoColumn(input.value().to_string());
                 } else {
                     control = Control::BufferReset;
                 }
-                if self.mode == InputMode::Filter {
+                if matches!(self.mode, InputMode::Filter | InputMode::FilterWithin) {
                     // Share buffer history between Find and Filter, see also KeyCode::Up
                     self.buffer_history_container
                         .set(InputMode::Find, input.value());
@@ -281,6 +864,117 @@ impl InputHandler {
                 self.reset_buffer();
                 Control::ToggleLineWrap(true)
             }
+            KeyCode::Char('C') => {
+                self.reset_buffer();
+                Control::ToggleColumnWrap
+            }
+            KeyCode::Char('U') => {
+                self.reset_buffer();
+                Control::ToggleCollapseColumn
+            }
+            KeyCode::Char('L') => {
+                self.reset_buffer();
+                Control::ToggleShowWhitespace
+            }
+            KeyCode::Char('E') => {
+                self.reset_buffer();
+                Control::ToggleEmptyPlaceholder
+            }
+            KeyCode::Char('P') => {
+                self.reset_buffer();
+                Control::ToggleCompact
+            }
+            KeyCode::Char('i') => {
+                self.reset_buffer();
+                Control::ToggleIgnoreCase
+            }
+            KeyCode::Char('A') => {
+                self.reset_buffer();
+                Control::ToggleIgnoreAccents
+            }
+            KeyCode::Char('V') => {
+                self.reset_buffer();
+                Control::ToggleSearchVisibleColumns
+            }
+            KeyCode::Char('N') => {
+                self.reset_buffer();
+                Control::ToggleWrapSearch
+            }
+            KeyCode::Char('Z') => {
+                self.reset_buffer();
+                Control::ToggleSortNullsLast
+            }
+            KeyCode::Char('>') => {
+                self.reset_buffer();
+                Control::IncreaseFilterContext
+            }
+            KeyCode::Char('<') => {
+                self.reset_buffer();
+                Control::DecreaseFilterContext
+            }
+            KeyCode::Char(']') => {
+                self.reset_buffer();
+                Control::IncreaseMaxRowHeight
+            }
+            KeyCode::Char('[') => {
+                self.reset_buffer();
+                Control::DecreaseMaxRowHeight
+            }
+            KeyCode::Char('B') => {
+                self.reset_buffer();
+                Control::ToggleScrollbars
+            }
+            KeyCode::Char('X') => {
+                self.reset_buffer();
+                Control::ToggleCrosshair
+            }
+            KeyCode::Char('H') => {
+                self.reset_buffer();
+                Control::ToggleHighlightAllColumns
+            }
+            KeyCode::Char('D') => {
+                self.reset_buffer();
+                Control::ToggleColumnFocus
+            }
+            KeyCode::Char('T') => {
+                self.reset_buffer();
+                Control::ToggleTextStats
+            }
+            KeyCode::Char('O') => {
+                self.reset_buffer();
+                Control::ToggleByteOffset
+            }
+            KeyCode::Char('F') => {
+                self.reset_buffer();
+                Control::ToggleFooter
+            }
+            KeyCode::Char('M') => {
+                self.reset_buffer();
+                Control::ToggleMatchMode
+            }
+            KeyCode::Char('I') => {
+                self.reset_buffer();
+                Control::ToggleColumnTypes
+            }
+            KeyCode::Char('R') => {
+                self.reset_buffer();
+                Control::ToggleRowNumbers
+            }
+            KeyCode::Char('G') => {
+                self.reset_buffer();
+                Control::ToggleColumnLetters
+            }
+            // Start of a negative line number to go to, counting from the end
+            KeyCode::Char(x @ '1'..='9') => {
+                let value = format!("-{x}");
+                self.buffer_state = BufferState::Active(Input::new(value.clone()));
+                self.mode = InputMode::GotoLine;
+                Control::BufferContent(Input::new(value))
+            }
+            KeyCode::Char('0') => {
+                self.reset_buffer();
+                Control::ToggleZeroBasedRowNumbers
+            }
             KeyCode::Char(x) => {
                 self.reset_buffer();
                 Control::UnknownOption(x.to_string())
@@ -289,15 +983,138 @@ impl InputHandler {
         }
     }
 
+    fn handler_buffering_mark_mode(&mut self, key_event: KeyEvent) -> Control {
+        match key_event.code {
+            KeyCode::Char(x) if x.is_ascii_alphabetic() => {
+                let control = match self.mode {
+                    InputMode::Mark => Control::SetMark(x),
+                    InputMode::RecordMacro => {
+                        self.recording_macro = true;
+                        Control::RecordMacro(x)
+                    }
+                    InputMode::PlayMacro => Control::PlayMacro(x),
+                    _ => Control::JumpToMark(x),
+                };
+                self.reset_buffer();
+                control
+            }
+            KeyCode::Char(_) | KeyCode::Esc | KeyCode::Backspace | KeyCode::Enter => {
+                self.reset_buffer();
+                Control::BufferCancel
+            }
+            _ => Control::Nothing,
+        }
+    }
+
+    fn handler_buffering_scroll_selection_mode(&mut self, key_event: KeyEvent) -> Control {
+        let control = match key_event.code {
+            KeyCode::Char('z') => Control::CenterSelection,
+            KeyCode::Char('t') => Control::SelectionToTop,
+            KeyCode::Char('b') => Control::SelectionToBottom,
+            KeyCode::Char(_) | KeyCode::Esc | KeyCode::Backspace | KeyCode::Enter => {
+                Control::BufferCancel
+            }
+            _ => return Control::Nothing,
+        };
+        self.reset_buffer();
+        control
+    }
+
     fn handler_help(&mut self, key_event: KeyEvent) -> Control {
+        if self.mode == InputMode::HelpFind {
+            return self.handler_help_find(key_event);
+        }
+        if key_event.code == KeyCode::Esc {
+            return Control::Quit;
+        }
+        match self.keymap.action(&key_event) {
+            Some(Action::Quit) => Control::Quit,
+            Some(Action::ScrollDown) => Control::ScrollDown,
+            Some(Action::ScrollUp) => Control::ScrollUp,
+            Some(Action::NextFound) => Control::ScrollToNextFound,
+            Some(Action::PrevFound) => Control::ScrollToPrevFound,
+            Some(Action::Find) => {
+                self.init_buffer(InputMode::HelpFind);
+                Control::empty_buffer()
+            }
+            _ => Control::Nothing,
+        }
+    }
+
+    fn handler_help_find(&mut self, key_event: KeyEvent) -> Control {
+        let input = match &mut self.buffer_state {
+            BufferState::Active(input) => input,
+            BufferState::Inactive => return Control::Nothing,
+        };
         match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => Control::Quit,
-            KeyCode::Char('j') | KeyCode::Down => Control::ScrollDown,
-            KeyCode::Char('k') | KeyCode::Up => Control::ScrollUp,
+            KeyCode::Esc => {
+                self.buffer_state = BufferState::Inactive;
+                self.mode = InputMode::Help;
+                Control::BufferReset
+            }
+            KeyCode::Enter => {
+                let control = if input.value().is_empty() {
+                    Control::BufferReset
+                } else {
+                    Control::Find(input.value().to_string())
+                };
+                self.buffer_state = BufferState::Inactive;
+                self.mode = InputMode::Help;
+                control
+            }
+            _ => {
+                if input.handle_event(&Event::Key(key_event)).is_some() {
+                    return Control::BufferContent(input.clone());
+                }
+                Control::Nothing
+            }
+        }
+    }
+
+    fn handler_validation(&mut self, key_event: KeyEvent) -> Control {
+        if key_event.code == KeyCode::Esc {
+            return Control::Quit;
+        }
+        match self.keymap.action(&key_event) {
+            Some(Action::Quit) => Control::Quit,
+            Some(Action::ScrollDown) => Control::ScrollDown,
+            Some(Action::ScrollUp) => Control::ScrollUp,
+            Some(Action::Select) => Control::Select,
+            _ => Control::Nothing,
+        }
+    }
+
+    fn handler_message_log(&mut self, key_event: KeyEvent) -> Control {
+        if key_event.code == KeyCode::Esc {
+            return Control::Quit;
+        }
+        match self.keymap.action(&key_event) {
+            Some(Action::Quit) => Control::Quit,
+            Some(Action::ScrollDown) => Control::ScrollDown,
+            Some(Action::ScrollUp) => Control::ScrollUp,
             _ => Control::Nothing,
         }
     }
 
+    fn handler_command_palette(&mut self, key_event: KeyEvent) -> Control {
+        let input = match &mut self.buffer_state {
+            BufferState::Active(input) => input,
+            BufferState::Inactive => return Control::Nothing,
+        };
+        match key_event.code {
+            KeyCode::Esc => Control::Quit,
+            KeyCode::Enter => Control::Select,
+            KeyCode::Up => Control::ScrollUp,
+            KeyCode::Down => Control::ScrollDown,
+            _ => {
+                if input.handle_event(&Event::Key(key_event)).is_some() {
+                    return Control::BufferContent(input.clone());
+                }
+                Control::Nothing
+            }
+        }
+    }
+
     fn is_input_buffering(&self) -> bool {
         matches!(self.buffer_state, BufferState::Active(_))
     }
@@ -322,10 +1139,254 @@ impl InputHandler {
     }
 
     pub fn exit_help_mode(&mut self) {
+        self.buffer_state = BufferState::Inactive;
         self.mode = InputMode::Default;
     }
 
     fn is_help_mode(&mut self) -> bool {
-        self.mode == InputMode::Help
+        matches!(self.mode, InputMode::Help | InputMode::HelpFind)
+    }
+
+    pub fn enter_validation_mode(&mut self) {
+        self.mode = InputMode::Validation;
+    }
+
+    pub fn exit_validation_mode(&mut self) {
+        self.mode = InputMode::Default;
+    }
+
+    fn is_validation_mode(&mut self) -> bool {
+        self.mode == InputMode::Validation
+    }
+
+    pub fn enter_message_log_mode(&mut self) {
+        self.mode = InputMode::MessageLog;
+    }
+
+    pub fn exit_message_log_mode(&mut self) {
+        self.mode = InputMode::Default;
+    }
+
+    fn is_message_log_mode(&mut self) -> bool {
+        self.mode == InputMode::MessageLog
+    }
+
+    pub fn enter_command_palette_mode(&mut self) {
+        self.init_buffer(InputMode::CommandPalette);
+    }
+
+    pub fn exit_command_palette_mode(&mut self) {
+        self.reset_buffer();
+    }
+
+    fn is_command_palette_mode(&mut self) -> bool {
+        self.mode == InputMode::CommandPalette
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key() {
+        let parse = |s: &str| KeyBinding::parse(s).unwrap();
+        assert_eq!(
+            parse("j"),
+            KeyBinding::new(KeyCode::Char('j'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse("G"),
+            KeyBinding::new(KeyCode::Char('G'), KeyModifiers::SHIFT)
+        );
+        assert_eq!(parse("shift-g"), parse("G"));
+        assert_eq!(
+            parse("ctrl-f"),
+            KeyBinding::new(KeyCode::Char('f'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse("Shift-Down"),
+            KeyBinding::new(KeyCode::Down, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse("-"),
+            KeyBinding::new(KeyCode::Char('-'), KeyModifiers::NONE)
+        );
+        assert!(matches!(
+            KeyBinding::parse("ctrl-nope"),
+            Err(CsvlensError::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_goto_line_from_end() {
+        let mut handler = InputHandler::new(KeyMap::default());
+        let none = KeyModifiers::NONE;
+        assert!(matches!(
+            handler.run_action(Action::Option),
+            Control::BufferContent(_)
+        ));
+        assert!(matches!(
+            handler.handler_buffering(key(KeyCode::Char('1'), none)),
+            Control::BufferContent(input) if input.value() == "-1"
+        ));
+        assert_eq!(handler.mode, InputMode::GotoLine);
+        handler.handler_buffering(key(KeyCode::Char('0'), none));
+        assert!(matches!(
+            handler.handler_buffering(key(KeyCode::Char('G'), KeyModifiers::SHIFT)),
+            Control::ScrollToFromEnd(10)
+        ));
+    }
+
+    #[test]
+    fn test_record_macro_keys() {
+        let mut handler = InputHandler::new(KeyMap::default());
+        let none = KeyModifiers::NONE;
+        assert!(matches!(
+            handler.run_action(Action::RecordMacro),
+            Control::BufferContent(_)
+        ));
+        assert!(matches!(
+            handler.handler_buffering(key(KeyCode::Char('a'), none)),
+            Control::RecordMacro('a')
+        ));
+        // Pressed again while recording, it stops without asking for a letter
+        assert!(matches!(
+            handler.run_action(Action::RecordMacro),
+            Control::StopRecordingMacro
+        ));
+        assert!(matches!(
+            handler.run_action(Action::RecordMacro),
+            Control::BufferContent(_)
+        ));
+        assert!(matches!(
+            handler.handler_buffering(key(KeyCode::Esc, none)),
+            Control::BufferCancel
+        ));
+        assert!(matches!(
+            handler.run_action(Action::RecordMacro),
+            Control::BufferContent(_)
+        ));
+    }
+
+    #[test]
+    fn test_keymap_overrides() {
+        let overrides = HashMap::from([
+            ("ctrl-n".to_string(), "scroll_down".to_string()),
+            ("j".to_string(), "none".to_string()),
+            ("x".to_string(), "find".to_string()),
+        ]);
+        let keymap = KeyMap::with_overrides(&overrides).unwrap();
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('n'), KeyModifiers::CONTROL)),
+            Some(Action::ScrollDown)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('j'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Down, KeyModifiers::NONE)),
+            Some(Action::ScrollDown)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('x'), KeyModifiers::NONE)),
+            Some(Action::Find)
+        );
+
+        let overrides = HashMap::from([("j".to_string(), "fly".to_string())]);
+        assert!(matches!(
+            KeyMap::with_overrides(&overrides),
+            Err(CsvlensError::UnknownAction(_))
+        ));
+    }
+
+    #[test]
+    fn test_keys_for() {
+        let overrides = HashMap::from([("ctrl-n".to_string(), "scroll_down".to_string())]);
+        let keymap = KeyMap::with_overrides(&overrides).unwrap();
+        assert_eq!(
+            keymap.keys_for(Action::ScrollDown),
+            vec!["j", "down", "ctrl-n"]
+        );
+        assert_eq!(keymap.keys_for(Action::ToggleSort), vec!["J", "shift-down"]);
+        for key in keymap.keys_for(Action::ScrollBottom) {
+            assert_eq!(
+                keymap.bindings.get(&KeyBinding::parse(&key).unwrap()),
+                Some(&Action::ScrollBottom)
+            );
+        }
+        assert_eq!(Action::CommandPalette.name(), "command_palette");
+    }
+
+    #[test]
+    fn test_help_find() {
+        let mut input_handler = InputHandler::new(KeyMap::default());
+        input_handler.enter_help_mode();
+        assert!(matches!(
+            input_handler.handler_help(key(KeyCode::Char('/'), KeyModifiers::NONE)),
+            Control::BufferContent(_)
+        ));
+        // Keys are typed into the prompt rather than closing help
+        assert!(matches!(
+            input_handler.handler_help(key(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Control::BufferContent(_)
+        ));
+        assert!(matches!(
+            input_handler.handler_help(key(KeyCode::Enter, KeyModifiers::NONE)),
+            Control::Find(s) if s == "q"
+        ));
+        assert_eq!(input_handler.mode(), InputMode::Help);
+        assert!(matches!(
+            input_handler.handler_help(key(KeyCode::Char('N'), KeyModifiers::SHIFT)),
+            Control::ScrollToPrevFound
+        ));
+        assert!(matches!(
+            input_handler.handler_help(key(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Control::Quit
+        ));
+    }
+
+    #[test]
+    fn test_mouse_wheel_scrolls() {
+        let mouse = |kind| MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        let mut input_handler = InputHandler::new(KeyMap::default());
+        assert!(matches!(
+            input_handler.handler_mouse(mouse(MouseEventKind::ScrollDown)),
+            Control::ScrollDown
+        ));
+        assert!(matches!(
+            input_handler.handler_mouse(mouse(MouseEventKind::ScrollUp)),
+            Control::ScrollUp
+        ));
+
+        // Scrolls the help page too, but clicks are ignored there
+        input_handler.enter_help_mode();
+        assert!(matches!(
+            input_handler.handler_mouse(mouse(MouseEventKind::ScrollDown)),
+            Control::ScrollDown
+        ));
+        assert!(matches!(
+            input_handler.handler_mouse(mouse(MouseEventKind::Down(MouseButton::Left))),
+            Control::Nothing
+        ));
+        input_handler.exit_help_mode();
+
+        // Ignored while typing into the prompt
+        input_handler.init_buffer(InputMode::Find);
+        assert!(matches!(
+            input_handler.handler_mouse(mouse(MouseEventKind::ScrollDown)),
+            Control::Nothing
+        ));
     }
 }
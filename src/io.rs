@@ -1,5 +1,11 @@
+#[cfg(feature = "parquet")]
+use std::cmp::min;
 use std::fs::File;
+#[cfg(feature = "parquet")]
+use std::io;
 use std::io::{Read, Seek, SeekFrom, Write};
+#[cfg(feature = "parquet")]
+use std::sync::{Arc, Condvar, Mutex};
 use tempfile::NamedTempFile;
 
 use crate::errors::{CsvlensError, CsvlensResult};
@@ -40,6 +46,23 @@ impl SeekableFile {
         })
     }
 
+    /// Copy everything from `reader` to a temp file, e.g. content that is downloaded. `filename`
+    /// is only used for display.
+    #[cfg(feature = "url")]
+    pub fn from_reader<R: Read>(filename: &str, reader: &mut R) -> CsvlensResult<SeekableFile> {
+        let mut inner_file = NamedTempFile::new()?;
+        Self::chunked_copy(reader, &mut inner_file)?;
+        Ok(Self::from_temp_file(filename, inner_file))
+    }
+
+    /// Content already written to a temp file, e.g. a diff. `filename` is only used for display.
+    pub fn from_temp_file(filename: &str, inner_file: NamedTempFile) -> SeekableFile {
+        SeekableFile {
+            filename: Some(filename.to_owned()),
+            inner_file: Some(inner_file),
+        }
+    }
+
     pub fn filename(&self) -> &str {
         if let Some(f) = &self.inner_file {
             f.path().to_str().unwrap()
@@ -63,3 +86,165 @@ impl SeekableFile {
         Ok(total_copied)
     }
 }
+
+#[cfg(feature = "parquet")]
+/// Temp file written in the background, e.g. with content converted from another format, that can
+/// be read while it is written. Readers wait at the end of what is written so far until more is
+/// written or writing is finished.
+#[derive(Debug)]
+pub struct GrowingFile {
+    temp_file: NamedTempFile,
+    progress: Mutex<GrowingProgress>,
+    grown: Condvar,
+}
+
+#[cfg(feature = "parquet")]
+#[derive(Debug)]
+struct GrowingProgress {
+    len: u64,
+    finished: bool,
+    error: Option<String>,
+}
+
+#[cfg(feature = "parquet")]
+impl GrowingFile {
+    pub fn new() -> CsvlensResult<GrowingFile> {
+        Ok(GrowingFile {
+            temp_file: NamedTempFile::new()?,
+            progress: Mutex::new(GrowingProgress {
+                len: 0,
+                finished: false,
+                error: None,
+            }),
+            grown: Condvar::new(),
+        })
+    }
+
+    /// Mark writing as over so that readers reach the end of the content, along with why it
+    /// stopped short if it did
+    pub fn finish(&self, result: Result<(), String>) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.finished = true;
+        progress.error = result.err();
+        self.grown.notify_all();
+    }
+
+    /// Why writing stopped short, leaving the content incomplete, if it did
+    pub fn error(&self) -> Option<String> {
+        self.progress.lock().unwrap().error.clone()
+    }
+
+    /// Length of the content written so far, without waiting for more
+    pub fn written_len(&self) -> u64 {
+        self.progress.lock().unwrap().len
+    }
+
+    /// Length of the content, waiting for it to be complete
+    fn total_len(&self) -> u64 {
+        let progress = self.progress.lock().unwrap();
+        let progress = self.grown.wait_while(progress, |p| !p.finished).unwrap();
+        progress.len
+    }
+
+    /// Length of the content once more than `pos` bytes are written or it is complete
+    fn len_beyond(&self, pos: u64) -> u64 {
+        let progress = self.progress.lock().unwrap();
+        let progress = self
+            .grown
+            .wait_while(progress, |p| p.len <= pos && !p.finished)
+            .unwrap();
+        progress.len
+    }
+
+    /// New independent reader positioned at the start of the content
+    pub fn open(self: &Arc<Self>) -> io::Result<GrowingFileReader> {
+        Ok(GrowingFileReader {
+            file: self.clone(),
+            inner: self.temp_file.reopen()?,
+            pos: 0,
+        })
+    }
+}
+
+#[cfg(feature = "parquet")]
+/// Appends to the end of the content, letting waiting readers continue
+impl Write for &GrowingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut progress = self.progress.lock().unwrap();
+        let n = self.temp_file.as_file().write(buf)?;
+        progress.len += n as u64;
+        self.grown.notify_all();
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+pub struct GrowingFileReader {
+    file: Arc<GrowingFile>,
+    inner: File,
+    pos: u64,
+}
+
+#[cfg(feature = "parquet")]
+impl Read for GrowingFileReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.file.len_beyond(self.pos);
+        let available = min(buf.len() as u64, len.saturating_sub(self.pos)) as usize;
+        let n = self.inner.read(&mut buf[..available])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl Seek for GrowingFileReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.file.total_len().checked_add_signed(delta),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Seek before the start"))?;
+        self.pos = self.inner.seek(SeekFrom::Start(new_pos))?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(all(test, feature = "parquet"))]
+mod tests {
+
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_growing_file() {
+        let file = Arc::new(GrowingFile::new().unwrap());
+        let mut reader = file.open().unwrap();
+
+        let writer = file.clone();
+        let handle = thread::spawn(move || {
+            (&*writer).write_all(b"a,b\n").unwrap();
+            thread::sleep(Duration::from_millis(50));
+            (&*writer).write_all(b"1,2\n").unwrap();
+            writer.finish(Err("stopped short".to_string()));
+        });
+
+        // Reads wait for the rest to be written instead of stopping at what is there so far
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "a,b\n1,2\n");
+        handle.join().unwrap();
+
+        assert_eq!(file.written_len(), 8);
+        assert_eq!(file.error(), Some("stopped short".to_string()));
+        reader.seek(SeekFrom::Start(4)).unwrap();
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        assert_eq!(content, "1,2\n");
+    }
+}
@@ -37,24 +37,144 @@
 //!     println!("Selected: {}", selected_cell);
 //! }
 //! ```
+//!
+//! ## Inspecting the visible rows
+//!
+//! Use `Csvlens` directly to read what was shown after the user exits.
+//!
+//! ```rust,no_run
+//! use csvlens::{Csvlens, CsvlensOptions};
+//!
+//! let options = CsvlensOptions {
+//!     filename: Some("/path/to/your.csv".to_string()),
+//!     ..Default::default()
+//! };
+//! let mut csvlens = Csvlens::new(options).unwrap();
+//! csvlens.run().unwrap();
+//! let headers: Vec<&str> = csvlens.headers().iter().map(|h| h.name.as_str()).collect();
+//! println!("{}", headers.join(","));
+//! for row in csvlens.visible_rows() {
+//!     println!("{}", row.fields.join(","));
+//! }
+//! ```
+//!
+//! ## Reacting to selection changes
+//!
+//! ```rust,no_run
+//! use csvlens::{run_csvlens_with_options, CsvlensOptions, SelectionCallback};
+//! use std::io::Write;
+//!
+//! // Avoid printing to the terminal that csvlens is drawing on
+//! let mut log = std::fs::File::create("/path/to/selection.log").unwrap();
+//! let options = CsvlensOptions {
+//!     filename: Some("/path/to/your.csv".to_string()),
+//!     on_selection_change: Some(SelectionCallback(Box::new(move |event| {
+//!         let _ = writeln!(log, "{:?} {:?} {:?}", event.record_num, event.column_name, event.value);
+//!     }))),
+//!     ..Default::default()
+//! };
+//! run_csvlens_with_options(options).unwrap();
+//! ```
+//!
+//! ## Viewing in-memory content
+//!
+//! ```rust,no_run
+//! use csvlens::{run_csvlens_with_options, CsvlensOptions, Source};
+//!
+//! let data = b"name,count\napple,3\nbanana,5\n".to_vec();
+//! let options = CsvlensOptions {
+//!     filename: Some("fruits.csv".to_string()),
+//!     source: Some(Source::Bytes(data)),
+//!     ..Default::default()
+//! };
+//! run_csvlens_with_options(options).unwrap();
+//! ```
+//!
+//! ## Inspecting a file without the UI
+//!
+//! ```rust,no_run
+//! use csvlens::{inspect_csv, CsvlensOptions};
+//!
+//! let options = CsvlensOptions {
+//!     filename: Some("/path/to/your.csv".to_string()),
+//!     delimiter: Some("auto".to_string()),
+//!     ..Default::default()
+//! };
+//! let info = inspect_csv(options).unwrap();
+//! println!("Delimiter: {:?}", info.delimiter as char);
+//! println!("Columns: {}", info.headers.join(", "));
+//! ```
+//!
+//! ## Custom key bindings
+//!
+//! Keys can be rebound to actions by name, e.g. for emacs style navigation:
+//!
+//! ```rust,no_run
+//! use csvlens::{run_csvlens_with_options, CsvlensOptions};
+//! use std::collections::HashMap;
+//!
+//! let options = CsvlensOptions {
+//!     filename: Some("/path/to/your.csv".to_string()),
+//!     keymap: HashMap::from([
+//!         ("ctrl-n".to_string(), "scroll_down".to_string()),
+//!         ("ctrl-p".to_string(), "scroll_up".to_string()),
+//!         ("ctrl-s".to_string(), "find".to_string()),
+//!     ]),
+//!     ..Default::default()
+//! };
+//! run_csvlens_with_options(options).unwrap();
+//! ```
+mod aggregate;
 mod app;
+mod border;
+mod clipboard;
+mod column_letters;
 mod columns_filter;
 mod common;
 mod csv;
 mod delimiter;
+mod diff;
+mod download;
+mod duplicates;
 pub mod errors;
+mod export;
 mod find;
 mod help;
 mod history;
 mod input;
 mod io;
+mod messages;
+mod normalize;
+mod output;
+mod palette;
+mod parquet_file;
 mod runner;
+mod schema;
 mod sort;
+mod stack;
+mod theme;
+mod truncate;
 mod ui;
+mod undo;
 mod util;
+mod validate;
 mod view;
+mod whitespace;
 mod wrap;
 
+pub use border::BorderStyle;
+pub use clipboard::ClipboardBackend;
+pub use csv::Row;
+pub use output::{CopyMode, OutputGranularity, OutputMode};
+pub use runner::inspect_csv;
+pub use runner::render_csvlens_snapshot;
 pub use runner::run_csvlens;
 pub use runner::run_csvlens_with_options;
+pub use runner::CsvInfo;
+pub use runner::Csvlens;
 pub use runner::CsvlensOptions;
+pub use runner::Source;
+pub use truncate::TruncationMode;
+pub use view::Header;
+pub use view::SelectionCallback;
+pub use view::SelectionEvent;
@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::theme::Theme;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, StatefulWidget, Widget},
+};
+
+/// Number of messages kept in the log, older ones are dropped
+const MAX_MESSAGES: usize = 100;
+
+struct LoggedMessage {
+    time: Instant,
+    message: String,
+}
+
+/// Recent transient messages, to review ones that disappeared too quickly
+pub struct MessageLog {
+    entries: VecDeque<LoggedMessage>,
+    // Whether the latest entry is the message still being shown
+    showing: bool,
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        MessageLog {
+            entries: VecDeque::new(),
+            showing: false,
+        }
+    }
+
+    /// Add a message as it is shown
+    pub fn push(&mut self, message: &str) {
+        if self.entries.len() == MAX_MESSAGES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedMessage {
+            time: Instant::now(),
+            message: message.to_string(),
+        });
+        self.showing = true;
+    }
+
+    /// Change the message being shown, e.g. the progress of a count, which updates its entry
+    /// rather than adding one every time it changes
+    pub fn update(&mut self, message: &str) {
+        match self.entries.back_mut() {
+            Some(last) if self.showing => {
+                if last.message != message {
+                    last.message = message.to_string();
+                    last.time = Instant::now();
+                }
+            }
+            _ => self.push(message),
+        }
+    }
+
+    /// The message shown is cleared, so that the next update adds an entry
+    pub fn hide(&mut self) {
+        self.showing = false;
+    }
+
+    pub fn num_messages(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Messages from the newest to the oldest
+    #[cfg(test)]
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .rev()
+            .map(|entry| entry.message.as_str())
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{secs}s ago")
+    } else if secs < 60 * 60 {
+        format!("{}m ago", secs / 60)
+    } else {
+        format!("{}h ago", secs / (60 * 60))
+    }
+}
+
+pub struct MessageLogPage<'a> {
+    log: &'a MessageLog,
+    border_type: BorderType,
+    theme: &'a Theme,
+}
+
+impl<'a> MessageLogPage<'a> {
+    pub fn new(log: &'a MessageLog, border_type: BorderType, theme: &'a Theme) -> Self {
+        MessageLogPage {
+            log,
+            border_type,
+            theme,
+        }
+    }
+}
+
+pub struct MessageLogPageState {
+    active: bool,
+    offset: usize,
+}
+
+impl MessageLogPageState {
+    pub fn new() -> Self {
+        MessageLogPageState {
+            active: false,
+            offset: 0,
+        }
+    }
+
+    pub fn activate(&mut self) -> &Self {
+        self.active = true;
+        self.offset = 0;
+        self
+    }
+
+    pub fn deactivate(&mut self) -> &Self {
+        self.active = false;
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn scroll_up(&mut self) -> &Self {
+        self.offset = self.offset.saturating_sub(1);
+        self
+    }
+
+    pub fn scroll_down(&mut self, num_messages: usize) -> &Self {
+        if self.offset + 1 < num_messages {
+            self.offset += 1;
+        }
+        self
+    }
+}
+
+impl StatefulWidget for MessageLogPage<'_> {
+    type State = MessageLogPageState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let mut text = vec![
+            Line::from(Span::styled(
+                "Recent messages, newest first",
+                self.theme.heading,
+            )),
+            Line::from("q: close"),
+            Line::from(""),
+        ];
+        if self.log.entries.is_empty() {
+            text.push(Line::from("No messages yet"));
+        }
+        let now = Instant::now();
+        let time_style = self.theme.status;
+        for entry in self.log.entries.iter().rev().skip(state.offset) {
+            let age = format_age(now.saturating_duration_since(entry.time));
+            text.push(Line::from(vec![
+                Span::styled(format!("{age:>7}  "), time_style),
+                Span::raw(entry.message.as_str()),
+            ]));
+        }
+
+        let paragraph = Paragraph::new(text).block(
+            Block::default()
+                .title("Messages")
+                .borders(Borders::ALL)
+                .border_type(self.border_type),
+        );
+
+        paragraph.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_push_and_update() {
+        let mut log = MessageLog::new();
+        log.push("Copied");
+        log.hide();
+        // Shown again after another key press
+        log.push("Copied");
+        // Replaced before being cleared
+        log.push("Sorted");
+        log.hide();
+        log.update("Counting... 1 so far");
+        log.update("Counting... 2 so far");
+        log.update("Counting... 2 so far");
+        assert_eq!(
+            log.messages().collect::<Vec<_>>(),
+            vec!["Counting... 2 so far", "Sorted", "Copied", "Copied"]
+        );
+
+        for i in 0..MAX_MESSAGES + 1 {
+            log.push(&i.to_string());
+        }
+        assert_eq!(log.num_messages(), MAX_MESSAGES);
+        assert_eq!(log.messages().last(), Some("1"));
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_millis(1500)), "1s ago");
+        assert_eq!(format_age(Duration::from_secs(125)), "2m ago");
+        assert_eq!(format_age(Duration::from_secs(7200)), "2h ago");
+    }
+}
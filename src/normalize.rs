@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+
+/// Remove diacritics, e.g. "café" becomes "cafe"
+pub fn strip_diacritics(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        push_stripped(c, &mut out);
+    }
+    Cow::Owned(out)
+}
+
+/// Like `strip_diacritics`, but also returns the byte offset in `s` of the character each byte of
+/// the stripped string came from, followed by the length of `s`
+pub fn strip_diacritics_with_offsets(s: &str) -> (String, Vec<usize>) {
+    let mut out = String::with_capacity(s.len());
+    let mut offsets = Vec::with_capacity(s.len() + 1);
+    for (offset, c) in s.char_indices() {
+        let len_before = out.len();
+        push_stripped(c, &mut out);
+        offsets.resize(offsets.len() + out.len() - len_before, offset);
+    }
+    offsets.push(s.len());
+    (out, offsets)
+}
+
+/// Leave out the given thousands separator from a number, e.g. "1,234.5" becomes "1234.5" with
+/// ','. With '.' as the separator, ',' is taken as the decimal separator instead, e.g. "1.234,5".
+pub fn normalize_number(value: &str, thousands_separator: Option<char>) -> Cow<'_, str> {
+    let Some(separator) = thousands_separator else {
+        return Cow::Borrowed(value);
+    };
+    Cow::Owned(
+        value
+            .chars()
+            .filter(|c| *c != separator)
+            .map(|c| if separator == '.' && c == ',' { '.' } else { c })
+            .collect(),
+    )
+}
+
+/// Parse a number, leaving out the given thousands separator as in `normalize_number`
+pub fn parse_number(value: &str, thousands_separator: Option<char>) -> Option<f64> {
+    normalize_number(value, thousands_separator).parse().ok()
+}
+
+/// Whether a character can group the digits of numbers, i.e. is not part of numbers itself
+pub fn is_valid_thousands_separator(c: char) -> bool {
+    !(c.is_ascii_digit() || matches!(c, '-' | '+' | 'e' | 'E'))
+}
+
+fn push_stripped(c: char, out: &mut String) {
+    // Only decompose characters that carry a mark, so that e.g. Hangul syllables are kept whole
+    let mut decomposed = vec![];
+    decompose_canonical(c, |d| decomposed.push(d));
+    if decomposed.iter().any(|d| is_combining_mark(*d)) {
+        out.extend(decomposed.into_iter().filter(|d| !is_combining_mark(*d)));
+    } else {
+        out.push(c);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_strip_diacritics() {
+        assert!(matches!(strip_diacritics("cafe"), Cow::Borrowed("cafe")));
+        assert_eq!(strip_diacritics("Café Ñandú"), "Cafe Nandu");
+        assert_eq!(strip_diacritics("東京"), "東京");
+        assert_eq!(strip_diacritics("한국"), "한국");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("1234.5", None), Some(1234.5));
+        assert_eq!(parse_number("1,234.5", None), None);
+        assert_eq!(parse_number("1,234,567.5", Some(',')), Some(1234567.5));
+        assert_eq!(parse_number("1.234.567,5", Some('.')), Some(1234567.5));
+        assert_eq!(parse_number("1 000", Some(' ')), Some(1000.0));
+        assert_eq!(parse_number("-9", Some(',')), Some(-9.0));
+        assert_eq!(parse_number(",", Some(',')), None);
+        assert_eq!(parse_number("1,2x", Some(',')), None);
+    }
+
+    #[test]
+    fn test_is_valid_thousands_separator() {
+        assert!(is_valid_thousands_separator(','));
+        assert!(is_valid_thousands_separator('.'));
+        assert!(is_valid_thousands_separator(' '));
+        assert!(!is_valid_thousands_separator('0'));
+        assert!(!is_valid_thousands_separator('-'));
+        assert!(!is_valid_thousands_separator('+'));
+        assert!(!is_valid_thousands_separator('e'));
+    }
+
+    #[test]
+    fn test_strip_diacritics_with_offsets() {
+        let (stripped, offsets) = strip_diacritics_with_offsets("aé b");
+        assert_eq!(stripped, "ae b");
+        assert_eq!(offsets, vec![0, 1, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::view::Header;
+
+/// What is returned when a cell is selected with Enter
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputMode {
+    /// The value of the selected cell as is
+    #[default]
+    Value,
+    /// A JSON object with the record number, column name and value of the selected cell
+    Json,
+    /// Like json, but also with the shown columns of the selected row
+    JsonRow,
+}
+
+/// How much of the selection is returned when Enter is pressed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputGranularity {
+    /// The selected cell, or the echoed columns of the selected row
+    #[default]
+    Cell,
+    /// The whole selected row when in row selection mode, the selected cell otherwise
+    Row,
+}
+
+/// What is put on the clipboard when copying the selected cell or row
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CopyMode {
+    /// The value of the cell as is, or the fields of the row separated by tabs
+    #[default]
+    Value,
+    /// The cell quoted as a csv field where needed, or the row as a line delimited like the
+    /// file, so that pasting into another csv keeps it intact
+    Csv,
+}
+
+impl CopyMode {
+    pub fn format_cell(self, value: &str, delimiter: u8) -> String {
+        match self {
+            CopyMode::Value => value.to_string(),
+            CopyMode::Csv => quote_field(value, delimiter),
+        }
+    }
+
+    pub fn format_row(self, fields: &[String], delimiter: u8) -> String {
+        match self {
+            CopyMode::Value => fields.join("\t"),
+            CopyMode::Csv => delimited_line(fields, delimiter),
+        }
+    }
+}
+
+/// The selected cell and where it is, to be formatted according to an `OutputMode`
+pub struct SelectionOutput<'a> {
+    pub value: String,
+    pub record_num: Option<usize>,
+    pub column_name: Option<String>,
+    pub row: Option<(&'a [Header], &'a [String])>,
+}
+
+impl SelectionOutput<'_> {
+    pub fn format(self, mode: OutputMode) -> String {
+        if mode == OutputMode::Value {
+            return self.value;
+        }
+        let record_num = self
+            .record_num
+            .map_or("null".to_string(), |n| n.to_string());
+        let column_name = self
+            .column_name
+            .as_deref()
+            .map_or("null".to_string(), json_string);
+        let mut out = format!(
+            "{{\"record_num\":{},\"column\":{},\"value\":{}",
+            record_num,
+            column_name,
+            json_string(&self.value)
+        );
+        if mode == OutputMode::JsonRow {
+            out += ",\"row\":";
+            match self.row {
+                Some((headers, fields)) => out += json_row(headers, fields).as_str(),
+                None => out += "null",
+            }
+        }
+        out += "}";
+        out
+    }
+}
+
+/// The selected row, to be returned whole when the output granularity is `Row`
+pub struct RowOutput<'a> {
+    pub record_num: usize,
+    pub headers: &'a [Header],
+    pub fields: &'a [String],
+}
+
+impl RowOutput<'_> {
+    /// A line of the fields separated by the delimiter of the file, quoted as needed, or a JSON
+    /// object with the record number and the row for the JSON modes
+    pub fn format(self, mode: OutputMode, delimiter: u8) -> String {
+        if mode == OutputMode::Value {
+            return delimited_line(self.fields, delimiter);
+        }
+        format!(
+            "{{\"record_num\":{},\"row\":{}}}",
+            self.record_num,
+            json_row(self.headers, self.fields)
+        )
+    }
+}
+
+fn json_row(headers: &[Header], fields: &[String]) -> String {
+    let entries: Vec<String> = headers
+        .iter()
+        .zip(fields.iter())
+        .map(|(header, field)| format!("{}:{}", json_string(&header.name), json_string(field)))
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+/// The fields written as a csv record with the given delimiter, without the line terminator
+fn delimited_line<T: AsRef<[u8]>>(fields: &[T], delimiter: u8) -> String {
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(vec![]);
+    // Writing valid UTF-8 fields to memory cannot fail
+    writer.write_record(fields).unwrap();
+    let line = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+    line.strip_suffix('\n').unwrap_or(&line).to_string()
+}
+
+/// The field quoted like in a csv record if needed, e.g. if it has the delimiter
+fn quote_field(field: &str, delimiter: u8) -> String {
+    // A record of a single empty field is quoted so that it is not an empty line
+    if field.is_empty() {
+        return String::new();
+    }
+    delimited_line(&[field], delimiter)
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn headers() -> Vec<Header> {
+        ["a", "b"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Header {
+                name: name.to_string(),
+                origin_index: i,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_copy_mode() {
+        let fields = vec!["1".to_string(), "a,b".to_string(), "say \"hi\"".to_string()];
+        assert_eq!(CopyMode::Value.format_cell("a,b", b','), "a,b");
+        assert_eq!(CopyMode::Csv.format_cell("a,b", b','), "\"a,b\"");
+        assert_eq!(CopyMode::Csv.format_cell("a,b", b';'), "a,b");
+        assert_eq!(CopyMode::Csv.format_cell("x", b','), "x");
+        assert_eq!(
+            CopyMode::Value.format_row(&fields, b','),
+            "1\ta,b\tsay \"hi\""
+        );
+        assert_eq!(
+            CopyMode::Csv.format_row(&fields, b','),
+            "1,\"a,b\",\"say \"\"hi\"\"\""
+        );
+    }
+
+    #[test]
+    fn test_value() {
+        let output = SelectionOutput {
+            value: "x".to_string(),
+            record_num: Some(3),
+            column_name: Some("a".to_string()),
+            row: None,
+        };
+        assert_eq!(output.format(OutputMode::Value), "x");
+    }
+
+    #[test]
+    fn test_json() {
+        let headers = headers();
+        let fields = vec!["1".to_string(), "say \"hi\"\n".to_string()];
+        let output = SelectionOutput {
+            value: "say \"hi\"\n".to_string(),
+            record_num: Some(3),
+            column_name: Some("b".to_string()),
+            row: Some((&headers, &fields)),
+        };
+        assert_eq!(
+            output.format(OutputMode::Json),
+            r#"{"record_num":3,"column":"b","value":"say \"hi\"\n"}"#
+        );
+    }
+
+    #[test]
+    fn test_json_row() {
+        let headers = headers();
+        let fields = vec!["1".to_string(), "\u{1}".to_string()];
+        let output = SelectionOutput {
+            value: "1".to_string(),
+            record_num: Some(1),
+            column_name: Some("a".to_string()),
+            row: Some((&headers, &fields)),
+        };
+        assert_eq!(
+            output.format(OutputMode::JsonRow),
+            r#"{"record_num":1,"column":"a","value":"1","row":{"a":"1","b":"\u0001"}}"#
+        );
+    }
+
+    #[test]
+    fn test_row() {
+        let headers = headers();
+        let fields = vec!["1".to_string(), "x, \"y\"".to_string()];
+        let output = |mode, delimiter| {
+            RowOutput {
+                record_num: 2,
+                headers: &headers,
+                fields: &fields,
+            }
+            .format(mode, delimiter)
+        };
+        assert_eq!(output(OutputMode::Value, b','), r#"1,"x, ""y""""#);
+        assert_eq!(output(OutputMode::Value, b'\t'), "1\t\"x, \"\"y\"\"\"");
+        assert_eq!(
+            output(OutputMode::Json, b','),
+            r#"{"record_num":2,"row":{"a":"1","b":"x, \"y\""}}"#
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::input::{Action, KeyMap};
+use crate::theme::Theme;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, StatefulWidget, Widget},
+};
+
+/// Actions listed in the command palette, in the order shown, with a one-line description
+const COMMANDS: &[(Action, &str)] = &[
+    (
+        Action::Find,
+        "Find content matching regex and highlight matches",
+    ),
+    (Action::NextFound, "Jump to the next match"),
+    (Action::PrevFound, "Jump to the previous match"),
+    (
+        Action::Filter,
+        "Filter rows using regex (show only matches)",
+    ),
+    (Action::FilterWithin, "Filter the filtered rows further"),
+    (
+        Action::FilterDuplicates,
+        "Filter rows duplicated in the selected column",
+    ),
+    (Action::FilterColumns, "Filter columns using regex"),
+    (Action::FindLikeCell, "Find rows like the selected cell"),
+    (Action::FilterLikeCell, "Filter rows like the selected cell"),
+    (Action::ExportMatches, "Export the rows found to a CSV file"),
+    (Action::ToggleSort, "Sort rows by the selected column"),
+    (Action::ReverseOrder, "Reverse the order of the rows"),
+    (
+        Action::Cancel,
+        "Cancel a find, filter or sort still running",
+    ),
+    (Action::Validate, "Validate the file and list invalid rows"),
+    (Action::CountDistinct, "Count the distinct rows of the file"),
+    (Action::Select, "Print the selection to stdout and exit"),
+    (
+        Action::ToggleSelectionType,
+        "Toggle between row, column or cell selection",
+    ),
+    (Action::CopySelection, "Copy the selected row or cell"),
+    (
+        Action::CopyColumnName,
+        "Copy the name of the selected column",
+    ),
+    (Action::CopyFilePath, "Copy the absolute path of the file"),
+    (
+        Action::IncreaseWidth,
+        "Increase the selected column's width",
+    ),
+    (
+        Action::DecreaseWidth,
+        "Decrease the selected column's width",
+    ),
+    (
+        Action::Option,
+        "Toggle an option, e.g. -S for line wrapping",
+    ),
+    (Action::Mark, "Mark the selected row with a letter"),
+    (Action::JumpToMark, "Jump to the row marked with a letter"),
+    (
+        Action::GotoColumn,
+        "Go to a column by its name or letters, e.g. Q or AB",
+    ),
+    (
+        Action::SelectRange,
+        "Start or clear a range of rows to sum the selected column over",
+    ),
+    (
+        Action::RecordMacro,
+        "Record the following commands as a macro named by a letter, or stop recording",
+    ),
+    (Action::PlayMacro, "Play the macro named by a letter"),
+    (Action::ScrollSelection, "Scroll to move the selected row"),
+    (
+        Action::NextRaggedRow,
+        "Jump to the next row with a different number of fields",
+    ),
+    (
+        Action::PrevRaggedRow,
+        "Jump to the previous row with a different number of fields",
+    ),
+    (
+        Action::NextEmptyInColumn,
+        "Jump to the next empty cell in the column",
+    ),
+    (
+        Action::PrevEmptyInColumn,
+        "Jump to the previous empty cell in the column",
+    ),
+    (Action::NextGroup, "Jump to the next group of equal values"),
+    (
+        Action::PrevGroup,
+        "Jump to the previous group of equal values",
+    ),
+    (Action::ScrollTop, "Go to top"),
+    (Action::ScrollBottom, "Go to bottom"),
+    (Action::ScrollUp, "Scroll one row up"),
+    (Action::ScrollDown, "Scroll one row down"),
+    (Action::ScrollLeft, "Scroll one column left"),
+    (Action::ScrollRight, "Scroll one column right"),
+    (Action::ScrollPageUp, "Scroll one window up"),
+    (Action::ScrollPageDown, "Scroll one window down"),
+    (Action::ScrollHalfPageUp, "Scroll half a window up"),
+    (Action::ScrollHalfPageDown, "Scroll half a window down"),
+    (Action::ScrollPageLeft, "Scroll one window left"),
+    (Action::ScrollPageRight, "Scroll one window right"),
+    (Action::ScrollHalfPageLeft, "Scroll half a window left"),
+    (Action::ScrollHalfPageRight, "Scroll half a window right"),
+    (Action::ScrollLeftMost, "Scroll left to the first column"),
+    (Action::ScrollRightMost, "Scroll right to the last column"),
+    (Action::Reset, "Reset to the default view"),
+    (
+        Action::Undo,
+        "Undo the last change to filters, sort or columns",
+    ),
+    (Action::Redo, "Redo the last change undone"),
+    (
+        Action::RepeatLastCommand,
+        "Repeat the last find, filter or sort",
+    ),
+    (Action::ShowMessages, "Show recent messages"),
+    (Action::Help, "Display the help page"),
+    (Action::Quit, "Exit"),
+];
+
+/// An action listed in the command palette
+pub struct Command {
+    pub action: Action,
+    pub description: &'static str,
+    /// Keys currently bound to the action
+    pub keys: Vec<String>,
+}
+
+impl Command {
+    fn matches(&self, words: &[String]) -> bool {
+        let name = self.action.name().replace('_', " ");
+        let haystack = format!("{} {}", name, self.description).to_lowercase();
+        words.iter().all(|w| haystack.contains(w.as_str()))
+    }
+}
+
+/// List every command with the keys bound to it in the key map
+pub fn commands(keymap: &KeyMap) -> Vec<Command> {
+    COMMANDS
+        .iter()
+        .map(|(action, description)| Command {
+            action: *action,
+            description,
+            keys: keymap.keys_for(*action),
+        })
+        .collect()
+}
+
+pub struct CommandPalette<'a> {
+    border_type: BorderType,
+    theme: &'a Theme,
+}
+
+impl<'a> CommandPalette<'a> {
+    pub fn new(border_type: BorderType, theme: &'a Theme) -> Self {
+        CommandPalette { border_type, theme }
+    }
+}
+
+pub struct CommandPaletteState {
+    active: bool,
+    commands: Vec<Command>,
+    query: String,
+    // Indices into commands matching the query
+    matches: Vec<usize>,
+    selected: usize,
+    offset: usize,
+}
+
+impl CommandPaletteState {
+    pub fn new(commands: Vec<Command>) -> Self {
+        let matches = (0..commands.len()).collect();
+        CommandPaletteState {
+            active: false,
+            commands,
+            query: String::new(),
+            matches,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn activate(&mut self) -> &Self {
+        self.active = true;
+        self.set_query("");
+        self.offset = 0;
+        self
+    }
+
+    pub fn deactivate(&mut self) -> &Self {
+        self.active = false;
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Show only the commands whose name or description contains every word of the query,
+    /// ignoring case
+    pub fn set_query(&mut self, query: &str) -> &Self {
+        self.query = query.to_string();
+        let words: Vec<String> = query.split_whitespace().map(|w| w.to_lowercase()).collect();
+        self.matches = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| command.matches(&words))
+            .map(|(i, _)| i)
+            .collect();
+        self.selected = 0;
+        self
+    }
+
+    pub fn select_previous(&mut self) -> &Self {
+        self.selected = self.selected.saturating_sub(1);
+        self
+    }
+
+    pub fn select_next(&mut self) -> &Self {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+        self
+    }
+
+    /// Action of the highlighted command, if any command matches the query
+    pub fn selected_action(&self) -> Option<Action> {
+        self.matches
+            .get(self.selected)
+            .map(|i| self.commands[*i].action)
+    }
+}
+
+impl StatefulWidget for CommandPalette<'_> {
+    type State = CommandPaletteState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let query_style = self.theme.heading;
+        let mut text = vec![
+            Line::from(Span::styled(format!("> {}", state.query), query_style)),
+            Line::from("Type to filter, Enter: run the selected command, Esc: close"),
+            Line::from(""),
+        ];
+
+        // Keep the selected command in view. Minus 2 to account for borders.
+        let list_height = (area.height.saturating_sub(2) as usize).saturating_sub(text.len());
+        if state.selected < state.offset {
+            state.offset = state.selected;
+        } else if list_height > 0 && state.selected >= state.offset + list_height {
+            state.offset = state.selected + 1 - list_height;
+        }
+
+        let name_width = COMMANDS
+            .iter()
+            .map(|(action, _)| action.name().len())
+            .max()
+            .unwrap_or(0);
+        let keys: Vec<String> = state
+            .commands
+            .iter()
+            .map(|command| command.keys.join(", "))
+            .collect();
+        let keys_width = keys.iter().map(|k| k.chars().count()).max().unwrap_or(0);
+
+        if state.matches.is_empty() {
+            text.push(Line::from("No matching commands"));
+        }
+        for (i, index) in state
+            .matches
+            .iter()
+            .enumerate()
+            .skip(state.offset)
+            .take(list_height)
+        {
+            let command = &state.commands[*index];
+            let line = format!(
+                "{:<name_width$}  {:<keys_width$}  {}",
+                command.action.name(),
+                keys[*index],
+                command.description,
+            );
+            let style = if i == state.selected {
+                self.theme.selected
+            } else {
+                Style::default()
+            };
+            text.push(Line::from(Span::styled(line, style)));
+        }
+
+        let paragraph = Paragraph::new(text).block(
+            Block::default()
+                .title("Commands")
+                .borders(Borders::ALL)
+                .border_type(self.border_type),
+        );
+
+        paragraph.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_filter_commands() {
+        let mut state = CommandPaletteState::new(commands(&KeyMap::default()));
+        state.set_query("COPY name");
+        assert_eq!(state.selected_action(), Some(Action::CopyColumnName));
+        state.select_next();
+        assert_eq!(state.selected_action(), Some(Action::CopyColumnName));
+
+        state.set_query("reverse");
+        assert_eq!(state.selected_action(), Some(Action::ReverseOrder));
+
+        state.set_query("nothing like this");
+        assert_eq!(state.selected_action(), None);
+    }
+
+    #[test]
+    fn test_keys() {
+        let commands = commands(&KeyMap::default());
+        let keys = |action| {
+            commands
+                .iter()
+                .find(|c| c.action == action)
+                .unwrap()
+                .keys
+                .clone()
+        };
+        assert_eq!(keys(Action::ScrollBottom), vec!["G", "end", "shift-end"]);
+        assert_eq!(keys(Action::ScrollPageDown), vec!["ctrl-f", "pagedown"]);
+    }
+}
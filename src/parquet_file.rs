@@ -0,0 +1,159 @@
+use crate::csv::CsvSource;
+use crate::errors::{CsvlensError, CsvlensResult};
+use crate::io::SeekableFile;
+
+#[cfg(feature = "parquet")]
+use crate::io::GrowingFile;
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+#[cfg(feature = "parquet")]
+use std::fs::File;
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+#[cfg(feature = "parquet")]
+use std::thread;
+
+/// Whether the file is to be read as parquet, judging by its extension
+pub fn is_parquet(filename: &str) -> bool {
+    std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("parquet"))
+}
+
+/// Convert a parquet file to csv, with the column names as the header and nulls as empty values.
+/// Values are formatted the same way as Arrow writes csv. Only the first batch is converted up
+/// front, so that the file is known to be readable; the rest is converted in the background while
+/// the csv is read. Converting stops at a batch that fails to read or write, which is kept as the
+/// error of the source.
+#[cfg(feature = "parquet")]
+pub fn to_csv(file: SeekableFile) -> CsvlensResult<CsvSource> {
+    let filename = file.filename();
+    let failed = |reason: String| CsvlensError::Parquet(filename.to_string(), reason);
+    let f = File::open(filename).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => CsvlensError::FileNotFound(filename.to_string()),
+        _ => e.into(),
+    })?;
+    let mut reader = ParquetRecordBatchReaderBuilder::try_new(f)
+        .and_then(|builder| builder.build())
+        .map_err(|e| failed(e.to_string()))?;
+    let first_batch = reader
+        .next()
+        .transpose()
+        .map_err(|e| failed(e.to_string()))?;
+
+    // Starts with an empty batch so that the header is written even if the file has no rows
+    let schema = arrow::array::RecordBatchReader::schema(&reader);
+    let empty_batch = arrow::array::RecordBatch::new_empty(schema);
+
+    let growing = Arc::new(GrowingFile::new()?);
+    let _growing = growing.clone();
+    thread::spawn(move || {
+        let mut writer = arrow::csv::WriterBuilder::new()
+            .with_header(true)
+            .build(&*_growing);
+        let batches = [Ok(empty_batch)].into_iter().chain(first_batch.map(Ok));
+        let result = batches
+            .chain(reader)
+            .try_for_each(|batch| writer.write(&batch?))
+            .map_err(|e| CsvlensError::Parquet(file.filename().to_string(), e.to_string()));
+        drop(writer);
+        _growing.finish(result.map_err(|e| e.to_string()));
+        // Keeps the file, which may be a temp file, around until it is converted
+        drop(file);
+    });
+    Ok(CsvSource::Growing(growing))
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn to_csv(file: SeekableFile) -> CsvlensResult<CsvSource> {
+    Err(CsvlensError::Parquet(
+        file.filename().to_string(),
+        "csvlens was built without the parquet feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_is_parquet() {
+        assert!(is_parquet("data.parquet"));
+        assert!(is_parquet("dir/DATA.PARQUET"));
+        assert!(!is_parquet("data.csv"));
+        assert!(!is_parquet("parquet"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_to_csv() {
+        use arrow::array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+        use parquet::arrow::ArrowWriter;
+        use std::io::Read;
+
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c, d")])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let parquet_file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer =
+            ArrowWriter::try_new(parquet_file.reopen().unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let filename = parquet_file.path().to_str().unwrap().to_string();
+        let source = to_csv(SeekableFile::new(&Some(filename)).unwrap()).unwrap();
+        let mut content = String::new();
+        source.open().unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "id,name\n1,a\n2,\n3,\"c, d\"\n");
+        assert_eq!(source.len().unwrap(), content.len() as u64);
+        assert_eq!(source.error(), None);
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_to_csv_error() {
+        use arrow::array::{ArrayRef, Int64Array, RecordBatch};
+        use parquet::arrow::ArrowWriter;
+        use parquet::file::properties::WriterProperties;
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        // The first batch is in the first row group, which is intact
+        let batch = RecordBatch::try_from_iter(vec![(
+            "id",
+            Arc::new(Int64Array::from_iter_values(0..3000)) as ArrayRef,
+        )])
+        .unwrap();
+        let parquet_file = tempfile::NamedTempFile::new().unwrap();
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2048)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(parquet_file.reopen().unwrap(), batch.schema(), Some(props))
+                .unwrap();
+        writer.write(&batch).unwrap();
+        let metadata = writer.close().unwrap();
+        let offset = metadata.row_groups[1].columns[0]
+            .meta_data
+            .as_ref()
+            .unwrap()
+            .data_page_offset;
+        let mut f = parquet_file.reopen().unwrap();
+        f.seek(SeekFrom::Start(offset as u64)).unwrap();
+        f.write_all(&[0xff; 16]).unwrap();
+
+        let filename = parquet_file.path().to_str().unwrap().to_string();
+        let source = to_csv(SeekableFile::new(&Some(filename.clone())).unwrap()).unwrap();
+        let mut content = String::new();
+        source.open().unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content.lines().count(), 1 + 2048);
+        let error = source.error().unwrap();
+        assert!(error.starts_with(&format!("Failed to read {filename} as parquet: ")));
+    }
+}
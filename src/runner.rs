@@ -1,29 +1,55 @@
-use crate::app::App;
-use crate::delimiter::Delimiter;
-use crate::errors::CsvlensResult;
+use crate::app::{App, AppOptions};
+use crate::border::BorderStyle;
+use crate::clipboard::ClipboardBackend;
+use crate::csv::{CsvConfig, CsvSource, PosTableConfig, Row};
+use crate::delimiter::{terminator_from_arg, Delimiter, DELIMITER_ENV_VAR};
+use crate::diff::Diff;
+use crate::download;
+use crate::errors::{CsvlensError, CsvlensResult};
+use crate::input::KeyMap;
 use crate::io::SeekableFile;
+use crate::normalize;
+use crate::output::{CopyMode, OutputGranularity, OutputMode};
+use crate::parquet_file;
+use crate::stack::Stack;
+use crate::theme::NO_COLOR_ENV_VAR;
+use crate::truncate::{Truncation, TruncationMode};
+use crate::ui::{DEFAULT_SPACES_AFTER_LINE_NUMBER, DEFAULT_SPACES_BETWEEN_COLUMNS};
+use crate::view::{Header, SelectionCallback};
+use crate::whitespace::WhitespaceSymbols;
 
 #[cfg(feature = "cli")]
-use clap::{command, Parser};
+use clap::Parser;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::io::LineWriter;
 use std::panic;
+use std::sync::Arc;
 use std::thread::panicking;
 
 #[cfg(feature = "cli")]
 #[derive(Parser, Debug)]
 #[command(version)]
 struct Args {
-    /// CSV filename
+    /// CSV filename, or an http(s) URL to download it from if built with the url feature. Files
+    /// ending in .parquet are read as parquet if built with the parquet feature.
     filename: Option<String>,
 
-    /// Delimiter character (comma by default) or "auto" to auto-detect the delimiter
+    /// More files with the same columns, whose records are shown below those of the first one
+    /// with a "source" column naming the file of each record
+    #[arg(value_name = "FILENAME")]
+    stack_filenames: Vec<String>,
+
+    /// Delimiter character or "auto" to auto-detect the delimiter. Defaults to tab for .tsv files,
+    /// pipe for .psv files and otherwise to the CSVLENS_DELIMITER environment variable if set, or
+    /// comma.
     #[clap(short, long, value_name = "char")]
     delimiter: Option<String>,
 
@@ -31,14 +57,49 @@ struct Args {
     #[clap(short = 't', long)]
     tab_separated: bool,
 
+    /// Record terminator character, e.g. '\r' or ';'. Defaults to "crlf", which ends records at
+    /// any of \r\n, \n or \r.
+    #[clap(long, value_name = "char")]
+    terminator: Option<String>,
+
+    /// Read the file as parquet even if it does not end in .parquet. Requires the parquet
+    /// feature.
+    #[clap(long)]
+    parquet: bool,
+
     /// Do not interpret the first row as headers.
     #[clap(long)]
     no_headers: bool,
 
+    /// Treat the first row after the headers as the units of the columns. It stays pinned below
+    /// the headers and is left out of finding, filtering and sorting.
+    #[clap(long)]
+    units_row: bool,
+
+    /// Character grouping the digits of numbers, e.g. "," for 1,234,567.5 or "." for
+    /// 1.234.567,5. Such numbers are then sorted and summed as numbers rather than text.
+    #[arg(long, value_name = "char")]
+    thousands_separator: Option<char>,
+
+    /// Only read the first n records, e.g. to preview a large file. Finding and filtering then
+    /// search these records only.
+    #[arg(long, value_name = "n")]
+    head: Option<u64>,
+
     /// Use this regex to select columns to display by default
     #[arg(long, value_name = "regex")]
     columns: Option<String>,
 
+    /// Show only these columns, given as comma separated names, in the order given. Names that
+    /// are not found are skipped.
+    #[arg(
+        long,
+        value_name = "column_names",
+        value_delimiter = ',',
+        conflicts_with = "columns"
+    )]
+    select_columns: Option<Vec<String>>,
+
     /// Use this regex to filter rows to display by default
     #[arg(long, value_name = "regex")]
     filter: Option<String>,
@@ -47,14 +108,168 @@ struct Args {
     #[arg(long, value_name = "regex")]
     find: Option<String>,
 
-    /// Searches ignore case. Ignored if any uppercase letters are present in the search string
+    /// Searches ignore case. Ignored if any uppercase letters are present in the search string.
+    /// Sorting also ignores case.
     #[clap(short, long)]
     ignore_case: bool,
 
-    /// Print the value of this column to stdout for the selected row
+    /// Searches ignore accents, e.g. "cafe" matches "café"
+    #[clap(long)]
+    ignore_accents: bool,
+
+    /// Jumping past the last match with n goes back to the first one, and vice versa with N
+    #[clap(long)]
+    wrap_search: bool,
+
+    /// Sort rows with empty values in the sorted column last, in both ascending and descending
+    /// order
+    #[clap(long)]
+    sort_nulls_last: bool,
+
+    /// Print the value of this column to stdout for the selected row. Separate multiple columns
+    /// with commas to print their values joined by tabs.
     #[arg(long, value_name = "column_name")]
     echo_column: Option<String>,
 
+    /// Minimum number of rows to keep above and below the selected row when scrolling
+    #[arg(long, value_name = "n", default_value_t = 0)]
+    scrolloff: u64,
+
+    /// Number of rows to show before and after each match when filtering, like grep -C
+    #[arg(long, value_name = "n", default_value_t = 0)]
+    context: u64,
+
+    /// Show the changes from the csv file to this one, with rows marked as added (+), removed (-)
+    /// or changed (~)
+    #[arg(long, value_name = "filename")]
+    diff: Option<String>,
+
+    /// Match rows of the two files by the values of this column when diffing. Defaults to the
+    /// first column.
+    #[arg(long, value_name = "column_name")]
+    diff_key: Option<String>,
+
+    /// Clipboard backend to use when copying
+    #[arg(long, value_name = "backend", value_enum, default_value_t = ClipboardBackend::Auto)]
+    clipboard: ClipboardBackend,
+
+    /// Also copy to the PRIMARY selection (Linux only) so that it can be pasted with middle-click
+    #[clap(long)]
+    primary_selection: bool,
+
+    /// What to print when a cell is selected with Enter: the bare value, or a JSON object that
+    /// also has the record number and column name (json), plus the selected row (json-row)
+    #[arg(long, value_name = "mode", value_enum, default_value_t = OutputMode::Value)]
+    output: OutputMode,
+
+    /// What Enter returns in row selection mode: the echoed columns (cell), or the whole row as
+    /// a delimited line, or a JSON object with --output json (row)
+    #[arg(long, value_name = "granularity", value_enum, default_value_t = OutputGranularity::Cell)]
+    output_granularity: OutputGranularity,
+
+    /// What is copied to the clipboard: the bare value (value), or the cell quoted as a csv field
+    /// where needed and the row as a delimited line (csv)
+    #[arg(long, value_name = "mode", value_enum, default_value_t = CopyMode::Value)]
+    copy_mode: CopyMode,
+
+    /// Show the values of a column in a color, e.g. "status=red" or "id=#808080". Can be given
+    /// multiple times.
+    #[arg(long, value_name = "column=color", value_parser = parse_column_color)]
+    column_color: Vec<(String, String)>,
+
+    /// Characters shown for trailing spaces, tabs, newlines and other control characters when
+    /// whitespace is made visible with -L, e.g. "·→↵¤"
+    #[arg(long, value_name = "chars")]
+    whitespace_symbols: Option<String>,
+
+    /// Show empty cells as this text, dimmed, e.g. "NULL". Can be toggled with -E, which shows
+    /// "∅" if this is not given.
+    #[arg(long, value_name = "text")]
+    empty_placeholder: Option<String>,
+
+    /// Format of the status line, with placeholders such as {filename}, {row}, {total}, {col},
+    /// {cols_total}, {filter} and {sort}. See the README for the full list.
+    #[arg(long, value_name = "format")]
+    status_format: Option<String>,
+
+    /// Scroll to this row number at startup
+    #[arg(long, value_name = "n")]
+    initial_row: Option<u64>,
+
+    /// Scroll to this column at startup
+    #[arg(long, value_name = "column_name")]
+    initial_column: Option<String>,
+
+    /// Column identifying each row. The selection stays on the row with the same key when the
+    /// rows are sorted or filtered.
+    #[arg(long, value_name = "column_name")]
+    key_column: Option<String>,
+
+    /// Hide the row numbers, so that the columns start at the left edge. Can be toggled with -R.
+    #[clap(long)]
+    no_row_numbers: bool,
+
+    /// Number the rows from 0 instead of 1. Can be toggled with -0.
+    #[clap(long)]
+    zero_based: bool,
+
+    /// Show spreadsheet style column letters (A, B, ..., AA) above the header. Can be toggled
+    /// with -G.
+    #[clap(long)]
+    column_letters: bool,
+
+    /// Render without colors, telling the selection and matches apart by bold, underlined or
+    /// reversed text. Also turned on by setting the NO_COLOR environment variable.
+    #[clap(long)]
+    monochrome: bool,
+
+    /// Show at most this many lines of each row when wrapping, cutting the rest short. Can be
+    /// adjusted with -] and -[.
+    #[arg(long, value_name = "n", value_parser = clap::value_parser!(u16).range(1..))]
+    max_row_height: Option<u16>,
+
+    /// Start in word wrap mode if a column in the first rows has values wider than the terminal
+    #[clap(long)]
+    auto_wrap: bool,
+
+    /// Which part of values too wide for their column to cut short: the end, or the middle to
+    /// keep both ends visible, e.g. for IDs and paths
+    #[arg(long, value_name = "mode", value_enum, default_value_t = TruncationMode::End)]
+    truncate: TruncationMode,
+
+    /// Shown in place of the part of a value cut short. Defaults to "…".
+    #[arg(long, value_name = "string")]
+    truncation_indicator: Option<String>,
+
+    /// Show scrollbars indicating the current position
+    #[clap(long)]
+    scrollbars: bool,
+
+    /// Render without borders and separators to leave more room for the content
+    #[clap(long)]
+    compact: bool,
+
+    /// Glyphs to draw borders with
+    #[arg(long, value_name = "style", value_enum, default_value_t = BorderStyle::Square)]
+    border_style: BorderStyle,
+
+    /// Number of spaces between columns. Defaults to 4.
+    #[arg(long, value_name = "n")]
+    column_spacing: Option<u16>,
+
+    /// Number of spaces around the separator after the row numbers. Defaults to 2.
+    #[arg(long, value_name = "n")]
+    line_number_spacing: Option<u16>,
+
+    /// Number of threads to use when finding or filtering large files. Defaults to the number of
+    /// available CPUs.
+    #[arg(long, value_name = "n")]
+    find_threads: Option<usize>,
+
+    /// Do not capture mouse events, leaving text selection to the terminal
+    #[clap(long)]
+    no_mouse: bool,
+
     /// Show stats for debugging
     #[clap(long)]
     debug: bool,
@@ -65,40 +280,223 @@ impl From<Args> for CsvlensOptions {
     fn from(args: Args) -> Self {
         Self {
             filename: args.filename,
+            stack_filenames: args.stack_filenames,
+            source: None,
             delimiter: args.delimiter,
             tab_separated: args.tab_separated,
+            terminator: args.terminator,
+            parquet: args.parquet,
             no_headers: args.no_headers,
+            units_row: args.units_row,
+            thousands_separator: args.thousands_separator,
+            max_rows: args.head,
             columns: args.columns,
+            select_columns: args.select_columns,
             filter: args.filter,
             find: args.find,
             ignore_case: args.ignore_case,
+            ignore_accents: args.ignore_accents,
+            wrap_search: args.wrap_search,
+            sort_nulls_last: args.sort_nulls_last,
             echo_column: args.echo_column,
+            scrolloff: args.scrolloff,
+            filter_context: args.context,
+            diff_filename: args.diff,
+            diff_key: args.diff_key,
+            clipboard_backend: args.clipboard,
+            copy_to_primary: args.primary_selection,
+            on_selection_change: None,
+            output_mode: args.output,
+            output_granularity: args.output_granularity,
+            copy_mode: args.copy_mode,
+            whitespace_symbols: args.whitespace_symbols,
+            empty_placeholder: args.empty_placeholder,
+            status_format: args.status_format,
+            initial_row: args.initial_row,
+            initial_column: args.initial_column,
+            key_column: args.key_column,
+            keymap: HashMap::new(),
+            column_colors: args.column_color.into_iter().collect(),
+            scrollbars: args.scrollbars,
+            hide_row_numbers: args.no_row_numbers,
+            zero_based_row_numbers: args.zero_based,
+            column_letters: args.column_letters,
+            monochrome: args.monochrome,
+            auto_wrap: args.auto_wrap,
+            max_row_height: args.max_row_height,
+            truncation_mode: args.truncate,
+            truncation_indicator: args.truncation_indicator,
+            compact: args.compact,
+            border_style: args.border_style,
+            column_spacing: args.column_spacing,
+            line_number_spacing: args.line_number_spacing,
+            find_threads: args.find_threads,
+            no_mouse: args.no_mouse,
+            pos_table_entries: None,
+            pos_table_min_interval: None,
             debug: args.debug,
         }
     }
 }
 
+/// Split a column color given as "column=color". Names can contain "=", colors cannot.
+#[cfg(feature = "cli")]
+fn parse_column_color(s: &str) -> Result<(String, String), String> {
+    match s.rsplit_once('=') {
+        Some((column, color)) => Ok((column.to_string(), color.to_string())),
+        None => Err("expected the form column=color, e.g. status=red".to_string()),
+    }
+}
+
+/// Csv content to view in place of a file
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// Csv content held in memory
+    Bytes(Vec<u8>),
+}
+
 // Struct for library usage without clap directives
 #[derive(Debug, Default)]
 pub struct CsvlensOptions {
     pub filename: Option<String>,
+    /// More files with the same columns, whose records are shown below those of `filename` (or
+    /// `source`) with a "source" column in front naming the file of each record. Sorting,
+    /// filtering and finding then work across all the files.
+    pub stack_filenames: Vec<String>,
+    /// Read the content from here instead of `filename` or stdin. `filename` is then only used as
+    /// the name shown in the status bar.
+    pub source: Option<Source>,
+    /// Delimiter character, "\t" or "auto". If neither this nor `tab_separated` is set and the
+    /// file extension implies no delimiter, the CSVLENS_DELIMITER environment variable is used if
+    /// set, and comma otherwise.
     pub delimiter: Option<String>,
     pub tab_separated: bool,
+    /// Record terminator: a single character, "\r", "\n", "\0", or "crlf" (the default) to end
+    /// records at any of \r\n, \n or \r.
+    pub terminator: Option<String>,
+    /// Read the file as parquet, which files ending in .parquet are anyway. Requires the parquet
+    /// feature.
+    pub parquet: bool,
     pub no_headers: bool,
+    /// The first record holds the units of the columns. It is shown pinned below the headers
+    /// and is not part of the records found, filtered or sorted.
+    pub units_row: bool,
+    /// Character grouping the digits of numbers, left out when sorting and summing them. With
+    /// '.', ',' is taken as the decimal separator.
+    pub thousands_separator: Option<char>,
+    /// Only read the first this many records. Indexing, finding, filtering and sorting then only
+    /// see these records.
+    pub max_rows: Option<u64>,
     pub columns: Option<String>,
+    /// Names of the columns to show, in the order to show them in. Takes precedence over the
+    /// `columns` regex.
+    pub select_columns: Option<Vec<String>>,
     pub filter: Option<String>,
     pub find: Option<String>,
     pub ignore_case: bool,
+    /// Strip diacritics from both the search and the fields, so that e.g. "cafe" matches "café"
+    pub ignore_accents: bool,
+    /// Wrap around to the first match after the last one and vice versa when jumping between
+    /// matches
+    pub wrap_search: bool,
+    /// Put rows whose value in the sorted column is empty after all others, whatever the sort
+    /// order. By default they come first when ascending and last when descending.
+    pub sort_nulls_last: bool,
+    /// Column to print for the selected row on Enter, or several comma separated columns whose
+    /// values are joined by tabs
     pub echo_column: Option<String>,
+    pub scrolloff: u64,
+    /// Number of rows shown before and after each match when filtering
+    pub filter_context: u64,
+    /// Show the changes from the csv content to this file instead, aligning rows by `diff_key`
+    pub diff_filename: Option<String>,
+    /// Column whose values identify the same row in both files when diffing. Defaults to the
+    /// first column.
+    pub diff_key: Option<String>,
+    pub clipboard_backend: ClipboardBackend,
+    pub copy_to_primary: bool,
+    pub on_selection_change: Option<SelectionCallback>,
+    /// Format of the string returned when a cell is selected. The bare cell value by default.
+    pub output_mode: OutputMode,
+    /// Whether Enter in row selection mode returns the whole row, e.g. to pick rows in a script.
+    /// The row is a line delimited like the file, or a JSON object in the JSON output modes.
+    pub output_granularity: OutputGranularity,
+    /// Whether copied cells and rows are quoted like csv so that they paste intact into another
+    /// csv. The bare cell value by default.
+    pub copy_mode: CopyMode,
+    /// Four characters shown for trailing spaces, tabs, newlines and other control characters when
+    /// whitespace is made visible. Defaults to "·→↵¤".
+    pub whitespace_symbols: Option<String>,
+    /// Show empty cells as this text instead of blank. Only what is shown changes, finding,
+    /// sorting, copying and exporting still see the empty values.
+    pub empty_placeholder: Option<String>,
+    /// Template of the status line. `{row}`, `{total}`, `{col}` and `{cols_total}` are replaced
+    /// with the position, `{filename}` with the file name, and each of `{indexing}`, `{filter}`,
+    /// `{stats}`, `{columns}`, `{sort}`, `{validation}`, `{echo}`, `{ignore_case}`,
+    /// `{ignore_accents}`, `{match_mode}`, `{cell}`, `{range}`, `{text}`, `{byte}`, `{macro}` and
+    /// `{debug}` with its segment of the default status line, which starts with a space if shown.
+    /// Unknown placeholders are kept as is. Defaults to the usual layout.
+    pub status_format: Option<String>,
+    pub initial_row: Option<u64>,
+    pub initial_column: Option<String>,
+    /// Column whose value identifies a row, to keep the same row selected when sorting or
+    /// filtering. The selection stays at the same position if not given.
+    pub key_column: Option<String>,
+    /// Key binding overrides applied over the defaults, mapping a key such as "ctrl-n" or "J" to
+    /// an action name such as "scroll_down". Map a key to "none" to unbind it.
+    pub keymap: HashMap<String, String>,
+    /// Colors to show the values of columns in, mapping a column name to a color such as "red",
+    /// "lightblue", "#ff8000" or an ANSI color index
+    pub column_colors: HashMap<String, String>,
+    pub scrollbars: bool,
+    /// Hide the row numbers to the left of the rows, leaving their width to the columns
+    pub hide_row_numbers: bool,
+    /// Show the row numbers starting from 0, like indices, rather than from 1
+    pub zero_based_row_numbers: bool,
+    /// Show spreadsheet style letters of the columns above the header
+    pub column_letters: bool,
+    /// Render without colors. Also turned on by a non-empty NO_COLOR environment variable.
+    pub monochrome: bool,
+    /// Turn on word wrap at startup if a column in the first rows is wider than the terminal
+    pub auto_wrap: bool,
+    /// Lines of a wrapped row shown at most, so that a few long values don't push the other rows
+    /// off screen. Not limited if not given.
+    pub max_row_height: Option<u16>,
+    /// Which part of values too wide for their column to cut short
+    pub truncation_mode: TruncationMode,
+    /// Shown in place of the part of a value cut short, "…" if not given
+    pub truncation_indicator: Option<String>,
+    /// Render without borders and separators, e.g. for narrow terminals
+    pub compact: bool,
+    /// Glyphs to draw the table borders and the frames of pages such as help with
+    pub border_style: BorderStyle,
+    /// Spaces between columns, 4 if not given. Fewer fit more columns on narrow terminals.
+    pub column_spacing: Option<u16>,
+    /// Spaces around the separator after the row numbers, 2 if not given
+    pub line_number_spacing: Option<u16>,
+    /// Number of threads to use when finding or filtering large files. Defaults to the number of
+    /// available CPUs.
+    pub find_threads: Option<usize>,
+    /// Do not capture mouse events, e.g. to keep the terminal's own text selection working
+    pub no_mouse: bool,
+    /// Target number of record positions indexed in the background (10000 by default). Seeking to
+    /// an arbitrary row parses from the nearest indexed position, so more entries make jumping
+    /// around large files faster at the cost of memory. The number of entries is shown with
+    /// `debug`.
+    pub pos_table_entries: Option<u64>,
+    /// Minimum number of bytes between two indexed positions (500 by default), which keeps small
+    /// files from being indexed at every record.
+    pub pos_table_min_interval: Option<u64>,
     pub debug: bool,
 }
 
-struct AppRunner {
-    app: App,
+struct AppRunner<'a> {
+    app: &'a mut App,
+    mouse_capture: bool,
 }
 
-impl AppRunner {
-    fn new(app: App) -> AppRunner {
+impl<'a> AppRunner<'a> {
+    fn new(app: &'a mut App, mouse_capture: bool) -> AppRunner<'a> {
         let original_panic_hook = panic::take_hook();
 
         panic::set_hook(Box::new(move |info| {
@@ -106,16 +504,22 @@ impl AppRunner {
             // be printed with proper line breaks
             disable_raw_mode().unwrap();
             execute!(std::io::stderr(), LeaveAlternateScreen).unwrap();
+            if mouse_capture {
+                execute!(std::io::stderr(), DisableMouseCapture).unwrap();
+            }
             original_panic_hook(info);
         }));
 
-        AppRunner { app }
+        AppRunner { app, mouse_capture }
     }
 
     fn run(&mut self) -> CsvlensResult<Option<String>> {
         enable_raw_mode()?;
         let mut output = std::io::stderr();
         execute!(output, EnterAlternateScreen)?;
+        if self.mouse_capture {
+            execute!(output, EnableMouseCapture)?;
+        }
 
         let backend = CrosstermBackend::new(LineWriter::new(output));
         let mut terminal = Terminal::new(backend)?;
@@ -124,7 +528,7 @@ impl AppRunner {
     }
 }
 
-impl Drop for AppRunner {
+impl Drop for AppRunner<'_> {
     fn drop(&mut self) {
         // If panicked, restoring of terminal states would have been done in the
         // panic hook. Avoid doing that twice since that would clear the printed
@@ -132,14 +536,367 @@ impl Drop for AppRunner {
         if !panicking() {
             disable_raw_mode().unwrap();
             execute!(std::io::stderr(), LeaveAlternateScreen).unwrap();
+            if self.mouse_capture {
+                execute!(std::io::stderr(), DisableMouseCapture).unwrap();
+            }
         }
     }
 }
 
+/// A csvlens instance created from `CsvlensOptions`, for embedding csvlens in other programs.
+///
+/// Unlike `run_csvlens_with_options`, the instance is kept around after `run` returns so that what
+/// was shown can be inspected programmatically.
+///
+/// Example:
+///
+/// ```no_run
+/// use csvlens::{Csvlens, CsvlensOptions};
+///
+/// let options = CsvlensOptions {
+///     filename: Some("/path/to/your.csv".to_string()),
+///     ..Default::default()
+/// };
+/// let mut csvlens = Csvlens::new(options).unwrap();
+/// csvlens.run().unwrap();
+/// for row in csvlens.visible_rows() {
+///     println!("{}: {:?}", row.record_num, row.fields);
+/// }
+/// ```
+pub struct Csvlens {
+    app: App,
+    // Need to outlive app since they may be backing temp files
+    _files: Vec<SeekableFile>,
+    mouse_capture: bool,
+}
+
+impl Csvlens {
+    /// Create a csvlens instance. The file is opened but nothing is drawn until `run` is called.
+    pub fn new(mut options: CsvlensOptions) -> CsvlensResult<Self> {
+        if let Some(separator) = options.thousands_separator {
+            if !normalize::is_valid_thousands_separator(separator) {
+                return Err(CsvlensError::ThousandsSeparatorInvalid(separator));
+            }
+        }
+        let show_stats = options.debug;
+        let (source, file, delimiter, terminator) = open_source(&mut options)?;
+        let mut files: Vec<SeekableFile> = file.into_iter().collect();
+        let (source, delimiter, terminator, stack_warning) = if options.stack_filenames.is_empty() {
+            (source, delimiter, terminator, None)
+        } else {
+            let (stack, stacked_files) = open_stack(&options, source, delimiter, terminator)?;
+            files.extend(stacked_files);
+            let warning = stack.warning().map(str::to_owned);
+            let mut names = vec![options.filename.as_deref().unwrap_or("stdin")];
+            names.extend(options.stack_filenames.iter().map(String::as_str));
+            options.filename = Some(names.join(" + "));
+            // The stacked csv always has a header row
+            options.no_headers = false;
+            (
+                CsvSource::Stack(Arc::new(stack)),
+                Delimiter::Character(b','),
+                None,
+                warning,
+            )
+        };
+        let keymap = KeyMap::with_overrides(&options.keymap)?;
+        let monochrome = options.monochrome
+            || std::env::var_os(NO_COLOR_ENV_VAR).is_some_and(|value| !value.is_empty());
+        let whitespace_symbols = WhitespaceSymbols::from_arg(&options.whitespace_symbols)?;
+        let default_pos_table_config = PosTableConfig::default();
+        let pos_table_config = PosTableConfig {
+            num_entries: options
+                .pos_table_entries
+                .unwrap_or(default_pos_table_config.num_entries),
+            minimum_interval: options
+                .pos_table_min_interval
+                .unwrap_or(default_pos_table_config.minimum_interval),
+        };
+
+        let (source, delimiter, no_headers, units_row, filename, diff_rows) =
+            match &options.diff_filename {
+                Some(diff_filename) => {
+                    let old = CsvConfig::from_source(
+                        source.clone(),
+                        delimiter.resolve(&source, options.filename.as_deref()),
+                        options.no_headers,
+                    )
+                    .with_terminator(terminator)
+                    .with_units_row(options.units_row)
+                    .with_max_rows(options.max_rows);
+                    // Read in full by the diff, so the file is not needed afterwards
+                    let new_file = SeekableFile::new(&Some(diff_filename.clone()))?;
+                    let new_source = CsvSource::Path(new_file.filename().to_owned());
+                    let new = CsvConfig::from_source(
+                        new_source.clone(),
+                        delimiter.resolve(&new_source, Some(diff_filename)),
+                        options.no_headers,
+                    )
+                    .with_terminator(terminator)
+                    .with_units_row(options.units_row)
+                    .with_max_rows(options.max_rows);
+                    let diff = Diff::new(&old, &new, options.diff_key.as_deref())?;
+                    files.push(diff.file);
+                    let filename = format!(
+                        "{} vs {}",
+                        options.filename.as_deref().unwrap_or("stdin"),
+                        diff_filename
+                    );
+                    (
+                        diff.source,
+                        Delimiter::Character(b','),
+                        false,
+                        options.units_row,
+                        Some(filename),
+                        Some(diff.rows),
+                    )
+                }
+                None => (
+                    source,
+                    delimiter,
+                    options.no_headers,
+                    options.units_row,
+                    options.filename,
+                    None,
+                ),
+            };
+
+        // A diff is shown as the csv written by it, with the default terminator
+        let terminator = if diff_rows.is_some() {
+            None
+        } else {
+            terminator
+        };
+
+        let app = App::new(
+            source,
+            AppOptions {
+                delimiter,
+                original_filename: filename,
+                show_stats,
+                echo_column: options.echo_column,
+                ignore_case: options.ignore_case,
+                no_headers,
+                columns_regex: options.columns,
+                filter_regex: options.filter,
+                find_regex: options.find,
+                scrolloff: options.scrolloff,
+                clipboard_backend: options.clipboard_backend,
+                copy_to_primary: options.copy_to_primary,
+                on_selection_change: options.on_selection_change,
+                initial_row: options.initial_row,
+                initial_column: options.initial_column,
+                keymap,
+                show_scrollbars: options.scrollbars,
+                find_threads: options.find_threads,
+                pos_table_config,
+                output_mode: options.output_mode,
+                whitespace_symbols,
+                status_format: options.status_format,
+                compact: options.compact,
+                ignore_accents: options.ignore_accents,
+                wrap_search: options.wrap_search,
+                filter_context: options.filter_context,
+                diff_rows,
+                empty_placeholder: options.empty_placeholder,
+                terminator,
+                max_rows: options.max_rows,
+                sort_nulls_last: options.sort_nulls_last,
+                column_spacing: options
+                    .column_spacing
+                    .unwrap_or(DEFAULT_SPACES_BETWEEN_COLUMNS),
+                line_number_spacing: options
+                    .line_number_spacing
+                    .unwrap_or(DEFAULT_SPACES_AFTER_LINE_NUMBER),
+                border_style: options.border_style,
+                output_granularity: options.output_granularity,
+                column_colors: options.column_colors,
+                units_row,
+                copy_mode: options.copy_mode,
+                hide_row_numbers: options.hide_row_numbers,
+                zero_based_row_numbers: options.zero_based_row_numbers,
+                monochrome,
+                auto_wrap: options.auto_wrap,
+                column_names: options.select_columns,
+                truncation: Truncation::new(options.truncation_mode, options.truncation_indicator),
+                key_column: options.key_column,
+                max_row_height: options.max_row_height,
+                column_letters: options.column_letters,
+                thousands_separator: options.thousands_separator,
+                startup_message: stack_warning,
+            },
+        )?;
+
+        Ok(Csvlens {
+            app,
+            _files: files,
+            mouse_capture: !options.no_mouse,
+        })
+    }
+
+    /// Take over the terminal and run csvlens interactively until the user exits.
+    ///
+    /// On success, the result contains an optional string that is the value of the selected cell
+    /// or row if any, formatted according to `CsvlensOptions::output_mode` and
+    /// `CsvlensOptions::output_granularity`.
+    pub fn run(&mut self) -> CsvlensResult<Option<String>> {
+        let mut app_runner = AppRunner::new(&mut self.app, self.mouse_capture);
+        app_runner.run()
+    }
+
+    /// Render the current view to lines of plain text of the given size, without taking over the
+    /// terminal. Waits for any startup filter to finish first.
+    pub fn render_to_lines(&mut self, width: u16, height: u16) -> CsvlensResult<Vec<String>> {
+        self.app.render_to_lines(width, height)
+    }
+
+    /// Headers of the columns currently shown, i.e. after any column filter is applied.
+    pub fn headers(&self) -> &[Header] {
+        self.app.headers()
+    }
+
+    /// Rows that were visible in the last drawn frame, after any filter and sort order is
+    /// applied. Each row's fields correspond to `headers`.
+    pub fn visible_rows(&self) -> &[Row] {
+        self.app.visible_rows()
+    }
+}
+
+/// Open the file or content to view as a csv source, along with the file backing it if any (which
+/// needs to outlive the source), and the delimiter and terminator to read it with
+fn open_source(
+    options: &mut CsvlensOptions,
+) -> CsvlensResult<(CsvSource, Option<SeekableFile>, Delimiter, Option<u8>)> {
+    let (delimiter, terminator) = delimiter_and_terminator(options)?;
+
+    let is_parquet = options.source.is_none()
+        && (options.parquet
+            || options
+                .filename
+                .as_deref()
+                .is_some_and(parquet_file::is_parquet));
+    let (source, file) = match options.source.take() {
+        Some(Source::Bytes(bytes)) => (CsvSource::Bytes(bytes.into()), None),
+        None => open_file(&options.filename, is_parquet)?,
+    };
+    if is_parquet {
+        return Ok((source, file, Delimiter::Character(b','), None));
+    }
+    Ok((source, file, delimiter, terminator))
+}
+
+/// Delimiter and terminator to read csv files with, as given in the options
+fn delimiter_and_terminator(options: &CsvlensOptions) -> CsvlensResult<(Delimiter, Option<u8>)> {
+    let delimiter = Delimiter::from_arg(&options.delimiter, options.tab_separated)?
+        .with_env_fallback(std::env::var(DELIMITER_ENV_VAR).ok())?;
+    let terminator = terminator_from_arg(&options.terminator)?;
+    Ok((delimiter, terminator))
+}
+
+/// Open the file, downloading it if it is a URL, or stdin if no filename is given. Returns the
+/// source to read it as csv from, along with the file backing it if it needs to outlive the source.
+fn open_file(
+    filename: &Option<String>,
+    is_parquet: bool,
+) -> CsvlensResult<(CsvSource, Option<SeekableFile>)> {
+    let file = match filename {
+        Some(filename) if download::is_url(filename) => download::download(filename)?,
+        _ => SeekableFile::new(filename)?,
+    };
+    // Converted to csv in the background, then viewed like any other csv file
+    if is_parquet {
+        Ok((parquet_file::to_csv(file)?, None))
+    } else {
+        Ok((CsvSource::Path(file.filename().to_owned()), Some(file)))
+    }
+}
+
+/// Stack the records of `stack_filenames` below those of the already opened source, read with
+/// the given delimiter and terminator. Returns the stack along with the opened files, which need
+/// to outlive it since some may be backed by temp files.
+fn open_stack(
+    options: &CsvlensOptions,
+    source: CsvSource,
+    delimiter: Delimiter,
+    terminator: Option<u8>,
+) -> CsvlensResult<(Stack, Vec<SeekableFile>)> {
+    let config = |source: CsvSource, delimiter: u8, terminator: Option<u8>| {
+        CsvConfig::from_source(source, delimiter, options.no_headers)
+            .with_terminator(terminator)
+            .with_units_row(options.units_row)
+    };
+
+    let first_name = options.filename.as_deref().unwrap_or("stdin");
+    let first_delimiter = delimiter.resolve(&source, options.filename.as_deref());
+    let mut files = vec![(
+        first_name.to_string(),
+        config(source, first_delimiter, terminator),
+    )];
+    // The first file may be parquet, so the others are read with the options as given
+    let (delimiter, terminator) = delimiter_and_terminator(options)?;
+    let mut opened = vec![];
+    for filename in &options.stack_filenames {
+        let is_parquet = options.parquet || parquet_file::is_parquet(filename);
+        let (source, file) = open_file(&Some(filename.clone()), is_parquet)?;
+        let stacked = if is_parquet {
+            config(source, b',', None)
+        } else {
+            let delimiter = delimiter.resolve(&source, Some(filename));
+            config(source, delimiter, terminator)
+        };
+        files.push((filename.clone(), stacked));
+        opened.extend(file);
+    }
+
+    Ok((Stack::new(files)?, opened))
+}
+
+/// How csvlens reads a file with the given options, as resolved by `inspect_csv`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvInfo {
+    /// Delimiter the file is read with, e.g. as sniffed when the delimiter is "auto"
+    pub delimiter: u8,
+    /// Names of the columns, or numbers from 1 if the file has no headers
+    pub headers: Vec<String>,
+    /// Row below the headers if `units_row` is set
+    pub units: Option<Vec<String>>,
+}
+
+/// Resolve the delimiter and headers csvlens would use for the given options, without taking over
+/// the terminal. Only the file itself is looked at, i.e. options about the view such as filters
+/// and `diff_filename` are ignored.
+///
+/// Example:
+///
+/// ```no_run
+/// use csvlens::{inspect_csv, CsvlensOptions};
+///
+/// let options = CsvlensOptions {
+///     filename: Some("/path/to/your.csv".to_string()),
+///     delimiter: Some("auto".to_string()),
+///     ..Default::default()
+/// };
+/// let info = inspect_csv(options).unwrap();
+/// println!("{} columns separated by {:?}", info.headers.len(), info.delimiter as char);
+/// ```
+pub fn inspect_csv(mut options: CsvlensOptions) -> CsvlensResult<CsvInfo> {
+    let (source, _file, delimiter, terminator) = open_source(&mut options)?;
+    let delimiter = delimiter.resolve(&source, options.filename.as_deref());
+    let config = CsvConfig::from_source(source, delimiter, options.no_headers)
+        .with_terminator(terminator)
+        .with_units_row(options.units_row);
+    Ok(CsvInfo {
+        delimiter,
+        headers: config.read_headers()?,
+        units: config.read_units()?,
+    })
+}
+
 /// Run csvlens with options provided in a `CsvlensOptions` struct.
 ///
-/// On success, the result contains an optional string that is the value of the selected cell if
-/// any. If csvlens exits without selecting a cell, the result is None.
+/// On success, the result contains an optional string that is the value of the selected cell
+/// or row if any, formatted according to `output_mode` and `output_granularity`. If csvlens exits
+/// without selecting anything, the result is None.
 ///
 /// Example:
 ///
@@ -157,27 +914,32 @@ impl Drop for AppRunner {
 /// }
 /// ```
 pub fn run_csvlens_with_options(options: CsvlensOptions) -> CsvlensResult<Option<String>> {
-    let show_stats = options.debug;
-    let delimiter = Delimiter::from_arg(&options.delimiter, options.tab_separated)?;
-
-    let file = SeekableFile::new(&options.filename)?;
-    let filename = file.filename();
-
-    let app = App::new(
-        filename,
-        delimiter,
-        options.filename,
-        show_stats,
-        options.echo_column,
-        options.ignore_case,
-        options.no_headers,
-        options.columns,
-        options.filter,
-        options.find,
-    )?;
+    Csvlens::new(options)?.run()
+}
 
-    let mut app_runner = AppRunner::new(app);
-    app_runner.run()
+/// Render what csvlens would show at startup with the given options to lines of plain text,
+/// without a terminal. Useful for generating fixed-width previews.
+///
+/// Example:
+///
+/// ```no_run
+/// use csvlens::{render_csvlens_snapshot, CsvlensOptions};
+///
+/// let options = CsvlensOptions {
+///     filename: Some("/path/to/your.csv".to_string()),
+///     filter: Some("foo".to_string()),
+///     ..Default::default()
+/// };
+/// for line in render_csvlens_snapshot(options, 80, 24).unwrap() {
+///     println!("{}", line);
+/// }
+/// ```
+pub fn render_csvlens_snapshot(
+    options: CsvlensOptions,
+    width: u16,
+    height: u16,
+) -> CsvlensResult<Vec<String>> {
+    Csvlens::new(options)?.render_to_lines(width, height)
 }
 
 /// Run csvlens with a list of arguments. The accepted arguments are the same as the command line
@@ -220,3 +982,85 @@ where
     eprintln!("Error: CLI is not enabled. Compile with the 'cli' feature to use this binary.");
     std::process::exit(1);
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_inspect_csv() {
+        let options = CsvlensOptions {
+            filename: Some("tests/data/small.bsv".to_string()),
+            delimiter: Some("auto".to_string()),
+            ..Default::default()
+        };
+        let info = inspect_csv(options).unwrap();
+        assert_eq!(info.delimiter, b'|');
+        assert_eq!(info.headers, vec!["COL1", "COL2"]);
+        assert_eq!(info.units, None);
+
+        let options = CsvlensOptions {
+            filename: Some("data.csv".to_string()),
+            source: Some(Source::Bytes(b"a;b\nx;y\n".to_vec())),
+            delimiter: Some(";".to_string()),
+            no_headers: true,
+            ..Default::default()
+        };
+        let info = inspect_csv(options).unwrap();
+        assert_eq!(info.delimiter, b';');
+        assert_eq!(info.headers, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_stack_filenames() {
+        let options = CsvlensOptions {
+            filename: Some("tests/data/small.csv".to_string()),
+            stack_filenames: vec!["tests/data/small.bsv".to_string()],
+            delimiter: Some("auto".to_string()),
+            ..Default::default()
+        };
+        let lines = render_csvlens_snapshot(options, 80, 8).unwrap();
+        // Each file is read with its own delimiter. Fields are matched by column name, and the
+        // header of small.csv has a space before COL2 unlike small.bsv, so its COL2 is empty
+        let expected = vec![
+            "────────────────────────────────────────────────────────────────────────────────",
+            "      source                  COL1     COL2                                     ",
+            "───┬───────────────────────────────────────────┬────────────────────────────────",
+            "1  │  tests/data/small.csv    c1       v1      │                                ",
+            "2  │  tests/data/small.csv    c2       v2      │                                ",
+            "3  │  tests/data/small.bsv    c1               │                                ",
+            "───┴───────────────────────────────────────────┴────────────────────────────────",
+            "Columns differ from those of tests/data/small.csv: tests/data/small.bsv         ",
+        ];
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_invalid_thousands_separator() {
+        let options = CsvlensOptions {
+            filename: Some("tests/data/simple.csv".to_string()),
+            thousands_separator: Some('-'),
+            ..Default::default()
+        };
+        assert!(matches!(
+            Csvlens::new(options),
+            Err(CsvlensError::ThousandsSeparatorInvalid('-'))
+        ));
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_column_color_arg() {
+        let args = Args::try_parse_from(["csvlens", "--column-color", "a=b=red", "x.csv"]).unwrap();
+        assert_eq!(
+            args.column_color,
+            vec![("a=b".to_string(), "red".to_string())]
+        );
+
+        let err = Args::try_parse_from(["csvlens", "--column-color", "status", "x.csv"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected the form column=color"), "{err}");
+    }
+}
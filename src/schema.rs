@@ -0,0 +1,127 @@
+use crate::csv;
+use crate::errors::CsvlensResult;
+use crate::normalize::normalize_number;
+
+use arrow::datatypes::{DataType, Schema};
+
+/// Number of records looked at to infer the column types
+const MAX_RECORDS_TO_INFER: usize = 1000;
+
+/// Infer the type of each column with Arrow from the first records of the file
+pub fn infer_schema(config: &csv::CsvConfig) -> CsvlensResult<Schema> {
+    let mut format = arrow::csv::reader::Format::default()
+        .with_delimiter(config.delimiter())
+        .with_header(config.has_headers());
+    if let Some(terminator) = config.terminator() {
+        format = format.with_terminator(terminator);
+    }
+    let (schema, _) =
+        format.infer_schema(config.open_without_units()?, Some(MAX_RECORDS_TO_INFER))?;
+    Ok(schema)
+}
+
+/// Short name of the inferred type of each column, in the order of the columns in the file
+pub fn column_types(config: &csv::CsvConfig) -> CsvlensResult<Vec<String>> {
+    let schema = infer_schema(config)?;
+    let mut types: Vec<&str> = schema
+        .fields()
+        .iter()
+        .map(|field| type_name(field.data_type()))
+        .collect();
+    // Arrow does not know about thousands separators, taking grouped numbers as strings or e.g.
+    // "1.234" as a float
+    if let Some(separator) = config.thousands_separator() {
+        let number_types = grouped_number_types(config, separator, types.len())?;
+        for (t, number_type) in types.iter_mut().zip(number_types) {
+            if matches!(*t, "int" | "float" | "string") {
+                if let Some(number_type) = number_type {
+                    *t = number_type;
+                }
+            }
+        }
+    }
+    Ok(types.into_iter().map(str::to_string).collect())
+}
+
+/// Type of each column whose values in the first records are all numbers grouped with the
+/// thousands separator, or None if some are not or all of them are empty
+fn grouped_number_types(
+    config: &csv::CsvConfig,
+    separator: char,
+    num_columns: usize,
+) -> CsvlensResult<Vec<Option<&'static str>>> {
+    let mut types = vec![Some("int"); num_columns];
+    let mut any_values = vec![false; num_columns];
+    for r in config.new_reader()?.records().take(MAX_RECORDS_TO_INFER) {
+        let record = r?;
+        for (i, t) in types.iter_mut().enumerate() {
+            let value = record.get(i).unwrap_or_default().trim();
+            if value.is_empty() {
+                continue;
+            }
+            any_values[i] = true;
+            let value = normalize_number(value, Some(separator));
+            if value.parse::<i64>().is_err() {
+                *t = t.filter(|_| value.parse::<f64>().is_ok()).map(|_| "float");
+            }
+        }
+    }
+    Ok(types
+        .into_iter()
+        .zip(any_values)
+        .map(|(t, any_values)| t.filter(|_| any_values))
+        .collect())
+}
+
+fn type_name(data_type: &DataType) -> &'static str {
+    match data_type {
+        // Every value of the column is empty
+        DataType::Null => "empty",
+        DataType::Boolean => "bool",
+        t if t.is_integer() => "int",
+        t if t.is_floating() => "float",
+        DataType::Date32 | DataType::Date64 => "date",
+        DataType::Time32(_) | DataType::Time64(_) => "time",
+        DataType::Timestamp(_, _) => "datetime",
+        _ => "string",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_column_types() {
+        let config = csv::CsvConfig::new("tests/data/cities.csv", b',', false);
+        let types = column_types(&config).unwrap();
+        assert_eq!(
+            types,
+            vec!["int", "int", "int", "string", "int", "int", "int", "string", "string", "string"]
+        );
+    }
+
+    #[test]
+    fn test_column_types_thousands_separator() {
+        let source = csv::CsvSource::Bytes(
+            b"a,b,c,d\n\"1,234\",\"1,234.5\",x,1\n\"56\",7,y,\"2,000\"\n"
+                .to_vec()
+                .into(),
+        );
+        let config = csv::CsvConfig::from_source(source, b',', false);
+        let types = column_types(&config).unwrap();
+        assert_eq!(types, vec!["string", "string", "string", "string"]);
+
+        let config = config.with_thousands_separator(Some(','));
+        let types = column_types(&config).unwrap();
+        assert_eq!(types, vec!["int", "float", "string", "int"]);
+    }
+
+    #[test]
+    fn test_column_types_with_units_row() {
+        let config = csv::CsvConfig::new("tests/data/units.csv", b',', false).with_units_row(true);
+        let types = column_types(&config).unwrap();
+        assert_eq!(types, vec!["string", "int", "float"]);
+    }
+}
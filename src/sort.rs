@@ -1,12 +1,13 @@
 use crate::csv;
 use crate::errors::CsvlensResult;
+use crate::normalize::{parse_number, strip_diacritics};
+use crate::schema;
 
-use std::fs::File;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread::{self};
 
-use arrow::array::{Array, ArrayIter};
+use arrow::array::{Array, AsArray};
 use arrow::compute::concat;
 use arrow::compute::kernels;
 use arrow::datatypes::Fields;
@@ -28,17 +29,47 @@ pub enum SortOrder {
 
 #[derive(Debug)]
 pub struct Sorter {
-    pub column_index: usize,
-    column_name: String,
+    /// Column sorted by, or None to keep the rows in record order
+    pub column_index: Option<usize>,
+    column_name: Option<String>,
+    nulls_last: bool,
+    ignore_case: bool,
     internal: Arc<Mutex<SorterInternalState>>,
 }
 
 impl Sorter {
-    pub fn new(csv_config: Arc<csv::CsvConfig>, column_index: usize, column_name: String) -> Self {
-        let internal = SorterInternalState::init(csv_config, column_index);
+    pub fn new(
+        csv_config: Arc<csv::CsvConfig>,
+        column_index: usize,
+        column_name: String,
+        nulls_last: bool,
+        ignore_case: bool,
+    ) -> Self {
+        let internal = SorterInternalState::init(csv_config, Some(column_index), ignore_case);
+        Sorter {
+            column_index: Some(column_index),
+            column_name: Some(column_name),
+            nulls_last,
+            ignore_case,
+            internal,
+        }
+    }
+
+    /// Sorter keeping the rows in record order, which only needs to count the records. Shown in
+    /// descending order, it reverses the rows. Finished right away if the number of records is
+    /// already known.
+    pub fn record_order(csv_config: Arc<csv::CsvConfig>, num_records: Option<usize>) -> Self {
+        let internal = match num_records {
+            Some(num_records) => {
+                SorterInternalState::finished(SortResult::in_record_order(num_records))
+            }
+            None => SorterInternalState::init(csv_config, None, false),
+        };
         Sorter {
-            column_index,
-            column_name,
+            column_index: None,
+            column_name: None,
+            nulls_last: false,
+            ignore_case: false,
             internal,
         }
     }
@@ -51,21 +82,12 @@ impl Sorter {
     ) -> Option<Vec<u64>> {
         let m_guard = self.internal.lock().unwrap();
         if let Some(sort_result) = &m_guard.sort_result {
-            let mut out = vec![];
-            let index_range: Box<dyn Iterator<Item = u64>> = if order == SortOrder::Ascending {
-                let start = rows_from;
-                let end = start.saturating_add(num_rows);
-                Box::new(start..end)
-            } else {
-                let end = sort_result.num_rows() as u64 - rows_from;
-                let start = end.saturating_sub(num_rows);
-                Box::new((start..end).rev())
-            };
-            for i in index_range {
-                if let Some(record_index) = sort_result.record_indices.get(i as usize) {
-                    out.push(*record_index as u64)
-                }
-            }
+            let end = rows_from
+                .saturating_add(num_rows)
+                .min(sort_result.num_rows() as u64);
+            let out = (rows_from..end)
+                .map(|i| sort_result.record_index(i as usize, order, self.nulls_last) as u64)
+                .collect();
             return Some(out);
         }
         None
@@ -74,14 +96,9 @@ impl Sorter {
     pub fn get_record_order(&self, row_index: u64, order: SortOrder) -> Option<u64> {
         let m_guard = self.internal.lock().unwrap();
         if let Some(sort_result) = &m_guard.sort_result {
-            if let Some(mut record_order) =
-                sort_result.record_orders.get(row_index as usize).cloned()
-            {
-                if order == SortOrder::Descending {
-                    record_order = sort_result.num_rows() - record_order - 1;
-                }
-                return Some(record_order as u64);
-            }
+            return sort_result
+                .record_order(row_index as usize, order, self.nulls_last)
+                .map(|record_order| record_order as u64);
         }
         None
     }
@@ -90,8 +107,16 @@ impl Sorter {
         (self.internal.lock().unwrap()).status.clone()
     }
 
-    pub fn column_name(&self) -> &str {
-        self.column_name.as_str()
+    pub fn column_name(&self) -> Option<&str> {
+        self.column_name.as_deref()
+    }
+
+    pub fn nulls_last(&self) -> bool {
+        self.nulls_last
+    }
+
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
     }
 
     pub fn terminate(&self) {
@@ -99,7 +124,7 @@ impl Sorter {
         m.terminate();
     }
 
-    #[cfg(test)]
+    /// Block until the background thread is done
     pub fn wait_internal(&self) {
         loop {
             if self.internal.lock().unwrap().done {
@@ -116,15 +141,92 @@ impl Drop for Sorter {
     }
 }
 
+/// Sorted record indices in ascending order, where empty values (nulls) are placed at the front,
+/// and in descending order, where they are placed at the back. Rows with equal values are in
+/// record order in both.
 #[derive(Debug)]
 struct SortResult {
+    ascending: SortedIndices,
+    descending: SortedIndices,
+    num_nulls: usize,
+}
+
+#[derive(Debug, Default)]
+struct SortedIndices {
     record_indices: Vec<usize>,
     record_orders: Vec<usize>,
 }
 
+impl SortedIndices {
+    fn new(record_indices: Vec<usize>) -> Self {
+        let mut record_orders: Vec<usize> = vec![0; record_indices.len()];
+        for (record_order, record_index) in record_indices.iter().enumerate() {
+            record_orders[*record_index] = record_order;
+        }
+        SortedIndices {
+            record_indices,
+            record_orders,
+        }
+    }
+}
+
 impl SortResult {
+    fn new(ascending: Vec<usize>, descending: Vec<usize>, num_nulls: usize) -> Self {
+        SortResult {
+            ascending: SortedIndices::new(ascending),
+            descending: SortedIndices::new(descending),
+            num_nulls,
+        }
+    }
+
+    fn in_record_order(num_rows: usize) -> Self {
+        SortResult::new((0..num_rows).collect(), (0..num_rows).rev().collect(), 0)
+    }
+
+    fn empty() -> Self {
+        SortResult {
+            ascending: SortedIndices::default(),
+            descending: SortedIndices::default(),
+            num_nulls: 0,
+        }
+    }
+
     fn num_rows(&self) -> usize {
-        self.record_indices.len()
+        self.ascending.record_indices.len()
+    }
+
+    /// Record index of the row_order-th row shown in the given order
+    fn record_index(&self, row_order: usize, order: SortOrder, nulls_last: bool) -> usize {
+        let num_values = self.num_rows() - self.num_nulls;
+        let position = match (order, nulls_last) {
+            (SortOrder::Descending, _) => {
+                return self.descending.record_indices[row_order];
+            }
+            (SortOrder::Ascending, false) => row_order,
+            (SortOrder::Ascending, true) if row_order >= num_values => row_order - num_values,
+            (SortOrder::Ascending, true) => self.num_nulls + row_order,
+        };
+        self.ascending.record_indices[position]
+    }
+
+    /// Inverse of record_index()
+    fn record_order(
+        &self,
+        record_index: usize,
+        order: SortOrder,
+        nulls_last: bool,
+    ) -> Option<usize> {
+        if order == SortOrder::Descending {
+            return self.descending.record_orders.get(record_index).cloned();
+        }
+        let position = *self.ascending.record_orders.get(record_index)?;
+        let num_values = self.num_rows() - self.num_nulls;
+        let record_order = match nulls_last {
+            false => position,
+            true if position < self.num_nulls => num_values + position,
+            true => position - self.num_nulls,
+        };
+        Some(record_order)
     }
 }
 
@@ -139,7 +241,8 @@ struct SorterInternalState {
 impl SorterInternalState {
     pub fn init(
         config: Arc<csv::CsvConfig>,
-        column_index: usize,
+        column_index: Option<usize>,
+        ignore_case: bool,
     ) -> Arc<Mutex<SorterInternalState>> {
         let internal = SorterInternalState {
             sort_result: None,
@@ -156,29 +259,39 @@ impl SorterInternalState {
             fn run(
                 m: Arc<Mutex<SorterInternalState>>,
                 config: Arc<csv::CsvConfig>,
-                column_index: usize,
+                column_index: Option<usize>,
+                ignore_case: bool,
             ) -> CsvlensResult<SortResult> {
+                let Some(column_index) = column_index else {
+                    let num_rows = config.new_reader()?.byte_records().count();
+                    return Ok(SortResult::in_record_order(num_rows));
+                };
+
                 // Get schema
-                let schema =
-                    SorterInternalState::infer_schema(config.filename(), config.delimiter())?;
-                let file = File::open(config.filename())?;
-                let arrow_csv_reader = arrow::csv::ReaderBuilder::new(Arc::new(schema))
+                let schema = SorterInternalState::infer_schema(&config)?;
+                let mut builder = arrow::csv::ReaderBuilder::new(Arc::new(schema))
                     .with_delimiter(config.delimiter())
                     .with_header(!config.no_headers())
-                    .with_projection(vec![column_index])
-                    .build(file)?;
+                    .with_projection(vec![column_index]);
+                if let Some(terminator) = config.terminator() {
+                    builder = builder.with_terminator(terminator);
+                }
+                let arrow_csv_reader = builder.build(config.open_without_units()?)?;
 
                 // Parse csv in batches to construct the column
                 let mut arrs: Vec<Arc<dyn Array>> = Vec::new();
+                let mut num_rows_left = config.max_records();
                 for record_batch_result in arrow_csv_reader {
+                    if num_rows_left == 0 {
+                        break;
+                    }
                     let record_batch = record_batch_result?;
                     let arr = record_batch.column(0);
-                    arrs.push(arr.clone());
+                    let len = arr.len().min(num_rows_left);
+                    arrs.push(arr.slice(0, len));
+                    num_rows_left -= len;
                     if m.lock().unwrap().should_terminate {
-                        return Ok(SortResult {
-                            record_indices: vec![],
-                            record_orders: vec![],
-                        });
+                        return Ok(SortResult::empty());
                     }
                 }
                 let ref_arrs = arrs
@@ -188,26 +301,20 @@ impl SorterInternalState {
                 let combined_arr = concat(&ref_arrs)?;
 
                 // Sort
-                let sorted_indices =
-                    kernels::sort::sort_to_indices(combined_arr.as_ref(), None, None)?;
-
-                // Construct the result. Maybe this can be kept as arrow Arrays?
-                let mut sorted_record_indices: Vec<usize> = vec![];
-                let mut record_orders: Vec<usize> = vec![0; sorted_indices.len()];
-                for (record_order, sorted_record_index) in
-                    ArrayIter::new(&sorted_indices).flatten().enumerate()
-                {
-                    sorted_record_indices.push(sorted_record_index as usize);
-                    record_orders[sorted_record_index as usize] = record_order;
-                }
-                let sort_result = SortResult {
-                    record_indices: sorted_record_indices,
-                    record_orders,
-                };
-                Ok(sort_result)
+                let (ascending, descending) = stable_sort_to_indices(
+                    combined_arr.as_ref(),
+                    ignore_case,
+                    config.thousands_separator(),
+                )?;
+
+                Ok(SortResult::new(
+                    ascending,
+                    descending,
+                    combined_arr.null_count(),
+                ))
             }
 
-            let sort_result = run(_m.clone(), config, column_index);
+            let sort_result = run(_m.clone(), config, column_index, ignore_case);
 
             let mut m = _m.lock().unwrap();
             if let Ok(sort_result) = sort_result {
@@ -222,22 +329,31 @@ impl SorterInternalState {
         m_state
     }
 
-    fn infer_schema(filename: &str, delimiter: u8) -> CsvlensResult<Schema> {
-        let schema = arrow::csv::infer_schema_from_files(
-            &[filename.to_string()],
-            delimiter,
-            Some(1000),
-            true,
-        )?;
+    fn finished(sort_result: SortResult) -> Arc<Mutex<SorterInternalState>> {
+        Arc::new(Mutex::new(SorterInternalState {
+            sort_result: Some(sort_result),
+            status: SorterStatus::Finished,
+            should_terminate: false,
+            done: true,
+        }))
+    }
 
-        // Convert integer fields to float64 to be more permissive
+    fn infer_schema(config: &csv::CsvConfig) -> CsvlensResult<Schema> {
+        let schema = schema::infer_schema(config)?;
+
+        // Convert integer fields to float64 to be more permissive. With a thousands separator,
+        // numbers are read as text instead and parsed while sorting, since e.g. "1.234" would
+        // otherwise be taken as a decimal.
+        let number_type = if config.thousands_separator().is_some() {
+            arrow::datatypes::DataType::Utf8
+        } else {
+            arrow::datatypes::DataType::Float64
+        };
         let mut updated_fields = vec![];
         for field in schema.fields() {
-            if field.data_type().is_integer() {
-                let new_field = field
-                    .as_ref()
-                    .clone()
-                    .with_data_type(arrow::datatypes::DataType::Float64);
+            let data_type = field.data_type();
+            if data_type.is_integer() || data_type.is_floating() {
+                let new_field = field.as_ref().clone().with_data_type(number_type.clone());
                 updated_fields.push(new_field);
             } else {
                 updated_fields.push(field.as_ref().clone());
@@ -253,6 +369,107 @@ impl SorterInternalState {
     }
 }
 
+/// Indices that sort the array in ascending order with nulls first, and in descending order with
+/// nulls last. Rows with equal values keep their original order in both.
+///
+/// Strings are collated rather than compared byte by byte: they are first compared with case and
+/// accents folded, so that "école" sorts next to "ecole" instead of after "zebra", and only then
+/// by code point to break the tie, unless ignore_case is set, in which case values differing only
+/// in case are equal and keep their row order. This computes a folded copy of every value while
+/// sorting, so it takes about twice the memory of the column and is slower than sorting raw bytes.
+///
+/// With a thousands separator, strings are compared as numbers if they all parse as such, e.g.
+/// "1,000" sorts after "9".
+fn stable_sort_to_indices(
+    arr: &dyn Array,
+    ignore_case: bool,
+    thousands_separator: Option<char>,
+) -> CsvlensResult<(Vec<usize>, Vec<usize>)> {
+    if let Some(strings) = arr.as_string_opt::<i32>() {
+        let mut nulls = vec![];
+        let mut values = vec![];
+        for (i, value) in strings.iter().enumerate() {
+            match value {
+                Some(s) => values.push((i, s)),
+                None => nulls.push(i),
+            }
+        }
+        if thousands_separator.is_some() {
+            let numbers: Option<Vec<(usize, f64)>> = values
+                .iter()
+                .map(|(i, s)| Some((*i, parse_number(s.trim(), thousands_separator)?)))
+                .collect();
+            if let Some(mut numbers) = numbers {
+                // sort_by is stable
+                numbers.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+                return Ok(with_nulls(nulls, &numbers, |a, b| a.total_cmp(b).is_eq()));
+            }
+        }
+        // Fold each value only once; sort_by is stable
+        let mut keyed: Vec<(usize, (String, &str))> = values
+            .into_iter()
+            .map(|(i, s)| {
+                let tie_breaker = if ignore_case { "" } else { s };
+                (i, (strip_diacritics(s).to_lowercase(), tie_breaker))
+            })
+            .collect();
+        keyed.sort_by(|(_, a), (_, b)| a.cmp(b));
+        return Ok(with_nulls(nulls, &keyed, |a, b| a == b));
+    }
+
+    let sorted_indices = kernels::sort::sort_to_indices(arr, None, None)?;
+    let mut indices: Vec<usize> = sorted_indices
+        .values()
+        .iter()
+        .map(|i| *i as usize)
+        .collect();
+
+    // The arrow sort is unstable, so put each run of equal values back in row order
+    let cmp = arrow::array::make_comparator(arr, arr, Default::default())?;
+    let mut run_start = 0;
+    for i in 1..=indices.len() {
+        if i == indices.len() || cmp(indices[run_start], indices[i]).is_ne() {
+            indices[run_start..i].sort_unstable();
+            run_start = i;
+        }
+    }
+    // Nulls compare equal to each other and less than any value, so they are the first run
+    let descending = reverse_runs(&indices, |a, b| cmp(a, b).is_eq());
+    Ok((indices, descending))
+}
+
+/// Ascending and descending indices from values sorted in ascending order with their indices,
+/// with the nulls in front of them in ascending order and behind them in descending order
+fn with_nulls<T>(
+    nulls: Vec<usize>,
+    sorted: &[(usize, T)],
+    is_equal: impl Fn(&T, &T) -> bool,
+) -> (Vec<usize>, Vec<usize>) {
+    let positions: Vec<usize> = (0..sorted.len()).collect();
+    let mut descending: Vec<usize> =
+        reverse_runs(&positions, |a, b| is_equal(&sorted[a].1, &sorted[b].1))
+            .into_iter()
+            .map(|position| sorted[position].0)
+            .collect();
+    descending.extend_from_slice(&nulls);
+    let mut ascending = nulls;
+    ascending.extend(sorted.iter().map(|(i, _)| *i));
+    (ascending, descending)
+}
+
+/// Reverse the order of the runs of equal items, keeping the order of the items within each run
+fn reverse_runs(items: &[usize], is_equal: impl Fn(usize, usize) -> bool) -> Vec<usize> {
+    let mut reversed = Vec::with_capacity(items.len());
+    let mut run_end = items.len();
+    for start in (0..items.len()).rev() {
+        if start == 0 || !is_equal(items[start - 1], items[start]) {
+            reversed.extend_from_slice(&items[start..run_end]);
+            run_end = start;
+        }
+    }
+    reversed
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -261,7 +478,7 @@ mod tests {
     #[test]
     fn test_simple() {
         let config = Arc::new(csv::CsvConfig::new("tests/data/simple.csv", b',', false));
-        let s = Sorter::new(config, 0, "A1".to_string());
+        let s = Sorter::new(config, 0, "A1".to_string(), false, false);
         s.wait_internal();
         let rows = s.get_sorted_indices(0, 5, SortOrder::Ascending).unwrap();
         let expected = vec![0, 9, 99, 999, 1000];
@@ -271,17 +488,214 @@ mod tests {
     #[test]
     fn test_descending() {
         let config = Arc::new(csv::CsvConfig::new("tests/data/simple.csv", b',', false));
-        let s = Sorter::new(config, 0, "A1".to_string());
+        let s = Sorter::new(config, 0, "A1".to_string(), false, false);
         s.wait_internal();
         let rows = s.get_sorted_indices(0, 5, SortOrder::Descending).unwrap();
         let expected = vec![998, 997, 996, 995, 994];
         assert_eq!(rows, expected);
     }
 
+    #[test]
+    fn test_record_order() {
+        let config = csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"a,b\n3,x\n1,y\n2,z\n5,w\n".to_vec().into()),
+            b',',
+            false,
+        )
+        .with_max_rows(Some(3));
+        let config = Arc::new(config);
+        let s = Sorter::record_order(config.clone(), None);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 5, SortOrder::Descending).unwrap();
+        assert_eq!(rows, vec![2, 1, 0]);
+        assert_eq!(s.get_record_order(0, SortOrder::Descending), Some(2));
+
+        // Known number of records, no need to count them
+        let s = Sorter::record_order(config, Some(3));
+        assert_eq!(s.status(), SorterStatus::Finished);
+        let rows = s.get_sorted_indices(0, 5, SortOrder::Descending).unwrap();
+        assert_eq!(rows, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_terminator() {
+        let config = csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"a,b\r3,x\ny\r1,z\r2,w\r".to_vec().into()),
+            b',',
+            false,
+        )
+        .with_terminator(Some(b'\r'));
+        let s = Sorter::new(Arc::new(config), 0, "a".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 5, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_nulls_last() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"a,b\n3,x\n,y\n1,z\n,w\n2,v\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let s = Sorter::new(config.clone(), 0, "a".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![1, 3, 2, 4, 0]);
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Descending).unwrap();
+        assert_eq!(rows, vec![0, 4, 2, 1, 3]);
+
+        let s = Sorter::new(config, 0, "a".to_string(), true, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![2, 4, 0, 1, 3]);
+        let rows = s.get_sorted_indices(1, 3, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![4, 0, 1]);
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Descending).unwrap();
+        assert_eq!(rows, vec![0, 4, 2, 1, 3]);
+        for order in [SortOrder::Ascending, SortOrder::Descending] {
+            let rows = s.get_sorted_indices(0, 10, order).unwrap();
+            for (i, row) in rows.iter().enumerate() {
+                assert_eq!(s.get_record_order(*row, order), Some(i as u64));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unicode_collation() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(
+                "a,b\nzebra,0\nécole,1\nEcole,2\nbanana,3\necole,4\nBanana,5\n"
+                    .as_bytes()
+                    .to_vec()
+                    .into(),
+            ),
+            b',',
+            false,
+        ));
+        let s = Sorter::new(config, 0, "a".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![5, 3, 2, 4, 1, 0]);
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(
+                b"city,n\nboston,0\nAustin,1\nBoston,2\naustin,3\nBOSTON,4\n"
+                    .to_vec()
+                    .into(),
+            ),
+            b',',
+            false,
+        ));
+        let s = Sorter::new(config.clone(), 0, "city".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![1, 3, 4, 2, 0]);
+
+        let s = Sorter::new(config, 0, "city".to_string(), false, true);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![1, 3, 0, 2, 4]);
+    }
+
+    #[test]
+    fn test_stable() {
+        let mut data = "a,b\n".to_string();
+        for i in 0..1000 {
+            data.push_str(&format!("x{},{}\n", i % 3, i % 2));
+        }
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(data.into_bytes().into()),
+            b',',
+            false,
+        ));
+        // Strings in the first column, numbers in the second
+        for column_index in [0, 1] {
+            let s = Sorter::new(config.clone(), column_index, "a".to_string(), false, false);
+            s.wait_internal();
+            let rows = s.get_sorted_indices(0, 1000, SortOrder::Ascending).unwrap();
+            let modulo = 3 - column_index as u64;
+            let mut expected: Vec<u64> = (0..1000).collect();
+            expected.sort_by_key(|i| i % modulo);
+            assert_eq!(rows, expected);
+        }
+    }
+
+    #[test]
+    fn test_stable_descending() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(
+                b"a,b
+x,1
+,
+z,2
+x,1
+z,2
+,
+"
+                .to_vec()
+                .into(),
+            ),
+            b',',
+            false,
+        ));
+        for column_index in [0, 1] {
+            let s = Sorter::new(config.clone(), column_index, "a".to_string(), false, false);
+            s.wait_internal();
+            let rows = s.get_sorted_indices(0, 10, SortOrder::Descending).unwrap();
+            assert_eq!(rows, vec![2, 4, 0, 3, 1, 5]);
+            for (i, row) in rows.iter().enumerate() {
+                assert_eq!(
+                    s.get_record_order(*row, SortOrder::Descending),
+                    Some(i as u64)
+                );
+            }
+        }
+
+        let config = Arc::new(
+            csv::CsvConfig::from_source(
+                csv::CsvSource::Bytes(b"a\n\"1,000\"\n9\n1000\n9\n".to_vec().into()),
+                b',',
+                false,
+            )
+            .with_thousands_separator(Some(',')),
+        );
+        let s = Sorter::new(config, 0, "a".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Descending).unwrap();
+        assert_eq!(rows, vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let data = b"a,b\n\"1,000\",x\n9,y\n\"2,500.5\",z\n,w\n".to_vec();
+        let config = Arc::new(
+            csv::CsvConfig::from_source(csv::CsvSource::Bytes(data.into()), b',', false)
+                .with_thousands_separator(Some(',')),
+        );
+        let s = Sorter::new(config, 0, "a".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Ascending).unwrap();
+        assert_eq!(rows, vec![3, 1, 0, 2]);
+
+        let data = b"a;b\n1.000;x\n9;y\n2.500,5;z\n0,5;w\n".to_vec();
+        let config = Arc::new(
+            csv::CsvConfig::from_source(csv::CsvSource::Bytes(data.into()), b';', false)
+                .with_thousands_separator(Some('.')),
+        );
+        let s = Sorter::new(config, 0, "a".to_string(), false, false);
+        s.wait_internal();
+        let rows = s.get_sorted_indices(0, 10, SortOrder::Descending).unwrap();
+        assert_eq!(rows, vec![2, 0, 1, 3]);
+    }
+
     #[test]
     fn test_empty() {
         let config = Arc::new(csv::CsvConfig::new("tests/data/empty.csv", b',', false));
-        let s = Sorter::new(config, 1, "b".to_string());
+        let s = Sorter::new(config, 1, "b".to_string(), false, false);
         s.wait_internal();
         assert_eq!(
             s.status(),
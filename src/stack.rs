@@ -0,0 +1,439 @@
+use crate::csv::{CsvConfig, ReadSeek};
+use crate::errors::CsvlensResult;
+
+use csv::{ByteRecord, Position, Reader, WriterBuilder};
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+/// Name of the column added in front of stacked files, naming the file each record is from
+pub const SOURCE_COLUMN_NAME: &str = "source";
+
+/// Number of bytes of the combined csv generated at a time. Where the first record of each chunk
+/// starts is remembered to seek to.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Records of several csv files, one file after another, read as a single csv led by a column
+/// naming the file each record is from. Nothing is copied: the combined csv is generated from the
+/// files as it is read.
+pub struct Stack {
+    files: Vec<StackedFile>,
+    /// Header, and units row if the first file has one, of the combined csv
+    head: Vec<u8>,
+    /// Where records start in the combined csv, taken every so often while reading it
+    checkpoints: Mutex<Vec<Checkpoint>>,
+    /// Set if some files have different columns than the first one
+    warning: Option<String>,
+}
+
+struct StackedFile {
+    name: String,
+    config: CsvConfig,
+    /// Index in this file of each column of the first file, if the columns differ
+    columns: Option<Vec<Option<usize>>>,
+}
+
+#[derive(Clone, Debug)]
+struct Checkpoint {
+    /// Offset of the record in the combined csv
+    offset: u64,
+    file_index: usize,
+    /// Position of the record in its file
+    position: Position,
+}
+
+impl Stack {
+    /// Stack the files with the columns of the first one. The fields of other files are matched
+    /// to those columns by name, leaving the columns they don't have empty and dropping the ones
+    /// the first file doesn't have, which is warned about.
+    ///
+    /// The combined csv always has a header, and a units row if the first file has one.
+    pub fn new(files: Vec<(String, CsvConfig)>) -> CsvlensResult<Stack> {
+        let mut stacked_files = vec![];
+        let mut first_headers: Option<Vec<String>> = None;
+        let mut head = vec![];
+        let mut mismatched = vec![];
+        for (name, config) in files {
+            let headers = config.read_headers()?;
+            let columns = match &first_headers {
+                None => {
+                    let mut writer = WriterBuilder::new().flexible(true).from_writer(&mut head);
+                    writer.write_field(source_column_name(&headers))?;
+                    writer.write_record(&headers)?;
+                    if let Some(units) = config.read_units()? {
+                        writer.write_field("")?;
+                        writer.write_record(&units)?;
+                    }
+                    writer.flush()?;
+                    drop(writer);
+                    first_headers = Some(headers);
+                    None
+                }
+                Some(first_headers) if headers == *first_headers => None,
+                Some(first_headers) => {
+                    mismatched.push(name.clone());
+                    Some(
+                        first_headers
+                            .iter()
+                            .map(|column| headers.iter().position(|h| h == column))
+                            .collect(),
+                    )
+                }
+            };
+            stacked_files.push(StackedFile {
+                name,
+                config,
+                columns,
+            });
+        }
+
+        let warning = match (stacked_files.first(), mismatched.is_empty()) {
+            (Some(first), false) => Some(format!(
+                "Columns differ from those of {}: {}",
+                first.name,
+                mismatched.join(", ")
+            )),
+            _ => None,
+        };
+        Ok(Stack {
+            files: stacked_files,
+            head,
+            checkpoints: Mutex::new(vec![]),
+            warning,
+        })
+    }
+
+    pub fn warning(&self) -> Option<&str> {
+        self.warning.as_deref()
+    }
+
+    /// Open a reader of the combined csv positioned at its start
+    pub fn open(self: &Arc<Self>) -> StackReader {
+        StackReader {
+            stack: self.clone(),
+            buf_offset: 0,
+            buf: vec![],
+            buf_pos: 0,
+            head_done: false,
+            file_index: 0,
+            reader: None,
+            positioned: false,
+            record: ByteRecord::new(),
+        }
+    }
+
+    /// Why some of the files are incomplete, if writing them in the background stopped short
+    pub fn error(&self) -> Option<String> {
+        self.files
+            .iter()
+            .find_map(|file| file.config.source().error())
+    }
+
+    /// Size of the combined csv in bytes, roughly: the sizes of the files it is read from
+    pub fn len(&self) -> CsvlensResult<u64> {
+        let mut len = self.head.len() as u64;
+        for file in &self.files {
+            len += file.config.source().len()?;
+        }
+        Ok(len)
+    }
+
+    fn add_checkpoint(&self, checkpoint: Checkpoint) {
+        let mut checkpoints = self.checkpoints.lock().unwrap();
+        if let Err(i) = checkpoints.binary_search_by_key(&checkpoint.offset, |c| c.offset) {
+            checkpoints.insert(i, checkpoint);
+        }
+    }
+
+    /// Last checkpoint at or before the given offset
+    fn checkpoint_before(&self, offset: u64) -> Option<Checkpoint> {
+        let checkpoints = self.checkpoints.lock().unwrap();
+        let i = checkpoints.partition_point(|c| c.offset <= offset);
+        i.checked_sub(1).map(|i| checkpoints[i].clone())
+    }
+}
+
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.files.iter().map(|file| file.name.as_str()).collect();
+        f.debug_struct("Stack").field("files", &names).finish()
+    }
+}
+
+/// Name of the column added in front of the given columns, made different from all of them
+fn source_column_name(headers: &[String]) -> String {
+    let mut name = SOURCE_COLUMN_NAME.to_string();
+    let mut n = 1;
+    while headers.contains(&name) {
+        n += 1;
+        name = format!("{SOURCE_COLUMN_NAME}_{n}");
+    }
+    name
+}
+
+/// Reader of the combined csv of a Stack, generating it a chunk at a time
+pub struct StackReader {
+    stack: Arc<Stack>,
+    /// Offset in the combined csv of the start of buf
+    buf_offset: u64,
+    buf: Vec<u8>,
+    /// Position in buf of the next byte to read
+    buf_pos: usize,
+    head_done: bool,
+    /// File the next record is read from, and its reader once opened
+    file_index: usize,
+    reader: Option<Reader<Box<dyn ReadSeek>>>,
+    /// Whether the position of the reader is where its next record starts, which is only known
+    /// once a record is read or the reader is seeked, e.g. not before the header is read
+    positioned: bool,
+    record: ByteRecord,
+}
+
+impl StackReader {
+    /// Replace buf with the next chunk of the combined csv, which is empty at the end
+    fn next_chunk(&mut self) -> io::Result<()> {
+        self.buf_offset += self.buf.len() as u64;
+        self.buf_pos = 0;
+        self.buf.clear();
+        if !self.head_done {
+            self.buf.extend_from_slice(&self.stack.head);
+            self.head_done = true;
+            return Ok(());
+        }
+
+        let mut writer = WriterBuilder::new()
+            .flexible(true)
+            .from_writer(std::mem::take(&mut self.buf));
+        while writer.get_ref().len() < CHUNK_SIZE {
+            let Some(file) = self.stack.files.get(self.file_index) else {
+                break;
+            };
+            let reader = match &mut self.reader {
+                Some(reader) => reader,
+                None => self.reader.insert(
+                    file.config
+                        .new_seekable_reader()
+                        .map_err(io::Error::other)?,
+                ),
+            };
+            let position = reader.position().clone();
+            if !reader.read_byte_record(&mut self.record)? {
+                self.file_index += 1;
+                self.reader = None;
+                self.positioned = false;
+                continue;
+            }
+            let positioned = std::mem::replace(&mut self.positioned, true);
+            if positioned && writer.get_ref().is_empty() {
+                self.stack.add_checkpoint(Checkpoint {
+                    offset: self.buf_offset,
+                    file_index: self.file_index,
+                    position,
+                });
+            }
+            writer.write_field(&file.name)?;
+            match &file.columns {
+                None => writer.write_record(&self.record)?,
+                Some(columns) => {
+                    writer.write_record(columns.iter().map(|column| {
+                        column.and_then(|i| self.record.get(i)).unwrap_or_default()
+                    }))?
+                }
+            }
+            writer.flush()?;
+        }
+        self.buf = writer.into_inner().map_err(|e| e.into_error())?;
+        Ok(())
+    }
+
+    /// Start generating again from the last checkpoint at or before the given offset
+    fn restart_before(&mut self, offset: u64) -> io::Result<()> {
+        self.buf.clear();
+        self.buf_pos = 0;
+        match self.stack.checkpoint_before(offset) {
+            Some(checkpoint) => {
+                let file = &self.stack.files[checkpoint.file_index];
+                let mut reader = file
+                    .config
+                    .new_seekable_reader()
+                    .map_err(io::Error::other)?;
+                reader.seek(checkpoint.position)?;
+                self.reader = Some(reader);
+                self.positioned = true;
+                self.file_index = checkpoint.file_index;
+                self.buf_offset = checkpoint.offset;
+                self.head_done = true;
+            }
+            None => {
+                self.reader = None;
+                self.positioned = false;
+                self.file_index = 0;
+                self.buf_offset = 0;
+                self.head_done = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for StackReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.buf_pos >= self.buf.len() {
+            self.next_chunk()?;
+        }
+        let n = out.len().min(self.buf.len() - self.buf_pos);
+        out[..n].copy_from_slice(&self.buf[self.buf_pos..self.buf_pos + n]);
+        self.buf_pos += n;
+        Ok(n)
+    }
+}
+
+impl Seek for StackReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let current = self.buf_offset + self.buf_pos as u64;
+        let target = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(delta) => current.checked_add_signed(delta).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "seek before the start")
+            })?,
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "size of stacked files is not known up front",
+                ));
+            }
+        };
+        if target < self.buf_offset || target > self.buf_offset + self.buf.len() as u64 {
+            self.restart_before(target)?;
+        }
+        while target > self.buf_offset + self.buf.len() as u64 {
+            self.next_chunk()?;
+            if self.buf.is_empty() {
+                // Past the end, where reading returns nothing
+                break;
+            }
+        }
+        self.buf_pos = (target - self.buf_offset).min(self.buf.len() as u64) as usize;
+        Ok(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::csv::CsvSource;
+
+    fn config(content: &str) -> CsvConfig {
+        CsvConfig::from_source(CsvSource::Bytes(Arc::from(content.as_bytes())), b',', false)
+    }
+
+    fn combined(stack: Stack) -> String {
+        let mut out = String::new();
+        Arc::new(stack).open().read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_stack() {
+        let files = vec![
+            ("a.csv".to_string(), config("id,name\n1,a\n2,b\n")),
+            ("b.csv".to_string(), config("id,name\n3,\"c, d\"\n")),
+        ];
+        let stack = Stack::new(files).unwrap();
+        assert_eq!(stack.warning(), None);
+        assert_eq!(
+            combined(stack),
+            "source,id,name\na.csv,1,a\na.csv,2,b\nb.csv,3,\"c, d\"\n"
+        );
+    }
+
+    #[test]
+    fn test_stack_mismatched_columns() {
+        let files = vec![
+            ("a.csv".to_string(), config("id,name\n1,a\n")),
+            ("b.csv".to_string(), config("id,name\n2,b\n")),
+            ("c.csv".to_string(), config("name,x\nc,y\n")),
+        ];
+        let stack = Stack::new(files).unwrap();
+        assert_eq!(
+            stack.warning(),
+            Some("Columns differ from those of a.csv: c.csv")
+        );
+        assert_eq!(
+            combined(stack),
+            "source,id,name\na.csv,1,a\nb.csv,2,b\nc.csv,,c\n"
+        );
+    }
+
+    #[test]
+    fn test_stack_source_column_taken() {
+        let files = vec![
+            ("a.csv".to_string(), config("source,source_2\nx,y\n")),
+            ("b.csv".to_string(), config("source,source_2\nz,w\n")),
+        ];
+        let stack = Stack::new(files).unwrap();
+        assert_eq!(
+            combined(stack),
+            "source_3,source,source_2\na.csv,x,y\nb.csv,z,w\n"
+        );
+    }
+
+    #[test]
+    fn test_stack_no_headers_and_units() {
+        let no_headers = |content: &str| {
+            CsvConfig::from_source(CsvSource::Bytes(Arc::from(content.as_bytes())), b';', true)
+        };
+        let files = vec![
+            ("a".to_string(), no_headers("1;x\n")),
+            ("b".to_string(), no_headers("2;y\n")),
+        ];
+        let stack = Stack::new(files).unwrap();
+        assert_eq!(combined(stack), "source,1,2\na,1,x\nb,2,y\n");
+
+        let files = vec![
+            (
+                "a".to_string(),
+                config("t,v\ns,m\n1,2\n").with_units_row(true),
+            ),
+            (
+                "b".to_string(),
+                config("t,v\ns,m\n3,4\n").with_units_row(true),
+            ),
+        ];
+        let stack = Stack::new(files).unwrap();
+        assert_eq!(combined(stack), "source,t,v\n,s,m\na,1,2\nb,3,4\n");
+    }
+
+    #[test]
+    fn test_stack_seek() {
+        let mut a = "n\n".to_string();
+        let mut b = "n\n".to_string();
+        for i in 0..20000 {
+            a.push_str(&format!("{i}\n"));
+            b.push_str(&format!("{}\n", i + 20000));
+        }
+        let new_stack = || {
+            let files = vec![("a".to_string(), config(&a)), ("b".to_string(), config(&b))];
+            Arc::new(Stack::new(files).unwrap())
+        };
+        let stack = new_stack();
+        let mut all = vec![];
+        stack.open().read_to_end(&mut all).unwrap();
+        assert!(stack.checkpoints.lock().unwrap().len() > 2);
+
+        // Seeking anywhere, with or without checkpoints to start from, reads the same bytes
+        let unread = new_stack();
+        let mut reused = unread.open();
+        for offset in [all.len() as u64 - 10, 5, 300_000, 131_075, 0, 200_000] {
+            for reader in [&mut reused, &mut stack.open(), &mut new_stack().open()] {
+                reader.seek(SeekFrom::Start(offset)).unwrap();
+                let mut buf = [0; 20];
+                let n = reader.read(&mut buf).unwrap();
+                assert!(n > 0);
+                let offset = offset as usize;
+                assert_eq!(&buf[..n], &all[offset..offset + n]);
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use ratatui::style::{Color, Modifier, Style};
+
+/// Environment variable that turns off colors when set to anything but an empty string, see
+/// https://no-color.org
+pub const NO_COLOR_ENV_VAR: &str = "NO_COLOR";
+
+/// Styles the table and the pages shown over it are drawn with
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Borders, row numbers, scrollbars and other decorations around the content
+    pub border: Style,
+    /// Part of the scrollbars showing the rows or columns on screen, over a track in the border
+    /// style
+    pub scrollbar_thumb: Style,
+    pub status: Style,
+    /// First line of the pages shown over the table, such as the help page
+    pub heading: Style,
+    pub selected: Style,
+    /// Row and column of the selected cell with the crosshair on, and the range selection
+    pub crosshair: Style,
+    /// Columns other than the selected one when focusing on it
+    pub focus_dim: Style,
+    pub found: Style,
+    /// Patched over a match when it is the one the finder is at
+    pub found_current: Style,
+    pub diff_added: Style,
+    pub diff_removed: Style,
+    pub diff_changed: Style,
+    /// Whether colors given to columns by name are applied
+    pub column_colors: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            border: Style::default().fg(Color::Rgb(64, 64, 64)),
+            scrollbar_thumb: Style::default().fg(Color::Rgb(128, 128, 128)),
+            status: Style::default().fg(Color::Rgb(128, 128, 128)),
+            heading: Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Rgb(200, 200, 200)),
+            selected: Style::default()
+                .fg(Color::Rgb(192, 192, 192))
+                .bg(Color::Rgb(64, 64, 64))
+                .add_modifier(Modifier::BOLD),
+            crosshair: Style::default().bg(Color::Rgb(36, 36, 36)),
+            focus_dim: Style::default().fg(Color::Rgb(96, 96, 96)),
+            found: Style::default().fg(Color::Rgb(200, 0, 0)),
+            found_current: Style::default().bg(Color::LightYellow),
+            diff_added: Style::default().fg(Color::Green),
+            diff_removed: Style::default().fg(Color::Red),
+            diff_changed: Style::default().fg(Color::Yellow),
+            column_colors: true,
+        }
+    }
+}
+
+impl Theme {
+    /// Default foreground and background everywhere, telling states apart by modifiers only
+    pub fn monochrome() -> Self {
+        Theme {
+            border: Style::default(),
+            scrollbar_thumb: Style::default().add_modifier(Modifier::BOLD),
+            status: Style::default(),
+            heading: Style::default().add_modifier(Modifier::BOLD),
+            selected: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            crosshair: Style::default().add_modifier(Modifier::ITALIC),
+            focus_dim: Style::default().add_modifier(Modifier::DIM),
+            found: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            found_current: Style::default().add_modifier(Modifier::REVERSED),
+            diff_added: Style::default().add_modifier(Modifier::BOLD),
+            diff_removed: Style::default().add_modifier(Modifier::CROSSED_OUT),
+            diff_changed: Style::default().add_modifier(Modifier::UNDERLINED),
+            column_colors: false,
+        }
+    }
+
+    pub fn new(monochrome: bool) -> Self {
+        if monochrome {
+            Theme::monochrome()
+        } else {
+            Theme::default()
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use ratatui::text::Span;
+
+/// Shown in place of the part of a value cut short, unless another indicator is given
+pub const DEFAULT_TRUNCATION_INDICATOR: &str = "…";
+
+/// Which part of a value too wide for its column is cut short
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TruncationMode {
+    /// Keep the start of the value
+    #[default]
+    End,
+    /// Keep both the start and the end of the value, e.g. for IDs and paths
+    Middle,
+}
+
+/// How values too wide for their column are cut short
+#[derive(Clone, Debug, PartialEq)]
+pub struct Truncation {
+    pub mode: TruncationMode,
+    pub indicator: String,
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Truncation {
+            mode: TruncationMode::default(),
+            indicator: DEFAULT_TRUNCATION_INDICATOR.to_string(),
+        }
+    }
+}
+
+impl Truncation {
+    pub fn new(mode: TruncationMode, indicator: Option<String>) -> Self {
+        Truncation {
+            mode,
+            indicator: indicator.unwrap_or_else(|| DEFAULT_TRUNCATION_INDICATOR.to_string()),
+        }
+    }
+
+    pub fn indicator_width(&self) -> usize {
+        Span::raw(self.indicator.as_str()).width()
+    }
+
+    /// Cut the spans in the middle to fit in the given width, putting the indicator between what
+    /// is left of the start and the end. Spans that already fit are returned as they are.
+    pub fn truncate_middle<'a>(&self, spans: &[Span<'a>], width: usize) -> Vec<Span<'a>> {
+        let total_width: usize = spans.iter().map(|span| span.width()).sum();
+        if total_width <= width {
+            return spans.to_vec();
+        }
+        let available = width.saturating_sub(self.indicator_width());
+        let tail_width = available / 2;
+        let head_width = available - tail_width;
+
+        let mut out = vec![];
+        let mut remaining = head_width;
+        let mut indicator_style = spans.first().map(|span| span.style).unwrap_or_default();
+        for span in spans {
+            indicator_style = span.style;
+            let span_width = span.width();
+            if span_width > remaining {
+                out.push(Span::styled(
+                    take_width(&span.content, remaining).to_string(),
+                    span.style,
+                ));
+                break;
+            }
+            out.push(span.clone());
+            remaining -= span_width;
+        }
+        out.push(Span::styled(self.indicator.clone(), indicator_style));
+
+        let mut tail = vec![];
+        let mut remaining = tail_width;
+        for span in spans.iter().rev() {
+            let span_width = span.width();
+            if span_width > remaining {
+                tail.push(Span::styled(
+                    take_width_from_end(&span.content, remaining).to_string(),
+                    span.style,
+                ));
+                break;
+            }
+            tail.push(span.clone());
+            remaining -= span_width;
+        }
+        out.extend(tail.into_iter().rev());
+        out
+    }
+
+    /// Cut the end of the spans so that they fit in the given width followed by the indicator. The
+    /// indicator is cut short too if it is wider than that.
+    pub fn truncate_end<'a>(&self, spans: &[Span<'a>], width: usize) -> Vec<Span<'a>> {
+        let indicator_style = spans.last().map(|span| span.style).unwrap_or_default();
+
+        let mut out = vec![];
+        let mut remaining = width.saturating_sub(self.indicator_width());
+        for span in spans {
+            let span_width = span.width();
+            if span_width > remaining {
+                let content = take_width(&span.content, remaining);
+                if !content.is_empty() {
+                    out.push(Span::styled(content.to_string(), span.style));
+                }
+                break;
+            }
+            out.push(span.clone());
+            remaining -= span_width;
+        }
+        let kept_width: usize = out.iter().map(|span| span.width()).sum();
+        let indicator = take_width(&self.indicator, width - kept_width);
+        out.push(Span::styled(indicator.to_string(), indicator_style));
+        out
+    }
+}
+
+fn char_width(c: char) -> usize {
+    let mut bytes = [0; 4];
+    Span::raw(&*c.encode_utf8(&mut bytes)).width()
+}
+
+/// Longest start of the string that fits in the given width
+pub fn take_width(s: &str, width: usize) -> &str {
+    let mut total = 0;
+    for (i, c) in s.char_indices() {
+        total += char_width(c);
+        if total > width {
+            return &s[..i];
+        }
+    }
+    s
+}
+
+/// Longest end of the string that fits in the given width
+fn take_width_from_end(s: &str, width: usize) -> &str {
+    let mut total = 0;
+    for (i, c) in s.char_indices().rev() {
+        total += char_width(c);
+        if total > width {
+            return &s[i + c.len_utf8()..];
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use ratatui::style::{Color, Style};
+
+    #[test]
+    fn test_truncate_middle() {
+        let truncation = Truncation::new(TruncationMode::Middle, None);
+        let spans = vec![Span::raw("HelloBigWorld")];
+        assert_eq!(
+            truncation.truncate_middle(&spans, 11),
+            vec![Span::raw("Hello"), Span::raw("…"), Span::raw("World")]
+        );
+        assert_eq!(truncation.truncate_middle(&spans, 13), spans);
+    }
+
+    #[test]
+    fn test_truncate_middle_keeps_styles() {
+        let truncation = Truncation::new(TruncationMode::Middle, Some("..".to_string()));
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![
+            Span::raw("ab"),
+            Span::styled("cdefgh", red),
+            Span::raw("ij"),
+        ];
+        assert_eq!(
+            truncation.truncate_middle(&spans, 8),
+            vec![
+                Span::raw("ab"),
+                Span::styled("c", red),
+                Span::styled("..", red),
+                Span::styled("h", red),
+                Span::raw("ij"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truncate_end() {
+        let truncation = Truncation::new(TruncationMode::End, Some("...".to_string()));
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![Span::raw("abcd"), Span::styled("e", red)];
+        assert_eq!(
+            truncation.truncate_end(&spans, 5),
+            vec![Span::raw("ab"), Span::styled("...", red)]
+        );
+
+        // The indicator is cut short too rather than taking up more than the width
+        let spans = vec![Span::raw("ab")];
+        assert_eq!(truncation.truncate_end(&spans, 2), vec![Span::raw("..")]);
+    }
+
+    #[test]
+    fn test_truncate_middle_wide_chars() {
+        let truncation = Truncation::new(TruncationMode::Middle, None);
+        let spans = vec![Span::raw("日本語のテキスト")];
+        // 3 columns on either side, where only one wide character fits
+        assert_eq!(
+            truncation.truncate_middle(&spans, 7),
+            vec![Span::raw("日"), Span::raw("…"), Span::raw("ト")]
+        );
+    }
+}
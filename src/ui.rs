@@ -1,32 +1,57 @@
+use crate::border::BorderStyle;
+use crate::column_letters::column_letters;
 use crate::common::InputMode;
 use crate::csv::Row;
+use crate::diff::{DiffRow, DiffStatus};
 use crate::find;
+use crate::normalize;
 use crate::sort;
 use crate::sort::SortOrder;
+use crate::theme::Theme;
+use crate::truncate::{self, Truncation, TruncationMode};
 use crate::view;
 use crate::view::Header;
+use crate::whitespace::WhitespaceSymbols;
 use crate::wrap;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::prelude::Position;
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::symbols::line;
+use ratatui::symbols::{block, line};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::Widget;
-use ratatui::widgets::{Block, Borders, StatefulWidget};
+use ratatui::widgets::{
+    Block, Borders, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget,
+};
 use regex::Regex;
 use tui_input::Input;
 
+use std::borrow::Cow;
 use std::cmp::{max, min};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
 
-const NUM_SPACES_AFTER_LINE_NUMBER: u16 = 2;
-const NUM_SPACES_BETWEEN_COLUMNS: u16 = 4;
+/// Spaces between the row numbers and the first column, unless configured otherwise
+pub const DEFAULT_SPACES_AFTER_LINE_NUMBER: u16 = 2;
+/// Spaces between columns, unless configured otherwise
+pub const DEFAULT_SPACES_BETWEEN_COLUMNS: u16 = 4;
 const MAX_COLUMN_WIDTH_FRACTION: f32 = 0.3;
+/// Lines a wrapped header takes up at most, so that it does not crowd out the rows
+const MAX_WRAPPED_HEADER_HEIGHT: u16 = 3;
+/// Lines taken up by the status bar, including the separator above it
+pub const STATUS_HEIGHT: u16 = 2;
 
-#[derive(Debug)]
+/// Shown in place of empty cells if enabled without a placeholder of choice
+pub const DEFAULT_EMPTY_PLACEHOLDER: &str = "∅";
+
+/// Status line format reproducing the default layout
+pub const DEFAULT_STATUS_FORMAT: &str = "{filename} [Row {row}/{total}, Col {col}/{cols_total}]\
+{indexing}{filter}{stats}{columns}{sort}{validation}{echo}{ignore_case}{ignore_accents}\
+{match_mode}{cell}{range}{text}{byte}{macro}{debug}";
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct ColumnWidthOverrides {
     overrides: HashMap<usize, u16>,
 }
@@ -71,11 +96,19 @@ impl<'a> CsvTable<'a> {
 }
 
 impl<'a> CsvTable<'a> {
+    #[allow(clippy::too_many_arguments)]
     fn get_column_widths(
         &self,
         area_width: u16,
         overrides: &ColumnWidthOverrides,
         sorter_state: &SorterState,
+        footer: Option<&Vec<String>>,
+        column_types: Option<&Vec<String>>,
+        units: Option<&Vec<String>>,
+        column_letters: Option<&Vec<String>>,
+        show_whitespace: bool,
+        empty_placeholder: Option<&str>,
+        column_spacing: u16,
     ) -> Vec<u16> {
         let mut column_widths = Vec::new();
 
@@ -91,8 +124,16 @@ impl<'a> CsvTable<'a> {
 
         let overriden_indices = overrides.overriden_indices();
 
-        for row in self.rows.iter() {
-            for (i, value) in row.fields.iter().enumerate() {
+        for fields in self
+            .rows
+            .iter()
+            .map(|row| &row.fields)
+            .chain(footer)
+            .chain(column_types)
+            .chain(units)
+            .chain(column_letters)
+        {
+            for (i, value) in fields.iter().enumerate() {
                 if i >= column_widths.len() {
                     continue;
                 }
@@ -100,8 +141,17 @@ impl<'a> CsvTable<'a> {
                     continue;
                 }
                 let v = column_widths.get_mut(i).unwrap();
-                value.split('\n').for_each(|x| {
-                    let value_len = x.len() as u16;
+                if let (true, Some(placeholder)) = (value.is_empty(), empty_placeholder) {
+                    *v = max(*v, placeholder.chars().count() as u16);
+                    continue;
+                }
+                let num_lines = value.split('\n').count();
+                value.split('\n').enumerate().for_each(|(j, x)| {
+                    let mut value_len = x.len() as u16;
+                    // Room for the newline symbol, the other symbols replace a character each
+                    if show_whitespace && j + 1 < num_lines {
+                        value_len += 1;
+                    }
                     if *v < value_len {
                         *v = value_len;
                     }
@@ -114,9 +164,9 @@ impl<'a> CsvTable<'a> {
         let mut clipped_columns: Vec<(usize, u16)> = vec![];
         for (i, w) in column_widths.iter_mut().enumerate() {
             if overriden_indices.contains(&self.header.get(i).unwrap().origin_index) {
-                *w = max(*w, NUM_SPACES_BETWEEN_COLUMNS);
+                *w = max(*w, column_spacing);
             } else {
-                *w += NUM_SPACES_BETWEEN_COLUMNS;
+                *w += column_spacing;
                 if *w > max_single_column_width {
                     clipped_columns.push((i, *w));
                     *w = max_single_column_width;
@@ -169,60 +219,105 @@ impl<'a> CsvTable<'a> {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn get_row_heights(
         &self,
         area_height: u16,
         rows: &[Row],
         column_widths: &[u16],
-        enable_line_wrap: bool,
+        column_wraps: &[bool],
         is_word_wrap: bool,
+        whitespace_symbols: Option<&WhitespaceSymbols>,
+        column_spacing: u16,
+        max_row_height: Option<u16>,
+        cache: &mut RowHeightsCache,
     ) -> Vec<u16> {
-        if !enable_line_wrap {
+        if !column_wraps.iter().any(|w| *w) {
             return rows.iter().map(|_| 1).collect();
         }
+        cache.validate(
+            column_widths,
+            column_wraps,
+            is_word_wrap,
+            whitespace_symbols,
+            column_spacing,
+            self.header,
+        );
         let mut total_height = 0;
         let mut row_heights = Vec::new();
-        for (i, row) in rows.iter().enumerate() {
+        for row in rows.iter() {
             if total_height >= area_height {
                 // Exit early if we've already filled the available height. Important since
                 // LineWrapper at its current state is not particularly efficient...
                 row_heights.push(1);
                 continue;
             }
-            for (j, content) in row.fields.iter().enumerate() {
-                let num_lines = match column_widths.get(j) {
-                    Some(w) => {
-                        let usable_width = (*w).saturating_sub(NUM_SPACES_BETWEEN_COLUMNS);
-                        if usable_width > 0 {
-                            let spans = [Span::styled(content.as_str(), Style::default())];
-                            let mut line_wrapper =
-                                wrap::LineWrapper::new(&spans, usable_width as usize, is_word_wrap);
-                            let mut num_lines = 0;
-                            loop {
-                                line_wrapper.next();
-                                num_lines += 1;
-                                if line_wrapper.finished() {
-                                    break;
-                                }
+            let height = match cache.get(row.record_num) {
+                Some(height) => height,
+                None => {
+                    let height = Self::get_row_height(
+                        row,
+                        column_widths,
+                        column_wraps,
+                        is_word_wrap,
+                        whitespace_symbols,
+                        column_spacing,
+                    );
+                    cache.insert(row.record_num, height);
+                    height
+                }
+            };
+            // Cached uncapped, so that changing the cap doesn't invalidate the cache
+            let height = max_row_height.map_or(height, |max| min(height, max.max(1)));
+            row_heights.push(height);
+            total_height += height;
+        }
+        row_heights
+    }
+
+    fn get_row_height(
+        row: &Row,
+        column_widths: &[u16],
+        column_wraps: &[bool],
+        is_word_wrap: bool,
+        whitespace_symbols: Option<&WhitespaceSymbols>,
+        column_spacing: u16,
+    ) -> u16 {
+        let mut height = 1;
+        for (j, content) in row.fields.iter().enumerate() {
+            // Columns that are not wrapped stay on a single line
+            if !column_wraps.get(j).cloned().unwrap_or(false) {
+                continue;
+            }
+            let num_lines = match column_widths.get(j) {
+                Some(w) => {
+                    let usable_width = (*w).saturating_sub(column_spacing);
+                    if usable_width > 0 {
+                        let content = match whitespace_symbols {
+                            Some(symbols) => symbols.show(content),
+                            None => Cow::Borrowed(content.as_str()),
+                        };
+                        let spans = [Span::styled(content.as_ref(), Style::default())];
+                        let mut line_wrapper =
+                            wrap::LineWrapper::new(&spans, usable_width as usize, is_word_wrap);
+                        let mut num_lines = 0;
+                        loop {
+                            line_wrapper.next();
+                            num_lines += 1;
+                            if line_wrapper.finished() {
+                                break;
                             }
-                            num_lines
-                        } else {
-                            1
                         }
+                        num_lines
+                    } else {
+                        1
                     }
-                    None => 1,
-                };
-                if let Some(height) = row_heights.get_mut(i) {
-                    if *height < num_lines {
-                        *height = num_lines;
-                    }
-                } else {
-                    row_heights.push(num_lines);
                 }
-            }
-            total_height += row_heights[i];
+                None => 1,
+            };
+            height = max(height, num_lines);
         }
-        row_heights
+        height
     }
 
     fn render_row_numbers(
@@ -230,6 +325,7 @@ impl<'a> CsvTable<'a> {
         buf: &mut Buffer,
         state: &mut CsvTableState,
         area: Rect,
+        y_header: u16,
         rows: &[Row],
         view_layout: &ViewLayout,
     ) {
@@ -237,17 +333,19 @@ impl<'a> CsvTable<'a> {
         let y_first_record = area.y;
         let mut y = area.y;
         for (i, row) in rows.iter().enumerate() {
-            let row_num_formatted = row.record_num.to_string();
-            let mut style = Style::default().fg(Color::Rgb(64, 64, 64));
-            if let Some(selection) = &state.selection {
-                if selection.row.is_selected(i) {
-                    style = style
-                        .add_modifier(Modifier::BOLD)
-                        .add_modifier(Modifier::UNDERLINED);
+            if state.show_row_numbers {
+                let row_num_formatted = state.row_number(row.record_num).to_string();
+                let mut style = state.theme.border;
+                if let Some(selection) = &state.selection {
+                    if selection.row.is_selected(i) {
+                        style = style
+                            .add_modifier(Modifier::BOLD)
+                            .add_modifier(Modifier::UNDERLINED);
+                    }
                 }
+                let span = Span::styled(row_num_formatted, style);
+                buf.set_span(0, y, &span, view_layout.row_number_layout.max_length);
             }
-            let span = Span::styled(row_num_formatted, style);
-            buf.set_span(0, y, &span, view_layout.row_number_layout.max_length);
             y += view_layout.row_heights[i];
             if y >= area.bottom() {
                 break;
@@ -256,60 +354,82 @@ impl<'a> CsvTable<'a> {
 
         state.borders_state = Some(BordersState {
             x_row_separator: view_layout.row_number_layout.x_row_separator,
+            y_header,
             y_first_record,
+            y_rows_bottom: area.bottom(),
         });
     }
 
-    fn render_header_borders(&self, buf: &mut Buffer, area: Rect) -> (u16, u16) {
+    fn render_header_borders(
+        &self,
+        buf: &mut Buffer,
+        area: Rect,
+        state: &CsvTableState,
+        view_layout: &ViewLayout,
+    ) -> (u16, u16) {
+        let height = state.header_area_height(view_layout.header_height);
+        if state.compact {
+            // Header right at the top, no borders around it
+            return (0, height);
+        }
         let block = Block::default()
             .borders(Borders::TOP | Borders::BOTTOM)
-            .border_style(Style::default().fg(Color::Rgb(64, 64, 64)));
-        let height = 3;
+            .border_type(state.border_style.border_type())
+            .border_style(state.theme.border);
         let area = Rect::new(0, 0, area.width, height);
         block.render(area, buf);
         // y pos of header text and next line
-        (height.saturating_sub(2), height)
+        (1, height)
     }
 
     fn render_other_borders(&self, buf: &mut Buffer, area: Rect, state: &CsvTableState) {
         // TODO: maybe should be combined with render_header_borders() above
         // Render vertical separator
-        if state.borders_state.is_none() {
+        if state.borders_state.is_none() || state.compact {
             return;
         }
 
         let borders_state = state.borders_state.as_ref().unwrap();
         let y_first_record = borders_state.y_first_record;
         let section_width = borders_state.x_row_separator;
+        let border_type = state.border_style.border_type();
+        let line_set = state.border_style.line_set();
 
         if area.width < section_width {
             return;
         }
 
-        let line_number_block = Block::default()
-            .borders(Borders::RIGHT)
-            .border_style(Style::default().fg(Color::Rgb(64, 64, 64)));
-        let line_number_area = Rect::new(0, y_first_record, section_width, area.height);
-        line_number_block.render(line_number_area, buf);
-
-        // Intersection with header separator
-        if let Some(cell) = buf.cell_mut(Position::new(section_width - 1, y_first_record - 1)) {
-            cell.set_symbol(line::HORIZONTAL_DOWN);
+        // No separator if the row numbers are hidden
+        if section_width > 0 {
+            let line_number_block = Block::default()
+                .borders(Borders::RIGHT)
+                .border_type(border_type)
+                .border_style(state.theme.border);
+            let line_number_area = Rect::new(0, y_first_record, section_width, area.height);
+            line_number_block.render(line_number_area, buf);
+
+            // Intersection with header separator
+            if let Some(cell) = buf.cell_mut(Position::new(section_width - 1, y_first_record - 1)) {
+                cell.set_symbol(line_set.horizontal_down);
+            }
         }
 
         // Status separator at the bottom (rendered here first for the interesection)
         let block = Block::default()
             .borders(Borders::TOP)
-            .border_style(Style::default().fg(Color::Rgb(64, 64, 64)));
+            .border_type(border_type)
+            .border_style(state.theme.border);
         let status_separator_area = Rect::new(0, y_first_record + area.height, area.width, 1);
         block.render(status_separator_area, buf);
 
         // Intersection with bottom separator
-        if let Some(cell) = buf.cell_mut(Position::new(
-            section_width - 1,
-            y_first_record + area.height,
-        )) {
-            cell.set_symbol(line::HORIZONTAL_UP);
+        if section_width > 0 {
+            if let Some(cell) = buf.cell_mut(Position::new(
+                section_width - 1,
+                y_first_record + area.height,
+            )) {
+                cell.set_symbol(line_set.horizontal_up);
+            }
         }
 
         // Vertical line after last rendered column
@@ -320,14 +440,14 @@ impl<'a> CsvTable<'a> {
                 col_ending_pos_x,
                 y_first_record.saturating_sub(1),
             )) {
-                cell.set_style(Style::default().fg(Color::Rgb(64, 64, 64)))
-                    .set_symbol(line::HORIZONTAL_DOWN);
+                cell.set_style(state.theme.border)
+                    .set_symbol(line_set.horizontal_down);
             }
 
             for y in y_first_record..y_first_record + area.height {
                 if let Some(cell) = buf.cell_mut(Position::new(col_ending_pos_x, y)) {
-                    cell.set_style(Style::default().fg(Color::Rgb(64, 64, 64)))
-                        .set_symbol(line::VERTICAL);
+                    cell.set_style(state.theme.border)
+                        .set_symbol(line_set.vertical);
                 }
             }
 
@@ -335,20 +455,142 @@ impl<'a> CsvTable<'a> {
                 col_ending_pos_x,
                 y_first_record + area.height,
             )) {
-                cell.set_style(Style::default().fg(Color::Rgb(64, 64, 64)))
-                    .set_symbol(line::HORIZONTAL_UP);
+                cell.set_style(state.theme.border)
+                    .set_symbol(line_set.horizontal_up);
             }
         }
     }
 
+    fn render_scrollbars(&self, buf: &mut Buffer, area: Rect, state: &CsvTableState) {
+        let track_style = state.theme.border;
+        let thumb_style = state.theme.scrollbar_thumb;
+        let line_set = state.border_style.line_set();
+
+        // Vertical scrollbar on the right edge of the rows area
+        let total_rows = match state.total_line_number {
+            Some((n, _)) => n,
+            None => self.rows.len(),
+        };
+        let vertical_area = Rect::new(area.right().saturating_sub(1), area.y, 1, area.height);
+        let mut vertical_state = ScrollbarState::new(total_rows)
+            .position(state.rows_offset as usize)
+            .viewport_content_length(self.rows.len());
+        Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_symbol(Some(line_set.vertical))
+            .track_style(track_style)
+            .thumb_symbol(block::FULL)
+            .thumb_style(thumb_style)
+            .render(vertical_area, buf, &mut vertical_state);
+
+        // Horizontal scrollbar on the status separator, after the row numbers section
+        let x_start = state
+            .borders_state
+            .as_ref()
+            .map(|bs| bs.x_row_separator)
+            .unwrap_or(0);
+        let horizontal_area = Rect::new(
+            x_start,
+            area.bottom(),
+            area.width.saturating_sub(x_start).saturating_sub(1),
+            1,
+        );
+        let mut horizontal_state = ScrollbarState::new(state.total_cols)
+            .position(state.cols_offset as usize)
+            .viewport_content_length(state.num_cols_rendered as usize);
+        Scrollbar::new(ScrollbarOrientation::HorizontalBottom)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_symbol(Some(line_set.horizontal))
+            .track_style(track_style)
+            .thumb_symbol(line::THICK_HORIZONTAL)
+            .thumb_style(thumb_style)
+            .render(horizontal_area, buf, &mut horizontal_state);
+    }
+
+    fn render_footer(
+        &self,
+        buf: &mut Buffer,
+        state: &mut CsvTableState,
+        layout: &ViewLayout,
+        area: Rect,
+        y: u16,
+    ) {
+        let style = state.theme.border;
+        buf.set_span(
+            0,
+            y,
+            &Span::styled("Σ", style),
+            layout.row_number_layout.max_length,
+        );
+        // Still being computed in the background
+        let Some(footer) = state.footer.clone() else {
+            return;
+        };
+        let footer_area = Rect::new(area.x, y, area.width, 1);
+        self.render_row(
+            buf,
+            state,
+            &layout.column_widths,
+            footer_area,
+            layout.row_number_layout.width_with_spaces,
+            y,
+            RowType::Footer,
+            &footer,
+            None,
+            layout,
+            None,
+        );
+    }
+
+    /// Inferred types of the shown columns, if they are to be shown
+    fn column_types_row(&self, state: &CsvTableState) -> Option<Vec<String>> {
+        let column_types = state
+            .column_types
+            .as_ref()
+            .filter(|_| state.show_column_types)?;
+        Some(self.shown_columns_of(column_types))
+    }
+
+    /// Units of the shown columns, if the file has a units row
+    fn units_row(&self, state: &CsvTableState) -> Option<Vec<String>> {
+        Some(self.shown_columns_of(state.units.as_ref()?))
+    }
+
+    /// Spreadsheet style letters of the shown columns, by their position in the file, if they are
+    /// to be shown
+    fn column_letters_row(&self, state: &CsvTableState) -> Option<Vec<String>> {
+        if !state.show_column_letters {
+            return None;
+        }
+        Some(
+            self.header
+                .iter()
+                .map(|h| column_letters(h.origin_index))
+                .collect(),
+        )
+    }
+
+    /// Values for the shown columns out of values given for all columns in the order of the file
+    fn shown_columns_of(&self, values: &[String]) -> Vec<String> {
+        self.header
+            .iter()
+            .map(|h| values.get(h.origin_index).cloned().unwrap_or_default())
+            .collect()
+    }
+
     fn get_effective_column_name(&self, column_name: &str, sorter_state: &SorterState) -> String {
         if let SorterState::Enabled(info) = sorter_state {
-            if info.status == sort::SorterStatus::Finished && info.column_name == column_name {
+            if info.status == sort::SorterStatus::Finished
+                && info.column_name.as_deref() == Some(column_name)
+            {
                 let indicator = match info.order {
                     SortOrder::Ascending => "▴",
                     SortOrder::Descending => "▾",
                 };
-                return format!("{} [{}]", column_name, indicator);
+                let case_indicator = if info.ignore_case { "i" } else { "" };
+                return format!("{} [{}{}]", column_name, indicator, case_indicator);
             }
         }
         column_name.to_string()
@@ -377,7 +619,8 @@ impl<'a> CsvTable<'a> {
         let mut col_ending_pos_x = 0;
         let mut num_cols_rendered: u64 = 0;
         let row_height = match row_type {
-            RowType::Header => 1,
+            RowType::Header => view_layout.header_height,
+            RowType::Footer | RowType::ColumnTypes | RowType::Units | RowType::ColumnLetters => 1,
             RowType::Record(i) => match remaining_height {
                 Some(h) => min(h, view_layout.row_heights[i]),
                 None => view_layout.row_heights[i],
@@ -388,7 +631,40 @@ impl<'a> CsvTable<'a> {
                 continue;
             }
             let effective_width = min(remaining_width, hlen);
+            // Blanked out repeated values of the collapsed column are not really empty
+            let is_placeholder = state.show_empty_placeholder
+                && hname.is_empty()
+                && matches!(row_type, RowType::Record(_))
+                && self.header.get(col_index).map(|h| h.origin_index) != state.collapsed_column;
+            // Only changes what is shown, finding still works on the original content
+            let mut shown_offsets = None;
+            let content = if is_placeholder {
+                Cow::Borrowed(state.empty_placeholder.as_str())
+            } else if state.show_whitespace && matches!(row_type, RowType::Record(_)) {
+                match state.whitespace_symbols.show_with_offsets(hname) {
+                    Some((shown, offsets)) => {
+                        shown_offsets = Some(offsets);
+                        Cow::Owned(shown)
+                    }
+                    None => Cow::Borrowed(hname.as_str()),
+                }
+            } else {
+                Cow::Borrowed(hname.as_str())
+            };
+            let num_content_lines = match self.header.get(col_index) {
+                Some(h) if !state.is_column_wrapped(h.origin_index) => 1,
+                _ => row_height,
+            };
             let mut content_style = Style::default();
+            if let (RowType::Record(_), true, Some(color)) = (
+                &row_type,
+                state.theme.column_colors,
+                self.header
+                    .get(col_index)
+                    .and_then(|h| state.column_colors.get(&h.origin_index)),
+            ) {
+                content_style = content_style.fg(*color);
+            }
             if let RowType::Header = row_type {
                 content_style = content_style.add_modifier(Modifier::BOLD);
                 if let Some(selection) = &state.selection {
@@ -396,23 +672,77 @@ impl<'a> CsvTable<'a> {
                         content_style = content_style.add_modifier(Modifier::UNDERLINED);
                     }
                 }
+            } else if let RowType::Footer = row_type {
+                content_style = content_style.add_modifier(Modifier::ITALIC);
+            } else if let RowType::ColumnTypes = row_type {
+                content_style = content_style.add_modifier(Modifier::DIM);
+            } else if let RowType::Units = row_type {
+                content_style = content_style.add_modifier(Modifier::ITALIC);
+            } else if let RowType::ColumnLetters = row_type {
+                content_style = content_style.add_modifier(Modifier::DIM);
+            } else if row_index.is_some_and(|i| state.context_rows.contains(&(i as u64))) {
+                content_style = content_style.add_modifier(Modifier::DIM);
+            } else if let Some(diff_row) = row_index.and_then(|i| state.diff_rows.get(i)) {
+                let origin_index = self.header.get(col_index).map(|h| h.origin_index);
+                match diff_row.status {
+                    DiffStatus::Added => {
+                        content_style = content_style.patch(state.theme.diff_added)
+                    }
+                    DiffStatus::Removed => {
+                        content_style = content_style.patch(state.theme.diff_removed)
+                    }
+                    DiffStatus::Changed
+                        if origin_index.is_some_and(|i| diff_row.changed_columns.contains(&i)) =>
+                    {
+                        content_style = content_style.patch(state.theme.diff_changed)
+                    }
+                    _ => {}
+                }
+            }
+            if is_placeholder {
+                content_style = content_style.add_modifier(Modifier::DIM);
             }
             let is_selected = if let Some(selection) = &state.selection {
                 Self::is_position_selected(selection, &row_type, num_cols_rendered)
             } else {
                 false
             };
+            let is_in_range = !is_selected
+                && state.selection.as_ref().is_some_and(|selection| {
+                    Self::is_position_in_range(
+                        selection,
+                        state.selected_range,
+                        state.rows_offset,
+                        &row_type,
+                        num_cols_rendered,
+                    )
+                });
+            let is_in_crosshair = !is_selected
+                && state.show_crosshair
+                && state.selection.as_ref().is_some_and(|selection| {
+                    Self::is_position_in_crosshair(selection, &row_type, num_cols_rendered)
+                });
+            if state.focus_column
+                && state.selection.as_ref().is_some_and(|selection| {
+                    Self::is_position_out_of_focus(selection, num_cols_rendered)
+                })
+            {
+                content_style = content_style.patch(state.theme.focus_dim);
+            }
             let mut filler_style = Style::default();
             if is_selected {
-                let selected_style = Style::default()
-                    .fg(Color::Rgb(192, 192, 192))
-                    .bg(Color::Rgb(64, 64, 64))
-                    .add_modifier(Modifier::BOLD);
-                filler_style = filler_style.patch(selected_style);
-                content_style = content_style.patch(selected_style);
+                filler_style = filler_style.patch(state.theme.selected);
+                content_style = content_style.patch(state.theme.selected);
+            } else if is_in_crosshair || is_in_range {
+                filler_style = filler_style.patch(state.theme.crosshair);
+                content_style = content_style.patch(state.theme.crosshair);
             }
+            // The crosshair is padded all the way so that it forms continuous lines
             let short_padding = match &state.selection {
-                Some(selection) => !matches!(selection.selection_type(), view::SelectionType::Row),
+                Some(selection) => {
+                    !is_in_crosshair
+                        && !matches!(selection.selection_type(), view::SelectionType::Row)
+                }
                 None => false,
             };
             let filler_style = FillerStyle {
@@ -420,24 +750,43 @@ impl<'a> CsvTable<'a> {
                 short_padding,
             };
 
+            let highlight_all_columns = state.highlight_all_columns;
             let should_highlight_cell = |active: &FinderActiveState, content: &str| {
-                // Only highlight the selected column in column selection mode. But header search is
-                // always across all columns regardless of the selection mode.
+                // Only highlight the selected column in column selection mode, unless asked to
+                // highlight all columns. But header search is always across all columns
+                // regardless of the selection mode.
                 if let Some((target_column_index, _)) = active.column_index {
-                    if target_column_index != col_index && matches!(row_type, RowType::Record(_)) {
+                    if target_column_index != col_index
+                        && matches!(row_type, RowType::Record(_))
+                        && !highlight_all_columns
+                    {
+                        return false;
+                    }
+                }
+                if let Some(visible_columns) = &active.visible_columns {
+                    if !visible_columns.contains(&col_index) {
                         return false;
                     }
                 }
                 if active.is_filter && matches!(row_type, RowType::Header) {
                     return false;
                 }
-                active.target.is_match(content)
+                if matches!(
+                    row_type,
+                    RowType::Footer
+                        | RowType::ColumnTypes
+                        | RowType::Units
+                        | RowType::ColumnLetters
+                ) {
+                    return false;
+                }
+                active.is_match(content)
             };
             match &state.finder_state {
                 // TODO: seems like doing a bit too much of heavy lifting of
                 // checking for matches (finder's work)
                 FinderState::FinderActive(active) if should_highlight_cell(active, hname) => {
-                    let mut highlight_style = filler_style.style.fg(Color::Rgb(200, 0, 0));
+                    let mut highlight_style = filler_style.style.patch(state.theme.found);
                     if let Some(found_record) = &active.found_record {
                         match found_record {
                             find::FoundEntry::Row(entry) => {
@@ -445,7 +794,8 @@ impl<'a> CsvTable<'a> {
                                     if row_index == entry.row_index()
                                         && entry.column_index() == col_index
                                     {
-                                        highlight_style = highlight_style.bg(Color::LightYellow);
+                                        highlight_style =
+                                            highlight_style.patch(state.theme.found_current);
                                     }
                                 }
                             }
@@ -453,14 +803,24 @@ impl<'a> CsvTable<'a> {
                                 if matches!(row_type, RowType::Header)
                                     && entry.column_index() == col_index
                                 {
-                                    highlight_style = highlight_style.bg(Color::LightYellow);
+                                    highlight_style =
+                                        highlight_style.patch(state.theme.found_current);
                                 }
                             }
                         }
                     }
+                    // Matches are in the original content, which may be shown differently
+                    let match_ranges = match &shown_offsets {
+                        Some(offsets) => active
+                            .match_ranges(hname)
+                            .into_iter()
+                            .map(|(start, end)| (offsets[start], offsets[end]))
+                            .collect(),
+                        None => active.match_ranges(&content),
+                    };
                     let spans = CsvTable::get_highlighted_spans(
-                        active,
-                        hname,
+                        &content,
+                        match_ranges,
                         content_style,
                         highlight_style,
                     );
@@ -471,12 +831,15 @@ impl<'a> CsvTable<'a> {
                         y,
                         effective_width,
                         row_height,
+                        num_content_lines,
                         filler_style,
                         state.is_word_wrap,
+                        state.column_spacing,
+                        &state.truncation,
                     );
                 }
                 _ => {
-                    let span = Span::styled((*hname).as_str(), content_style);
+                    let span = Span::styled(content.as_ref(), content_style);
                     self.set_spans(
                         buf,
                         &[span],
@@ -484,8 +847,11 @@ impl<'a> CsvTable<'a> {
                         y,
                         effective_width,
                         row_height,
+                        num_content_lines,
                         filler_style,
                         state.is_word_wrap,
+                        state.column_spacing,
+                        &state.truncation,
                     );
                 }
             };
@@ -536,24 +902,65 @@ impl<'a> CsvTable<'a> {
         }
     }
 
-    fn get_highlighted_spans(
-        active: &FinderActiveState,
-        hname: &'a str,
+    /// Whether the position shares the row or column of the selected cell, but is not the cell
+    fn is_position_in_crosshair(
+        selection: &view::Selection,
+        row_type: &RowType,
+        num_cols_rendered: u64,
+    ) -> bool {
+        match (selection.selection_type(), row_type) {
+            (view::SelectionType::Cell, RowType::Record(i)) => {
+                selection.row.is_selected(*i)
+                    || selection.column.is_selected(num_cols_rendered as usize)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the position is in a row of the selected range, and in the selected column if any
+    fn is_position_in_range(
+        selection: &view::Selection,
+        selected_range: Option<(u64, u64)>,
+        rows_offset: u64,
+        row_type: &RowType,
+        num_cols_rendered: u64,
+    ) -> bool {
+        let (RowType::Record(i), Some((first, last))) = (row_type, selected_range) else {
+            return false;
+        };
+        let position = rows_offset.saturating_add(*i as u64);
+        (first..=last).contains(&position)
+            && (selection.column.index().is_none()
+                || selection.column.is_selected(num_cols_rendered as usize))
+    }
+
+    /// Whether a column is selected and the position is in another column
+    fn is_position_out_of_focus(selection: &view::Selection, num_cols_rendered: u64) -> bool {
+        selection.column.index().is_some()
+            && !selection.column.is_selected(num_cols_rendered as usize)
+    }
+
+    fn get_highlighted_spans<'b>(
+        hname: &'b str,
+        match_ranges: Vec<(usize, usize)>,
         style: Style,
         highlight_style: Style,
-    ) -> Vec<Span<'a>> {
+    ) -> Vec<Span<'b>> {
         // Each span can only have one style, hence split content into matches and non-matches and
         // set styles accordingly
-        let mut matches = active.target.find_iter(hname);
-        let non_matches = active.target.split(hname);
         let mut spans = vec![];
-        for part in non_matches {
-            if !part.is_empty() {
-                spans.push(Span::styled(part, style));
+        let mut last_end = 0;
+        for (start, end) in match_ranges {
+            if start > last_end {
+                spans.push(Span::styled(&hname[last_end..start], style));
             }
-            if let Some(m) = matches.next() {
-                spans.push(Span::styled(m.as_str(), highlight_style));
+            if end > start {
+                spans.push(Span::styled(&hname[start..end], highlight_style));
             }
+            last_end = end;
+        }
+        if last_end < hname.len() {
+            spans.push(Span::styled(&hname[last_end..], style));
         }
         spans
     }
@@ -567,37 +974,57 @@ impl<'a> CsvTable<'a> {
         y: u16,
         width: u16,
         height: u16,
+        num_content_lines: u16,
         filler_style: FillerStyle,
         is_word_wrap: bool,
+        column_spacing: u16,
+        truncation: &Truncation,
     ) {
-        const SUFFIX: &str = "…";
-        const SUFFIX_LEN: u16 = 1;
+        let suffix = truncation.indicator.as_str();
 
         // Reserve some space before the next column (same number used in get_column_widths)
-        let effective_width = width.saturating_sub(NUM_SPACES_BETWEEN_COLUMNS);
+        let effective_width = width.saturating_sub(column_spacing);
+
+        // Values that span lines are still cut at the end, after their first line
+        let truncated_in_middle;
+        let spans = if truncation.mode == TruncationMode::Middle
+            && min(height, num_content_lines) == 1
+            && !spans.iter().any(|span| span.content.contains('\n'))
+        {
+            truncated_in_middle = truncation.truncate_middle(spans, effective_width as usize);
+            truncated_in_middle.as_slice()
+        } else {
+            spans
+        };
 
         let buffer_space = if filler_style.short_padding {
-            NUM_SPACES_BETWEEN_COLUMNS / 2
+            column_spacing / 2
         } else {
-            NUM_SPACES_BETWEEN_COLUMNS
+            column_spacing
         } as usize;
 
         let mut line_wrapper =
             wrap::LineWrapper::new(spans, effective_width as usize, is_word_wrap);
 
+        let num_content_lines = min(height, num_content_lines);
         for offset in 0..height {
-            if let Some(mut line) = line_wrapper.next() {
+            let next_line = if offset < num_content_lines {
+                line_wrapper.next()
+            } else {
+                None
+            };
+            if let Some(mut line) = next_line {
                 // There is some content to render. Truncate with ... if there is no more vertical
                 // space available.
-                if offset == height - 1 && !line_wrapper.finished() {
-                    if let Some(last_span) = line.spans.pop() {
-                        let truncate_length = last_span.width().saturating_sub(SUFFIX_LEN as usize);
-                        let truncated_content: String =
-                            last_span.content.chars().take(truncate_length).collect();
-                        let truncated_span = Span::styled(truncated_content, last_span.style);
-                        line.spans.push(truncated_span);
-                        line.spans.push(Span::styled(SUFFIX, last_span.style));
-                    }
+                if offset == num_content_lines - 1 && !line_wrapper.finished() {
+                    // Keep to the width of the line unless there is no room for the indicator
+                    let available_width =
+                        min(effective_width as usize + buffer_space, width as usize);
+                    let truncated_width = min(
+                        max(line.width(), truncation.indicator_width()),
+                        available_width,
+                    );
+                    line.spans = truncation.truncate_end(&line.spans, truncated_width);
                 }
                 let padding_width = min(
                     (effective_width as usize + buffer_space).saturating_sub(line.width()),
@@ -616,12 +1043,14 @@ impl<'a> CsvTable<'a> {
 
                 // It's possible that no spans are yielded due to insufficient remaining width.
                 // Render ... in this case.
-                if !line_wrapper.finished() {
-                    let truncated_content: String = content
-                        .chars()
-                        .take(content.len().saturating_sub(1))
-                        .collect();
-                    content = format!("{SUFFIX}{}", truncated_content.as_str());
+                if offset < num_content_lines && !line_wrapper.finished() {
+                    let content_width = Span::raw(content.as_str()).width();
+                    let indicator = truncate::take_width(suffix, content_width);
+                    let truncated_content = truncate::take_width(
+                        &content,
+                        content_width.saturating_sub(Span::raw(indicator).width()),
+                    );
+                    content = format!("{indicator}{truncated_content}");
                 }
                 let span = Span::styled(content, filler_style.style);
                 buf.set_line(x, y + offset, &Line::from(vec![span]), width);
@@ -631,8 +1060,8 @@ impl<'a> CsvTable<'a> {
 
     fn render_status(&self, area: Rect, buf: &mut Buffer, state: &mut CsvTableState) {
         // Content of status line (separator already plotted elsewhere)
-        let style = Style::default().fg(Color::Rgb(128, 128, 128));
-        let mut content: String;
+        let style = state.theme.status;
+        let content: String;
         state.cursor_xy = None;
         if let Some(msg) = &state.transient_message {
             content = msg.to_owned();
@@ -642,8 +1071,18 @@ impl<'a> CsvTable<'a> {
                     InputMode::GotoLine => "Go to line",
                     InputMode::Find => "Find",
                     InputMode::Filter => "Filter",
+                    InputMode::FilterWithin => "Filter within matches",
                     InputMode::FilterColumns => "Columns regex",
                     InputMode::Option => "Option",
+                    InputMode::Mark => "Mark",
+                    InputMode::JumpToMark => "Jump to mark",
+                    InputMode::RecordMacro => "Record macro",
+                    InputMode::PlayMacro => "Play macro",
+                    InputMode::ScrollSelection => {
+                        "Move selected row to (z: center, t: top, b: bottom)"
+                    }
+                    InputMode::ExportMatches => "Export matches to",
+                    InputMode::GotoColumn => "Go to column",
                     _ => "",
                 };
                 if prefix.is_empty() {
@@ -653,7 +1092,10 @@ impl<'a> CsvTable<'a> {
                 }
             };
             let prefix = get_prefix(buffer_mode);
-            content = format!("{prefix}{}", input.value());
+            content = match &state.buffer_hint {
+                Some(hint) => format!("{prefix}{} ({hint})", input.value()),
+                None => format!("{prefix}{}", input.value()),
+            };
             state.cursor_xy = Some((
                 area.x
                     .saturating_add(prefix.len() as u16)
@@ -661,114 +1103,351 @@ impl<'a> CsvTable<'a> {
                 area.bottom().saturating_sub(1),
             ));
         } else {
-            // Filename
-            if let Some(f) = &state.filename {
-                content = f.to_string();
-            } else {
-                content = "stdin".to_string();
-            }
+            content = render_status_format(&state.status_format, &self.status_values(state));
+        }
+        let span = Span::styled(content, style);
+        buf.set_span(area.x, area.bottom().saturating_sub(1), &span, area.width);
+    }
 
-            // Row / Col
-            let total_str = match state.total_line_number {
-                Some((total, false)) => format!("{}", total),
-                Some((total, true)) => format!("{}+", total),
-                _ => "?".to_owned(),
-            };
-            let current_row;
-            if let Some(selection) = &state.selection {
-                current_row = if let Some(i) = selection.row.index() {
-                    self.rows.get(i as usize)
-                } else {
-                    self.rows.first()
-                }
+    /// Values of the placeholders available in the status line format. Optional segments are
+    /// empty when not applicable, and otherwise start with a space so that they can be chained.
+    fn status_values(&self, state: &CsvTableState) -> HashMap<&'static str, String> {
+        let mut values = HashMap::new();
+
+        // Filename
+        let filename = match &state.filename {
+            Some(f) => f.to_string(),
+            None => "stdin".to_string(),
+        };
+        values.insert("filename", filename);
+
+        // Row / Col
+        let total_str = match state.total_line_number {
+            Some((total, false)) => format!("{}", total),
+            Some((total, true)) => format!("{}+", total),
+            _ => "?".to_owned(),
+        };
+        let current_row;
+        if let Some(selection) = &state.selection {
+            current_row = if let Some(i) = selection.row.index() {
+                self.rows.get(i as usize)
             } else {
-                current_row = self.rows.first()
+                self.rows.first()
             }
+        } else {
+            current_row = self.rows.first()
+        }
 
-            let row_num = match current_row {
-                Some(row) => row.record_num.to_string(),
-                _ => "-".to_owned(),
-            };
-            content += format!(
-                " [Row {}/{}, Col {}/{}]",
-                row_num,
-                total_str,
-                state.cols_offset + 1,
-                state.total_cols,
-            )
-            .as_str();
+        let row_num = match current_row {
+            Some(row) => state.row_number(row.record_num).to_string(),
+            _ => "-".to_owned(),
+        };
+        values.insert("row", row_num);
+        values.insert("total", total_str);
+        values.insert("col", (state.cols_offset + 1).to_string());
+        values.insert("cols_total", state.total_cols.to_string());
+
+        // Indexing progress of large files
+        let mut indexing = String::new();
+        if let Some(progress) = state.indexing_progress {
+            indexing = format!(" [Indexing {}%]", (progress * 100.0) as u64);
+        }
+        values.insert("indexing", indexing);
 
-            // Finder
-            if let FinderState::FinderActive(s) = &state.finder_state {
-                content += format!(" {}", s.status_line()).as_str();
-            }
+        // Finder
+        let mut filter = String::new();
+        if let FinderState::FinderActive(s) = &state.finder_state {
+            filter = format!(" {}", s.status_line());
+        }
+        values.insert("filter", filter);
 
-            if let Some(stats_line) = &state.debug_stats.status_line() {
-                content += format!(" {stats_line}").as_str();
-            }
+        let mut stats = String::new();
+        if let Some(stats_line) = &state.debug_stats.status_line() {
+            stats = format!(" {stats_line}");
+        }
+        values.insert("stats", stats);
 
-            // Filter columns
-            if let FilterColumnsState::Enabled(info) = &state.filter_columns_state {
-                content += format!(" {}", info.status_line()).as_str();
+        // Filter columns
+        let mut columns = String::new();
+        if let FilterColumnsState::Enabled(info) = &state.filter_columns_state {
+            columns = format!(" {}", info.status_line());
+        }
+        values.insert("columns", columns);
+
+        // Sorter
+        let mut sort = String::new();
+        if let SorterState::Enabled(info) = &state.sorter_state {
+            let sorter_status_line = info.status_line();
+            if !sorter_status_line.is_empty() {
+                sort = format!(" {}", sorter_status_line);
             }
+        }
+        values.insert("sort", sort);
 
-            // Sorter
-            if let SorterState::Enabled(info) = &state.sorter_state {
-                let sorter_status_line = info.status_line();
-                if !sorter_status_line.is_empty() {
-                    content += format!(" {}", sorter_status_line).as_str();
-                }
-            }
+        // Validation summary
+        let mut validation = String::new();
+        if let Some(validation_status) = &state.validation_status {
+            validation = format!(" {validation_status}");
+        }
+        values.insert("validation", validation);
 
-            // Echo option
-            if let Some(column_name) = &state.echo_column {
-                content += format!(" [Echo {column_name} ↵]").as_str();
-            }
+        // Echo option
+        let mut echo = String::new();
+        if let Some(column_name) = &state.echo_column {
+            echo = format!(" [Echo {column_name} ↵]");
+        }
+        values.insert("echo", echo);
 
-            // Ignore case option
-            if state.ignore_case {
-                content += " [ignore-case]";
-            }
+        // Ignore case option
+        let mut ignore_case = String::new();
+        if state.ignore_case {
+            ignore_case = " [ignore-case]".to_string();
+        }
+        values.insert("ignore_case", ignore_case);
+
+        // Ignore accents option
+        let mut ignore_accents = String::new();
+        if state.ignore_accents {
+            ignore_accents = " [ignore-accents]".to_string();
+        }
+        values.insert("ignore_accents", ignore_accents);
 
-            // Debug
-            if !state.debug.is_empty() {
-                content += format!(" (debug: {})", state.debug).as_str();
+        // Match mode of find and filter
+        let mut match_mode = String::new();
+        if state.match_mode != find::MatchMode::Substring {
+            match_mode = format!(" [{}]", state.match_mode.description());
+        }
+        values.insert("match_mode", match_mode);
+
+        // Length and type of the selected cell
+        let mut cell = String::new();
+        if let Some(value) = self.get_selected_cell_value(state) {
+            cell = format!(
+                " [len {}, {}]",
+                Span::raw(value).width(),
+                CellValueType::from_value(value, state.thousands_separator)
+            );
+        }
+        values.insert("cell", cell);
+
+        // Range of rows selected
+        let mut range = String::new();
+        if let Some(summary) = &state.range_summary {
+            range = format!(" [Range {summary}]");
+        }
+        values.insert("range", range);
+
+        // Word and character counts of the selected cell and row
+        let mut text = String::new();
+        if state.show_text_stats {
+            let mut counts = vec![];
+            if let Some(value) = self.get_selected_cell_value(state) {
+                counts.push(format!(
+                    "{} words, {} chars",
+                    value.split_whitespace().count(),
+                    value.chars().count()
+                ));
+            }
+            if let Some(row) = self.get_selected_row(state) {
+                let row_chars: usize = row.fields.iter().map(|f| f.chars().count()).sum();
+                counts.push(format!("row {row_chars} chars"));
+            }
+            if !counts.is_empty() {
+                text = format!(" [{}]", counts.join(", "));
             }
         }
-        let span = Span::styled(content, style);
-        buf.set_span(area.x, area.bottom().saturating_sub(1), &span, area.width);
+        values.insert("text", text);
+
+        // Byte offset of the top row
+        let mut byte = String::new();
+        if let Some(offset) = state.top_byte_offset {
+            byte = format!(" [Byte {offset}]");
+        }
+        values.insert("byte", byte);
+
+        // Macro being recorded
+        let mut macro_recording = String::new();
+        if let Some(c) = state.recording_macro {
+            macro_recording = format!(" [Recording '{c}']");
+        }
+        values.insert("macro", macro_recording);
+
+        // Debug
+        let mut debug = String::new();
+        if !state.debug.is_empty() {
+            debug = format!(" (debug: {})", state.debug);
+        }
+        values.insert("debug", debug);
+
+        values
     }
 
-    fn get_view_layout(&self, area: Rect, state: &mut CsvTableState, rows: &[Row]) -> ViewLayout {
-        let max_row_num = rows.iter().map(|x| x.record_num).max().unwrap_or(0);
+    /// The selected row, in row or cell selection mode
+    fn get_selected_row(&self, state: &CsvTableState) -> Option<&'a Row> {
+        let selection = state.selection.as_ref()?;
+        if matches!(selection.selection_type(), view::SelectionType::Column) {
+            return None;
+        }
+        self.rows.get(selection.row.index()? as usize)
+    }
+
+    /// Value of the selected cell, only in cell selection mode
+    fn get_selected_cell_value(&self, state: &CsvTableState) -> Option<&'a str> {
+        let selection = state.selection.as_ref()?;
+        if !matches!(selection.selection_type(), view::SelectionType::Cell) {
+            return None;
+        }
+        let row_index = selection.row.index()?;
+        let column_index = selection.column.index()?.saturating_add(state.cols_offset);
+        self.rows
+            .get(row_index as usize)
+            .and_then(|row| row.fields.get(column_index as usize))
+            .map(|value| value.as_str())
+    }
+
+    /// Lines the column names take up when rendered in the area, more than one if they are wrapped.
+    /// Lets the rows be sized before rendering.
+    pub fn header_height(&self, area: Rect, state: &CsvTableState) -> u16 {
+        let header_height = |table: &CsvTable| {
+            let table_area = Self::table_area(area, state);
+            let (_, column_widths) = table.get_column_layout(table_area, state, table.rows);
+            table.get_header_height(&column_widths, state)
+        };
+        match self.collapse_duplicates(state) {
+            Some(rows) => header_height(&CsvTable::new(self.header, &rows)),
+            None => header_height(self),
+        }
+    }
+
+    /// Area of the table, leaving out the right most column for the vertical scrollbar
+    fn table_area(area: Rect, state: &CsvTableState) -> Rect {
+        let table_width = if state.show_scrollbars {
+            area.width.saturating_sub(1)
+        } else {
+            area.width
+        };
+        Rect::new(area.x, area.y, table_width, area.height)
+    }
+
+    /// Layout of the row numbers and widths of the columns that fit in the area
+    fn get_column_layout(
+        &self,
+        area: Rect,
+        state: &CsvTableState,
+        rows: &[Row],
+    ) -> (RowNumberLayout, Vec<u16>) {
+        let max_row_num = rows
+            .iter()
+            .map(|x| state.row_number(x.record_num))
+            .max()
+            .unwrap_or(0);
         let max_row_num_length = format!("{max_row_num}").len() as u16;
-        let row_num_section_width_with_spaces =
-            max_row_num_length + 2 * NUM_SPACES_AFTER_LINE_NUMBER + 1;
-        let x_row_separator = max_row_num_length + NUM_SPACES_AFTER_LINE_NUMBER + 1;
+        // Without the separator, the row numbers are only followed by spaces
+        let (row_num_section_width_with_spaces, x_row_separator) = if !state.show_row_numbers {
+            // Columns start right at the left edge
+            (0, 0)
+        } else if state.compact {
+            (
+                max_row_num_length + state.line_number_spacing,
+                max_row_num_length,
+            )
+        } else {
+            (
+                max_row_num_length + 2 * state.line_number_spacing + 1,
+                max_row_num_length + state.line_number_spacing + 1,
+            )
+        };
 
+        let column_types = self.column_types_row(state);
+        let units = self.units_row(state);
+        let column_letters = self.column_letters_row(state);
         let column_widths = self.get_column_widths(
             area.width.saturating_sub(row_num_section_width_with_spaces),
             &state.column_width_overrides,
             &state.sorter_state,
+            state.footer.as_ref().filter(|_| state.show_footer),
+            column_types.as_ref(),
+            units.as_ref(),
+            column_letters.as_ref(),
+            state.show_whitespace,
+            Some(state.empty_placeholder.as_str()).filter(|_| state.show_empty_placeholder),
+            state.column_spacing,
         );
+        let row_number_layout = RowNumberLayout {
+            max_length: max_row_num_length,
+            width_with_spaces: row_num_section_width_with_spaces,
+            x_row_separator,
+        };
+        (row_number_layout, column_widths)
+    }
+
+    fn get_column_wraps(&self, state: &CsvTableState) -> Vec<bool> {
+        self.header
+            .iter()
+            .map(|h| state.is_column_wrapped(h.origin_index))
+            .collect()
+    }
+
+    /// Lines the column names take up given the column widths
+    fn get_header_height(&self, column_widths: &[u16], state: &CsvTableState) -> u16 {
+        let column_wraps = self.get_column_wraps(state);
+        // Names too long for their columns wrap like the values of wrapped columns do
+        if column_wraps.iter().any(|w| *w) {
+            let names = Row {
+                record_num: 0,
+                fields: self
+                    .header
+                    .iter()
+                    .map(|h| self.get_effective_column_name(h.name.as_str(), &state.sorter_state))
+                    .collect(),
+            };
+            min(
+                Self::get_row_height(
+                    &names,
+                    column_widths,
+                    &column_wraps,
+                    state.is_word_wrap,
+                    None,
+                    state.column_spacing,
+                ),
+                MAX_WRAPPED_HEADER_HEIGHT,
+            )
+        } else {
+            1
+        }
+    }
+
+    fn get_view_layout(&self, area: Rect, state: &mut CsvTableState, rows: &[Row]) -> ViewLayout {
+        let (row_number_layout, column_widths) = self.get_column_layout(area, state, rows);
+        let column_wraps = self.get_column_wraps(state);
+        let header_height = self.get_header_height(&column_widths, state);
         let _tic = std::time::Instant::now();
         let row_heights = self.get_row_heights(
             area.height,
             self.rows,
             &column_widths,
-            state.enable_line_wrap,
+            &column_wraps,
             state.is_word_wrap,
+            Some(&state.whitespace_symbols).filter(|_| state.show_whitespace),
+            state.column_spacing,
+            state.max_row_height,
+            &mut state.row_heights_cache,
         );
+        state.tallest_row_height = if column_wraps.iter().any(|w| *w) {
+            self.rows
+                .iter()
+                .filter_map(|row| state.row_heights_cache.get(row.record_num))
+                .max()
+                .unwrap_or(1)
+        } else {
+            1
+        };
         state.num_cols_rendered = 0;
         state.col_ending_pos_x = 0;
 
-        let row_number_layout = RowNumberLayout {
-            max_length: max_row_num_length,
-            width_with_spaces: row_num_section_width_with_spaces,
-            x_row_separator,
-        };
         ViewLayout {
             column_widths,
+            header_height,
             row_heights,
             row_number_layout,
         }
@@ -779,18 +1458,67 @@ impl StatefulWidget for CsvTable<'_> {
     type State = CsvTableState;
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        match self.collapse_duplicates(state) {
+            Some(rows) => {
+                // What is shown in a row depends on its neighbours, so cached heights don't apply
+                state.row_heights_cache = RowHeightsCache::default();
+                CsvTable::new(self.header, &rows).render_table(area, buf, state);
+            }
+            None => self.render_table(area, buf, state),
+        }
+    }
+}
+
+impl CsvTable<'_> {
+    /// Rows with the repeated values of the collapsed column blanked out, and the first value of
+    /// each run followed by the length of the run. Only the loaded rows are counted, so the runs
+    /// going on above or below them are marked with a "+". This is most meaningful when sorted by
+    /// that column.
+    fn collapse_duplicates(&self, state: &CsvTableState) -> Option<Vec<Row>> {
+        let origin_index = state.collapsed_column?;
+        let column_index = self
+            .header
+            .iter()
+            .position(|h| h.origin_index == origin_index)?;
+        let mut rows = self.rows.to_vec();
+        let mut run_start = 0;
+        for i in 1..=rows.len() {
+            let value = |row: usize| self.rows[row].fields.get(column_index);
+            if i < rows.len() && value(i) == value(run_start) {
+                if let Some(field) = rows[i].fields.get_mut(column_index) {
+                    field.clear();
+                }
+                continue;
+            }
+            let run_length = i - run_start;
+            let (continues_above, continues_below) = state.collapsed_runs_continue;
+            let is_partial =
+                (run_start == 0 && continues_above) || (i == rows.len() && continues_below);
+            if run_length > 1 || is_partial {
+                if let Some(field) = rows[run_start].fields.get_mut(column_index) {
+                    let plus_marker = if is_partial { "+" } else { "" };
+                    *field = format!("{field} (×{run_length}{plus_marker})");
+                }
+            }
+            run_start = i;
+        }
+        Some(rows)
+    }
+
+    fn render_table(&self, area: Rect, buf: &mut Buffer, state: &mut CsvTableState) {
         // TODO: draw relative to the provided area
 
         if area.area() == 0 {
             return;
         }
 
-        let status_height = 2;
+        // Reserve the right most column for the vertical scrollbar
+        let table_area = Self::table_area(area, state);
 
-        let layout = self.get_view_layout(area, state, self.rows);
+        let layout = self.get_view_layout(table_area, state, self.rows);
         state.view_layout = Some(layout.clone());
 
-        let (y_header, y_first_record) = self.render_header_borders(buf, area);
+        let (y_header, y_first_record) = self.render_header_borders(buf, area, state, &layout);
 
         // row area: including row numbers and row content
         let rows_area = Rect::new(
@@ -799,18 +1527,49 @@ impl StatefulWidget for CsvTable<'_> {
             area.width,
             area.height
                 .saturating_sub(y_first_record)
-                .saturating_sub(status_height),
+                .saturating_sub(STATUS_HEIGHT),
+        );
+        // The footer takes up the last line of the row area
+        let footer_height = if state.show_footer { 1 } else { 0 };
+        let records_area = Rect::new(
+            rows_area.x,
+            rows_area.y,
+            rows_area.width,
+            rows_area.height.saturating_sub(footer_height),
+        );
+        let data_area = Rect::new(
+            records_area.x,
+            records_area.y,
+            table_area.width,
+            records_area.height,
         );
 
-        self.render_row_numbers(buf, state, rows_area, self.rows, &layout);
+        self.render_row_numbers(buf, state, records_area, y_header, self.rows, &layout);
         let row_num_section_width = layout.row_number_layout.width_with_spaces;
 
         state.reset_more_cols_to_show();
+        let mut y_header = y_header;
+        if let Some(column_letters) = self.column_letters_row(state) {
+            self.render_row(
+                buf,
+                state,
+                &layout.column_widths,
+                data_area,
+                row_num_section_width,
+                y_header,
+                RowType::ColumnLetters,
+                &column_letters,
+                None,
+                &layout,
+                None,
+            );
+            y_header += 1;
+        }
         self.render_row(
             buf,
             state,
             &layout.column_widths,
-            rows_area,
+            data_area,
             row_num_section_width,
             y_header,
             RowType::Header,
@@ -823,15 +1582,47 @@ impl StatefulWidget for CsvTable<'_> {
             &layout,
             None,
         );
+        let mut y_below_header = y_header + layout.header_height;
+        if let Some(units) = self.units_row(state) {
+            self.render_row(
+                buf,
+                state,
+                &layout.column_widths,
+                data_area,
+                row_num_section_width,
+                y_below_header,
+                RowType::Units,
+                &units,
+                None,
+                &layout,
+                None,
+            );
+            y_below_header += 1;
+        }
+        if let Some(column_types) = self.column_types_row(state) {
+            self.render_row(
+                buf,
+                state,
+                &layout.column_widths,
+                data_area,
+                row_num_section_width,
+                y_below_header,
+                RowType::ColumnTypes,
+                &column_types,
+                None,
+                &layout,
+                None,
+            );
+        }
 
-        let mut remaining_height = rows_area.height;
+        let mut remaining_height = records_area.height;
         let mut y_offset = y_first_record;
         for (i, row) in self.rows.iter().enumerate() {
             let rendered_height = self.render_row(
                 buf,
                 state,
                 &layout.column_widths,
-                rows_area,
+                data_area,
                 row_num_section_width,
                 y_offset,
                 RowType::Record(i),
@@ -842,26 +1633,78 @@ impl StatefulWidget for CsvTable<'_> {
             );
             remaining_height = remaining_height.saturating_sub(rendered_height);
             y_offset += rendered_height;
-            if y_offset >= rows_area.bottom() {
+            if y_offset >= records_area.bottom() {
                 break;
             }
         }
 
+        if state.show_footer && records_area.bottom() < rows_area.bottom() {
+            self.render_footer(buf, state, &layout, data_area, records_area.bottom());
+        }
+
         let status_area = Rect::new(
             area.x,
-            area.bottom().saturating_sub(status_height),
+            area.bottom().saturating_sub(STATUS_HEIGHT),
             area.width,
-            status_height,
+            STATUS_HEIGHT,
         );
         self.render_status(status_area, buf, state);
 
         self.render_other_borders(buf, rows_area, state);
+
+        if state.show_scrollbars {
+            self.render_scrollbars(buf, rows_area, state);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CellValueType {
+    Empty,
+    Integer,
+    Float,
+    Text,
+}
+
+impl CellValueType {
+    fn from_value(value: &str, thousands_separator: Option<char>) -> Self {
+        let value = normalize::normalize_number(value.trim(), thousands_separator);
+        if value.is_empty() {
+            CellValueType::Empty
+        } else if value.parse::<i64>().is_ok() {
+            CellValueType::Integer
+        } else if value.parse::<f64>().is_ok_and(f64::is_finite) {
+            // Not "inf" or "NaN", which are more likely text
+            CellValueType::Float
+        } else {
+            CellValueType::Text
+        }
+    }
+}
+
+impl std::fmt::Display for CellValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            CellValueType::Empty => "empty",
+            CellValueType::Integer => "integer",
+            CellValueType::Float => "float",
+            CellValueType::Text => "text",
+        };
+        write!(f, "{s}")
     }
 }
 
 pub enum RowType {
     /// Header row
     Header,
+    /// Footer row with column aggregates
+    Footer,
+    /// Row below the header with the inferred type of each column
+    ColumnTypes,
+    /// Units row of the file, pinned below the header
+    Units,
+    /// Row above the header with the spreadsheet style letters of the columns
+    ColumnLetters,
     /// Regular row. Contains the row index (not the record number) and the row itself.
     Record(usize),
 }
@@ -883,16 +1726,21 @@ pub struct RowNumberLayout {
 #[derive(Debug, Clone)]
 pub struct ViewLayout {
     pub column_widths: Vec<u16>,
+    /// Lines taken up by the column names, more than one if they are wrapped
+    pub header_height: u16,
     pub row_heights: Vec<u16>,
     pub row_number_layout: RowNumberLayout,
 }
 
 impl ViewLayout {
-    pub fn num_rows_renderable(&self, frame_height: u16) -> usize {
+    /// Rows loaded since the layout was computed, whose heights are not known yet, are assumed to
+    /// take up one line each
+    pub fn num_rows_renderable(&self, frame_height: u16, num_rows: usize) -> usize {
         let mut out = 0;
         let mut remaining = frame_height;
-        for h in &self.row_heights {
-            if *h > remaining {
+        for i in 0..max(num_rows, self.row_heights.len()) {
+            let h = self.row_heights.get(i).copied().unwrap_or(1);
+            if h > remaining {
                 if remaining > 0 {
                     // Include partially rendered row
                     out += 1;
@@ -906,6 +1754,71 @@ impl ViewLayout {
     }
 }
 
+/// Heights of wrapped rows computed in previous frames, keyed by record number. Only valid for the
+/// column widths, wrap mode and columns it was computed with.
+#[derive(Debug, Default)]
+pub struct RowHeightsCache {
+    column_widths: Vec<u16>,
+    column_wraps: Vec<bool>,
+    is_word_wrap: bool,
+    whitespace_symbols: Option<WhitespaceSymbols>,
+    column_spacing: u16,
+    column_names: Vec<String>,
+    heights: HashMap<usize, u16>,
+}
+
+impl RowHeightsCache {
+    /// Upper bound of the number of cached rows to keep memory usage in check
+    const MAX_ENTRIES: usize = 10_000;
+
+    fn validate(
+        &mut self,
+        column_widths: &[u16],
+        column_wraps: &[bool],
+        is_word_wrap: bool,
+        whitespace_symbols: Option<&WhitespaceSymbols>,
+        column_spacing: u16,
+        headers: &[Header],
+    ) {
+        let is_valid = self.column_widths == column_widths
+            && self.column_wraps == column_wraps
+            && self.is_word_wrap == is_word_wrap
+            && self.whitespace_symbols.as_ref() == whitespace_symbols
+            && self.column_spacing == column_spacing
+            && self.column_names.len() == headers.len()
+            && self
+                .column_names
+                .iter()
+                .zip(headers)
+                .all(|(c, h)| *c == h.name);
+        if !is_valid {
+            self.column_widths = column_widths.to_vec();
+            self.column_wraps = column_wraps.to_vec();
+            self.is_word_wrap = is_word_wrap;
+            self.whitespace_symbols = whitespace_symbols.cloned();
+            self.column_spacing = column_spacing;
+            self.column_names = headers.iter().map(|h| h.name.clone()).collect();
+            self.heights.clear();
+        }
+    }
+
+    fn get(&self, record_num: usize) -> Option<u16> {
+        self.heights.get(&record_num).cloned()
+    }
+
+    fn insert(&mut self, record_num: usize, height: u16) {
+        if self.heights.len() >= Self::MAX_ENTRIES {
+            self.heights.clear();
+        }
+        self.heights.insert(record_num, height);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.heights.len()
+    }
+}
+
 pub enum BufferState {
     Disabled,
     Enabled(InputMode, Input),
@@ -913,26 +1826,42 @@ pub enum BufferState {
 
 pub enum FinderState {
     FinderInactive,
-    FinderActive(FinderActiveState),
+    FinderActive(Box<FinderActiveState>),
 }
 
 impl FinderState {
     pub fn from_finder(finder: &find::Finder, rows_view: &view::RowsView) -> FinderState {
         let active_state = FinderActiveState::new(finder, rows_view);
-        FinderState::FinderActive(active_state)
+        FinderState::FinderActive(Box::new(active_state))
+    }
+
+    /// State of a finder previewing the pattern being typed, which highlights matches among the
+    /// rows shown without filtering them
+    pub fn from_preview(finder: &find::Finder, rows_view: &view::RowsView) -> FinderState {
+        let mut active_state = FinderActiveState::new(finder, rows_view);
+        active_state.is_filter = false;
+        active_state.filter_context = 0;
+        FinderState::FinderActive(Box::new(active_state))
     }
 }
 
 pub struct FinderActiveState {
     find_complete: bool,
+    error: Option<String>,
     total_found: u64,
     cursor: Option<find::FinderCursor>,
     target: Regex,
+    duplicates: bool,
     column_index: Option<(usize, String)>,
     found_record: Option<find::FoundEntry>,
     selected_offset: Option<u64>,
     is_filter: bool,
     header_has_match: bool,
+    filter_context: u64,
+    visible_columns: Option<Range<usize>>,
+    ignore_accents: bool,
+    /// Descriptions of the filters this one is stacked on
+    stacked_targets: Vec<String>,
 }
 
 impl FinderActiveState {
@@ -941,9 +1870,11 @@ impl FinderActiveState {
         let total_count = finder.count() + if header_has_match { 1 } else { 0 };
         FinderActiveState {
             find_complete: finder.done(),
+            error: finder.error(),
             total_found: total_count as u64,
             cursor: finder.cursor(),
             target: finder.target(),
+            duplicates: finder.duplicates(),
             column_index: finder
                 .column_index()
                 .map(|i| (i, rows_view.get_column_name_from_local_index(i))),
@@ -951,13 +1882,56 @@ impl FinderActiveState {
             selected_offset: rows_view.selected_offset(),
             is_filter: rows_view.is_filter(),
             header_has_match,
+            filter_context: if rows_view.is_filter() && finder.sorter().is_none() {
+                rows_view.filter_context()
+            } else {
+                0
+            },
+            visible_columns: finder.visible_columns(),
+            ignore_accents: finder.ignore_accents(),
+            stacked_targets: finder
+                .conditions()
+                .iter()
+                .map(|c| target_description(c.target(), c.duplicates()))
+                .collect(),
+        }
+    }
+
+    fn is_match(&self, content: &str) -> bool {
+        if self.duplicates {
+            // Only highlight the duplicated values if they are in a single column
+            self.column_index.is_some()
+        } else if self.ignore_accents {
+            self.target.is_match(&normalize::strip_diacritics(content))
+        } else {
+            self.target.is_match(content)
+        }
+    }
+
+    /// Byte ranges of the matches in the original content, even if matching ignores accents
+    fn match_ranges(&self, content: &str) -> Vec<(usize, usize)> {
+        if self.duplicates {
+            vec![(0, content.len())]
+        } else if self.ignore_accents {
+            let (stripped, offsets) = normalize::strip_diacritics_with_offsets(content);
+            self.target
+                .find_iter(&stripped)
+                .map(|m| (offsets[m.start()], offsets[m.end()]))
+                .collect()
+        } else {
+            self.target
+                .find_iter(content)
+                .map(|m| (m.start(), m.end()))
+                .collect()
         }
     }
 
     fn status_line(&self) -> String {
         let plus_marker;
         let line;
-        if self.total_found == 0 {
+        if let Some(error) = &self.error {
+            line = format!("failed: {error}");
+        } else if self.total_found == 0 {
             if self.find_complete {
                 line = "Not found".to_owned();
             } else {
@@ -987,15 +1961,47 @@ impl FinderActiveState {
             } else {
                 cursor_str = "-".to_owned();
             }
-            line = format!("{cursor_str}/{}{plus_marker}", self.total_found);
+            if self.filter_context > 0 {
+                // The selected row may only be context, so there is no position among the matches
+                line = format!("{}{plus_marker} matches", self.total_found);
+            } else {
+                line = format!("{cursor_str}/{}{plus_marker}", self.total_found);
+            }
         }
         let action = if self.is_filter { "Filter" } else { "Find" };
         let target_column = self
             .column_index
             .as_ref()
             .map(|(_, name)| format!(" in {}", name))
+            .or_else(|| {
+                self.visible_columns
+                    .as_ref()
+                    .map(|r| format!(" in cols {}-{}", r.start + 1, r.end))
+            })
             .unwrap_or_default();
-        format!("[{action} \"{}\"{target_column}: {line}]", self.target)
+        let context = if self.filter_context > 0 {
+            format!(" ±{}", self.filter_context)
+        } else {
+            String::new()
+        };
+        let stacked: String = self
+            .stacked_targets
+            .iter()
+            .map(|target| format!("[Filter {target}] "))
+            .collect();
+        format!(
+            "{stacked}[{action} {}{target_column}{context}: {line}]",
+            target_description(&self.target, self.duplicates)
+        )
+    }
+}
+
+/// How a find or filter target is shown in the status line
+fn target_description(target: &Regex, duplicates: bool) -> String {
+    if duplicates {
+        "duplicates".to_string()
+    } else {
+        format!("\"{target}\"")
     }
 }
 
@@ -1008,7 +2014,7 @@ impl FilterColumnsState {
     pub fn from_rows_view(rows_view: &view::RowsView) -> Self {
         if let Some(columns_filter) = rows_view.columns_filter() {
             Self::Enabled(FilterColumnsInfo {
-                pattern: columns_filter.pattern(),
+                pattern: columns_filter.pattern().to_string(),
                 shown: columns_filter.num_filtered(),
                 total: columns_filter.num_original(),
                 disabled_because_no_match: columns_filter.disabled_because_no_match(),
@@ -1020,7 +2026,7 @@ impl FilterColumnsState {
 }
 
 pub struct FilterColumnsInfo {
-    pattern: Regex,
+    pattern: String,
     shown: usize,
     total: usize,
     disabled_because_no_match: bool,
@@ -1048,34 +2054,53 @@ impl SorterState {
     fn from_sorter(sorter: &sort::Sorter, sort_order: SortOrder) -> Self {
         Self::Enabled(SorterInfo {
             status: sorter.status(),
-            column_name: sorter.column_name().to_string(),
+            column_name: sorter.column_name().map(str::to_string),
             order: sort_order,
+            ignore_case: sorter.ignore_case(),
         })
     }
 }
 
 struct SorterInfo {
     status: sort::SorterStatus,
-    column_name: String,
+    /// None when reversing the record order
+    column_name: Option<String>,
     order: SortOrder,
+    ignore_case: bool,
 }
 
 impl SorterInfo {
     fn status_line(&self) -> String {
-        let prefix = format!("[Sorting by {}", self.column_name);
+        let prefix = match &self.column_name {
+            Some(column_name) => format!("[Sorting by {}", column_name),
+            None => "[Reversing".to_string(),
+        };
         match &self.status {
             sort::SorterStatus::Running => format!("{prefix}...]").to_string(),
             sort::SorterStatus::Error(error_msg) => {
                 format!("{} failed: {}]", prefix, error_msg).to_string()
             }
+            _ if self.column_name.is_none() => "[Reversed]".to_string(),
             _ => "".to_string(),
         }
     }
 }
 
+/// Part of the table drawn at some terminal coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablePosition {
+    /// Header of the column with the given index
+    Header(u64),
+    /// Cell with the given row and column indices
+    Cell(u64, u64),
+}
+
 struct BordersState {
     x_row_separator: u16,
+    /// Top of the header, including the column letters above it
+    y_header: u16,
     y_first_record: u16,
+    y_rows_bottom: u16,
 }
 
 pub struct DebugStats {
@@ -1151,12 +2176,20 @@ pub struct CsvTableState {
     pub more_cols_to_show: Option<bool>,
     filename: Option<String>,
     total_line_number: Option<(usize, bool)>,
+    pub indexing_progress: Option<f64>,
     total_cols: usize,
     pub debug_stats: DebugStats,
     buffer_content: BufferState,
+    /// Shown after the input being typed, e.g. how many columns a columns regex matches so far
+    pub buffer_hint: Option<String>,
+    /// Letter of the macro being recorded, if any
+    pub recording_macro: Option<char>,
     pub finder_state: FinderState,
     pub filter_columns_state: FilterColumnsState,
+    /// Record indices of rows shown only as context around filtered matches
+    pub context_rows: Arc<HashSet<u64>>,
     sorter_state: SorterState,
+    pub validation_status: Option<String>,
     borders_state: Option<BordersState>,
     // TODO: should probably be with BordersState
     col_ending_pos_x: u16,
@@ -1164,10 +2197,73 @@ pub struct CsvTableState {
     pub transient_message: Option<String>,
     pub echo_column: Option<String>,
     pub ignore_case: bool,
+    pub ignore_accents: bool,
     pub view_layout: Option<ViewLayout>,
     pub enable_line_wrap: bool,
     pub is_word_wrap: bool,
+    /// Origin indices of columns wrapped on their own, regardless of enable_line_wrap
+    pub wrapped_columns: HashSet<usize>,
+    /// Origin index of the column whose consecutive duplicate values are shown only once
+    pub collapsed_column: Option<usize>,
+    /// Whether the runs of equal values in the collapsed column at the top and at the bottom of
+    /// the rows go on beyond them, so that only part of the runs is counted
+    pub collapsed_runs_continue: (bool, bool),
+    /// Color of the values of columns, by origin index
+    pub column_colors: HashMap<usize, Color>,
+    /// Status of each record when viewing the diff of two files, empty otherwise
+    pub diff_rows: Vec<DiffRow>,
+    pub show_whitespace: bool,
+    /// Render without borders and separators
+    pub compact: bool,
+    pub border_style: BorderStyle,
+    /// Spaces between columns and after the row numbers
+    pub column_spacing: u16,
+    pub line_number_spacing: u16,
+    pub status_format: String,
+    pub whitespace_symbols: WhitespaceSymbols,
+    /// Show empty cells as the placeholder instead of blank
+    pub show_empty_placeholder: bool,
+    pub empty_placeholder: String,
+    /// Highlight the row and column of the selected cell
+    pub show_crosshair: bool,
+    pub theme: Theme,
+    pub truncation: Truncation,
+    /// Lines a wrapped row is cut at, ending with the truncation indicator
+    pub max_row_height: Option<u16>,
+    /// Lines the tallest row shown would take without max_row_height
+    pub tallest_row_height: u16,
+    /// Positions in the view of the first and last rows of the range selection
+    pub selected_range: Option<(u64, u64)>,
+    /// Size of the range selection and the sum and average of the selected column over it
+    pub range_summary: Option<String>,
+    pub focus_column: bool,
+    /// Highlight matches in every column, even if the finder only looks in one
+    pub highlight_all_columns: bool,
+    /// Show word and character counts of the selected cell and row in the status line
+    pub show_text_stats: bool,
+    /// Show the byte offset where the top row starts in the status line
+    pub show_byte_offset: bool,
+    pub top_byte_offset: Option<u64>,
+    pub show_scrollbars: bool,
+    /// Show the row numbers to the left of the rows
+    pub show_row_numbers: bool,
+    /// Number the rows from 0 instead of 1
+    pub zero_based_row_numbers: bool,
+    /// Character grouping the digits of numbers, left out when telling the type of a value
+    pub thousands_separator: Option<char>,
+    pub show_footer: bool,
+    /// Show the type of each column inferred from the first records below the header
+    pub show_column_types: bool,
+    /// Inferred type of each column in the order of the file, once first shown
+    pub column_types: Option<Vec<String>>,
+    /// Values of the units row of the file in the order of the file, pinned below the header
+    pub units: Option<Vec<String>>,
+    /// Show the spreadsheet style letters of the columns above the header
+    pub show_column_letters: bool,
+    pub match_mode: find::MatchMode,
+    pub footer: Option<Vec<String>>,
     pub column_width_overrides: ColumnWidthOverrides,
+    row_heights_cache: RowHeightsCache,
     pub cursor_xy: Option<(u16, u16)>,
     pub debug: String,
 }
@@ -1186,27 +2282,113 @@ impl CsvTableState {
             more_cols_to_show: None,
             filename,
             total_line_number: None,
+            indexing_progress: None,
             total_cols,
             debug_stats: DebugStats::new(),
             buffer_content: BufferState::Disabled,
+            buffer_hint: None,
+            recording_macro: None,
             finder_state: FinderState::FinderInactive,
             filter_columns_state: FilterColumnsState::Disabled,
+            context_rows: Arc::default(),
             sorter_state: SorterState::Disabled,
+            validation_status: None,
             borders_state: None,
             col_ending_pos_x: 0,
             selection: None,
             transient_message: None,
             echo_column: echo_column.clone(),
             ignore_case,
+            ignore_accents: false,
             view_layout: None,
             enable_line_wrap: false,
             is_word_wrap: false,
+            wrapped_columns: HashSet::new(),
+            collapsed_column: None,
+            collapsed_runs_continue: (false, false),
+            column_colors: HashMap::new(),
+            diff_rows: vec![],
+            show_whitespace: false,
+            compact: false,
+            border_style: BorderStyle::default(),
+            column_spacing: DEFAULT_SPACES_BETWEEN_COLUMNS,
+            line_number_spacing: DEFAULT_SPACES_AFTER_LINE_NUMBER,
+            status_format: DEFAULT_STATUS_FORMAT.to_string(),
+            whitespace_symbols: WhitespaceSymbols::default(),
+            show_empty_placeholder: false,
+            empty_placeholder: DEFAULT_EMPTY_PLACEHOLDER.to_string(),
+            show_crosshair: false,
+            theme: Theme::default(),
+            truncation: Truncation::default(),
+            max_row_height: None,
+            tallest_row_height: 1,
+            selected_range: None,
+            range_summary: None,
+            focus_column: false,
+            highlight_all_columns: false,
+            show_text_stats: false,
+            show_byte_offset: false,
+            top_byte_offset: None,
+            show_scrollbars: false,
+            show_row_numbers: true,
+            zero_based_row_numbers: false,
+            thousands_separator: None,
+            show_footer: false,
+            show_column_types: false,
+            column_types: None,
+            units: None,
+            show_column_letters: false,
+            match_mode: find::MatchMode::default(),
+            footer: None,
             column_width_overrides: ColumnWidthOverrides::new(),
+            row_heights_cache: RowHeightsCache::default(),
             cursor_xy: None,
             debug: "".into(),
         }
     }
 
+    /// Whether the row of column types takes up a line below the header
+    pub fn shows_column_types(&self) -> bool {
+        self.show_column_types && self.column_types.is_some()
+    }
+
+    /// Number shown for the row of the given 1-based record number
+    pub fn row_number(&self, record_num: usize) -> usize {
+        if self.zero_based_row_numbers {
+            record_num.saturating_sub(1)
+        } else {
+            record_num
+        }
+    }
+
+    /// Number of lines above the first record given the height of the column names, including the
+    /// borders around the header unless compact
+    pub fn header_area_height(&self, header_height: u16) -> u16 {
+        let height = header_height + self.num_rows_below_header();
+        if self.compact {
+            height
+        } else {
+            height + 2
+        }
+    }
+
+    /// Number of lines taken up besides the header by the column letters, units and column types
+    /// rows
+    pub fn num_rows_below_header(&self) -> u16 {
+        self.show_column_letters as u16
+            + self.units.is_some() as u16
+            + self.shows_column_types() as u16
+    }
+
+    /// Name of the file as given, None if reading from stdin
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    pub fn is_column_wrapped(&self, origin_index: usize) -> bool {
+        self.enable_line_wrap || self.wrapped_columns.contains(&origin_index)
+    }
+
     pub fn set_rows_offset(&mut self, offset: u64) {
         self.rows_offset = offset;
     }
@@ -1229,7 +2411,7 @@ impl CsvTableState {
     }
 
     pub fn has_more_cols_to_show(&self) -> bool {
-        self.more_cols_to_show.map_or(true, |v| v)
+        self.more_cols_to_show.unwrap_or(true)
     }
 
     pub fn set_total_line_number(&mut self, n: usize, is_approx: bool) {
@@ -1242,10 +2424,19 @@ impl CsvTableState {
 
     pub fn set_buffer(&mut self, mode: InputMode, input: Input) {
         self.buffer_content = BufferState::Enabled(mode, input);
+        self.buffer_hint = None;
     }
 
     pub fn reset_buffer(&mut self) {
         self.buffer_content = BufferState::Disabled;
+        self.buffer_hint = None;
+    }
+
+    pub fn buffer_mode(&self) -> Option<InputMode> {
+        match &self.buffer_content {
+            BufferState::Enabled(mode, _) => Some(*mode),
+            BufferState::Disabled => None,
+        }
     }
 
     pub fn line_number_and_spaces_width(&self) -> u16 {
@@ -1253,7 +2444,55 @@ impl CsvTableState {
             .as_ref()
             .map(|bs| bs.x_row_separator)
             .unwrap_or(0)
-            + NUM_SPACES_AFTER_LINE_NUMBER
+            + self.line_number_spacing
+    }
+
+    /// Resolve terminal coordinates to the header or cell drawn there. Indices are relative to the
+    /// current view. Returns None if the coordinates are outside the table.
+    pub fn table_position_at(&self, x: u16, y: u16) -> Option<TablePosition> {
+        let borders_state = self.borders_state.as_ref()?;
+        let view_layout = self.view_layout.as_ref()?;
+        if y >= borders_state.y_rows_bottom {
+            return None;
+        }
+
+        let mut column_index = None;
+        let mut x_column_end = view_layout.row_number_layout.width_with_spaces;
+        if x < x_column_end {
+            return None;
+        }
+        for (i, width) in view_layout
+            .column_widths
+            .iter()
+            .skip(self.cols_offset as usize)
+            .take(self.num_cols_rendered as usize)
+            .enumerate()
+        {
+            x_column_end = x_column_end.saturating_add(*width);
+            if x < x_column_end {
+                column_index = Some(i as u64);
+                break;
+            }
+        }
+        let column_index = column_index?;
+
+        if y < borders_state.y_first_record {
+            // Not the borders above and below the header
+            let y_header_end =
+                borders_state.y_header + view_layout.header_height + self.num_rows_below_header();
+            return (borders_state.y_header..y_header_end)
+                .contains(&y)
+                .then_some(TablePosition::Header(column_index));
+        }
+
+        let mut y_row_end = borders_state.y_first_record;
+        for (i, height) in view_layout.row_heights.iter().enumerate() {
+            y_row_end = y_row_end.saturating_add(*height);
+            if y < y_row_end {
+                return Some(TablePosition::Cell(i as u64, column_index));
+            }
+        }
+        None
     }
 
     pub fn update_sorter(&mut self, sorter: &Option<Arc<sort::Sorter>>, sort_order: SortOrder) {
@@ -1264,3 +2503,141 @@ impl CsvTableState {
         }
     }
 }
+
+/// Substitute `{name}` placeholders in the status line format. Unknown placeholders and stray
+/// braces are kept as is.
+fn render_status_format(format: &str, values: &HashMap<&'static str, String>) -> String {
+    let mut out = String::new();
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after
+            .find('}')
+            .and_then(|end| values.get(&after[..end]).map(|v| (end, v)))
+        {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_row_heights_cache() {
+        let headers = vec![Header {
+            name: "a".to_string(),
+            origin_index: 0,
+        }];
+        let rows = vec![
+            Row::new(1, vec!["short"]),
+            Row::new(2, vec!["a bit longer"]),
+        ];
+        let table = CsvTable::new(&headers, &rows);
+        let mut cache = RowHeightsCache::default();
+
+        let heights = table.get_row_heights(
+            10,
+            &rows,
+            &[9],
+            &[true],
+            false,
+            None,
+            DEFAULT_SPACES_BETWEEN_COLUMNS,
+            None,
+            &mut cache,
+        );
+        assert_eq!(heights, vec![1, 3]);
+        assert_eq!(cache.len(), 2);
+
+        // Cached heights are reused for the same widths
+        cache.insert(2, 5);
+        let heights = table.get_row_heights(
+            10,
+            &rows,
+            &[9],
+            &[true],
+            false,
+            None,
+            DEFAULT_SPACES_BETWEEN_COLUMNS,
+            None,
+            &mut cache,
+        );
+        assert_eq!(heights, vec![1, 5]);
+
+        // ... and recomputed when the widths change
+        let heights = table.get_row_heights(
+            10,
+            &rows,
+            &[20],
+            &[true],
+            false,
+            None,
+            DEFAULT_SPACES_BETWEEN_COLUMNS,
+            None,
+            &mut cache,
+        );
+        assert_eq!(heights, vec![1, 1]);
+
+        // ... or the spacing, which changes the width left for the content
+        table.get_row_heights(10, &rows, &[9], &[true], false, None, 4, None, &mut cache);
+        let heights =
+            table.get_row_heights(10, &rows, &[9], &[true], false, None, 0, None, &mut cache);
+        assert_eq!(heights, vec![1, 2]);
+
+        // Capped heights don't change what is cached
+        let heights = table.get_row_heights(
+            10,
+            &rows,
+            &[9],
+            &[true],
+            false,
+            None,
+            0,
+            Some(1),
+            &mut cache,
+        );
+        assert_eq!(heights, vec![1, 1]);
+        assert_eq!(cache.get(2), Some(2));
+    }
+
+    #[test]
+    fn test_cell_value_type() {
+        let from_value = |value| CellValueType::from_value(value, None);
+        assert_eq!(from_value(""), CellValueType::Empty);
+        assert_eq!(from_value("  "), CellValueType::Empty);
+        assert_eq!(from_value("-42"), CellValueType::Integer);
+        assert_eq!(from_value("3.14"), CellValueType::Float);
+        assert_eq!(from_value("1e5"), CellValueType::Float);
+        assert_eq!(from_value("N"), CellValueType::Text);
+        assert_eq!(from_value("1,234"), CellValueType::Text);
+        assert_eq!(from_value("inf"), CellValueType::Text);
+        assert_eq!(from_value("NaN"), CellValueType::Text);
+
+        let from_value = |value| CellValueType::from_value(value, Some('.'));
+        assert_eq!(from_value("1.234"), CellValueType::Integer);
+        assert_eq!(from_value("1.234,5"), CellValueType::Float);
+    }
+
+    #[test]
+    fn test_render_status_format() {
+        let values = HashMap::from([("row", "3".to_string()), ("filter", "".to_string())]);
+        assert_eq!(
+            render_status_format("Row {row}{filter} {unknown} {row", &values),
+            "Row 3 {unknown} {row"
+        );
+        assert_eq!(render_status_format("{{row}}", &values), "{3}");
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+/// Past and undone states for undo and redo, keeping at most `capacity` past states
+pub struct UndoStack<T> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    capacity: usize,
+}
+
+impl<T> UndoStack<T> {
+    pub fn new(capacity: usize) -> Self {
+        UndoStack {
+            undo: VecDeque::new(),
+            redo: vec![],
+            capacity,
+        }
+    }
+
+    /// Record the state before a change. Anything undone so far can no longer be redone.
+    pub fn push(&mut self, state: T) {
+        self.redo.clear();
+        self.undo.push_back(state);
+        if self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Step back to the previous state, given the current one to return to on redo
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    /// Step forward to the last undone state, given the current one to return to on undo
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut stack = UndoStack::new(10);
+        stack.push(0);
+        stack.push(1);
+        assert_eq!(stack.undo(2), Some(1));
+        assert_eq!(stack.undo(1), Some(0));
+        assert_eq!(stack.undo(0), None);
+        assert_eq!(stack.redo(0), Some(1));
+        assert_eq!(stack.redo(1), Some(2));
+        assert_eq!(stack.redo(2), None);
+
+        // A new change drops what was undone
+        assert_eq!(stack.undo(2), Some(1));
+        stack.push(1);
+        assert_eq!(stack.redo(3), None);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut stack = UndoStack::new(2);
+        for i in 0..5 {
+            stack.push(i);
+        }
+        assert_eq!(stack.undo(5), Some(4));
+        assert_eq!(stack.undo(4), Some(3));
+        assert_eq!(stack.undo(3), None);
+    }
+}
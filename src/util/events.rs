@@ -1,9 +1,12 @@
 use std::time::{Duration, Instant};
 
-use crossterm::event::{poll, read, Event, KeyEvent, KeyEventKind};
+use crossterm::event::{
+    poll, read, Event, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+};
 
 pub enum CsvlensEvent<I> {
     Input(I),
+    Mouse(MouseEvent),
     Tick,
 }
 
@@ -44,10 +47,19 @@ impl CsvlensEvents {
                 Event::Key(event) if event.kind == KeyEventKind::Press => {
                     Ok(CsvlensEvent::Input(event))
                 }
+                // Ignore mouse movements and other buttons to avoid redrawing needlessly
+                Event::Mouse(event)
+                    if matches!(
+                        event.kind,
+                        MouseEventKind::Down(MouseButton::Left)
+                            | MouseEventKind::ScrollUp
+                            | MouseEventKind::ScrollDown
+                    ) =>
+                {
+                    Ok(CsvlensEvent::Mouse(event))
+                }
                 _ => {
-                    let time_spent = now.elapsed();
-                    let rest = self.tick_rate.saturating_sub(time_spent);
-
+                    let rest = remaining_tick_rate(self.tick_rate, now, Instant::now());
                     Self { tick_rate: rest }.next()
                 }
             },
@@ -56,3 +68,35 @@ impl CsvlensEvents {
         }
     }
 }
+
+/// Time left in the current tick. Clocks are not guaranteed to be monotonic on all platforms (e.g.
+/// Windows), so never subtract instants or durations directly here.
+fn remaining_tick_rate(tick_rate: Duration, start: Instant, now: Instant) -> Duration {
+    tick_rate.saturating_sub(now.saturating_duration_since(start))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_remaining_tick_rate() {
+        let tick_rate = Duration::from_millis(250);
+        let start = Instant::now();
+        assert_eq!(
+            remaining_tick_rate(tick_rate, start, start + Duration::from_millis(100)),
+            Duration::from_millis(150)
+        );
+        // More time spent than the tick rate
+        assert_eq!(
+            remaining_tick_rate(tick_rate, start, start + Duration::from_secs(1)),
+            Duration::ZERO
+        );
+        // Clock went backwards
+        assert_eq!(
+            remaining_tick_rate(tick_rate, start + Duration::from_secs(1), start),
+            tick_rate
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use crate::csv;
+use crate::errors::CsvlensResult;
+use crate::theme::Theme;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Paragraph, StatefulWidget, Widget},
+};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::{self};
+
+/// Maximum number of issues kept for display. Scanning continues to count the rest.
+const MAX_ISSUES: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    /// Record number as shown in the row number column
+    pub record_num: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidatorStatus {
+    Running,
+    Finished,
+    Error(String),
+}
+
+#[derive(Debug)]
+pub struct Validator {
+    internal: Arc<Mutex<ValidatorInternalState>>,
+}
+
+impl Validator {
+    pub fn new(config: Arc<csv::CsvConfig>) -> Self {
+        let internal = ValidatorInternalState::init(config);
+        Validator { internal }
+    }
+
+    pub fn status(&self) -> ValidatorStatus {
+        (self.internal.lock().unwrap()).status.clone()
+    }
+
+    /// Total number of issues found so far, including those not kept for display
+    pub fn num_issues(&self) -> usize {
+        (self.internal.lock().unwrap()).num_issues
+    }
+
+    pub fn get_issue(&self, index: usize) -> Option<ValidationIssue> {
+        (self.internal.lock().unwrap()).issues.get(index).cloned()
+    }
+
+    pub fn get_issues(&self, offset: usize, num_issues: usize) -> Vec<ValidationIssue> {
+        let m_guard = self.internal.lock().unwrap();
+        m_guard
+            .issues
+            .iter()
+            .skip(offset)
+            .take(num_issues)
+            .cloned()
+            .collect()
+    }
+
+    pub fn num_issues_kept(&self) -> usize {
+        (self.internal.lock().unwrap()).issues.len()
+    }
+
+    /// One line summary for the status bar
+    pub fn status_line(&self) -> String {
+        match self.status() {
+            ValidatorStatus::Running => "[Validating...]".to_string(),
+            ValidatorStatus::Finished => match self.num_issues() {
+                0 => "[Valid]".to_string(),
+                1 => "[1 invalid row]".to_string(),
+                n => format!("[{n} invalid rows]"),
+            },
+            ValidatorStatus::Error(e) => format!("[Validation error: {e}]"),
+        }
+    }
+
+    pub fn terminate(&self) {
+        let mut m = self.internal.lock().unwrap();
+        m.should_terminate = true;
+    }
+
+    /// Block until the background thread is done
+    #[cfg(test)]
+    pub fn wait_internal(&self) {
+        loop {
+            if self.internal.lock().unwrap().done {
+                break;
+            }
+            thread::sleep(core::time::Duration::from_millis(100));
+        }
+    }
+}
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
+
+#[derive(Debug)]
+struct ValidatorInternalState {
+    issues: Vec<ValidationIssue>,
+    num_issues: usize,
+    status: ValidatorStatus,
+    should_terminate: bool,
+    done: bool,
+}
+
+impl ValidatorInternalState {
+    fn init(config: Arc<csv::CsvConfig>) -> Arc<Mutex<ValidatorInternalState>> {
+        let internal = ValidatorInternalState {
+            issues: vec![],
+            num_issues: 0,
+            status: ValidatorStatus::Running,
+            should_terminate: false,
+            done: false,
+        };
+
+        let m_state = Arc::new(Mutex::new(internal));
+
+        let _m = m_state.clone();
+
+        let _handle = thread::spawn(move || {
+            fn run(
+                m: &Arc<Mutex<ValidatorInternalState>>,
+                config: Arc<csv::CsvConfig>,
+            ) -> CsvlensResult<()> {
+                let mut reader = config.new_reader()?;
+                let num_header_fields = reader.byte_headers()?.len();
+                for (row_index, record) in reader.records().enumerate() {
+                    let message = match record {
+                        Ok(record) if record.len() == num_header_fields => None,
+                        Ok(record) => Some(format!(
+                            "Expected {} fields, found {}",
+                            num_header_fields,
+                            record.len()
+                        )),
+                        Err(e) if e.is_io_error() => return Err(e.into()),
+                        Err(e) => Some(e.to_string()),
+                    };
+                    let mut m = m.lock().unwrap();
+                    if m.should_terminate {
+                        break;
+                    }
+                    if let Some(message) = message {
+                        m.num_issues += 1;
+                        if m.issues.len() < MAX_ISSUES {
+                            m.issues.push(ValidationIssue {
+                                record_num: row_index + 1,
+                                message,
+                            });
+                        }
+                    }
+                }
+                Ok(())
+            }
+
+            let res = run(&_m, config);
+
+            let mut m = _m.lock().unwrap();
+            m.status = match res {
+                Ok(_) => ValidatorStatus::Finished,
+                Err(e) => ValidatorStatus::Error(e.to_string()),
+            };
+            m.done = true;
+        });
+
+        m_state
+    }
+}
+
+pub struct ValidationPage<'a> {
+    validator: &'a Validator,
+    border_type: BorderType,
+    theme: &'a Theme,
+}
+
+impl<'a> ValidationPage<'a> {
+    pub fn new(validator: &'a Validator, border_type: BorderType, theme: &'a Theme) -> Self {
+        ValidationPage {
+            validator,
+            border_type,
+            theme,
+        }
+    }
+}
+
+pub struct ValidationPageState {
+    active: bool,
+    selected: usize,
+    offset: usize,
+}
+
+impl ValidationPageState {
+    pub fn new() -> Self {
+        ValidationPageState {
+            active: false,
+            selected: 0,
+            offset: 0,
+        }
+    }
+
+    pub fn activate(&mut self) -> &Self {
+        self.active = true;
+        self.selected = 0;
+        self.offset = 0;
+        self
+    }
+
+    pub fn deactivate(&mut self) -> &Self {
+        self.active = false;
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select_previous(&mut self) -> &Self {
+        self.selected = self.selected.saturating_sub(1);
+        self
+    }
+
+    pub fn select_next(&mut self, num_issues: usize) -> &Self {
+        if self.selected + 1 < num_issues {
+            self.selected += 1;
+        }
+        self
+    }
+}
+
+impl StatefulWidget for ValidationPage<'_> {
+    type State = ValidationPageState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let summary = match self.validator.status() {
+            ValidatorStatus::Running => format!(
+                "Validating... {} invalid rows so far.",
+                self.validator.num_issues()
+            ),
+            ValidatorStatus::Finished if self.validator.num_issues() == 0 => {
+                "All rows have the same number of fields as the header.".to_string()
+            }
+            ValidatorStatus::Finished => {
+                let num_kept = self.validator.num_issues_kept();
+                let num_issues = self.validator.num_issues();
+                if num_kept < num_issues {
+                    format!("{num_issues} invalid rows, showing the first {num_kept}.")
+                } else {
+                    format!("{num_issues} invalid rows.")
+                }
+            }
+            ValidatorStatus::Error(e) => format!("Validation failed: {e}"),
+        };
+        let mut text = vec![
+            Line::from(Span::styled(summary, self.theme.heading)),
+            Line::from("Enter: jump to the selected row, q: close"),
+            Line::from(""),
+        ];
+
+        // Keep the selected issue in view. Minus 2 to account for borders.
+        let list_height = (area.height.saturating_sub(2) as usize).saturating_sub(text.len());
+        if state.selected < state.offset {
+            state.offset = state.selected;
+        } else if list_height > 0 && state.selected >= state.offset + list_height {
+            state.offset = state.selected + 1 - list_height;
+        }
+
+        let issues = self.validator.get_issues(state.offset, list_height);
+        for (i, issue) in issues.iter().enumerate() {
+            let line = format!("Row {}: {}", issue.record_num, issue.message);
+            let style = if state.offset + i == state.selected {
+                self.theme.selected
+            } else {
+                Style::default()
+            };
+            text.push(Line::from(Span::styled(line, style)));
+        }
+
+        let paragraph = Paragraph::new(text).block(
+            Block::default()
+                .title("Validation")
+                .borders(Borders::ALL)
+                .border_type(self.border_type),
+        );
+
+        paragraph.render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_irregular() {
+        let config = Arc::new(csv::CsvConfig::new(
+            "tests/data/irregular_more_fields.csv",
+            b',',
+            false,
+        ));
+        let validator = Validator::new(config);
+        validator.wait_internal();
+        assert_eq!(validator.status(), ValidatorStatus::Finished);
+        assert_eq!(
+            validator.get_issues(0, 10),
+            vec![
+                ValidationIssue {
+                    record_num: 1,
+                    message: "Expected 2 fields, found 3".to_string(),
+                },
+                ValidationIssue {
+                    record_num: 2,
+                    message: "Expected 2 fields, found 4".to_string(),
+                },
+            ]
+        );
+        assert_eq!(validator.status_line(), "[2 invalid rows]");
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let config = Arc::new(csv::CsvConfig::from_source(
+            csv::CsvSource::Bytes(b"a,b\n1,2\n\xff,3\n4,5\n".to_vec().into()),
+            b',',
+            false,
+        ));
+        let validator = Validator::new(config);
+        validator.wait_internal();
+        assert_eq!(validator.num_issues(), 1);
+        assert_eq!(validator.get_issue(0).unwrap().record_num, 2);
+        assert_eq!(validator.status_line(), "[1 invalid row]");
+    }
+}
@@ -4,29 +4,124 @@ use crate::errors::CsvlensResult;
 use crate::find;
 use crate::input::Control;
 use crate::sort::{SortOrder, Sorter};
+use ::csv::Position;
 
-use std::cmp::min;
+use std::cmp::{max, min};
+use std::collections::HashSet;
+use std::fmt;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 struct RowsFilter {
     indices: Vec<u64>,
+    /// Whether each of the indices is only shown as context around a match
+    context_flags: Vec<bool>,
     total: usize,
     max_index: Option<u64>,
+    /// All shown record indices including context rows, if there are any
+    with_context: Option<Arc<WithContext>>,
+}
+
+/// Found record indices expanded with their context rows, along with what they were expanded
+/// from, so that they are only expanded again once that changes
+struct WithContext {
+    indices: Vec<(u64, bool)>,
+    finder_id: usize,
+    count: usize,
+    context: u64,
+    num_records: Option<u64>,
+}
+
+impl WithContext {
+    fn new(finder: &find::Finder, context: u64, num_records: Option<u64>) -> Self {
+        let count = finder.count();
+        let founds = finder.get_subset_found(0, count);
+        WithContext {
+            indices: expand_with_context(&founds, context, num_records),
+            finder_id: finder.id(),
+            count,
+            context,
+            num_records,
+        }
+    }
+
+    fn is_current(&self, finder: &find::Finder, context: u64, num_records: Option<u64>) -> bool {
+        self.finder_id == finder.id()
+            && self.count == finder.count()
+            && self.context == context
+            && self.num_records == num_records
+    }
 }
 
 impl RowsFilter {
-    fn new(finder: &find::Finder, rows_from: u64, num_rows: u64) -> RowsFilter {
+    /// The expansion with context rows of the previous filter is reused if still current
+    fn new(
+        finder: &find::Finder,
+        rows_from: u64,
+        num_rows: u64,
+        context: u64,
+        num_records: Option<u64>,
+        previous: Option<&RowsFilter>,
+    ) -> RowsFilter {
+        // Context rows are neighbours in the file, which would not make sense in sorted order
+        if context > 0 && finder.sorter().is_none() {
+            let with_context = previous
+                .and_then(|filter| filter.with_context.clone())
+                .filter(|w| w.is_current(finder, context, num_records))
+                .unwrap_or_else(|| Arc::new(WithContext::new(finder, context, num_records)));
+            let indices = &with_context.indices;
+            let start = min(rows_from as usize, indices.len());
+            let end = min(start.saturating_add(num_rows as usize), indices.len());
+            let (indices, context_flags) = indices[start..end].iter().cloned().unzip();
+            return RowsFilter {
+                indices,
+                context_flags,
+                total: with_context.indices.len(),
+                max_index: with_context.indices.last().map(|(i, _)| *i),
+                with_context: Some(with_context),
+            };
+        }
+
         let (total, max_index) = finder.count_and_max_row_index();
         let indices = finder.get_subset_found(rows_from as usize, num_rows as usize);
         RowsFilter {
+            context_flags: vec![false; indices.len()],
             indices,
             total,
             max_index,
+            with_context: None,
         }
     }
 }
 
+/// Add up to `context` records before and after each of the found record indices, which must be
+/// sorted. Each resulting index is paired with whether it is only there as context.
+fn expand_with_context(founds: &[u64], context: u64, num_records: Option<u64>) -> Vec<(u64, bool)> {
+    let mut out: Vec<(u64, bool)> = vec![];
+    for &found in founds {
+        let next_index = out.last().map_or(0, |(i, _)| i + 1);
+        if found < next_index {
+            // Already included as context after a previous match
+            let pos = out.len() - (next_index - found) as usize;
+            out[pos].1 = false;
+        } else {
+            for i in max(found.saturating_sub(context), next_index)..found {
+                out.push((i, true));
+            }
+            out.push((found, false));
+        }
+        let next_index = out.last().map_or(0, |(i, _)| i + 1);
+        let mut end = found.saturating_add(context).saturating_add(1);
+        if let Some(n) = num_records {
+            end = min(end, n);
+        }
+        for i in next_index..end {
+            out.push((i, true));
+        }
+    }
+    out
+}
+
 #[derive(Clone)]
 pub struct SelectionDimension {
     index: Option<u64>,
@@ -181,6 +276,29 @@ impl Selection {
     }
 }
 
+/// Details of the current selection, passed to `CsvlensOptions::on_selection_change`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionEvent {
+    /// Record number of the selected row, if a row or cell is selected
+    pub record_num: Option<usize>,
+    /// Name of the selected column, if a column or cell is selected
+    pub column_name: Option<String>,
+    /// Value of the selected cell, if a cell is selected
+    pub value: Option<String>,
+}
+
+/// Callback invoked whenever the selected row, column or cell changes.
+///
+/// The callback runs on the main loop while csvlens owns the terminal. It should return quickly
+/// and must not panic: a panic is not caught and would bring down csvlens like any other panic.
+pub struct SelectionCallback(pub Box<dyn FnMut(SelectionEvent)>);
+
+impl fmt::Debug for SelectionCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SelectionCallback")
+    }
+}
+
 #[derive(Debug)]
 pub struct Header {
     pub name: String,
@@ -199,9 +317,13 @@ pub struct RowsView {
     headers: Vec<Header>,
     num_rows: u64,
     num_rows_rendered: u64,
+    scrolloff: u64,
     rows_from: u64,
     cols_offset: u64,
     filter: Option<RowsFilter>,
+    filter_context: u64,
+    /// Record indices of the shown rows that are only there as context around a match
+    context_rows: Arc<HashSet<u64>>,
     columns_filter: Option<Arc<ColumnsFilter>>,
     sorter: Option<Arc<Sorter>>,
     sort_order: SortOrder,
@@ -220,9 +342,12 @@ impl RowsView {
             headers,
             num_rows,
             num_rows_rendered: num_rows,
+            scrolloff: 0,
             rows_from,
             cols_offset: 0,
             filter: None,
+            filter_context: 0,
+            context_rows: Arc::default(),
             columns_filter: None,
             sorter: None,
             sort_order: SortOrder::Ascending,
@@ -244,6 +369,12 @@ impl RowsView {
         &self.rows
     }
 
+    /// Rows that fit in the last rendered frame. This can be fewer than rows() when line wrap is
+    /// enabled.
+    pub fn visible_rows(&self) -> &[Row] {
+        &self.rows[..min(self.rows.len(), self.num_rows_rendered as usize)]
+    }
+
     pub fn get_column_name_from_global_index(&self, column_index: usize) -> String {
         self.raw_headers()
             .get(column_index)
@@ -293,13 +424,35 @@ impl RowsView {
         None
     }
 
-    pub fn get_row_value(&self) -> Option<(usize, String)> {
-        if let Some(row_index) = self.selection.row.index() {
-            if let Some(row) = self.rows().get(row_index as usize) {
-                return Some((row.record_num, row.fields.join("\t")));
-            }
+    /// Get the name of the selected column, if the selection type is Column or Cell.
+    pub fn get_column_name_from_selection(&self) -> Option<String> {
+        self.selection.column.index().and_then(|column_index| {
+            self.headers()
+                .get(column_index.saturating_add(self.cols_offset()) as usize)
+                .map(|header| header.name.clone())
+        })
+    }
+
+    /// Build a SelectionEvent describing the current selection
+    pub fn selection_event(&self) -> SelectionEvent {
+        SelectionEvent {
+            record_num: self.selected_record_num(),
+            column_name: self.get_column_name_from_selection(),
+            value: self.get_cell_value_from_selection(),
         }
-        None
+    }
+
+    /// The currently selected row, if any
+    pub fn selected_row(&self) -> Option<&Row> {
+        self.selection
+            .row
+            .index()
+            .and_then(|i| self.rows().get(i as usize))
+    }
+
+    /// Record number of the currently selected row, if any
+    pub fn selected_record_num(&self) -> Option<usize> {
+        self.selected_row().map(|row| row.record_num)
     }
 
     pub fn num_rows(&self) -> u64 {
@@ -321,15 +474,46 @@ impl RowsView {
         self.selection.row.set_bound(num_rows_rendered);
     }
 
+    /// Set the minimum number of rows to keep visible above and below the selected row when
+    /// scrolling
+    pub fn set_scrolloff(&mut self, scrolloff: u64) {
+        self.scrolloff = scrolloff;
+    }
+
+    fn effective_scrolloff(&self) -> u64 {
+        // Margins from the top and bottom must not overlap
+        min(self.scrolloff, self.num_rows_rendered.saturating_sub(1) / 2)
+    }
+
     pub fn set_filter(&mut self, finder: &find::Finder) -> CsvlensResult<()> {
-        let filter = RowsFilter::new(finder, self.rows_from, self.num_rows);
+        let filter = RowsFilter::new(
+            finder,
+            self.rows_from,
+            self.num_rows,
+            self.filter_context,
+            self.reader.get_total_line_numbers().map(|n| n as u64),
+            self.filter.as_ref(),
+        );
         // only need to reload rows if the currently shown indices changed
         let mut needs_reload = true;
+        let mut context_changed = true;
         if let Some(cur_filter) = &self.filter {
             if cur_filter.indices == filter.indices {
                 needs_reload = false;
+                context_changed = cur_filter.context_flags != filter.context_flags;
             }
         }
+        if context_changed {
+            self.context_rows = Arc::new(
+                filter
+                    .indices
+                    .iter()
+                    .zip(filter.context_flags.iter())
+                    .filter(|(_, is_context)| **is_context)
+                    .map(|(i, _)| *i)
+                    .collect(),
+            );
+        }
         // but always need to update filter because it holds other states such
         // as total count
         self.filter = Some(filter);
@@ -344,11 +528,38 @@ impl RowsView {
         self.filter.is_some()
     }
 
+    /// Number of rows shown before and after each match when filtering
+    pub fn filter_context(&self) -> u64 {
+        self.filter_context
+    }
+
+    /// Takes effect the next time the filter is set
+    pub fn set_filter_context(&mut self, filter_context: u64) {
+        self.filter_context = filter_context;
+    }
+
+    /// Record indices of the shown rows that are only there as context around a match
+    pub fn context_rows(&self) -> Arc<HashSet<u64>> {
+        self.context_rows.clone()
+    }
+
+    /// Position of the given record index among the filtered rows including context rows, if
+    /// there are any
+    pub fn get_position_with_context(&self, record_index: u64) -> Option<u64> {
+        let with_context = self.filter.as_ref()?.with_context.as_ref()?;
+        with_context
+            .indices
+            .binary_search_by_key(&record_index, |(i, _)| *i)
+            .ok()
+            .map(|pos| pos as u64)
+    }
+
     pub fn reset_filter(&mut self) -> CsvlensResult<()> {
         if !self.is_filter() {
             return Ok(());
         }
         self.filter = None;
+        self.context_rows = Arc::default();
         self.do_get_rows()
     }
 
@@ -415,6 +626,17 @@ impl RowsView {
         Ok(())
     }
 
+    /// Byte offset in the file where the top row starts
+    pub fn top_byte_offset(&mut self) -> CsvlensResult<Option<u64>> {
+        match self.rows.first() {
+            Some(row) => {
+                let record_index = row.record_num.saturating_sub(1) as u64;
+                self.reader.byte_at_record_index(record_index)
+            }
+            None => Ok(None),
+        }
+    }
+
     pub fn rows_from(&self) -> u64 {
         self.rows_from
     }
@@ -433,6 +655,18 @@ impl RowsView {
         Ok(())
     }
 
+    /// Scroll such that the row at the given order (0-based position in the current view, after
+    /// any filter and sorter) is selected, as far as the bottom of the data allows.
+    pub fn scroll_to_row_order(&mut self, row_order: u64) -> CsvlensResult<()> {
+        self.set_rows_from(row_order)?;
+        // rows_from could have been clamped near the bottom
+        let local_index = row_order.saturating_sub(self.rows_from);
+        if self.selection.row.index().is_some() {
+            self.selection.row.set_index(local_index);
+        }
+        Ok(())
+    }
+
     /// Offset of the first column to show. All columns are still read into Row
     /// (per ColumnsFilter if any).
     pub fn cols_offset(&self) -> u64 {
@@ -462,6 +696,94 @@ impl RowsView {
         self.reader.get_last_indexed_line_number()
     }
 
+    /// Positions of records indexed by the reader, only available once indexing is done
+    pub fn get_complete_pos_table(&self) -> Option<Vec<Position>> {
+        self.reader.get_total_line_numbers()?;
+        Some(self.reader.get_pos_table())
+    }
+
+    pub fn get_indexing_progress(&self) -> Option<f64> {
+        self.reader.get_indexing_progress()
+    }
+
+    /// Rows at the given positions in the current view, after any filter and sorter, like the
+    /// shown rows but with all of their fields. The finder must be the one the filter is set with.
+    pub fn get_rows_in_view_order(
+        &mut self,
+        finder: Option<&find::Finder>,
+        rows_from: u64,
+        num_rows: u64,
+    ) -> CsvlensResult<Vec<Row>> {
+        let indices = if let (Some(_), Some(finder)) = (&self.filter, finder) {
+            let filter = RowsFilter::new(
+                finder,
+                rows_from,
+                num_rows,
+                self.filter_context,
+                self.reader.get_total_line_numbers().map(|n| n as u64),
+                self.filter.as_ref(),
+            );
+            if rows_from >= filter.total as u64 {
+                return Ok(vec![]);
+            }
+            Some(filter.indices)
+        } else if let Some(sorter) = &self.sorter {
+            sorter.get_sorted_indices(rows_from, num_rows, self.sort_order)
+        } else {
+            None
+        };
+        let rows = match indices {
+            Some(indices) => self.reader.get_rows_for_indices(&indices)?.0,
+            None => self.reader.get_rows(rows_from, num_rows)?.0,
+        };
+        Ok(rows)
+    }
+
+    /// Record indices of the rows at the given positions in the current view, like
+    /// get_rows_in_view_order() but without reading the rows. None until the rows are done being
+    /// filtered and sorted.
+    pub fn get_record_indices_in_view_order(
+        &self,
+        finder: Option<&find::Finder>,
+        rows_from: u64,
+        num_rows: u64,
+    ) -> Option<Vec<u64>> {
+        if let (Some(_), Some(finder)) = (&self.filter, finder) {
+            if !finder.done() {
+                return None;
+            }
+            let filter = RowsFilter::new(
+                finder,
+                rows_from,
+                num_rows,
+                self.filter_context,
+                self.reader.get_total_line_numbers().map(|n| n as u64),
+                self.filter.as_ref(),
+            );
+            if rows_from >= filter.total as u64 {
+                return Some(vec![]);
+            }
+            return Some(filter.indices);
+        }
+        if let Some(sorter) = &self.sorter {
+            return sorter.get_sorted_indices(rows_from, num_rows, self.sort_order);
+        }
+        Some((rows_from..rows_from.saturating_add(num_rows)).collect())
+    }
+
+    /// Records in file order with all of their fields, regardless of any filter or sort
+    pub fn get_records(&mut self, rows_from: u64, num_rows: u64) -> CsvlensResult<Vec<Row>> {
+        Ok(self.reader.get_rows(rows_from, num_rows)?.0)
+    }
+
+    pub fn get_next_ragged_record(&self, record_index: u64) -> Option<u64> {
+        self.reader.get_next_ragged_record(record_index)
+    }
+
+    pub fn get_prev_ragged_record(&self, record_index: u64) -> Option<u64> {
+        self.reader.get_prev_ragged_record(record_index)
+    }
+
     pub fn in_view(&self, row_index: u64) -> bool {
         let last_row = self.rows_from().saturating_add(self.num_rows());
         if row_index >= self.rows_from() && row_index < last_row {
@@ -474,8 +796,14 @@ impl RowsView {
         match control {
             Control::ScrollDown => {
                 if let Some(i) = self.selection.row.index() {
-                    if i >= self.num_rows_rendered.saturating_sub(1) {
+                    let scrolloff = self.effective_scrolloff();
+                    if i.saturating_add(scrolloff) >= self.num_rows_rendered.saturating_sub(1) {
+                        let rows_from = self.rows_from;
                         self.increase_rows_from(1)?;
+                        if self.rows_from == rows_from {
+                            // Already at the bottom, move within the margin instead
+                            self.selection.row.select_next();
+                        }
                     } else {
                         self.selection.row.select_next();
                     }
@@ -493,7 +821,7 @@ impl RowsView {
             }
             Control::ScrollUp => {
                 if let Some(i) = self.selection.row.index() {
-                    if i == 0 {
+                    if i <= self.effective_scrolloff() && self.rows_from > 0 {
                         self.decrease_rows_from(1)?;
                     } else {
                         self.selection.row.select_previous();
@@ -532,12 +860,62 @@ impl RowsView {
                 self.set_rows_from(rows_from)?;
                 self.selection.row.select_first()
             }
+            Control::ScrollToFromEnd(n) => {
+                if let Some(total) = self.get_total_line_numbers_indexed() {
+                    let row_order = (total as u64).saturating_sub(*n as u64);
+                    self.set_rows_from(row_order)?;
+                    // rows_from could have been clamped near the bottom
+                    self.selection
+                        .row
+                        .set_index(row_order.saturating_sub(self.rows_from));
+                }
+            }
+            // Rows are only shown in file order when neither filtered nor sorted
+            Control::GotoByte(byte) if self.filter.is_none() && self.sorter.is_none() => {
+                if let Some(record_index) = self.reader.record_index_at_byte(*byte)? {
+                    let mut rows_from = record_index;
+                    if let Some(n) = self.bottom_rows_from() {
+                        rows_from = min(rows_from, n);
+                    }
+                    self.set_rows_from(rows_from)?;
+                    self.selection
+                        .row
+                        .set_index(record_index.saturating_sub(self.rows_from));
+                }
+            }
+            Control::CenterSelection => {
+                self.scroll_selection_to(self.num_rows_rendered.saturating_sub(1) / 2)?;
+            }
+            Control::SelectionToTop => {
+                self.scroll_selection_to(0)?;
+            }
+            Control::SelectionToBottom => {
+                self.scroll_selection_to(self.num_rows_rendered.saturating_sub(1))?;
+            }
             _ => {}
         }
         Ok(())
     }
 
-    fn get_total_line_numbers_indexed(&self) -> Option<usize> {
+    /// Scroll such that the currently selected row ends up at the given local index, as far as
+    /// the top and bottom of the data allow. The selected record stays the same.
+    ///
+    /// With line wrap enabled, num_rows_rendered is based on the row heights of the current
+    /// frame, so the final position is only approximate.
+    fn scroll_selection_to(&mut self, local_index: u64) -> CsvlensResult<()> {
+        if let Some(i) = self.selection.row.index() {
+            let selected_row_order = self.rows_from.saturating_add(i);
+            self.set_rows_from(selected_row_order.saturating_sub(local_index))?;
+            self.selection
+                .row
+                .set_index(selected_row_order.saturating_sub(self.rows_from));
+        }
+        Ok(())
+    }
+
+    /// Number of rows that can be counted from the end, possibly only those indexed or found so
+    /// far. None if too little is known to go to a row from the end.
+    pub fn get_total_line_numbers_indexed(&self) -> Option<usize> {
         if let Some(max_line_number) = self
             .reader
             .get_total_line_numbers()
@@ -603,7 +981,7 @@ impl RowsView {
         } else {
             self.reader.get_rows(self.rows_from, self.num_rows)?
         };
-        let elapsed = start.elapsed();
+        let elapsed = Instant::now().saturating_duration_since(start);
         if let Some(columns_filter) = &self.columns_filter {
             rows = Self::subset_columns(&rows, columns_filter.indices());
         }
@@ -617,7 +995,7 @@ impl RowsView {
         Ok(())
     }
 
-    #[cfg(test)]
+    /// Block until the background thread is done
     pub fn wait_internal(&self) {
         self.reader.wait_internal()
     }
@@ -0,0 +1,129 @@
+use crate::errors::{CsvlensError, CsvlensResult};
+
+use std::borrow::Cow;
+
+/// Characters shown in place of whitespace and control characters when they are made visible
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WhitespaceSymbols {
+    /// Shown for spaces at the end of a line
+    pub trailing_space: char,
+    pub tab: char,
+    /// Shown at the end of a line that is followed by a line break
+    pub newline: char,
+    /// Shown for any other control character, e.g. carriage returns
+    pub control: char,
+}
+
+impl Default for WhitespaceSymbols {
+    fn default() -> Self {
+        WhitespaceSymbols {
+            trailing_space: '·',
+            tab: '→',
+            newline: '↵',
+            control: '¤',
+        }
+    }
+}
+
+impl WhitespaceSymbols {
+    /// Parse the symbols from a string of exactly four characters, in the order of trailing space,
+    /// tab, newline and other control characters
+    pub fn from_arg(symbols_arg: &Option<String>) -> CsvlensResult<Self> {
+        let Some(s) = symbols_arg else {
+            return Ok(WhitespaceSymbols::default());
+        };
+        let chars: Vec<char> = s.chars().collect();
+        match chars[..] {
+            [trailing_space, tab, newline, control] => Ok(WhitespaceSymbols {
+                trailing_space,
+                tab,
+                newline,
+                control,
+            }),
+            _ => Err(CsvlensError::WhitespaceSymbolsLength(s.clone())),
+        }
+    }
+
+    /// Replace whitespace and control characters in the content of a cell with visible symbols.
+    /// Line breaks are kept so that line wrapping still works.
+    pub fn show<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        match self.show_with_offsets(content) {
+            Some((shown, _)) => Cow::Owned(shown),
+            None => Cow::Borrowed(content),
+        }
+    }
+
+    /// Like `show`, but also returns the byte offset in the shown string of the character each
+    /// byte of `content` is shown as, followed by the length of the shown string. None if there is
+    /// nothing to replace.
+    pub fn show_with_offsets(&self, content: &str) -> Option<(String, Vec<usize>)> {
+        let needs_replacing = content.ends_with(' ')
+            || content.contains(" \n")
+            || content.chars().any(|c| c.is_control());
+        if !needs_replacing {
+            return None;
+        }
+        let mut out = String::with_capacity(content.len());
+        let mut offsets = Vec::with_capacity(content.len() + 1);
+        for (i, line) in content.split('\n').enumerate() {
+            if i > 0 {
+                offsets.push(out.len());
+                out.push(self.newline);
+                out.push('\n');
+            }
+            let trimmed = line.trim_end_matches(' ');
+            for c in trimmed.chars() {
+                offsets.resize(offsets.len() + c.len_utf8(), out.len());
+                out.push(match c {
+                    '\t' => self.tab,
+                    c if c.is_control() => self.control,
+                    c => c,
+                });
+            }
+            for _ in 0..(line.len() - trimmed.len()) {
+                offsets.push(out.len());
+                out.push(self.trailing_space);
+            }
+        }
+        offsets.push(out.len());
+        Some((out, offsets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_show() {
+        let symbols = WhitespaceSymbols::default();
+        assert!(matches!(symbols.show("a b"), Cow::Borrowed("a b")));
+        assert_eq!(symbols.show("a b  "), "a b··");
+        assert_eq!(symbols.show("a\tb"), "a→b");
+        assert_eq!(symbols.show("a \r\nb"), "a ¤↵\nb");
+        assert_eq!(symbols.show("a  \nb\u{0}"), "a··↵\nb¤");
+    }
+
+    #[test]
+    fn test_show_with_offsets() {
+        let symbols = WhitespaceSymbols::default();
+        assert_eq!(symbols.show_with_offsets("a b"), None);
+        let (shown, offsets) = symbols.show_with_offsets("é\tb \nc").unwrap();
+        assert_eq!(shown, "é→b·↵\nc");
+        // é and · are 2 bytes, → and ↵ are 3 bytes
+        assert_eq!(offsets, vec![0, 0, 2, 5, 6, 8, 12, 13]);
+        assert_eq!(&shown[offsets[2]..offsets[4]], "→b");
+    }
+
+    #[test]
+    fn test_from_arg() {
+        assert_eq!(
+            WhitespaceSymbols::from_arg(&None).unwrap(),
+            WhitespaceSymbols::default()
+        );
+        let symbols = WhitespaceSymbols::from_arg(&Some("_>$?".to_string())).unwrap();
+        assert_eq!(symbols.show("a\t \n\r"), "a>_$\n?");
+        assert!(WhitespaceSymbols::from_arg(&Some("_>".to_string())).is_err());
+    }
+}
@@ -314,6 +314,6 @@ mod tests {
         assert_eq!(wrapper.next(), Some(Line::from(vec![Span::raw("")])));
         assert_eq!(wrapper.next(), Some(Line::from(vec![Span::raw("")])));
         assert_eq!(wrapper.next(), Some(Line::from(vec![Span::raw("")])));
-        assert_eq!(wrapper.finished(), false);
+        assert!(!wrapper.finished());
     }
 }